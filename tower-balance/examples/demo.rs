@@ -24,7 +24,7 @@ use tower_balance as lb;
 use tower_discover::Discover;
 use tower_in_flight_limit::InFlightLimit;
 use tower_service::Service;
-use tower_util::ServiceFn;
+use tower_util::service_fn;
 
 const REQUESTS: usize = 50_000;
 const CONCURRENCY: usize = 50;
@@ -96,7 +96,7 @@ fn gen_disco() -> impl Discover<
     Service = impl Service<Req, Response = Rsp, Error = Error, Future = impl Send> + Send,
 > + Send {
     tower_discover::ServiceList::new(MAX_ENDPOINT_LATENCIES.iter().map(|latency| {
-        let svc = ServiceFn::new(move |_| {
+        let svc = service_fn(move |_| {
             let start = Instant::now();
             let maxms = u64::from(latency.subsec_nanos() / 1_000 / 1_000)
                 .saturating_add(latency.as_secs().saturating_mul(1_000));
@@ -0,0 +1,225 @@
+//! Annotating responses with how they were balanced.
+//!
+//! A `Balance` hides which endpoint served a request, and how long the
+//! request waited for an endpoint to become ready, from its callers.
+//! `Balance::annotated` wraps each response in an `Annotated`, carrying the
+//! key of the endpoint that served the request and the time spent waiting for
+//! a ready endpoint, so that callers and logging layers can explain slow
+//! requests.
+//!
+//! Under a `Retry`, only the endpoint that served the final attempt is known;
+//! see `tower_retry::annotate` for the number of attempts made.
+
+use futures::{Async, Future, Poll};
+use std::fmt;
+use std::time::{Duration, Instant};
+use tokio_timer::clock;
+use tower_discover::Discover;
+use tower_service::Service;
+
+use error::Error;
+use future::ResponseFuture;
+use {Balance, Choose};
+
+/// A response along with how it was obtained.
+#[derive(Clone, Debug)]
+pub struct Annotated<T, K> {
+    response: T,
+    endpoint: K,
+    queued: Duration,
+}
+
+/// A `Balance` that annotates its responses.
+pub struct AnnotatedBalance<D: Discover, C> {
+    balance: Balance<D, C>,
+    /// When the balancer started waiting for a ready endpoint for the next
+    /// request.
+    waiting_since: Option<Instant>,
+}
+
+/// The `Future` returned by an `AnnotatedBalance` service.
+pub struct AnnotatedFuture<F, K> {
+    inner: ResponseFuture<F>,
+    endpoint: Option<K>,
+    queued: Duration,
+}
+
+// ===== impl Annotated =====
+
+impl<T, K> Annotated<T, K> {
+    /// Returns a reference to the response.
+    pub fn response(&self) -> &T {
+        &self.response
+    }
+
+    /// Consumes `self`, returning the response.
+    pub fn into_response(self) -> T {
+        self.response
+    }
+
+    /// The key of the endpoint that served the request.
+    pub fn endpoint(&self) -> &K {
+        &self.endpoint
+    }
+
+    /// The time from the balancer being polled for the request to an endpoint
+    /// being ready to serve it.
+    pub fn queued(&self) -> Duration {
+        self.queued
+    }
+}
+
+// ===== impl AnnotatedBalance =====
+
+impl<D: Discover, C> AnnotatedBalance<D, C> {
+    pub(crate) fn new(balance: Balance<D, C>) -> Self {
+        AnnotatedBalance {
+            balance,
+            waiting_since: None,
+        }
+    }
+
+    /// Get a reference to the inner balancer
+    pub fn get_ref(&self) -> &Balance<D, C> {
+        &self.balance
+    }
+
+    /// Consume `self`, returning the inner balancer
+    pub fn into_inner(self) -> Balance<D, C> {
+        self.balance
+    }
+}
+
+impl<D, C, Svc, Request> Service<Request> for AnnotatedBalance<D, C>
+where
+    D: Discover<Service = Svc>,
+    D::Key: Clone,
+    D::Error: Into<Error>,
+    Svc: Service<Request>,
+    Svc::Error: Into<Error>,
+    C: Choose<D::Key, Svc>,
+{
+    type Response = Annotated<Svc::Response, D::Key>;
+    type Error = Error;
+    type Future = AnnotatedFuture<Svc::Future, D::Key>;
+
+    fn poll_ready(&mut self) -> Poll<(), Self::Error> {
+        if self.waiting_since.is_none() {
+            self.waiting_since = Some(clock::now());
+        }
+
+        self.balance.poll_ready()
+    }
+
+    fn call(&mut self, request: Request) -> Self::Future {
+        let now = clock::now();
+        let queued = now - self.waiting_since.take().unwrap_or(now);
+        let endpoint = self.balance.chosen_key().cloned();
+
+        AnnotatedFuture {
+            inner: self.balance.call(request),
+            endpoint,
+            queued,
+        }
+    }
+}
+
+impl<D, C> fmt::Debug for AnnotatedBalance<D, C>
+where
+    D: Discover,
+    Balance<D, C>: fmt::Debug,
+{
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_struct("AnnotatedBalance")
+            .field("balance", &self.balance)
+            .field("waiting_since", &self.waiting_since)
+            .finish()
+    }
+}
+
+// ===== impl AnnotatedFuture =====
+
+impl<F, K> Future for AnnotatedFuture<F, K>
+where
+    F: Future,
+    F::Error: Into<Error>,
+{
+    type Item = Annotated<F::Item, K>;
+    type Error = Error;
+
+    fn poll(&mut self) -> Poll<Self::Item, Self::Error> {
+        let response = try_ready!(self.inner.poll());
+
+        Ok(Async::Ready(Annotated {
+            response,
+            endpoint: self.endpoint.take().expect("polled after ready"),
+            queued: self.queued,
+        }))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    extern crate tower_mock;
+
+    use self::tower_mock::clock::MockClock;
+    use super::*;
+    use futures::future;
+    use std::sync::atomic::{AtomicBool, Ordering};
+    use std::sync::Arc;
+    use tower_discover::ServiceList;
+
+    /// Responds right away once ready.
+    struct Gated {
+        ready: Arc<AtomicBool>,
+    }
+
+    impl Service<()> for Gated {
+        type Response = ();
+        type Error = Error;
+        type Future = future::FutureResult<(), Error>;
+
+        fn poll_ready(&mut self) -> Poll<(), Self::Error> {
+            if self.ready.load(Ordering::SeqCst) {
+                Ok(Async::Ready(()))
+            } else {
+                Ok(Async::NotReady)
+            }
+        }
+
+        fn call(&mut self, _: ()) -> Self::Future {
+            future::ok(())
+        }
+    }
+
+    #[test]
+    fn annotates_endpoint_and_queue_time() {
+        MockClock::new().enter(|clock| {
+            let ready = Arc::new(AtomicBool::new(false));
+            let services = vec![
+                Gated {
+                    ready: Arc::new(AtomicBool::new(false)),
+                },
+                Gated {
+                    ready: ready.clone(),
+                },
+            ];
+            let mut balance = Balance::round_robin(ServiceList::new(services)).annotated();
+
+            assert!(balance.poll_ready().unwrap().is_not_ready());
+            clock.advance(Duration::from_secs(3));
+            ready.store(true, Ordering::SeqCst);
+            assert!(balance.poll_ready().unwrap().is_ready());
+
+            let rsp = balance.call(()).wait().unwrap();
+            assert_eq!(*rsp.endpoint(), 1);
+            assert_eq!(rsp.queued(), Duration::from_secs(3));
+
+            // The next request does not wait.
+            assert!(balance.poll_ready().unwrap().is_ready());
+            let rsp = balance.call(()).wait().unwrap();
+            assert_eq!(*rsp.endpoint(), 1);
+            assert_eq!(rsp.queued(), Duration::from_secs(0));
+        });
+    }
+}
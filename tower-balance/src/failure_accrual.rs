@@ -0,0 +1,506 @@
+//! Ejecting endpoints that keep failing.
+//!
+//! A dead endpoint often fails requests faster than healthy endpoints can
+//! serve them, so that a load-aware balancer sends it more and more of the
+//! traffic. `FailureAccrual` counts the consecutive failed responses of an
+//! endpoint, and once there are too many, ejects it: the endpoint is not
+//! ready, so that the balancer sets it aside, until a given duration has
+//! elapsed. A single request is then sent to the endpoint as a probe. If it
+//! succeeds, the endpoint is readmitted; otherwise it is ejected again.
+
+use futures::task::{self, Task};
+use futures::{Async, Future, Poll};
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+use tower_discover::{Change, Discover};
+use tower_service::Service;
+use tower_util::backoff::Delayed;
+use tower_util::{Unready, UnreadyReason};
+
+use weight::{HasWeight, Weight};
+use Load;
+
+/// Ejects an `S`-typed endpoint after `max_failures` consecutive failed
+/// responses.
+///
+/// See the [module documentation](index.html) for details.
+#[derive(Debug)]
+pub struct FailureAccrual<S> {
+    service: S,
+    policy: Policy,
+    state: Arc<Mutex<State>>,
+}
+
+/// Wraps a `D`-typed stream of discovery updates with `FailureAccrual`.
+#[derive(Debug)]
+pub struct WithFailureAccrual<D> {
+    discover: D,
+    policy: Policy,
+}
+
+/// Records the outcome of a request sent to a `FailureAccrual` endpoint.
+#[derive(Debug)]
+pub struct ResponseFuture<F> {
+    future: F,
+    policy: Policy,
+    state: Arc<Mutex<State>>,
+    /// Set while the response has yet to be recorded.
+    pending: bool,
+    /// Set if the request is the probe of an ejected endpoint. Only its
+    /// outcome readmits, or ejects again, the endpoint: the responses to
+    /// requests sent before the endpoint was ejected may still come in.
+    probe: bool,
+}
+
+#[derive(Clone, Copy, Debug)]
+struct Policy {
+    max_failures: usize,
+    eject_for: Duration,
+}
+
+#[derive(Debug)]
+struct State {
+    failures: usize,
+    health: Health,
+}
+
+#[derive(Debug)]
+enum Health {
+    Healthy,
+    /// The endpoint is not used until the delay has elapsed.
+    Ejected(Delayed<()>),
+    /// The endpoint may be sent a single request, whose outcome decides
+    /// whether the endpoint is readmitted.
+    Probing {
+        in_flight: bool,
+        /// The task waiting for the probe to complete.
+        task: Option<Task>,
+    },
+}
+
+// ===== impl FailureAccrual =====
+
+impl<S> FailureAccrual<S> {
+    /// Wraps `service`, ejecting it for `eject_for` after `max_failures`
+    /// consecutive failed responses.
+    ///
+    /// # Panics
+    ///
+    /// This function panics if `max_failures` is 0.
+    pub fn new(service: S, max_failures: usize, eject_for: Duration) -> Self {
+        Self::with_policy(service, Policy::new(max_failures, eject_for))
+    }
+
+    fn with_policy(service: S, policy: Policy) -> Self {
+        FailureAccrual {
+            service,
+            policy,
+            state: Arc::new(Mutex::new(State {
+                failures: 0,
+                health: Health::Healthy,
+            })),
+        }
+    }
+
+    /// Returns `true` if the endpoint is ejected, or waiting for the outcome
+    /// of a probe.
+    pub fn is_ejected(&self) -> bool {
+        match self.state.lock().expect("failure accrual state").health {
+            Health::Healthy => false,
+            _ => true,
+        }
+    }
+}
+
+impl<S, Request> Service<Request> for FailureAccrual<S>
+where
+    S: Service<Request>,
+{
+    type Response = S::Response;
+    type Error = S::Error;
+    type Future = ResponseFuture<S::Future>;
+
+    fn poll_ready(&mut self) -> Poll<(), Self::Error> {
+        {
+            let mut state = self.state.lock().expect("failure accrual state");
+
+            if let Health::Ejected(ref mut delay) = state.health {
+                if let Ok(Async::NotReady) = delay.poll() {
+                    return Ok(Async::NotReady);
+                }
+
+                trace!("probing ejected endpoint");
+                state.health = Health::Probing {
+                    in_flight: false,
+                    task: None,
+                };
+            }
+
+            if let Health::Probing {
+                in_flight: true,
+                ref mut task,
+            } = state.health
+            {
+                *task = Some(task::current());
+                return Ok(Async::NotReady);
+            }
+        }
+
+        self.service.poll_ready()
+    }
+
+    fn call(&mut self, request: Request) -> Self::Future {
+        let mut probe = false;
+        if let Health::Probing {
+            ref mut in_flight, ..
+        } = self.state.lock().expect("failure accrual state").health
+        {
+            *in_flight = true;
+            probe = true;
+        }
+
+        ResponseFuture {
+            future: self.service.call(request),
+            policy: self.policy,
+            state: self.state.clone(),
+            pending: true,
+            probe,
+        }
+    }
+}
+
+impl<S, Request> Unready<Request> for FailureAccrual<S>
+where
+    S: Unready<Request>,
+{
+    fn unready_reason(&self) -> Option<UnreadyReason> {
+        match self.state.lock().expect("failure accrual state").health {
+            Health::Ejected(_)
+            | Health::Probing {
+                in_flight: true, ..
+            } => Some(UnreadyReason::CircuitOpen),
+            _ => self.service.unready_reason(),
+        }
+    }
+}
+
+impl<S: Load> Load for FailureAccrual<S> {
+    type Metric = S::Metric;
+
+    fn load(&self) -> Self::Metric {
+        self.service.load()
+    }
+}
+
+impl<S: HasWeight> HasWeight for FailureAccrual<S> {
+    fn weight(&self) -> Weight {
+        self.service.weight()
+    }
+}
+
+// ===== impl WithFailureAccrual =====
+
+impl<D> WithFailureAccrual<D> {
+    /// Wraps `discover`'s services with `FailureAccrual`, ejecting them for
+    /// `eject_for` after `max_failures` consecutive failed responses.
+    ///
+    /// # Panics
+    ///
+    /// This function panics if `max_failures` is 0.
+    pub fn new(discover: D, max_failures: usize, eject_for: Duration) -> Self {
+        WithFailureAccrual {
+            discover,
+            policy: Policy::new(max_failures, eject_for),
+        }
+    }
+}
+
+impl<D> Discover for WithFailureAccrual<D>
+where
+    D: Discover,
+{
+    type Key = D::Key;
+    type Service = FailureAccrual<D::Service>;
+    type Error = D::Error;
+
+    /// Yields the next discovery change set.
+    fn poll(&mut self) -> Poll<Change<D::Key, Self::Service>, D::Error> {
+        use self::Change::*;
+
+        let change = match try_ready!(self.discover.poll()) {
+            Insert(k, svc) => Insert(k, FailureAccrual::with_policy(svc, self.policy)),
+            Remove(k) => Remove(k),
+        };
+
+        Ok(Async::Ready(change))
+    }
+}
+
+// ===== impl ResponseFuture =====
+
+impl<F: Future> Future for ResponseFuture<F> {
+    type Item = F::Item;
+    type Error = F::Error;
+
+    fn poll(&mut self) -> Poll<Self::Item, Self::Error> {
+        let result = self.future.poll();
+
+        match result {
+            Ok(Async::NotReady) => {}
+            Ok(Async::Ready(_)) => self.record(true),
+            Err(_) => self.record(false),
+        }
+
+        result
+    }
+}
+
+impl<F> ResponseFuture<F> {
+    fn record(&mut self, success: bool) {
+        if !self.pending {
+            return;
+        }
+        self.pending = false;
+
+        let mut state = self.state.lock().expect("failure accrual state");
+
+        if self.probe {
+            if let Health::Probing { ref mut task, .. } = state.health {
+                if let Some(task) = task.take() {
+                    task.notify();
+                }
+            }
+
+            if success {
+                debug!("readmitting endpoint");
+                state.failures = 0;
+                state.health = Health::Healthy;
+            } else {
+                debug!("probe failed; ejecting endpoint again");
+                state.failures += 1;
+                state.health = Health::Ejected(Delayed::new((), self.policy.eject_for));
+            }
+            return;
+        }
+
+        match state.health {
+            Health::Healthy => {}
+            // The response to a request sent before the endpoint was ejected.
+            _ => return,
+        }
+
+        if success {
+            state.failures = 0;
+            return;
+        }
+
+        state.failures += 1;
+        if state.failures == self.policy.max_failures {
+            debug!("ejecting endpoint after {} failures", state.failures);
+            state.health = Health::Ejected(Delayed::new((), self.policy.eject_for));
+        }
+    }
+}
+
+impl<F> Drop for ResponseFuture<F> {
+    fn drop(&mut self) {
+        if !self.pending || !self.probe {
+            return;
+        }
+
+        // A canceled probe tells nothing of the endpoint, so another request
+        // may be sent in its place.
+        if let Ok(mut state) = self.state.lock() {
+            if let Health::Probing {
+                ref mut in_flight,
+                ref mut task,
+            } = state.health
+            {
+                *in_flight = false;
+                if let Some(task) = task.take() {
+                    task.notify();
+                }
+            }
+        }
+    }
+}
+
+// ===== impl Policy =====
+
+impl Policy {
+    fn new(max_failures: usize, eject_for: Duration) -> Self {
+        assert!(max_failures > 0, "max_failures must be at least 1");
+        Policy {
+            max_failures,
+            eject_for,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    extern crate tokio_mock_task;
+    extern crate tower_mock;
+
+    use self::tokio_mock_task::MockTask;
+    use self::tower_mock::clock::MockClock;
+    use self::tower_mock::Mock;
+    use super::*;
+
+    type Svc = Mock<&'static str, &'static str>;
+
+    fn call(
+        task: &mut MockTask,
+        svc: &mut FailureAccrual<Svc>,
+    ) -> ResponseFuture<<Svc as Service<&'static str>>::Future> {
+        assert!(task.enter(|| svc.poll_ready()).unwrap().is_ready());
+        svc.call("hello")
+    }
+
+    #[test]
+    fn ejects_after_consecutive_failures() {
+        let mut task = MockTask::new();
+        let (inner, mut handle) = Svc::new();
+        let mut svc = FailureAccrual::new(inner, 2, Duration::from_secs(10));
+
+        MockClock::new().enter(|clock| {
+            for _ in 0..2 {
+                let mut rsp = call(&mut task, &mut svc);
+                handle.next_request().unwrap().error("boom");
+                assert!(task.enter(|| rsp.poll()).is_err());
+            }
+
+            assert!(svc.is_ejected());
+            assert!(task.enter(|| svc.poll_ready()).unwrap().is_not_ready());
+
+            // Once the endpoint has been ejected for long enough, a probe is
+            // sent, and no other request until it completes.
+            clock.advance(Duration::from_secs(10));
+            assert!(task.is_notified());
+            let mut probe = call(&mut task, &mut svc);
+            assert!(task.enter(|| svc.poll_ready()).unwrap().is_not_ready());
+
+            handle.next_request().unwrap().respond("world");
+            assert_eq!(task.enter(|| probe.poll()).unwrap(), Async::Ready("world"));
+            assert!(task.is_notified());
+            assert!(!svc.is_ejected());
+            assert!(task.enter(|| svc.poll_ready()).unwrap().is_ready());
+        });
+    }
+
+    #[test]
+    fn failed_probe_ejects_again() {
+        let mut task = MockTask::new();
+        let (inner, mut handle) = Svc::new();
+        let mut svc = FailureAccrual::new(inner, 1, Duration::from_secs(10));
+
+        MockClock::new().enter(|clock| {
+            let mut rsp = call(&mut task, &mut svc);
+            handle.next_request().unwrap().error("boom");
+            assert!(task.enter(|| rsp.poll()).is_err());
+
+            clock.advance(Duration::from_secs(10));
+            let mut probe = call(&mut task, &mut svc);
+            handle.next_request().unwrap().error("boom");
+            assert!(task.enter(|| probe.poll()).is_err());
+
+            assert!(svc.is_ejected());
+            assert!(task.enter(|| svc.poll_ready()).unwrap().is_not_ready());
+        });
+    }
+
+    #[test]
+    fn only_probe_readmits() {
+        let mut task = MockTask::new();
+        let (inner, mut handle) = Svc::new();
+        let mut svc = FailureAccrual::new(inner, 1, Duration::from_secs(10));
+
+        MockClock::new().enter(|clock| {
+            let mut late = call(&mut task, &mut svc);
+            let late_request = handle.next_request().unwrap();
+            let mut failed = call(&mut task, &mut svc);
+            handle.next_request().unwrap().error("boom");
+            assert!(task.enter(|| failed.poll()).is_err());
+            assert!(svc.is_ejected());
+
+            clock.advance(Duration::from_secs(10));
+            let mut probe = call(&mut task, &mut svc);
+            let probe_request = handle.next_request().unwrap();
+
+            // A request sent before the endpoint was ejected neither
+            // readmits it nor lets another probe be sent.
+            late_request.respond("world");
+            assert!(task.enter(|| late.poll()).unwrap().is_ready());
+            assert!(svc.is_ejected());
+            assert!(task.enter(|| svc.poll_ready()).unwrap().is_not_ready());
+
+            probe_request.respond("world");
+            assert!(task.enter(|| probe.poll()).unwrap().is_ready());
+            assert!(!svc.is_ejected());
+        });
+    }
+
+    #[test]
+    fn canceled_request_does_not_release_probe() {
+        let mut task = MockTask::new();
+        let (inner, mut handle) = Svc::new();
+        let mut svc = FailureAccrual::new(inner, 1, Duration::from_secs(10));
+
+        MockClock::new().enter(|clock| {
+            let canceled = call(&mut task, &mut svc);
+            let mut failed = call(&mut task, &mut svc);
+            handle.next_request().unwrap();
+            handle.next_request().unwrap().error("boom");
+            assert!(task.enter(|| failed.poll()).is_err());
+
+            clock.advance(Duration::from_secs(10));
+            let probe = call(&mut task, &mut svc);
+
+            drop(canceled);
+            assert!(task.enter(|| svc.poll_ready()).unwrap().is_not_ready());
+
+            // Canceling the probe itself lets another one be sent.
+            drop(probe);
+            assert!(task.is_notified());
+            assert!(task.enter(|| svc.poll_ready()).unwrap().is_ready());
+        });
+    }
+
+    #[test]
+    fn successes_reset_failures() {
+        let mut task = MockTask::new();
+        let (inner, mut handle) = Svc::new();
+        let mut svc = FailureAccrual::new(inner, 2, Duration::from_secs(10));
+
+        MockClock::new().enter(|_| {
+            for &fail in &[true, false, true] {
+                let mut rsp = call(&mut task, &mut svc);
+                let request = handle.next_request().unwrap();
+                if fail {
+                    request.error("boom");
+                } else {
+                    request.respond("world");
+                }
+                let _ = task.enter(|| rsp.poll());
+            }
+
+            assert!(!svc.is_ejected());
+        });
+    }
+
+    #[test]
+    fn ejected_endpoint_reports_open_circuit() {
+        let mut task = MockTask::new();
+        let inner = ::tower_util::service_fn(|_: ()| Err::<(), _>("boom"));
+        let mut svc = FailureAccrual::new(inner, 1, Duration::from_secs(10));
+
+        MockClock::new().enter(|_| {
+            assert!(task.enter(|| svc.poll_ready()).unwrap().is_ready());
+            let mut rsp = svc.call(());
+            assert!(task.enter(|| rsp.poll()).is_err());
+
+            assert!(task.enter(|| svc.poll_ready()).unwrap().is_not_ready());
+            assert_eq!(svc.unready_reason(), Some(UnreadyReason::CircuitOpen));
+        });
+    }
+}
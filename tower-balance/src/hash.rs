@@ -0,0 +1,349 @@
+//! Balancing requests by a key extracted from each request.
+//!
+//! `HashBalance` maps each request to an endpoint by hashing a key extracted
+//! from the request onto a ring of the endpoints, as in consistent hashing.
+//! Requests with the same key keep going to the same endpoint, e.g. to make
+//! the best use of its caches. When an endpoint is added or removed, only the
+//! keys next to it on the ring move to another endpoint.
+//!
+//! Each endpoint is placed on the ring many times, as virtual nodes, so that
+//! keys are spread evenly across endpoints. A request whose endpoint is not
+//! ready goes to the next ready endpoint on the ring instead.
+//!
+//! Keys and endpoints are hashed with 64-bit FNV-1a, rather than the standard
+//! library's `DefaultHasher`, whose algorithm may change between Rust
+//! releases. Requests thus keep going to the same endpoints across builds,
+//! and across processes built with different compilers, as long as the keys
+//! hash the same bytes: integers, for instance, are hashed in the byte order
+//! and width of the platform.
+
+use crate::error::{self, Error};
+use crate::future::ResponseFuture;
+use futures::{Async, Poll};
+use indexmap::IndexMap;
+use std::fmt;
+use std::hash::{Hash, Hasher};
+use tower_discover::{Change, Discover};
+use tower_service::Service;
+
+/// Balances requests across endpoints by hashing a key extracted from each
+/// request.
+///
+/// See the [module documentation](index.html) for details.
+pub struct HashBalance<D: Discover, F> {
+    discover: D,
+
+    /// Extracts the key of a request.
+    key: F,
+
+    /// The number of virtual nodes of each endpoint.
+    virtual_nodes: usize,
+
+    /// The virtual nodes of all endpoints, sorted by their hash.
+    ring: Vec<(u64, D::Key)>,
+
+    /// Endpoints that may be called.
+    ready: IndexMap<D::Key, D::Service>,
+
+    /// Endpoints that must be polled before they are called.
+    not_ready: IndexMap<D::Key, D::Service>,
+}
+
+// ===== impl HashBalance =====
+
+impl<D, F> HashBalance<D, F>
+where
+    D: Discover,
+    D::Key: Clone,
+{
+    /// Creates a new balancer, sending each request to the endpoint of the key
+    /// returned by `key`.
+    pub fn new(discover: D, key: F) -> Self {
+        HashBalance {
+            discover,
+            key,
+            virtual_nodes: 100,
+            ring: Vec::new(),
+            ready: IndexMap::default(),
+            not_ready: IndexMap::default(),
+        }
+    }
+
+    /// Set the number of virtual nodes of each endpoint on the ring (100 by
+    /// default).
+    ///
+    /// More virtual nodes spread keys more evenly, at the cost of memory and
+    /// of the time taken to update the ring when endpoints change.
+    ///
+    /// # Panics
+    ///
+    /// This function panics if `virtual_nodes` is 0, or if endpoints have
+    /// already been discovered.
+    pub fn virtual_nodes(mut self, virtual_nodes: usize) -> Self {
+        assert!(
+            virtual_nodes > 0,
+            "endpoints need at least one virtual node"
+        );
+        assert!(self.ring.is_empty(), "endpoints were already discovered");
+        self.virtual_nodes = virtual_nodes;
+        self
+    }
+
+    /// Polls `discover` for updates, adding new endpoints to `not_ready`, and
+    /// to the ring.
+    fn update_from_discover(&mut self) -> Result<(), error::Balance>
+    where
+        D::Error: Into<Error>,
+    {
+        while let Async::Ready(change) =
+            self.discover.poll().map_err(|e| error::Balance(e.into()))?
+        {
+            match change {
+                Change::Insert(key, svc) => {
+                    // A replaced endpoint keeps its place on the ring.
+                    let replaced = self.ready.remove(&key).is_some();
+                    let replaced = self.not_ready.remove(&key).is_some() || replaced;
+                    if !replaced {
+                        self.add_to_ring(&key);
+                    }
+
+                    self.not_ready.insert(key, svc);
+                }
+                Change::Remove(key) => {
+                    if self.ready.remove(&key).is_none() {
+                        self.not_ready.remove(&key);
+                    }
+                    self.ring.retain(|&(_, ref k)| *k != key);
+                }
+            }
+        }
+
+        Ok(())
+    }
+
+    fn add_to_ring(&mut self, key: &D::Key) {
+        for node in 0..self.virtual_nodes {
+            let mut hasher = Fnv::default();
+            (key, node).hash(&mut hasher);
+            self.ring.push((hasher.finish(), key.clone()));
+        }
+
+        self.ring.sort_by_key(|&(hash, _)| hash);
+    }
+
+    /// Calls `poll_ready` on all endpoints in `not_ready`, moving the ready
+    /// ones to `ready`.
+    fn promote_to_ready<Request>(&mut self) -> Result<(), <D::Service as Service<Request>>::Error>
+    where
+        D::Service: Service<Request>,
+    {
+        for idx in (0..self.not_ready.len()).rev() {
+            let is_ready = {
+                let (_, svc) = self
+                    .not_ready
+                    .get_index_mut(idx)
+                    .expect("invalid not_ready index");
+                svc.poll_ready()?.is_ready()
+            };
+
+            if is_ready {
+                let (key, svc) = self
+                    .not_ready
+                    .swap_remove_index(idx)
+                    .expect("invalid not_ready index");
+                self.ready.insert(key, svc);
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Returns the first ready endpoint at or after `hash` on the ring.
+    fn ready_key_for(&self, hash: u64) -> Option<D::Key> {
+        let start = match self.ring.binary_search_by_key(&hash, |&(h, _)| h) {
+            Ok(idx) | Err(idx) => idx,
+        };
+
+        self.ring
+            .iter()
+            .cycle()
+            .skip(start)
+            .take(self.ring.len())
+            .map(|&(_, ref key)| key)
+            .find(|key| self.ready.contains_key(*key))
+            .cloned()
+    }
+}
+
+impl<D, F, K, Svc, Request> Service<Request> for HashBalance<D, F>
+where
+    D: Discover<Service = Svc>,
+    D::Key: Clone,
+    D::Error: Into<Error>,
+    Svc: Service<Request>,
+    Svc::Error: Into<Error>,
+    F: Fn(&Request) -> K,
+    K: Hash,
+{
+    type Response = Svc::Response;
+    type Error = Error;
+    type Future = ResponseFuture<Svc::Future>;
+
+    fn poll_ready(&mut self) -> Poll<(), Self::Error> {
+        self.update_from_discover()?;
+        self.promote_to_ready().map_err(Into::into)?;
+
+        if self.ready.is_empty() {
+            return Ok(Async::NotReady);
+        }
+
+        Ok(Async::Ready(()))
+    }
+
+    fn call(&mut self, request: Request) -> Self::Future {
+        let mut hasher = Fnv::default();
+        (self.key)(&request).hash(&mut hasher);
+
+        let key = self.ready_key_for(hasher.finish()).expect("not ready");
+
+        // The endpoint's readiness is used up by the call, so it is polled
+        // again before it is called next.
+        let mut svc = self.ready.swap_remove(&key).expect("invalid ready key");
+        let rsp = svc.call(request);
+        self.not_ready.insert(key, svc);
+
+        ResponseFuture::new(rsp)
+    }
+}
+
+impl<D, F> fmt::Debug for HashBalance<D, F>
+where
+    D: Discover + fmt::Debug,
+    D::Key: fmt::Debug,
+    D::Service: fmt::Debug,
+{
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_struct("HashBalance")
+            .field("discover", &self.discover)
+            .field("virtual_nodes", &self.virtual_nodes)
+            .field("ready", &self.ready)
+            .field("not_ready", &self.not_ready)
+            .finish()
+    }
+}
+
+// ===== impl Fnv =====
+
+/// The 64-bit FNV-1a hash, which places keys and endpoints on the ring.
+struct Fnv(u64);
+
+impl Default for Fnv {
+    fn default() -> Self {
+        Fnv(0xcbf2_9ce4_8422_2325)
+    }
+}
+
+impl Hasher for Fnv {
+    fn write(&mut self, bytes: &[u8]) {
+        for &byte in bytes {
+            self.0 ^= u64::from(byte);
+            self.0 = self.0.wrapping_mul(0x0000_0100_0000_01b3);
+        }
+    }
+
+    fn finish(&self) -> u64 {
+        self.0
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use futures::{future, Future};
+    use std::collections::VecDeque;
+
+    struct Disco(VecDeque<Change<usize, Endpoint>>);
+
+    impl Discover for Disco {
+        type Key = usize;
+        type Service = Endpoint;
+        type Error = Error;
+
+        fn poll(&mut self) -> Poll<Change<Self::Key, Self::Service>, Self::Error> {
+            Ok(self
+                .0
+                .pop_front()
+                .map(Async::Ready)
+                .unwrap_or(Async::NotReady))
+        }
+    }
+
+    /// Responds with its own id.
+    struct Endpoint(usize);
+
+    impl Service<usize> for Endpoint {
+        type Response = usize;
+        type Error = Error;
+        type Future = future::FutureResult<usize, Error>;
+
+        fn poll_ready(&mut self) -> Poll<(), Self::Error> {
+            Ok(Async::Ready(()))
+        }
+
+        fn call(&mut self, _: usize) -> Self::Future {
+            future::ok(self.0)
+        }
+    }
+
+    fn new_balance(n: usize) -> HashBalance<Disco, fn(&usize) -> usize> {
+        let changes = (0..n).map(|i| Change::Insert(i, Endpoint(i))).collect();
+        HashBalance::new(Disco(changes), |req: &usize| *req)
+    }
+
+    fn endpoint_for(balance: &mut HashBalance<Disco, fn(&usize) -> usize>, req: usize) -> usize {
+        assert!(balance.poll_ready().unwrap().is_ready());
+        balance.call(req).wait().unwrap()
+    }
+
+    #[test]
+    fn fnv_is_stable() {
+        let hash = |bytes: &[u8]| {
+            let mut hasher = Fnv::default();
+            hasher.write(bytes);
+            hasher.finish()
+        };
+
+        assert_eq!(hash(b""), 0xcbf2_9ce4_8422_2325);
+        assert_eq!(hash(b"a"), 0xaf63_dc4c_8601_ec8c);
+        assert_eq!(hash(b"foobar"), 0x8594_4171_f739_67e8);
+    }
+
+    #[test]
+    fn same_key_same_endpoint() {
+        let mut balance = new_balance(5);
+
+        for req in 0..100 {
+            let endpoint = endpoint_for(&mut balance, req);
+            assert_eq!(endpoint_for(&mut balance, req), endpoint);
+        }
+    }
+
+    #[test]
+    fn only_keys_of_removed_endpoint_move() {
+        let mut balance = new_balance(5);
+        let before = (0..100)
+            .map(|req| endpoint_for(&mut balance, req))
+            .collect::<Vec<_>>();
+        assert!(before.iter().any(|&endpoint| endpoint == 0));
+
+        balance.discover.0.push_back(Change::Remove(0));
+        for req in 0..100 {
+            let endpoint = endpoint_for(&mut balance, req);
+            if before[req] == 0 {
+                assert_ne!(endpoint, 0);
+            } else {
+                assert_eq!(endpoint, before[req]);
+            }
+        }
+    }
+}
@@ -1,3 +1,35 @@
+//! Tower middleware that balances requests across a set of endpoints.
+//!
+//! `Balance` dispatches requests to the services of a `Discover`, which
+//! inserts and removes endpoints as they come and go. Endpoints that are not
+//! ready are set aside until they are, and each request goes to a ready
+//! endpoint picked by a `Choose` strategy:
+//!
+//! - `Balance::p2c` picks the less loaded of two random endpoints, as
+//!   measured by their `Load`. Wrap the endpoints with one of the `load`
+//!   metrics, such as `PendingRequests` or `PeakEwma`, to use it.
+//! - `Balance::round_robin` picks endpoints in turn, for endpoints whose load
+//!   is not known.
+//!
+//! `HashBalance` instead sends requests with the same key, extracted from each
+//! request, to the same endpoint, using consistent hashing.
+//!
+//! Endpoints that keep failing may be ejected for a while with
+//! `FailureAccrual`, so that a dead endpoint does not absorb the traffic.
+//!
+//! Endpoints may be given a `Weight`, so that `Balance::p2c` prefers those
+//! with greater weights; see the `weight` module. `SlowStart` ramps the
+//! weight of newly added endpoints up over time.
+//!
+//! The endpoints of a `Balance` are kept in a `ReadyCache`, which remembers
+//! the endpoints found ready, so that only the others are polled again.
+//!
+//! `Balance::annotated` tells callers which endpoint served each request; see
+//! the `annotate` module.
+//!
+//! `Pool` grows and shrinks a set of endpoints created by a `MakeService`
+//! according to the load of a `Balance`.
+
 #[macro_use]
 extern crate futures;
 #[macro_use]
@@ -13,25 +45,36 @@ extern crate tower_util;
 extern crate quickcheck;
 
 use futures::{Async, Poll};
-use indexmap::IndexMap;
 use rand::{rngs::SmallRng, SeedableRng};
 use std::fmt;
 use tower_discover::Discover;
 use tower_service::Service;
 
+pub mod annotate;
 pub mod choose;
 pub mod error;
+pub mod failure_accrual;
 pub mod future;
+pub mod hash;
 pub mod load;
 pub mod pool;
+pub mod ready_cache;
+pub mod slow_start;
+pub mod weight;
 
 #[cfg(test)]
 mod test;
 
 pub use self::choose::Choose;
+pub use self::failure_accrual::{FailureAccrual, WithFailureAccrual};
+pub use self::hash::HashBalance;
 pub use self::load::Load;
 pub use self::pool::Pool;
+pub use self::ready_cache::ReadyCache;
+pub use self::slow_start::{SlowStart, WithSlowStart};
+pub use self::weight::{HasWeight, Weight, Weighted, WeightedLoad, WithWeighted};
 
+use self::annotate::AnnotatedBalance;
 use self::error::Error;
 use self::future::ResponseFuture;
 
@@ -44,19 +87,16 @@ pub struct Balance<D: Discover, C> {
     /// Determines which endpoint is ready to be used next.
     choose: C,
 
-    /// Holds an index into `ready`, indicating the service that has been chosen to
-    /// dispatch the next request.
+    /// Holds an index into the ready set of `services`, indicating the service that has
+    /// been chosen to dispatch the next request.
     chosen_ready_index: Option<usize>,
 
-    /// Holds an index into `ready`, indicating the service that dispatched the last
-    /// request.
+    /// Holds an index into the ready set of `services`, indicating the service that
+    /// dispatched the last request.
     dispatched_ready_index: Option<usize>,
 
-    /// Holds all possibly-available endpoints (i.e. from `discover`).
-    ready: IndexMap<D::Key, D::Service>,
-
-    /// Newly-added endpoints that have not yet become ready.
-    not_ready: IndexMap<D::Key, D::Service>,
+    /// Holds all endpoints from `discover`, tracking which are ready.
+    services: ReadyCache<D::Key, D::Service>,
 }
 
 // ===== impl Balance =====
@@ -116,37 +156,51 @@ where
             choose,
             chosen_ready_index: None,
             dispatched_ready_index: None,
-            ready: IndexMap::default(),
-            not_ready: IndexMap::default(),
+            services: ReadyCache::default(),
         }
     }
 
+    /// Annotate responses with the endpoint that served each request, and
+    /// the time spent waiting for a ready endpoint.
+    ///
+    /// See [`annotate`](annotate/index.html) for details.
+    pub fn annotated(self) -> AnnotatedBalance<D, C> {
+        AnnotatedBalance::new(self)
+    }
+
     /// Returns true iff there are ready services.
     ///
     /// This is not authoritative and is only useful after `poll_ready` has been called.
     pub fn is_ready(&self) -> bool {
-        !self.ready.is_empty()
+        self.services.ready_len() > 0
     }
 
     /// Returns true iff there are no ready services.
     ///
     /// This is not authoritative and is only useful after `poll_ready` has been called.
     pub fn is_not_ready(&self) -> bool {
-        self.ready.is_empty()
+        self.services.ready_len() == 0
     }
 
     /// Counts the number of services considered to be ready.
     ///
     /// This is not authoritative and is only useful after `poll_ready` has been called.
     pub fn num_ready(&self) -> usize {
-        self.ready.len()
+        self.services.ready_len()
     }
 
     /// Counts the number of services not considered to be ready.
     ///
     /// This is not authoritative and is only useful after `poll_ready` has been called.
     pub fn num_not_ready(&self) -> usize {
-        self.not_ready.len()
+        self.services.pending_len()
+    }
+
+    /// Returns the key of the endpoint chosen by `poll_ready` to serve the
+    /// next request, if any.
+    pub(crate) fn chosen_key(&self) -> Option<&D::Key> {
+        let idx = self.chosen_ready_index?;
+        self.services.get_ready_index(idx).map(|(key, _)| key)
     }
 }
 
@@ -156,9 +210,9 @@ where
     D::Error: Into<Error>,
     C: Choose<D::Key, D::Service>,
 {
-    /// Polls `discover` for updates, adding new items to `not_ready`.
+    /// Polls `discover` for updates, adding new items to `services` as pending.
     ///
-    /// Removals may alter the order of either `ready` or `not_ready`.
+    /// Removals may alter the order of the ready services.
     fn update_from_discover(&mut self) -> Result<(), error::Balance> {
         debug!("updating from discover");
         use tower_discover::Change::*;
@@ -167,20 +221,14 @@ where
             self.discover.poll().map_err(|e| error::Balance(e.into()))?
         {
             match change {
-                Insert(key, mut svc) => {
+                Insert(key, svc) => {
                     // If the `Insert`ed service is a duplicate of a service already
-                    // in the ready list, remove the ready service first. The new
-                    // service will then be inserted into the not-ready list.
-                    self.ready.remove(&key);
-
-                    self.not_ready.insert(key, svc);
+                    // in the cache, it is replaced by the new, pending, service.
+                    self.services.push(key, svc);
                 }
 
                 Remove(key) => {
-                    let _ejected = match self.ready.remove(&key) {
-                        None => self.not_ready.remove(&key),
-                        Some(s) => Some(s),
-                    };
+                    let _ejected = self.services.evict(&key);
                     // XXX is it safe to just drop the Service? Or do we need some sort of
                     // graceful teardown?
                     // TODO: poll_close
@@ -191,77 +239,6 @@ where
         Ok(())
     }
 
-    /// Calls `poll_ready` on all services in `not_ready`.
-    ///
-    /// When `poll_ready` returns ready, the service is removed from `not_ready` and inserted
-    /// into `ready`, potentially altering the order of `ready` and/or `not_ready`.
-    fn promote_to_ready<Request>(&mut self) -> Result<(), <D::Service as Service<Request>>::Error>
-    where
-        D::Service: Service<Request>,
-    {
-        let n = self.not_ready.len();
-        if n == 0 {
-            trace!("promoting to ready: not_ready is empty, skipping.");
-            return Ok(());
-        }
-
-        debug!("promoting to ready: {}", n);
-        // Iterate through the not-ready endpoints from right to left to prevent removals
-        // from reordering services in a way that could prevent a service from being polled.
-        for idx in (0..n).rev() {
-            let is_ready = {
-                let (_, svc) = self
-                    .not_ready
-                    .get_index_mut(idx)
-                    .expect("invalid not_ready index");;
-                svc.poll_ready()?.is_ready()
-            };
-            trace!("not_ready[{:?}]: is_ready={:?};", idx, is_ready);
-            if is_ready {
-                debug!("not_ready[{:?}]: promoting to ready", idx);
-                let (key, svc) = self
-                    .not_ready
-                    .swap_remove_index(idx)
-                    .expect("invalid not_ready index");
-                self.ready.insert(key, svc);
-            } else {
-                debug!("not_ready[{:?}]: not promoting to ready", idx);
-            }
-        }
-
-        debug!("promoting to ready: done");
-
-        Ok(())
-    }
-
-    /// Polls a `ready` service or moves it to `not_ready`.
-    ///
-    /// If the service exists in `ready` and does not poll as ready, it is moved to
-    /// `not_ready`, potentially altering the order of `ready` and/or `not_ready`.
-    fn poll_ready_index<Request>(
-        &mut self,
-        idx: usize,
-    ) -> Option<Poll<(), <D::Service as Service<Request>>::Error>>
-    where
-        D::Service: Service<Request>,
-    {
-        match self.ready.get_index_mut(idx) {
-            None => return None,
-            Some((_, svc)) => match svc.poll_ready() {
-                Ok(Async::Ready(())) => return Some(Ok(Async::Ready(()))),
-                Err(e) => return Some(Err(e)),
-                Ok(Async::NotReady) => {}
-            },
-        }
-
-        let (key, svc) = self
-            .ready
-            .swap_remove_index(idx)
-            .expect("invalid ready index");
-        self.not_ready.insert(key, svc);
-        Some(Ok(Async::NotReady))
-    }
-
     /// Chooses the next service to which a request will be dispatched.
     ///
     /// Ensures that .
@@ -272,23 +249,19 @@ where
         D::Service: Service<Request>,
     {
         loop {
-            let n = self.ready.len();
+            let n = self.services.ready_len();
             debug!("choosing from {} replicas", n);
             let idx = match n {
                 0 => return Ok(Async::NotReady),
                 1 => 0,
                 _ => {
-                    let replicas = choose::replicas(&self.ready).expect("too few replicas");
+                    let replicas = self.services.replicas().expect("too few replicas");
                     self.choose.choose(replicas)
                 }
             };
 
             // XXX Should we handle per-endpoint errors?
-            if self
-                .poll_ready_index(idx)
-                .expect("invalid ready index")?
-                .is_ready()
-            {
+            if self.services.check_ready_index(idx)? {
                 self.chosen_ready_index = Some(idx);
                 return Ok(Async::Ready(()));
             }
@@ -311,23 +284,21 @@ where
     /// Prepares the balancer to process a request.
     ///
     /// When `Async::Ready` is returned, `chosen_ready_index` is set with a valid index
-    /// into `ready` referring to a `Service` that is ready to disptach a request.
+    /// into the ready services, referring to a `Service` that is ready to disptach a request.
     fn poll_ready(&mut self) -> Poll<(), Self::Error> {
-        // Clear before `ready` is altered.
+        // Clear before the ready services are altered.
         self.chosen_ready_index = None;
 
-        // Before `ready` is altered, check the readiness of the last-used service, moving it
-        // to `not_ready` if appropriate.
+        // Before the ready services are altered, check the readiness of the last-used
+        // service, moving it back to pending if appropriate.
         if let Some(idx) = self.dispatched_ready_index.take() {
             // XXX Should we handle per-endpoint errors?
-            self.poll_ready_index(idx)
-                .expect("invalid dispatched ready key")
-                .map_err(Into::into)?;
+            self.services.check_ready_index(idx).map_err(Into::into)?;
         }
 
-        // Update `not_ready` and `ready`.
+        // Update the pending and ready services.
         self.update_from_discover()?;
-        self.promote_to_ready().map_err(Into::into)?;
+        self.services.poll_pending().map_err(Into::into)?;
 
         // Choose the next service to be used by `call`.
         self.choose_and_poll_ready().map_err(Into::into)
@@ -335,13 +306,9 @@ where
 
     fn call(&mut self, request: Request) -> Self::Future {
         let idx = self.chosen_ready_index.take().expect("not ready");
-        let (_, svc) = self
-            .ready
-            .get_index_mut(idx)
-            .expect("invalid chosen ready index");
         self.dispatched_ready_index = Some(idx);
 
-        let rsp = svc.call(request);
+        let rsp = self.services.call_ready_index(idx, request);
         ResponseFuture::new(rsp)
     }
 }
@@ -2,6 +2,7 @@ use futures::{Async, Poll};
 use tower_discover::{Change, Discover};
 use tower_service::Service;
 
+use weight::{HasWeight, Weight};
 use Load;
 
 /// Wraps a type so that `Load::load` returns a constant value.
@@ -26,6 +27,12 @@ impl<T, M: Copy> Load for Constant<T, M> {
     }
 }
 
+impl<T: HasWeight, M> HasWeight for Constant<T, M> {
+    fn weight(&self) -> Weight {
+        self.inner.weight()
+    }
+}
+
 impl<S, M, Request> Service<Request> for Constant<S, M>
 where
     S: Service<Request>,
@@ -1,4 +1,4 @@
-use futures::{Future, Poll};
+use futures::{Async, Future, Poll, Stream};
 
 /// Attaches `I`-typed instruments to `V` typed values.
 ///
@@ -29,6 +29,24 @@ pub trait Instrument<H, V>: Clone {
 #[derive(Clone, Copy, Debug)]
 pub struct NoInstrument;
 
+/// A `Instrument` implementation that holds each instrument until a streaming
+/// response completes.
+///
+/// With `NoInstrument`, a load metric observes a response as soon as its future
+/// is satisfied, i.e. at the time-to-first-byte. When responses are `Stream`s,
+/// `CompleteOnStreamEnd` instead keeps the handle alive until the stream yields
+/// its last item, fails, or is dropped, so that latency is measured to the
+/// time-to-last-byte.
+#[derive(Clone, Copy, Debug)]
+pub struct CompleteOnStreamEnd;
+
+/// A `Stream` that drops an `H`-typed handle once the inner stream completes.
+#[derive(Debug)]
+pub struct TrackCompletion<S, H> {
+    stream: S,
+    handle: Option<H>,
+}
+
 /// Attaches a `I`-typed instruments to the result of an `F`-typed `Future`.
 #[derive(Debug)]
 pub struct InstrumentFuture<F, I, H>
@@ -82,3 +100,52 @@ impl<H, V> Instrument<H, V> for NoInstrument {
         value
     }
 }
+
+// ===== CompleteOnStreamEnd =====
+
+impl<H, S: Stream> Instrument<H, S> for CompleteOnStreamEnd {
+    type Output = TrackCompletion<S, H>;
+
+    fn instrument(&self, handle: H, stream: S) -> Self::Output {
+        TrackCompletion {
+            stream,
+            handle: Some(handle),
+        }
+    }
+}
+
+// ===== TrackCompletion =====
+
+impl<S, H> TrackCompletion<S, H> {
+    /// Returns a reference to the inner stream.
+    pub fn get_ref(&self) -> &S {
+        &self.stream
+    }
+
+    /// Consumes `self`, returning the inner stream.
+    ///
+    /// The handle is dropped, so the response is considered complete.
+    pub fn into_inner(self) -> S {
+        self.stream
+    }
+}
+
+impl<S: Stream, H> Stream for TrackCompletion<S, H> {
+    type Item = S::Item;
+    type Error = S::Error;
+
+    fn poll(&mut self) -> Poll<Option<Self::Item>, Self::Error> {
+        match self.stream.poll() {
+            Ok(Async::Ready(Some(item))) => Ok(Async::Ready(Some(item))),
+            Ok(Async::NotReady) => Ok(Async::NotReady),
+            Ok(Async::Ready(None)) => {
+                drop(self.handle.take());
+                Ok(Async::Ready(None))
+            }
+            Err(e) => {
+                drop(self.handle.take());
+                Err(e)
+            }
+        }
+    }
+}
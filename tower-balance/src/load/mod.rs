@@ -2,11 +2,15 @@ mod constant;
 mod instrument;
 pub mod peak_ewma;
 pub mod pending_requests;
+pub mod response_size;
 
 pub use self::constant::Constant;
-pub use self::instrument::{Instrument, InstrumentFuture, NoInstrument};
+pub use self::instrument::{
+    CompleteOnStreamEnd, Instrument, InstrumentFuture, NoInstrument, TrackCompletion,
+};
 pub use self::peak_ewma::{PeakEwma, WithPeakEwma};
 pub use self::pending_requests::{PendingRequests, WithPendingRequests};
+pub use self::response_size::{Measure, ResponseSize, WithResponseSize};
 
 /// Exposes a load metric.
 ///
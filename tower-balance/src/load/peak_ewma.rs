@@ -1,4 +1,5 @@
 use futures::{Async, Poll};
+use std::ops;
 use std::sync::{Arc, Mutex};
 use std::time::{Duration, Instant};
 use tokio_timer::clock;
@@ -7,6 +8,7 @@ use tower_service::Service;
 
 use super::{Instrument, InstrumentFuture, NoInstrument};
 
+use weight::{HasWeight, Weight};
 use Load;
 
 /// Wraps an `S`-typed Service with Peak-EWMA load measurement.
@@ -21,11 +23,13 @@ use Load;
 /// As requests are sent to the underlying service, an `I`-typed instrumentation strategy
 /// is used to track responses to measure latency in an application-specific way. The
 /// default strategy measures latency as the elapsed time from the request being issued to
-/// the underlying service to the response future being satisfied (or dropped).
+/// the underlying service to the response future being satisfied (or dropped). When
+/// responses are streams, `CompleteOnStreamEnd` may be used instead so that latency is
+/// measured until the last item of the stream is received.
 ///
 /// When no latency information has been measured for an endpoint, an arbitrary default
 /// RTT of 1 second is used to prevent the endpoint from being overloaded before a
-/// meaningful baseline can be established..
+/// meaningful baseline can be established.
 ///
 /// ## Note
 ///
@@ -63,7 +67,7 @@ pub struct Handle {
     rtt_estimate: Arc<Mutex<RttEstimate>>,
 }
 
-/// Holds the current RTT estimateand the last time this value was updated.
+/// Holds the current RTT estimate and the last time this value was updated.
 struct RttEstimate {
     update_at: Instant,
     rtt_ns: f64,
@@ -189,6 +193,22 @@ impl<S, I> Load for PeakEwma<S, I> {
     }
 }
 
+impl<S: HasWeight, I> HasWeight for PeakEwma<S, I> {
+    fn weight(&self) -> Weight {
+        self.service.weight()
+    }
+}
+
+// ===== impl Cost =====
+
+impl ops::Div<Weight> for Cost {
+    type Output = Cost;
+
+    fn div(self, weight: Weight) -> Cost {
+        Cost(self.0 / weight.get())
+    }
+}
+
 impl<S, I> PeakEwma<S, I> {
     fn update_estimate(&self) -> f64 {
         let mut rtt = self.rtt_estimate.lock().expect("peak ewma prior_estimate");
@@ -393,6 +413,55 @@ mod tests {
         });
     }
 
+    /// With `CompleteOnStreamEnd`, latency is measured until the response
+    /// stream ends rather than until the response future is satisfied.
+    #[test]
+    fn stream_end_latency() {
+        use futures::stream::{self, Stream};
+        use load::CompleteOnStreamEnd;
+
+        struct StreamSvc;
+        impl Service<()> for StreamSvc {
+            type Response = stream::IterOk<::std::vec::IntoIter<()>, ()>;
+            type Error = ();
+            type Future = future::FutureResult<Self::Response, ()>;
+
+            fn poll_ready(&mut self) -> Poll<(), ()> {
+                Ok(().into())
+            }
+
+            fn call(&mut self, (): ()) -> Self::Future {
+                future::ok(stream::iter_ok(vec![(), ()]))
+            }
+        }
+
+        let time = Arc::new(Mutex::new(Instant::now()));
+        let clock = clock::Clock::new_with_now(Now(time.clone()));
+
+        let mut enter = enter().expect("enter");
+        clock::with_default(&clock, &mut enter, |_| {
+            let mut svc = PeakEwma::new(
+                StreamSvc,
+                Duration::from_millis(20),
+                NANOS_PER_MILLI * 1_000.0,
+                CompleteOnStreamEnd,
+            );
+
+            let mut rsp = svc.call(()).wait().unwrap();
+            assert!(svc.load() > Cost(20.0 * NANOS_PER_MILLI));
+
+            assert!(rsp.poll().unwrap().is_ready());
+            *time.lock().unwrap() += Duration::from_millis(100);
+            assert!(rsp.poll().unwrap().is_ready());
+            // The request is still pending; only the default estimate has decayed.
+            assert!(svc.load() > Cost(20.0 * NANOS_PER_MILLI));
+
+            // The stream ends; the 100ms RTT is recorded.
+            assert_eq!(rsp.poll(), Ok(Async::Ready(None)));
+            assert_eq!(svc.load(), Cost(100.0 * NANOS_PER_MILLI));
+        });
+    }
+
     #[test]
     fn nanos() {
         assert_eq!(super::nanos(Duration::new(0, 0)), 0.0);
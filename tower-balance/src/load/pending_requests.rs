@@ -1,12 +1,18 @@
 use futures::{Async, Poll};
+use std::ops;
 use std::sync::Arc;
 use tower_discover::{Change, Discover};
 use tower_service::Service;
 
 use super::{Instrument, InstrumentFuture, NoInstrument};
+use weight::{HasWeight, Weight};
 use Load;
 
 /// Expresses load based on the number of currently-pending requests.
+///
+/// By default, a request is pending until its response future is satisfied.
+/// With `CompleteOnStreamEnd`, a streaming response remains pending until the
+/// stream ends.
 #[derive(Debug)]
 pub struct PendingRequests<S, I = NoInstrument> {
     service: S,
@@ -36,7 +42,12 @@ pub struct Handle(RefCount);
 // ===== impl PendingRequests =====
 
 impl<S, I> PendingRequests<S, I> {
-    fn new(service: S, instrument: I) -> Self {
+    /// Wraps `service`, counting its requests until they are completed, as
+    /// determined by `instrument`.
+    ///
+    /// Services yielded by discovery are usually wrapped with
+    /// `WithPendingRequests` instead.
+    pub fn new(service: S, instrument: I) -> Self {
         Self {
             service,
             instrument,
@@ -58,6 +69,31 @@ impl<S, I> Load for PendingRequests<S, I> {
     }
 }
 
+impl<S: HasWeight, I> HasWeight for PendingRequests<S, I> {
+    fn weight(&self) -> Weight {
+        self.service.weight()
+    }
+}
+
+// ===== impl Count =====
+
+impl Count {
+    /// Returns the number of pending requests.
+    pub fn get(&self) -> usize {
+        self.0
+    }
+}
+
+impl ops::Div<Weight> for Count {
+    type Output = f64;
+
+    /// Scales the count by `weight`, counting the request about to be sent so
+    /// that weights also apply to idle endpoints.
+    fn div(self, weight: Weight) -> f64 {
+        (self.0 + 1) as f64 / weight.get()
+    }
+}
+
 impl<S, I, Request> Service<Request> for PendingRequests<S, I>
 where
     S: Service<Request>,
@@ -194,4 +230,38 @@ mod tests {
         drop(i0);
         assert_eq!(svc.load(), Count(0));
     }
+
+    #[test]
+    fn stream_end() {
+        use futures::stream::{self, Stream};
+        use futures::Async;
+        use load::CompleteOnStreamEnd;
+
+        struct StreamSvc;
+        impl Service<()> for StreamSvc {
+            type Response = stream::IterOk<::std::vec::IntoIter<()>, ()>;
+            type Error = ();
+            type Future = future::FutureResult<Self::Response, ()>;
+
+            fn poll_ready(&mut self) -> Poll<(), ()> {
+                Ok(().into())
+            }
+
+            fn call(&mut self, (): ()) -> Self::Future {
+                future::ok(stream::iter_ok(vec![()]))
+            }
+        }
+
+        let mut svc = PendingRequests::new(StreamSvc, CompleteOnStreamEnd);
+        assert_eq!(svc.load(), Count(0));
+
+        let mut rsp = svc.call(()).wait().unwrap();
+        assert_eq!(svc.load(), Count(1));
+
+        assert_eq!(rsp.poll(), Ok(Async::Ready(Some(()))));
+        assert_eq!(svc.load(), Count(1));
+
+        assert_eq!(rsp.poll(), Ok(Async::Ready(None)));
+        assert_eq!(svc.load(), Count(0));
+    }
 }
@@ -0,0 +1,257 @@
+use futures::{Async, Future, Poll};
+use std::ops;
+use std::sync::{Arc, Mutex};
+use tower_discover::{Change, Discover};
+use tower_service::Service;
+
+use weight::{HasWeight, Weight};
+use Load;
+
+/// Measures the size of a `V`-typed response.
+///
+/// The unit is up to the implementation: typically a number of bytes, e.g. from an HTTP
+/// response's `content-length` header, but any measure of how costly a response is to
+/// transfer will do, as long as it is used consistently.
+pub trait Measure<V>: Clone {
+    /// Returns the size of `value`.
+    fn measure(&self, value: &V) -> u64;
+}
+
+/// Wraps an `S`-typed Service with response size load measurement.
+///
+/// `ResponseSize` implements `Load` with the `Bytes` metric, which estimates how much data
+/// an endpoint has yet to transfer: the exponentially-weighted moving average (EWMA) of
+/// the sizes of its responses, as measured by an `M`-typed `Measure`, multiplied by the
+/// number of pending requests. This uses response size as a proxy for bandwidth cost, so
+/// that requests for large responses are not piled onto the same endpoint.
+///
+/// Until a response has been measured, an endpoint's responses are assumed to be of
+/// `default_size`.
+#[derive(Debug)]
+pub struct ResponseSize<S, M> {
+    service: S,
+    measure: M,
+    estimate: Arc<Mutex<SizeEstimate>>,
+}
+
+/// Wraps a `D`-typed stream of discovery updates with `ResponseSize`.
+#[derive(Debug)]
+pub struct WithResponseSize<D, M> {
+    discover: D,
+    default_size: u64,
+    measure: M,
+}
+
+/// Represents the amount of data an endpoint is expected to transfer.
+///
+/// The underlying value is the average response size multiplied by the number of pending
+/// requests.
+#[derive(Copy, Clone, Debug, PartialEq, PartialOrd)]
+pub struct Bytes(f64);
+
+/// Measures the response of an `F`-typed `Future` once it is satisfied.
+#[derive(Debug)]
+pub struct ResponseSizeFuture<F, M> {
+    future: F,
+    measure: M,
+    estimate: Arc<Mutex<SizeEstimate>>,
+}
+
+/// Holds the moving average of response sizes.
+#[derive(Debug)]
+struct SizeEstimate {
+    size: f64,
+}
+
+/// How much each new response contributes to the moving average.
+const WEIGHT: f64 = 0.25;
+
+// ===== impl Measure =====
+
+impl<F, V> Measure<V> for F
+where
+    F: Fn(&V) -> u64 + Clone,
+{
+    fn measure(&self, value: &V) -> u64 {
+        (self)(value)
+    }
+}
+
+// ===== impl WithResponseSize =====
+
+impl<D, M> WithResponseSize<D, M> {
+    /// Wraps a `D`-typed `Discover` so that services have a `ResponseSize` load metric.
+    ///
+    /// The provided `default_size` is used as the size estimate for newly added services.
+    pub fn new<Request>(discover: D, default_size: u64, measure: M) -> Self
+    where
+        D: Discover,
+        D::Service: Service<Request>,
+        M: Measure<<D::Service as Service<Request>>::Response>,
+    {
+        WithResponseSize {
+            discover,
+            default_size,
+            measure,
+        }
+    }
+}
+
+impl<D, M> Discover for WithResponseSize<D, M>
+where
+    D: Discover,
+    M: Clone,
+{
+    type Key = D::Key;
+    type Service = ResponseSize<D::Service, M>;
+    type Error = D::Error;
+
+    fn poll(&mut self) -> Poll<Change<D::Key, Self::Service>, D::Error> {
+        use self::Change::*;
+
+        let change = match try_ready!(self.discover.poll()) {
+            Insert(k, svc) => {
+                let s = ResponseSize::new(svc, self.default_size, self.measure.clone());
+                Insert(k, s)
+            }
+            Remove(k) => Remove(k),
+        };
+
+        Ok(Async::Ready(change))
+    }
+}
+
+// ===== impl ResponseSize =====
+
+impl<S, M> ResponseSize<S, M> {
+    fn new(service: S, default_size: u64, measure: M) -> Self {
+        Self {
+            service,
+            measure,
+            estimate: Arc::new(Mutex::new(SizeEstimate {
+                size: default_size as f64,
+            })),
+        }
+    }
+}
+
+impl<S, M, Request> Service<Request> for ResponseSize<S, M>
+where
+    S: Service<Request>,
+    M: Measure<S::Response>,
+{
+    type Response = S::Response;
+    type Error = S::Error;
+    type Future = ResponseSizeFuture<S::Future, M>;
+
+    fn poll_ready(&mut self) -> Poll<(), Self::Error> {
+        self.service.poll_ready()
+    }
+
+    fn call(&mut self, req: Request) -> Self::Future {
+        ResponseSizeFuture {
+            future: self.service.call(req),
+            measure: self.measure.clone(),
+            estimate: self.estimate.clone(),
+        }
+    }
+}
+
+impl<S, M> Load for ResponseSize<S, M> {
+    type Metric = Bytes;
+
+    fn load(&self) -> Self::Metric {
+        let pending = Arc::strong_count(&self.estimate) as u32 - 1;
+        let size = self.estimate.lock().expect("response size estimate").size;
+
+        let bytes = Bytes(size * f64::from(pending + 1));
+        trace!("load size={:.0} pending={} bytes={:?}", size, pending, bytes);
+        bytes
+    }
+}
+
+impl<S: HasWeight, M> HasWeight for ResponseSize<S, M> {
+    fn weight(&self) -> Weight {
+        self.service.weight()
+    }
+}
+
+// ===== impl Bytes =====
+
+impl ops::Div<Weight> for Bytes {
+    type Output = Bytes;
+
+    fn div(self, weight: Weight) -> Bytes {
+        Bytes(self.0 / weight.get())
+    }
+}
+
+// ===== impl ResponseSizeFuture =====
+
+impl<F, M> Future for ResponseSizeFuture<F, M>
+where
+    F: Future,
+    M: Measure<F::Item>,
+{
+    type Item = F::Item;
+    type Error = F::Error;
+
+    fn poll(&mut self) -> Poll<Self::Item, Self::Error> {
+        let rsp = try_ready!(self.future.poll());
+
+        let size = self.measure.measure(&rsp) as f64;
+        if let Ok(mut estimate) = self.estimate.lock() {
+            estimate.size = estimate.size * (1.0 - WEIGHT) + size * WEIGHT;
+        }
+
+        Ok(rsp.into())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use futures::{future, Future, Poll};
+
+    struct Svc;
+    impl Service<u64> for Svc {
+        type Response = u64;
+        type Error = ();
+        type Future = future::FutureResult<u64, ()>;
+
+        fn poll_ready(&mut self) -> Poll<(), ()> {
+            Ok(().into())
+        }
+
+        fn call(&mut self, size: u64) -> Self::Future {
+            future::ok(size)
+        }
+    }
+
+    fn size(rsp: &u64) -> u64 {
+        *rsp
+    }
+
+    #[test]
+    fn default_size() {
+        let mut svc = ResponseSize::new(Svc, 100, size as fn(&u64) -> u64);
+        assert_eq!(svc.load(), Bytes(100.0));
+
+        let rsp = svc.call(100);
+        assert_eq!(svc.load(), Bytes(200.0));
+
+        assert_eq!(rsp.wait().unwrap(), 100);
+        assert_eq!(svc.load(), Bytes(100.0));
+    }
+
+    #[test]
+    fn weighted_by_size() {
+        let mut svc = ResponseSize::new(Svc, 100, size as fn(&u64) -> u64);
+
+        svc.call(500).wait().unwrap();
+        assert_eq!(svc.load(), Bytes(200.0));
+
+        svc.call(0).wait().unwrap();
+        assert_eq!(svc.load(), Bytes(150.0));
+    }
+}
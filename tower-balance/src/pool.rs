@@ -12,6 +12,9 @@
 //! more services, then the latest added service is removed. In either case, the load estimate is
 //! reset to its initial value (see [`Builder::initial`] to prevent services from being rapidly
 //! added or removed.
+//!
+//! The number of services is kept between [`Builder::min_services`] and
+//! [`Builder::max_services`].
 #![deny(missing_docs)]
 
 use super::{Balance, Choose};
@@ -40,6 +43,8 @@ where
     target: Target,
     load: Load,
     services: usize,
+    min: usize,
+    max: usize,
 }
 
 impl<MS, Target, Request> Discover for PoolDiscoverer<MS, Target, Request>
@@ -55,12 +60,13 @@ where
     type Error = MS::MakeError;
 
     fn poll(&mut self) -> Poll<Change<Self::Key, Self::Service>, Self::Error> {
-        if self.services == 0 && self.making.is_none() {
+        if self.services < self.min && self.making.is_none() {
+            try_ready!(self.maker.poll_ready());
             self.making = Some(self.maker.make_service(self.target.clone()));
         }
 
         if let Load::High = self.load {
-            if self.making.is_none() {
+            if self.making.is_none() && self.services < self.max {
                 try_ready!(self.maker.poll_ready());
                 // TODO: it'd be great if we could avoid the clone here and use, say, &Target
                 self.making = Some(self.maker.make_service(self.target.clone()));
@@ -80,10 +86,14 @@ where
 
         match self.load {
             Load::High => {
-                unreachable!("found high load but no Service being made");
+                assert!(
+                    self.services >= self.max,
+                    "found high load but no Service being made"
+                );
+                Ok(Async::NotReady)
             }
             Load::Normal => Ok(Async::NotReady),
-            Load::Low if self.services == 1 => Ok(Async::NotReady),
+            Load::Low if self.services <= self.min => Ok(Async::NotReady),
             Load::Low => {
                 self.load = Load::Normal;
                 let rm = self.services;
@@ -105,6 +115,8 @@ pub struct Builder {
     high: f64,
     init: f64,
     alpha: f64,
+    min: usize,
+    max: usize,
 }
 
 impl Default for Builder {
@@ -114,6 +126,8 @@ impl Default for Builder {
             low: 0.00001,
             high: 0.2,
             alpha: 0.03,
+            min: 1,
+            max: usize::max_value(),
         }
     }
 }
@@ -176,7 +190,37 @@ impl Builder {
         self
     }
 
+    /// The minimum number of services in the pool.
+    ///
+    /// This many services are made up front, and services are not removed when that would leave
+    /// fewer of them.
+    ///
+    /// The default value is 1.
+    ///
+    /// # Panics
+    ///
+    /// This function panics if `min` is 0.
+    pub fn min_services(&mut self, min: usize) -> &mut Self {
+        assert!(min > 0, "a pool needs at least one service");
+        self.min = min;
+        self
+    }
+
+    /// The maximum number of services in the pool.
+    ///
+    /// Once there are this many services, no more are added, however high the load.
+    ///
+    /// By default, the number of services is not limited.
+    pub fn max_services(&mut self, max: usize) -> &mut Self {
+        self.max = max;
+        self
+    }
+
     /// See [`Pool::new`].
+    ///
+    /// # Panics
+    ///
+    /// This function panics if the maximum number of services is less than the minimum.
     pub fn build<C, MS, Target, Request>(
         &self,
         make_service: MS,
@@ -190,12 +234,19 @@ impl Builder {
         Target: Clone,
         C: Choose<usize, MS::Service>,
     {
+        assert!(
+            self.min <= self.max,
+            "max_services must be at least min_services"
+        );
+
         let d = PoolDiscoverer {
             maker: make_service,
             making: None,
             target,
             load: Load::Normal,
             services: 0,
+            min: self.min,
+            max: self.max,
         };
 
         Pool {
@@ -233,6 +284,8 @@ where
     /// `Service` that is then added to the load-balanced pool. If multiple services are available,
     /// `choose` is used to determine which one to use (just as in `Balance`). If many calls to
     /// `poll_ready` succeed, the most recently added `Service` is dropped from the pool.
+    ///
+    /// Use a [`Builder`] to bound the number of services in the pool.
     pub fn new(make_service: MS, target: Target, choose: C) -> Self {
         Builder::new().build(make_service, target, choose)
     }
@@ -259,7 +312,7 @@ where
             if self.ewma < self.options.low {
                 self.balance.discover.load = Load::Low;
 
-                if self.balance.discover.services > 1 {
+                if self.balance.discover.services > self.balance.discover.min {
                     // reset EWMA so we don't immediately try to remove another service
                     self.ewma = self.options.init;
                 }
@@ -293,3 +346,98 @@ where
         Service::call(&mut self.balance, req)
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use futures::future;
+    use std::{error, fmt};
+
+    #[derive(Debug)]
+    struct Error;
+
+    impl fmt::Display for Error {
+        fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+            f.write_str("error")
+        }
+    }
+
+    impl error::Error for Error {}
+
+    struct Maker;
+    impl Service<()> for Maker {
+        type Response = Svc;
+        type Error = Error;
+        type Future = future::FutureResult<Svc, Error>;
+
+        fn poll_ready(&mut self) -> Poll<(), Error> {
+            Ok(().into())
+        }
+
+        fn call(&mut self, (): ()) -> Self::Future {
+            future::ok(Svc)
+        }
+    }
+
+    struct Svc;
+    impl Service<()> for Svc {
+        type Response = ();
+        type Error = Error;
+        type Future = future::FutureResult<(), Error>;
+
+        fn poll_ready(&mut self) -> Poll<(), Error> {
+            Ok(().into())
+        }
+
+        fn call(&mut self, (): ()) -> Self::Future {
+            future::ok(())
+        }
+    }
+
+    fn discoverer(min: usize, max: usize) -> PoolDiscoverer<Maker, (), ()> {
+        PoolDiscoverer {
+            maker: Maker,
+            making: None,
+            target: (),
+            load: Load::Normal,
+            services: 0,
+            min,
+            max,
+        }
+    }
+
+    fn next_change(d: &mut PoolDiscoverer<Maker, (), ()>) -> Option<Change<usize, Svc>> {
+        match d.poll().unwrap() {
+            Async::Ready(change) => Some(change),
+            Async::NotReady => None,
+        }
+    }
+
+    #[test]
+    fn makes_min_services() {
+        let mut d = discoverer(2, 3);
+        assert!(next_change(&mut d).is_some());
+        assert!(next_change(&mut d).is_some());
+        assert!(next_change(&mut d).is_none());
+        assert_eq!(d.services, 2);
+
+        // Services are not removed below the minimum.
+        d.load = Load::Low;
+        assert!(next_change(&mut d).is_none());
+        assert_eq!(d.services, 2);
+    }
+
+    #[test]
+    fn makes_at_most_max_services() {
+        let mut d = discoverer(1, 2);
+        assert!(next_change(&mut d).is_some());
+
+        d.load = Load::High;
+        assert!(next_change(&mut d).is_some());
+        assert_eq!(d.services, 2);
+
+        d.load = Load::High;
+        assert!(next_change(&mut d).is_none());
+        assert_eq!(d.services, 2);
+    }
+}
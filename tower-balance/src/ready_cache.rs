@@ -0,0 +1,301 @@
+//! A cache of services, tracking which are ready.
+
+use choose::{self, Replicas, TooFew};
+use futures::Async;
+use indexmap::IndexMap;
+use std::hash::Hash;
+use tower_service::Service;
+
+/// Tracks which of a set of keyed services are ready.
+///
+/// Services are pushed into the cache as pending, and `poll_pending` moves
+/// them to the ready set once their `poll_ready` says so. Only pending
+/// services are polled, so that finding a ready service does not need to poll
+/// every service, and the services found ready stay ready until they are
+/// checked again with `check_ready_index`.
+///
+/// Indices into the ready set are only valid until the cache is altered:
+/// pushing, evicting, and polling services may reorder it.
+///
+/// Each key is held at most once, in either set, so the cache holds no more
+/// services than there are endpoints. With the `audit-bounds` feature
+/// enabled, moving a service into a set already holding its key panics.
+#[derive(Debug)]
+pub struct ReadyCache<K, S>
+where
+    K: Hash + Eq,
+{
+    /// Services that may be called.
+    ready: IndexMap<K, S>,
+
+    /// Services that must be polled before they are called.
+    pending: IndexMap<K, S>,
+}
+
+impl<K, S> ReadyCache<K, S>
+where
+    K: Hash + Eq,
+{
+    /// Returns the number of ready services.
+    pub fn ready_len(&self) -> usize {
+        self.ready.len()
+    }
+
+    /// Returns the number of pending services.
+    pub fn pending_len(&self) -> usize {
+        self.pending.len()
+    }
+
+    /// Adds a pending service, replacing the service with the same key, if
+    /// any.
+    pub fn push(&mut self, key: K, svc: S) {
+        self.ready.remove(&key);
+        self.pending.insert(key, svc);
+    }
+
+    /// Removes the service with the given key, returning it if it was in the
+    /// cache.
+    pub fn evict(&mut self, key: &K) -> Option<S> {
+        match self.ready.swap_remove(key) {
+            None => self.pending.swap_remove(key),
+            Some(svc) => Some(svc),
+        }
+    }
+
+    /// Calls `poll_ready` on all pending services, moving the ready ones to
+    /// the ready set.
+    ///
+    /// Fails with the first error returned by a service, leaving the failed
+    /// service pending.
+    pub fn poll_pending<Request>(&mut self) -> Result<(), S::Error>
+    where
+        S: Service<Request>,
+    {
+        let n = self.pending.len();
+        if n == 0 {
+            trace!("promoting to ready: no pending services, skipping.");
+            return Ok(());
+        }
+
+        debug!("promoting to ready: {}", n);
+        // Iterate through the pending services from right to left to prevent removals
+        // from reordering services in a way that could prevent a service from being polled.
+        for idx in (0..n).rev() {
+            let is_ready = {
+                let (_, svc) = self
+                    .pending
+                    .get_index_mut(idx)
+                    .expect("invalid pending index");
+                svc.poll_ready()?.is_ready()
+            };
+            trace!("pending[{:?}]: is_ready={:?};", idx, is_ready);
+            if is_ready {
+                debug!("pending[{:?}]: promoting to ready", idx);
+                let (key, svc) = self
+                    .pending
+                    .swap_remove_index(idx)
+                    .expect("invalid pending index");
+                let _replaced = self.ready.insert(key, svc);
+                audit(_replaced.is_none(), "ready");
+            } else {
+                debug!("pending[{:?}]: not promoting to ready", idx);
+            }
+        }
+
+        debug!("promoting to ready: done");
+
+        Ok(())
+    }
+
+    /// Polls the ready service at `idx`, moving it to the pending set if it is
+    /// not ready anymore.
+    ///
+    /// Returns `true` if the service is still ready.
+    ///
+    /// # Panics
+    ///
+    /// This function panics if `idx` is not a valid index into the ready set.
+    pub fn check_ready_index<Request>(&mut self, idx: usize) -> Result<bool, S::Error>
+    where
+        S: Service<Request>,
+    {
+        {
+            let (_, svc) = self.ready.get_index_mut(idx).expect("invalid ready index");
+            if let Async::Ready(()) = svc.poll_ready()? {
+                return Ok(true);
+            }
+        }
+
+        let (key, svc) = self
+            .ready
+            .swap_remove_index(idx)
+            .expect("invalid ready index");
+        let _replaced = self.pending.insert(key, svc);
+        audit(_replaced.is_none(), "pending");
+        Ok(false)
+    }
+
+    /// Returns the key and service at `idx` in the ready set, if any.
+    pub fn get_ready_index(&self, idx: usize) -> Option<(&K, &S)> {
+        self.ready.get_index(idx)
+    }
+
+    /// Calls the ready service at `idx`.
+    ///
+    /// The service stays in the ready set, so it must be checked with
+    /// `check_ready_index` before it is called again.
+    ///
+    /// # Panics
+    ///
+    /// This function panics if `idx` is not a valid index into the ready set.
+    pub fn call_ready_index<Request>(&mut self, idx: usize, request: Request) -> S::Future
+    where
+        S: Service<Request>,
+    {
+        let (_, svc) = self.ready.get_index_mut(idx).expect("invalid ready index");
+        svc.call(request)
+    }
+
+    /// Returns the ready services, for a `Choose` strategy to choose from.
+    pub(crate) fn replicas(&self) -> Result<Replicas<K, S>, TooFew> {
+        choose::replicas(&self.ready)
+    }
+}
+
+impl<K, S> Default for ReadyCache<K, S>
+where
+    K: Hash + Eq,
+{
+    fn default() -> Self {
+        ReadyCache {
+            ready: IndexMap::default(),
+            pending: IndexMap::default(),
+        }
+    }
+}
+
+/// Checks that a service was moved into a set not already holding its key.
+#[cfg(feature = "audit-bounds")]
+fn audit(unique: bool, set: &str) {
+    assert!(unique, "endpoint held twice by the {} services", set);
+}
+
+#[cfg(not(feature = "audit-bounds"))]
+fn audit(_: bool, _: &str) {}
+
+#[cfg(test)]
+mod tests {
+    extern crate tokio_mock_task;
+    extern crate tower_mock;
+
+    use self::tokio_mock_task::MockTask;
+    use self::tower_mock::{Handle, Mock};
+    use super::*;
+
+    type Svc = Mock<&'static str, &'static str>;
+
+    /// Returns a service and its handle, allowing `n` requests.
+    fn service(n: u64) -> (Svc, Handle<&'static str, &'static str>) {
+        let (svc, mut handle) = Svc::new();
+        handle.allow(n);
+        (svc, handle)
+    }
+
+    fn ready_keys(cache: &ReadyCache<&'static str, Svc>) -> Vec<&'static str> {
+        cache.ready.keys().cloned().collect()
+    }
+
+    #[test]
+    fn pushed_services_are_pending_until_ready() {
+        let mut task = MockTask::new();
+        let mut cache = ReadyCache::default();
+        let (a, _a) = service(1);
+        let (b, mut b_handle) = service(0);
+        cache.push("a", a);
+        cache.push("b", b);
+        assert_eq!((cache.ready_len(), cache.pending_len()), (0, 2));
+
+        task.enter(|| cache.poll_pending()).unwrap();
+        assert_eq!(ready_keys(&cache), vec!["a"]);
+        assert_eq!(cache.pending_len(), 1);
+
+        b_handle.allow(1);
+        assert!(task.is_notified());
+        task.enter(|| cache.poll_pending()).unwrap();
+        assert_eq!(cache.ready_len(), 2);
+        assert_eq!(cache.pending_len(), 0);
+    }
+
+    #[test]
+    fn push_replaces_service_with_same_key() {
+        let mut task = MockTask::new();
+        let mut cache = ReadyCache::default();
+        let (a, _a) = service(1);
+        cache.push("a", a);
+        task.enter(|| cache.poll_pending()).unwrap();
+        assert_eq!(cache.ready_len(), 1);
+
+        // The new service is pending, even though the old one was ready.
+        let (a, _a) = service(0);
+        cache.push("a", a);
+        assert_eq!((cache.ready_len(), cache.pending_len()), (0, 1));
+    }
+
+    #[test]
+    fn evicts_ready_and_pending_services() {
+        let mut task = MockTask::new();
+        let mut cache = ReadyCache::default();
+        let (a, _a) = service(1);
+        let (b, _b) = service(0);
+        cache.push("a", a);
+        cache.push("b", b);
+        task.enter(|| cache.poll_pending()).unwrap();
+
+        assert!(cache.evict(&"a").is_some());
+        assert!(cache.evict(&"b").is_some());
+        assert!(cache.evict(&"c").is_none());
+        assert_eq!((cache.ready_len(), cache.pending_len()), (0, 0));
+    }
+
+    #[test]
+    fn failed_service_stays_pending() {
+        let mut task = MockTask::new();
+        let mut cache = ReadyCache::default();
+        let (a, mut a_handle) = service(0);
+        cache.push("a", a);
+
+        a_handle.error("boom");
+        let err = task.enter(|| cache.poll_pending()).unwrap_err();
+        assert_eq!(err.to_string(), "boom");
+        assert_eq!((cache.ready_len(), cache.pending_len()), (0, 1));
+    }
+
+    #[test]
+    fn check_ready_index_reorders_ready_services() {
+        let mut task = MockTask::new();
+        let mut cache = ReadyCache::default();
+        let handles = ["a", "b", "c"]
+            .iter()
+            .map(|&key| {
+                let (svc, handle) = service(1);
+                cache.push(key, svc);
+                task.enter(|| cache.poll_pending()).unwrap();
+                handle
+            })
+            .collect::<Vec<_>>();
+        assert_eq!(ready_keys(&cache), vec!["a", "b", "c"]);
+
+        // A service that is still ready keeps its index.
+        assert!(task.enter(|| cache.check_ready_index(1)).unwrap());
+        assert_eq!(ready_keys(&cache), vec!["a", "b", "c"]);
+
+        // Calling "a" uses up its only request, so it is moved to the pending
+        // set, and the last ready service takes its index.
+        let _rsp = task.enter(|| cache.call_ready_index(0, "hello"));
+        assert!(!task.enter(|| cache.check_ready_index(0)).unwrap());
+        assert_eq!(ready_keys(&cache), vec!["c", "b"]);
+        assert_eq!(cache.pending_len(), 1);
+
+        drop(handles);
+    }
+}
@@ -0,0 +1,271 @@
+//! Warming up newly added endpoints.
+//!
+//! A fresh endpoint is often slow to serve its first requests, e.g. while it
+//! fills its caches or opens connections. `SlowStart` ramps the weight of an
+//! endpoint up from almost nothing to its full weight over a window of time
+//! after it first became ready, so that a load-aware balancer sends it a
+//! growing share of the traffic, rather than a full share right away. The
+//! time it takes to connect to the endpoint does not count towards the ramp:
+//!
+//! ```rust,ignore
+//! let discover = WithSlowStart::new(
+//!     WithPendingRequests::new(discover, NoInstrument),
+//!     Duration::from_secs(30),
+//! );
+//! let balance = Balance::p2c(discover);
+//! ```
+//!
+//! The ramp applies on top of the weights applied by `WithWeighted`.
+
+use futures::{Async, Poll};
+use std::ops;
+use std::time::{Duration, Instant};
+use tokio_timer::clock;
+use tower_discover::{Change, Discover};
+use tower_service::Service;
+
+use weight::{HasWeight, Weight};
+use Load;
+
+/// The weight of an endpoint that was just added, relative to its full weight.
+const MIN_RAMP: f64 = 0.01;
+
+/// Ramps the weight of an `S`-typed endpoint up over `window`.
+#[derive(Debug)]
+pub struct SlowStart<S> {
+    inner: S,
+    /// When the endpoint first became ready.
+    ready_at: Option<Instant>,
+    window: Duration,
+}
+
+/// Wraps a `D`-typed stream of discovery updates with `SlowStart`.
+#[derive(Debug)]
+pub struct WithSlowStart<D> {
+    discover: D,
+    window: Duration,
+}
+
+// ===== impl SlowStart =====
+
+impl<S> SlowStart<S> {
+    /// Wraps `inner`, ramping its weight up over the `window` after it first
+    /// becomes ready.
+    pub fn new(inner: S, window: Duration) -> Self {
+        SlowStart {
+            inner,
+            ready_at: None,
+            window,
+        }
+    }
+
+    /// Get a reference to the inner service
+    pub fn get_ref(&self) -> &S {
+        &self.inner
+    }
+
+    /// Consume `self`, returning the inner service
+    pub fn into_inner(self) -> S {
+        self.inner
+    }
+
+    /// Returns the share of its full weight the endpoint has now.
+    fn ramp(&self) -> Weight {
+        let ready_at = match self.ready_at {
+            Some(ready_at) => ready_at,
+            None => return Weight::new(MIN_RAMP),
+        };
+
+        let elapsed = clock::now() - ready_at;
+        if elapsed >= self.window {
+            return Weight::DEFAULT;
+        }
+
+        let ramp = secs(elapsed) / secs(self.window);
+        Weight::new(ramp.max(MIN_RAMP))
+    }
+}
+
+impl<S> Load for SlowStart<S>
+where
+    S: Load,
+    S::Metric: ops::Div<Weight>,
+{
+    type Metric = <S::Metric as ops::Div<Weight>>::Output;
+
+    fn load(&self) -> Self::Metric {
+        self.inner.load() / self.ramp()
+    }
+}
+
+impl<S: HasWeight> HasWeight for SlowStart<S> {
+    fn weight(&self) -> Weight {
+        self.inner.weight()
+    }
+}
+
+impl<S, Request> Service<Request> for SlowStart<S>
+where
+    S: Service<Request>,
+{
+    type Response = S::Response;
+    type Error = S::Error;
+    type Future = S::Future;
+
+    fn poll_ready(&mut self) -> Poll<(), Self::Error> {
+        try_ready!(self.inner.poll_ready());
+        if self.ready_at.is_none() {
+            self.ready_at = Some(clock::now());
+        }
+        Ok(Async::Ready(()))
+    }
+
+    fn call(&mut self, req: Request) -> Self::Future {
+        self.inner.call(req)
+    }
+}
+
+// ===== impl WithSlowStart =====
+
+impl<D: Discover> WithSlowStart<D> {
+    /// Ramps the weight of `discover`'s services up over the `window` after
+    /// each first becomes ready.
+    pub fn new(discover: D, window: Duration) -> Self {
+        WithSlowStart { discover, window }
+    }
+}
+
+impl<D: Discover> Discover for WithSlowStart<D> {
+    type Key = D::Key;
+    type Service = SlowStart<D::Service>;
+    type Error = D::Error;
+
+    /// Yields the next discovery change set.
+    fn poll(&mut self) -> Poll<Change<D::Key, Self::Service>, D::Error> {
+        use self::Change::*;
+
+        let change = match try_ready!(self.discover.poll()) {
+            Insert(k, svc) => Insert(k, SlowStart::new(svc, self.window)),
+            Remove(k) => Remove(k),
+        };
+
+        Ok(Async::Ready(change))
+    }
+}
+
+fn secs(d: Duration) -> f64 {
+    d.as_secs() as f64 + f64::from(d.subsec_nanos()) / 1_000_000_000.0
+}
+
+#[cfg(test)]
+mod tests {
+    extern crate tokio_mock_task;
+    extern crate tower_mock;
+
+    use self::tokio_mock_task::MockTask;
+    use self::tower_mock::clock::MockClock;
+    use super::*;
+    use futures::future;
+    use load::{Constant, NoInstrument, WithPendingRequests};
+    use std::sync::atomic::{AtomicBool, AtomicUsize, Ordering};
+    use std::sync::Arc;
+    use tower_discover::ServiceList;
+    use Balance;
+
+    type Error = Box<::std::error::Error + Send + Sync>;
+
+    /// Counts its calls, and never responds.
+    struct Counting {
+        calls: Arc<AtomicUsize>,
+        ready: Arc<AtomicBool>,
+    }
+
+    impl Counting {
+        fn new(ready: bool) -> (Self, Arc<AtomicUsize>, Arc<AtomicBool>) {
+            let calls = Arc::new(AtomicUsize::new(0));
+            let ready = Arc::new(AtomicBool::new(ready));
+            let svc = Counting {
+                calls: calls.clone(),
+                ready: ready.clone(),
+            };
+            (svc, calls, ready)
+        }
+    }
+
+    impl Service<()> for Counting {
+        type Response = ();
+        type Error = Error;
+        type Future = future::Empty<(), Error>;
+
+        fn poll_ready(&mut self) -> Poll<(), Self::Error> {
+            if self.ready.load(Ordering::SeqCst) {
+                Ok(Async::Ready(()))
+            } else {
+                Ok(Async::NotReady)
+            }
+        }
+
+        fn call(&mut self, _: ()) -> Self::Future {
+            self.calls.fetch_add(1, Ordering::SeqCst);
+            future::empty()
+        }
+    }
+
+    #[test]
+    fn ramps_weight_up_over_window() {
+        MockClock::new().enter(|clock| {
+            let mut svc = SlowStart::new(Constant::new((), 10.0), Duration::from_secs(10));
+            assert!(svc.load() > 100.0);
+
+            // The ramp starts once the endpoint is ready.
+            clock.advance(Duration::from_secs(5));
+            assert!(svc.load() > 100.0);
+            assert!(svc.poll_ready().unwrap().is_ready());
+
+            clock.advance(Duration::from_secs(5));
+            assert_eq!(svc.load(), 20.0);
+
+            clock.advance(Duration::from_secs(5));
+            assert_eq!(svc.load(), 10.0);
+
+            clock.advance(Duration::from_secs(5));
+            assert_eq!(svc.load(), 10.0);
+        });
+    }
+
+    #[test]
+    fn p2c_under_selects_fresh_endpoints() {
+        MockClock::new().enter(|clock| {
+            let mut task = MockTask::new();
+            let (warm, warm_calls, _) = Counting::new(true);
+            let (fresh, fresh_calls, fresh_ready) = Counting::new(false);
+            let discover =
+                WithPendingRequests::new(ServiceList::new(vec![warm, fresh]), NoInstrument);
+            let mut balance = Balance::p2c(WithSlowStart::new(discover, Duration::from_secs(10)));
+
+            // Only the warm endpoint is ready, and its ramp is over by the
+            // time the fresh one has connected.
+            assert!(task.enter(|| balance.poll_ready()).unwrap().is_ready());
+            clock.advance(Duration::from_secs(20));
+            fresh_ready.store(true, Ordering::SeqCst);
+
+            // Responses are held, so that the endpoints' loads keep growing.
+            let mut responses = Vec::new();
+            for _ in 0..30 {
+                assert!(task.enter(|| balance.poll_ready()).unwrap().is_ready());
+                responses.push(balance.call(()));
+            }
+            assert_eq!(warm_calls.load(Ordering::SeqCst), 30);
+            assert_eq!(fresh_calls.load(Ordering::SeqCst), 0);
+
+            // Once its ramp is over, the fresh endpoint catches up.
+            clock.advance(Duration::from_secs(10));
+            for _ in 0..30 {
+                assert!(task.enter(|| balance.poll_ready()).unwrap().is_ready());
+                responses.push(balance.call(()));
+            }
+            assert_eq!(warm_calls.load(Ordering::SeqCst), 30);
+            assert_eq!(fresh_calls.load(Ordering::SeqCst), 30);
+        });
+    }
+}
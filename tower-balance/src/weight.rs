@@ -0,0 +1,298 @@
+//! Weighted endpoints.
+//!
+//! Endpoints are not always alike: some run on bigger machines than others,
+//! and a new version may be rolled out to a small share of the traffic at
+//! first. Discovery expresses this by yielding `Weighted` services, which
+//! only attach a weight to each endpoint. Once the endpoints are wrapped with
+//! a load metric, which passes the weight on, `WithWeighted` scales their load
+//! by their weight, so that a load-aware balancer such as `Balance::p2c`
+//! prefers endpoints with greater weights:
+//!
+//! ```rust,ignore
+//! // Yields `Weighted<S>`, each wrapped in `PendingRequests`, then in
+//! // `WeightedLoad`.
+//! let discover = WithWeighted::new(WithPendingRequests::new(discover, NoInstrument));
+//! let balance = Balance::p2c(discover);
+//! ```
+//!
+//! An endpoint with a weight of 2 is chosen over an endpoint with a weight of
+//! 1 until it has about twice as much load. Weights bias the choice of the
+//! balancer, rather than fixing the share of requests each endpoint receives.
+
+use futures::{Async, Poll};
+use std::ops;
+use tower_discover::{Change, Discover};
+use tower_service::Service;
+
+use Load;
+
+/// The weight of an endpoint, relative to the weights of other endpoints.
+#[derive(Clone, Copy, Debug, PartialEq, PartialOrd)]
+pub struct Weight(f64);
+
+/// Endpoints whose weight is known.
+pub trait HasWeight {
+    /// Returns the weight of the endpoint.
+    fn weight(&self) -> Weight;
+}
+
+/// A service with a weight, yielded by discovery.
+#[derive(Debug)]
+pub struct Weighted<T> {
+    inner: T,
+    weight: Weight,
+}
+
+/// Scales the load of a service by its weight.
+#[derive(Debug)]
+pub struct WeightedLoad<S> {
+    inner: S,
+}
+
+/// Wraps `inner`'s services with `WeightedLoad`.
+#[derive(Debug)]
+pub struct WithWeighted<D> {
+    discover: D,
+}
+
+// ===== impl Weight =====
+
+impl Weight {
+    /// The weight of endpoints that are not weighted.
+    pub const DEFAULT: Weight = Weight(1.0);
+
+    /// Creates a new weight.
+    ///
+    /// # Panics
+    ///
+    /// This function panics if `weight` is not a positive, finite number.
+    pub fn new(weight: f64) -> Self {
+        assert!(
+            weight > 0.0 && weight.is_finite(),
+            "weight must be positive and finite"
+        );
+        Weight(weight)
+    }
+
+    /// Returns the weight as a number.
+    pub fn get(&self) -> f64 {
+        self.0
+    }
+}
+
+impl Default for Weight {
+    fn default() -> Self {
+        Weight::DEFAULT
+    }
+}
+
+impl ops::Div<Weight> for f64 {
+    type Output = f64;
+
+    fn div(self, weight: Weight) -> f64 {
+        self / weight.0
+    }
+}
+
+// ===== impl Weighted =====
+
+impl<T> Weighted<T> {
+    /// Attaches `weight` to `inner`.
+    pub fn new(inner: T, weight: Weight) -> Self {
+        Weighted { inner, weight }
+    }
+
+    /// Get a reference to the inner service
+    pub fn get_ref(&self) -> &T {
+        &self.inner
+    }
+
+    /// Consume `self`, returning the inner service
+    pub fn into_inner(self) -> T {
+        self.inner
+    }
+}
+
+impl<T> HasWeight for Weighted<T> {
+    fn weight(&self) -> Weight {
+        self.weight
+    }
+}
+
+impl<S, Request> Service<Request> for Weighted<S>
+where
+    S: Service<Request>,
+{
+    type Response = S::Response;
+    type Error = S::Error;
+    type Future = S::Future;
+
+    fn poll_ready(&mut self) -> Poll<(), Self::Error> {
+        self.inner.poll_ready()
+    }
+
+    fn call(&mut self, req: Request) -> Self::Future {
+        self.inner.call(req)
+    }
+}
+
+// ===== impl WeightedLoad =====
+
+impl<S> WeightedLoad<S> {
+    /// Scales the load of `inner` by its weight.
+    ///
+    /// Services yielded by discovery are usually wrapped with `WithWeighted`
+    /// instead.
+    pub fn new(inner: S) -> Self {
+        WeightedLoad { inner }
+    }
+
+    /// Get a reference to the inner service
+    pub fn get_ref(&self) -> &S {
+        &self.inner
+    }
+
+    /// Consume `self`, returning the inner service
+    pub fn into_inner(self) -> S {
+        self.inner
+    }
+}
+
+impl<S> Load for WeightedLoad<S>
+where
+    S: Load + HasWeight,
+    S::Metric: ops::Div<Weight>,
+{
+    type Metric = <S::Metric as ops::Div<Weight>>::Output;
+
+    fn load(&self) -> Self::Metric {
+        self.inner.load() / self.inner.weight()
+    }
+}
+
+impl<S: HasWeight> HasWeight for WeightedLoad<S> {
+    fn weight(&self) -> Weight {
+        self.inner.weight()
+    }
+}
+
+impl<S, Request> Service<Request> for WeightedLoad<S>
+where
+    S: Service<Request>,
+{
+    type Response = S::Response;
+    type Error = S::Error;
+    type Future = S::Future;
+
+    fn poll_ready(&mut self) -> Poll<(), Self::Error> {
+        self.inner.poll_ready()
+    }
+
+    fn call(&mut self, req: Request) -> Self::Future {
+        self.inner.call(req)
+    }
+}
+
+// ===== impl WithWeighted =====
+
+impl<D> WithWeighted<D>
+where
+    D: Discover,
+    D::Service: HasWeight,
+{
+    /// Wraps `discover`'s services with `WeightedLoad`.
+    pub fn new(discover: D) -> Self {
+        WithWeighted { discover }
+    }
+}
+
+impl<D> Discover for WithWeighted<D>
+where
+    D: Discover,
+    D::Service: HasWeight,
+{
+    type Key = D::Key;
+    type Service = WeightedLoad<D::Service>;
+    type Error = D::Error;
+
+    /// Yields the next discovery change set.
+    fn poll(&mut self) -> Poll<Change<D::Key, Self::Service>, D::Error> {
+        use self::Change::*;
+
+        let change = match try_ready!(self.discover.poll()) {
+            Insert(k, svc) => Insert(k, WeightedLoad::new(svc)),
+            Remove(k) => Remove(k),
+        };
+
+        Ok(Async::Ready(change))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    extern crate tokio_mock_task;
+
+    use self::tokio_mock_task::MockTask;
+    use super::*;
+    use futures::future;
+    use load::{Constant, NoInstrument, WithPendingRequests};
+    use std::sync::atomic::{AtomicUsize, Ordering};
+    use std::sync::Arc;
+    use tower_discover::ServiceList;
+    use Balance;
+
+    type Error = Box<::std::error::Error + Send + Sync>;
+
+    /// Counts its calls, and never responds.
+    struct Counting(Arc<AtomicUsize>);
+
+    impl Service<()> for Counting {
+        type Response = ();
+        type Error = Error;
+        type Future = future::Empty<(), Error>;
+
+        fn poll_ready(&mut self) -> Poll<(), Self::Error> {
+            Ok(Async::Ready(()))
+        }
+
+        fn call(&mut self, _: ()) -> Self::Future {
+            self.0.fetch_add(1, Ordering::SeqCst);
+            future::empty()
+        }
+    }
+
+    #[test]
+    fn greater_weights_scale_load_down() {
+        let light = WeightedLoad::new(Constant::new(Weighted::new((), Weight::new(2.0)), 10.0));
+        let heavy = WeightedLoad::new(Constant::new(Weighted::new((), Weight::DEFAULT), 10.0));
+
+        assert_eq!(light.load(), 5.0);
+        assert_eq!(heavy.load(), 10.0);
+    }
+
+    #[test]
+    fn p2c_prefers_greater_weights() {
+        let mut task = MockTask::new();
+        let heavy = Arc::new(AtomicUsize::new(0));
+        let light = Arc::new(AtomicUsize::new(0));
+        let services = vec![
+            Weighted::new(Counting(heavy.clone()), Weight::new(2.0)),
+            Weighted::new(Counting(light.clone()), Weight::DEFAULT),
+        ];
+        let discover = WithPendingRequests::new(ServiceList::new(services), NoInstrument);
+        let mut balance = Balance::p2c(WithWeighted::new(discover));
+
+        // Responses are held, so that the endpoints' loads keep growing.
+        let mut responses = Vec::new();
+        for _ in 0..30 {
+            assert!(task.enter(|| balance.poll_ready()).unwrap().is_ready());
+            responses.push(balance.call(()));
+        }
+
+        // With both endpoints to choose from, the heavier one is chosen
+        // until it has about twice as many requests.
+        let heavy = heavy.load(Ordering::SeqCst);
+        assert!(heavy >= 19 && heavy <= 21, "heavier endpoint got {}", heavy);
+        assert_eq!(light.load(Ordering::SeqCst), 30 - heavy);
+    }
+}
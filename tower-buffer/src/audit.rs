@@ -0,0 +1,60 @@
+//! Auditing the number of requests held by a buffer.
+//!
+//! With the `audit-bounds` feature enabled, the depth of the queue is checked
+//! every time a message is sent, and exceeding the buffer's bound panics, as
+//! does building an unbounded buffer. This allows tests to certify that a
+//! stack stays within bounded memory under overload. Without the feature,
+//! auditing compiles down to nothing.
+
+pub(crate) use self::imp::Audit;
+
+#[cfg(feature = "audit-bounds")]
+mod imp {
+    /// Checks the number of messages held by a buffer and all of its clones.
+    #[derive(Clone, Debug)]
+    pub(crate) struct Audit {
+        max: usize,
+    }
+
+    impl Audit {
+        pub(crate) fn new(bound: usize) -> Self {
+            Audit {
+                // The worker may hold one message it took out of the channel
+                // while waiting for the inner service to become ready.
+                max: bound + 1,
+            }
+        }
+
+        pub(crate) fn unbounded() -> Self {
+            panic!("unbounded buffer built with the `audit-bounds` feature enabled");
+        }
+
+        /// Checks the depth of the queue once a message made it in.
+        pub(crate) fn check(&self, held: usize) {
+            if held > self.max {
+                panic!(
+                    "buffer holds {} messages, exceeding its bound of {}",
+                    held, self.max
+                );
+            }
+        }
+    }
+}
+
+#[cfg(not(feature = "audit-bounds"))]
+mod imp {
+    #[derive(Clone, Debug)]
+    pub(crate) struct Audit;
+
+    impl Audit {
+        pub(crate) fn new(_: usize) -> Self {
+            Audit
+        }
+
+        pub(crate) fn unbounded() -> Self {
+            Audit
+        }
+
+        pub(crate) fn check(&self, _: usize) {}
+    }
+}
@@ -0,0 +1,107 @@
+//! The channel carrying messages from `Buffer` handles to the worker, which
+//! is either bounded or unbounded.
+
+use futures::{Async, Poll, Stream};
+use tokio_sync::mpsc;
+
+pub(crate) enum Sender<T> {
+    Bounded(mpsc::Sender<T>),
+    Unbounded(mpsc::UnboundedSender<T>),
+}
+
+pub(crate) enum Receiver<T> {
+    Bounded(mpsc::Receiver<T>),
+    Unbounded(mpsc::UnboundedReceiver<T>),
+}
+
+/// The channel is closed.
+pub(crate) struct Closed;
+
+/// A message could not be sent, either because the channel is full or
+/// because it is closed.
+pub(crate) struct TrySendError<T> {
+    value: T,
+    closed: bool,
+}
+
+pub(crate) fn bounded<T>(bound: usize) -> (Sender<T>, Receiver<T>) {
+    let (tx, rx) = mpsc::channel(bound);
+    (Sender::Bounded(tx), Receiver::Bounded(rx))
+}
+
+pub(crate) fn unbounded<T>() -> (Sender<T>, Receiver<T>) {
+    let (tx, rx) = mpsc::unbounded_channel();
+    (Sender::Unbounded(tx), Receiver::Unbounded(rx))
+}
+
+// ===== impl Sender =====
+
+impl<T> Sender<T> {
+    /// Reserves a slot for the next message.
+    ///
+    /// An unbounded channel is always ready. Whether it is closed only shows
+    /// once a message is sent.
+    pub(crate) fn poll_ready(&mut self) -> Poll<(), Closed> {
+        match *self {
+            Sender::Bounded(ref mut tx) => tx.poll_ready().map_err(|_| Closed),
+            Sender::Unbounded(_) => Ok(Async::Ready(())),
+        }
+    }
+
+    pub(crate) fn try_send(&mut self, value: T) -> Result<(), TrySendError<T>> {
+        match *self {
+            Sender::Bounded(ref mut tx) => tx.try_send(value).map_err(|e| TrySendError {
+                closed: e.is_closed(),
+                value: e.into_inner(),
+            }),
+            Sender::Unbounded(ref mut tx) => tx.try_send(value).map_err(|e| TrySendError {
+                closed: true,
+                value: e.into_inner(),
+            }),
+        }
+    }
+}
+
+impl<T> Clone for Sender<T> {
+    fn clone(&self) -> Self {
+        match *self {
+            Sender::Bounded(ref tx) => Sender::Bounded(tx.clone()),
+            Sender::Unbounded(ref tx) => Sender::Unbounded(tx.clone()),
+        }
+    }
+}
+
+// ===== impl Receiver =====
+
+impl<T> Receiver<T> {
+    pub(crate) fn close(&mut self) {
+        match *self {
+            Receiver::Bounded(ref mut rx) => rx.close(),
+            Receiver::Unbounded(ref mut rx) => rx.close(),
+        }
+    }
+}
+
+impl<T> Stream for Receiver<T> {
+    type Item = T;
+    type Error = ();
+
+    fn poll(&mut self) -> Poll<Option<T>, ()> {
+        match *self {
+            Receiver::Bounded(ref mut rx) => rx.poll().map_err(|_| ()),
+            Receiver::Unbounded(ref mut rx) => rx.poll().map_err(|_| ()),
+        }
+    }
+}
+
+// ===== impl TrySendError =====
+
+impl<T> TrySendError<T> {
+    pub(crate) fn is_closed(&self) -> bool {
+        self.closed
+    }
+
+    pub(crate) fn into_inner(self) -> T {
+        self.value
+    }
+}
@@ -0,0 +1,112 @@
+use std::cmp;
+use std::sync::atomic::{AtomicIsize, AtomicUsize, Ordering};
+use std::sync::Arc;
+
+/// Reports how many requests are waiting in a `Buffer`.
+///
+/// A request is counted from the moment it is accepted by the buffer until
+/// the worker dispatches it to the inner service, or discards it because its
+/// caller went away. The handle is shared by all clones of the buffer, and may
+/// be polled from anywhere, e.g. by a metrics reporter, so that capacity
+/// issues show up before requests start waiting on a full buffer.
+#[derive(Clone, Debug)]
+pub struct QueueDepth {
+    inner: Arc<Inner>,
+}
+
+#[derive(Debug)]
+struct Inner {
+    /// May drop below zero for a moment, as the worker may give up the count
+    /// of a message before its sender counts it.
+    current: AtomicIsize,
+    high_watermark: AtomicUsize,
+}
+
+/// Held by a message while it counts towards the depth of the queue.
+#[derive(Debug)]
+pub(crate) struct Token {
+    inner: Option<Arc<Inner>>,
+}
+
+impl QueueDepth {
+    pub(crate) fn new() -> Self {
+        QueueDepth {
+            inner: Arc::new(Inner {
+                current: AtomicIsize::new(0),
+                high_watermark: AtomicUsize::new(0),
+            }),
+        }
+    }
+
+    /// Returns the number of requests currently waiting in the buffer.
+    pub fn current(&self) -> usize {
+        cmp::max(0, self.inner.current.load(Ordering::SeqCst)) as usize
+    }
+
+    /// Returns the largest number of requests that waited in the buffer at
+    /// once since it was created, or since the high watermark was last reset.
+    pub fn high_watermark(&self) -> usize {
+        self.inner.high_watermark.load(Ordering::SeqCst)
+    }
+
+    /// Resets the high watermark to the current depth, returning its previous
+    /// value, e.g. to report the high watermark of each reporting interval.
+    pub fn reset_high_watermark(&self) -> usize {
+        // A request sent in between raises the new high watermark itself, so
+        // that no depth is lost between intervals.
+        let high = self.inner.high_watermark.swap(0, Ordering::SeqCst);
+        self.raise_high_watermark(self.current());
+        high
+    }
+
+    /// Returns the token of a message about to be sent, which only counts
+    /// once the message is `sent`.
+    pub(crate) fn token(&self) -> Token {
+        Token {
+            inner: Some(self.inner.clone()),
+        }
+    }
+
+    /// Counts a message that made it into the buffer, returning the depth of
+    /// the queue.
+    ///
+    /// The worker may have dropped the message, and its token, already. The
+    /// count then lags behind the messages held for a moment, but never
+    /// exceeds it.
+    pub(crate) fn sent(&self) -> usize {
+        let depth = self.inner.current.fetch_add(1, Ordering::SeqCst) + 1;
+        let depth = cmp::max(0, depth) as usize;
+        self.raise_high_watermark(depth);
+        depth
+    }
+
+    fn raise_high_watermark(&self, depth: usize) {
+        let mut high = self.inner.high_watermark.load(Ordering::SeqCst);
+        while depth > high {
+            match self.inner.high_watermark.compare_exchange_weak(
+                high,
+                depth,
+                Ordering::SeqCst,
+                Ordering::SeqCst,
+            ) {
+                Ok(_) => break,
+                Err(actual) => high = actual,
+            }
+        }
+    }
+}
+
+impl Token {
+    /// Gives up the token of a message that never made it into the buffer.
+    pub(crate) fn cancel(mut self) {
+        self.inner = None;
+    }
+}
+
+impl Drop for Token {
+    fn drop(&mut self) {
+        if let Some(ref inner) = self.inner {
+            inner.current.fetch_sub(1, Ordering::SeqCst);
+        }
+    }
+}
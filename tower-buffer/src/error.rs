@@ -15,6 +15,24 @@ pub struct Closed {
     _p: (),
 }
 
+/// An error when the service wrapped by a `Buffer` panicked, taking the
+/// buffer's worker down with it.
+///
+/// Like the errors of the service, it is reported as the `source` of a
+/// `ServiceError` to all subsequent calls.
+#[derive(Debug)]
+pub struct Panicked {
+    message: Option<String>,
+}
+
+/// An error when a request does not fit in the buffer, either because the
+/// buffer fails fast, or because it only has room in a priority lane that the
+/// request does not qualify for.
+#[derive(Debug)]
+pub struct Full {
+    _p: (),
+}
+
 /// Error produced when spawning the worker fails
 #[derive(Debug)]
 pub struct SpawnError {
@@ -68,6 +86,50 @@ impl fmt::Display for Closed {
 
 impl std::error::Error for Closed {}
 
+// ===== impl Panicked =====
+
+impl Panicked {
+    pub(crate) fn new(payload: &(::std::any::Any + Send)) -> Self {
+        let message = payload
+            .downcast_ref::<&'static str>()
+            .map(|s| s.to_string())
+            .or_else(|| payload.downcast_ref::<String>().cloned());
+        Panicked { message }
+    }
+
+    /// Returns the message the service panicked with, if it was a string.
+    pub fn message(&self) -> Option<&str> {
+        self.message.as_ref().map(|s| s.as_str())
+    }
+}
+
+impl fmt::Display for Panicked {
+    fn fmt(&self, fmt: &mut fmt::Formatter) -> fmt::Result {
+        match self.message {
+            Some(ref message) => write!(fmt, "service panicked: {}", message),
+            None => fmt.write_str("service panicked"),
+        }
+    }
+}
+
+impl std::error::Error for Panicked {}
+
+// ===== impl Full =====
+
+impl Full {
+    pub(crate) fn new() -> Self {
+        Full { _p: () }
+    }
+}
+
+impl fmt::Display for Full {
+    fn fmt(&self, fmt: &mut fmt::Formatter) -> fmt::Result {
+        fmt.write_str("buffer full")
+    }
+}
+
+impl std::error::Error for Full {}
+
 // ===== impl SpawnError =====
 
 impl SpawnError {
@@ -1,8 +1,10 @@
 //! Future types
 
-use error::{Closed, Error};
+use error::Error;
 use futures::{Async, Future, Poll};
 use message;
+use std::fmt;
+use worker;
 
 /// Future eventually completed with the response to the original request.
 pub struct ResponseFuture<T> {
@@ -11,7 +13,7 @@ pub struct ResponseFuture<T> {
 
 enum ResponseState<T> {
     Failed(Option<Error>),
-    Rx(message::Rx<T>),
+    Rx(message::Rx<T>, worker::Handle),
     Poll(T),
 }
 
@@ -20,9 +22,9 @@ where
     T: Future,
     T::Error: Into<Error>,
 {
-    pub(crate) fn new(rx: message::Rx<T>) -> Self {
+    pub(crate) fn new(rx: message::Rx<T>, worker: worker::Handle) -> Self {
         ResponseFuture {
-            state: ResponseState::Rx(rx),
+            state: ResponseState::Rx(rx, worker),
         }
     }
 
@@ -51,11 +53,13 @@ where
                 Failed(ref mut e) => {
                     return Err(e.take().expect("polled after error"));
                 }
-                Rx(ref mut rx) => match rx.poll() {
+                Rx(ref mut rx, ref worker) => match rx.poll() {
                     Ok(Async::Ready(Ok(f))) => fut = f,
                     Ok(Async::Ready(Err(e))) => return Err(e.into()),
                     Ok(Async::NotReady) => return Ok(Async::NotReady),
-                    Err(_) => return Err(Closed::new().into()),
+                    // The worker went away without responding, e.g. because
+                    // the service panicked.
+                    Err(_) => return Err(worker.get_error_on_closed()),
                 },
                 Poll(ref mut fut) => {
                     return fut.poll().map_err(Into::into);
@@ -66,3 +70,31 @@ where
         }
     }
 }
+
+/// Future completed once a `Buffer` has drained.
+///
+/// See [`Buffer::drain`](../struct.Buffer.html#method.drain).
+pub struct Drain {
+    worker: worker::Handle,
+}
+
+impl Drain {
+    pub(crate) fn new(worker: worker::Handle) -> Self {
+        Drain { worker }
+    }
+}
+
+impl Future for Drain {
+    type Item = ();
+    type Error = ();
+
+    fn poll(&mut self) -> Poll<(), ()> {
+        Ok(self.worker.poll_drained())
+    }
+}
+
+impl fmt::Debug for Drain {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str("Drain")
+    }
+}
@@ -4,6 +4,83 @@
 //! out of the buffer and dispatching them to the inner service. By adding a
 //! buffer and a dedicated task, the `Buffer` layer in front of the service can
 //! be `Clone` even if the inner service is not.
+//!
+//! # Unbounded buffers
+//!
+//! A buffer created with `Buffer::unbounded` queues any number of requests:
+//! it is always ready, and memory is the only limit. This suits
+//! fire-and-forget pipelines whose producers are known to be slower than the
+//! service, but it gives up backpressure entirely. If the service falls
+//! behind, requests pile up without bound, and latency grows with the queue.
+//! Prefer a bounded buffer unless the rate of requests is limited elsewhere.
+//!
+//! # Queue depth
+//!
+//! `Buffer::queue_depth` returns a handle reporting how many requests are
+//! waiting in the buffer, and the most that ever did at once, so that a
+//! buffer filling up can be noticed before callers start waiting on it.
+//!
+//! # Failures
+//!
+//! If the inner service fails, or panics, the worker stops and the buffer is
+//! closed. The cause is kept, and every subsequent call, including those
+//! already waiting in the buffer, fails with an `error::ServiceError` whose
+//! `source` is the service's error, or `error::Panicked`.
+//!
+//! # Cancellation
+//!
+//! Dropping a `ResponseFuture` cancels its request. If the request is still
+//! waiting in the buffer, the worker skips it rather than dispatching it to
+//! the inner service, so abandoned requests do not use up its capacity.
+//! Requests already dispatched are not affected.
+//!
+//! # Express lane
+//!
+//! A buffer created with `Buffer::with_express_lane` has a second, small
+//! queue for urgent requests, such as health checks and admin probes.
+//! `Buffer::express_lane` returns a handle sending its requests to that
+//! queue. The handle is ready whenever the express lane has room, however
+//! full the main queue is, and the worker dispatches requests in the express
+//! lane before any request waiting in the main queue, so that probes measure
+//! the health of the service rather than the depth of the queue.
+//!
+//! # Priorities
+//!
+//! `Buffer::with_priority` generalizes the express lane to any number of
+//! priority levels. Every level has a queue of its own, and
+//! `Buffer::priority` returns a handle sending requests to the queue of a
+//! given level, which is ready whenever that queue has room. The worker
+//! always dispatches the most urgent request waiting. Priority `0` is the
+//! main queue.
+//!
+//! # Failing fast
+//!
+//! A buffer configured with `Buffer::fail_fast` never applies backpressure.
+//! It always reports itself ready, and requests that do not fit fail right
+//! away with `error::Full`, as load-shedding proxies expect.
+//!
+//! # Draining
+//!
+//! `Buffer::drain` stops a buffer from accepting requests and returns a
+//! future completed once the worker has dispatched the requests already
+//! queued, e.g. to shut a server down gracefully.
+//!
+//! # Unready reasons
+//!
+//! A full buffer reports `UnreadyReason::QueueFull` from `unready_reason`,
+//! and one being drained reports `UnreadyReason::Draining`. The buffer is
+//! usually full because the service is not ready, though. With
+//! `Buffer::forward_unready`, the worker records why the service is holding
+//! it back, and the buffer reports that reason instead, e.g.
+//! `UnreadyReason::InFlightLimit`.
+//!
+//! # Auditing bounds
+//!
+//! With the `audit-bounds` feature enabled, a `Buffer` panics if it ever holds
+//! more requests than its bound allows (plus the one request the worker may
+//! be waiting to dispatch), and building an unbounded `Buffer` panics right
+//! away. This is intended for tests that certify a stack uses bounded memory
+//! under overload.
 
 #[macro_use]
 extern crate futures;
@@ -11,25 +88,33 @@ extern crate tokio_executor;
 extern crate tokio_sync;
 extern crate tower_layer;
 extern crate tower_service;
+extern crate tower_util;
 
+mod audit;
+mod channel;
+mod depth;
 pub mod error;
 pub mod future;
 mod message;
 mod worker;
 
+pub use depth::QueueDepth;
 pub use worker::WorkerExecutor;
 
-use error::Error;
-use future::ResponseFuture;
+use audit::Audit;
+use error::{Error, Full};
+use future::{Drain, ResponseFuture};
 use message::Message;
-use worker::Worker;
+use worker::{Probe, Worker};
 
-use futures::Poll;
+use futures::{Async, Poll};
+use std::cmp;
+use std::sync::Arc;
 use tokio_executor::DefaultExecutor;
-use tokio_sync::mpsc;
 use tokio_sync::oneshot;
 use tower_layer::Layer;
 use tower_service::Service;
+use tower_util::{PollReadyN, Unready, UnreadyReason};
 
 /// Adds a buffer in front of an inner service.
 ///
@@ -38,33 +123,65 @@ pub struct Buffer<T, Request>
 where
     T: Service<Request>,
 {
-    tx: mpsc::Sender<Message<Request, T::Future>>,
+    tx: channel::Sender<Message<Request, T::Future>>,
+    /// Senders that have each reserved a slot via `poll_ready_n`.
+    reserved: Vec<channel::Sender<Message<Request, T::Future>>>,
+    /// Senders of all the queues of the buffer, from the main queue to the
+    /// most urgent priority lane, from which handles for each are made.
+    queues: Arc<Vec<channel::Sender<Message<Request, T::Future>>>>,
     worker: worker::Handle,
+    audit: Audit,
+    depth: QueueDepth,
+    /// Whether requests fail with `Full` rather than wait for room.
+    fail_fast: bool,
+    probe: Probe<T>,
 }
 
 /// Buffer requests with a bounded buffer
 pub struct BufferLayer<E = DefaultExecutor> {
     bound: usize,
     executor: E,
+    fail_fast: bool,
 }
 
 impl BufferLayer<DefaultExecutor> {
+    /// Creates a layer spawning the worker of each buffer onto the default
+    /// executor of the context the service is built in.
+    ///
+    /// See [`Buffer::new`](struct.Buffer.html#method.new).
     pub fn new(bound: usize) -> Self {
         BufferLayer {
             bound,
             executor: DefaultExecutor::current(),
+            fail_fast: false,
         }
     }
 }
 
 impl<E> BufferLayer<E> {
+    /// Creates a layer spawning the worker of each buffer onto `executor`.
+    ///
+    /// See [`Buffer::with_executor`](struct.Buffer.html#method.with_executor).
     pub fn with_executor<S, Request>(bound: usize, executor: E) -> Self
     where
         S: Service<Request>,
         S::Error: Into<Error>,
         E: WorkerExecutor<S, Request> + Clone,
     {
-        BufferLayer { bound, executor }
+        BufferLayer {
+            bound,
+            executor,
+            fail_fast: false,
+        }
+    }
+
+    /// Fail requests with `error::Full` when the buffer is full, rather than
+    /// waiting for room.
+    ///
+    /// See [`Buffer::fail_fast`](struct.Buffer.html#method.fail_fast).
+    pub fn fail_fast(mut self) -> Self {
+        self.fail_fast = true;
+        self
     }
 }
 
@@ -80,7 +197,12 @@ where
     type Service = Buffer<S, Request>;
 
     fn layer(&self, service: S) -> Result<Self::Service, Self::LayerError> {
-        Buffer::with_executor(service, self.bound, &mut self.executor.clone())
+        let buffer = Buffer::with_executor(service, self.bound, &mut self.executor.clone())?;
+        if self.fail_fast {
+            Ok(buffer.fail_fast())
+        } else {
+            Ok(buffer)
+        }
     }
 }
 
@@ -95,7 +217,8 @@ where
     /// backpressure is applied to callers.
     ///
     /// The default Tokio executor is used to run the given service, which means that this method
-    /// must be called while on the Tokio runtime.
+    /// must be called while on the Tokio runtime. Use `with_executor` to spawn the worker onto
+    /// another executor, e.g. on other runtimes or in tests.
     pub fn new(service: T, bound: usize) -> Result<Self, Error>
     where
         T: Send + 'static,
@@ -110,7 +233,8 @@ where
     ///
     /// `executor` is used to spawn a new `Worker` task that is dedicated to
     /// draining the buffer and dispatching the requests to the internal
-    /// service.
+    /// service. Any `TypedExecutor` of the worker will do, so the worker may
+    /// run on runtimes other than Tokio's, or be driven by hand in tests.
     ///
     /// `bound` gives the maximal number of requests that can be queued for the service before
     /// backpressure is applied to callers.
@@ -118,9 +242,227 @@ where
     where
         E: WorkerExecutor<T, Request>,
     {
-        let (tx, rx) = mpsc::channel(bound);
+        let (tx, rx) = channel::bounded(bound);
 
-        Worker::spawn(service, rx, executor).map(|worker| Buffer { tx, worker })
+        let probe = Probe::default();
+        Worker::spawn(service, rx, Vec::new(), probe.clone(), executor).map(|worker| Buffer {
+            queues: Arc::new(vec![tx.clone()]),
+            tx,
+            reserved: Vec::new(),
+            worker,
+            audit: Audit::new(bound),
+            depth: QueueDepth::new(),
+            fail_fast: false,
+            probe,
+        })
+    }
+
+    /// Creates a new `Buffer` wrapping `service`, queueing any number of
+    /// requests.
+    ///
+    /// The buffer is always ready, so callers never wait, and requests are
+    /// queued until memory runs out. See the crate level documentation for
+    /// the trade-offs.
+    ///
+    /// The default Tokio executor is used to run the given service, which means that this method
+    /// must be called while on the Tokio runtime.
+    ///
+    /// # Panics
+    ///
+    /// This function panics if the `audit-bounds` feature is enabled.
+    pub fn unbounded(service: T) -> Result<Self, Error>
+    where
+        T: Send + 'static,
+        T::Future: Send,
+        T::Error: Send + Sync,
+        Request: Send + 'static,
+    {
+        Self::unbounded_with_executor(service, &mut DefaultExecutor::current())
+    }
+
+    /// Creates a new `Buffer` wrapping `service`, queueing any number of
+    /// requests, with its worker spawned onto `executor`.
+    ///
+    /// See [`Buffer::unbounded`](#method.unbounded).
+    ///
+    /// # Panics
+    ///
+    /// This function panics if the `audit-bounds` feature is enabled.
+    pub fn unbounded_with_executor<E>(service: T, executor: &mut E) -> Result<Self, Error>
+    where
+        E: WorkerExecutor<T, Request>,
+    {
+        let audit = Audit::unbounded();
+        let (tx, rx) = channel::unbounded();
+
+        let probe = Probe::default();
+        Worker::spawn(service, rx, Vec::new(), probe.clone(), executor).map(|worker| Buffer {
+            queues: Arc::new(vec![tx.clone()]),
+            tx,
+            reserved: Vec::new(),
+            worker,
+            audit,
+            depth: QueueDepth::new(),
+            fail_fast: false,
+            probe,
+        })
+    }
+
+    /// Returns a handle reporting how many requests are waiting in the
+    /// buffer, shared by all of its clones.
+    pub fn queue_depth(&self) -> QueueDepth {
+        self.depth.clone()
+    }
+
+    /// Stops the buffer, and all of its clones, from accepting requests,
+    /// returning a future completed once the buffer has drained.
+    ///
+    /// Requests already in the buffer are still dispatched to the service,
+    /// and their responses complete as usual. Later calls fail with
+    /// `error::Closed`. The returned future completes once the worker has
+    /// dispatched every request it accepted, or has stopped for any other
+    /// reason, e.g. because the service failed.
+    ///
+    /// This is useful to shut a server down without dropping requests on the
+    /// floor.
+    pub fn drain(&self) -> Drain {
+        self.worker.close();
+        Drain::new(self.worker.clone())
+    }
+
+    /// Fail requests with `error::Full` when the buffer is full, rather than
+    /// waiting for room.
+    ///
+    /// The buffer then always reports itself ready, and `call` fails right
+    /// away if the request does not fit. This suits load-shedding proxies,
+    /// which prefer rejecting requests to queueing their callers.
+    pub fn fail_fast(mut self) -> Self {
+        self.fail_fast = true;
+        self
+    }
+
+    /// Report why the service is not ready from `unready_reason`, rather
+    /// than just that the buffer is full.
+    ///
+    /// This applies to all clones of the buffer. See the crate level
+    /// documentation for more details.
+    pub fn forward_unready(self) -> Self
+    where
+        T: Unready<Request>,
+    {
+        *self.probe.lock().expect("buffer unready probe") =
+            Some(<T as Unready<Request>>::unready_reason);
+        self
+    }
+
+    /// Creates a new `Buffer` wrapping `service`, with an express lane.
+    ///
+    /// The returned handle sends requests to the main queue, and
+    /// `express_lane` returns handles sending requests to the express lane.
+    /// `capacity` gives the maximal number of requests that can be queued in
+    /// the express lane, in addition to the `bound` requests of the main
+    /// queue. See the crate level documentation for more details.
+    pub fn with_express_lane<E>(
+        service: T,
+        bound: usize,
+        capacity: usize,
+        executor: &mut E,
+    ) -> Result<Self, Error>
+    where
+        E: WorkerExecutor<T, Request>,
+    {
+        let (tx, rx) = channel::bounded(bound);
+        let (express_tx, express_rx) = channel::bounded(capacity);
+
+        let probe = Probe::default();
+        Worker::spawn(service, rx, vec![express_rx], probe.clone(), executor).map(|worker| Buffer {
+            queues: Arc::new(vec![tx.clone(), express_tx]),
+            tx,
+            reserved: Vec::new(),
+            worker,
+            audit: Audit::new(bound + capacity),
+            depth: QueueDepth::new(),
+            fail_fast: false,
+            probe,
+        })
+    }
+
+    /// Creates a new `Buffer` wrapping `service`, with `levels` priority
+    /// levels.
+    ///
+    /// Requests of each of the priorities, from `0` to `levels - 1`, are
+    /// queued separately, with room for `bound` requests each, and requests
+    /// of a higher priority are dispatched first. The returned handle sends
+    /// requests to the main queue, of priority `0`, and `priority` returns
+    /// handles sending requests to the other levels. See the crate level
+    /// documentation for more details.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `levels` is 0.
+    pub fn with_priority<E>(
+        service: T,
+        bound: usize,
+        levels: usize,
+        executor: &mut E,
+    ) -> Result<Self, Error>
+    where
+        E: WorkerExecutor<T, Request>,
+    {
+        assert!(levels > 0, "a buffer needs at least one priority level");
+
+        let (txs, mut rxs): (Vec<_>, Vec<_>) = (0..levels).map(|_| channel::bounded(bound)).unzip();
+        let rx = rxs.remove(0);
+
+        let probe = Probe::default();
+        Worker::spawn(service, rx, rxs, probe.clone(), executor).map(|worker| Buffer {
+            tx: txs[0].clone(),
+            queues: Arc::new(txs),
+            reserved: Vec::new(),
+            worker,
+            audit: Audit::new(bound * levels),
+            depth: QueueDepth::new(),
+            fail_fast: false,
+            probe,
+        })
+    }
+
+    /// Returns a handle to the buffer sending requests to its express lane.
+    ///
+    /// The handle, and its clones, are ready whenever the express lane has
+    /// room, regardless of the main queue, and their requests are dispatched
+    /// before any request waiting in the main queue.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the buffer has no express lane, nor any priority lane.
+    pub fn express_lane(&self) -> Self {
+        assert!(self.queues.len() > 1, "buffer has no express lane");
+        self.priority(1)
+    }
+
+    /// Returns a handle to the buffer sending requests to the queue of
+    /// priority `level`.
+    ///
+    /// The handle, and its clones, are ready whenever that queue has room,
+    /// regardless of the other queues. Levels above those the buffer was
+    /// created with are treated as the most urgent one.
+    pub fn priority(&self, level: usize) -> Self {
+        let level = cmp::min(level, self.queues.len() - 1);
+        Buffer {
+            tx: self.queues[level].clone(),
+            ..self.clone()
+        }
+    }
+
+    /// Fails once the buffer has stopped accepting requests.
+    fn check_open(&self) -> Result<(), Error> {
+        // The worker may not have closed its queues yet.
+        if self.worker.is_closing() {
+            return Err(self.worker.get_error_on_closed());
+        }
+
+        Ok(())
     }
 }
 
@@ -134,6 +476,14 @@ where
     type Future = ResponseFuture<T::Future>;
 
     fn poll_ready(&mut self) -> Poll<(), Self::Error> {
+        self.check_open()?;
+
+        // A request that does not fit fails in `call` instead, so no room is
+        // reserved, and the task is not woken up when room is made.
+        if self.fail_fast {
+            return Ok(Async::Ready(()));
+        }
+
         // If the inner service has errored, then we error here.
         self.tx
             .poll_ready()
@@ -145,12 +495,36 @@ where
         // ideally we'd poll_ready again here so we don't allocate the oneshot
         // if the try_send is about to fail, but sadly we can't call poll_ready
         // outside of task context.
+        if self.worker.is_closing() {
+            return ResponseFuture::failed(self.worker.get_error_on_closed());
+        }
+
         let (tx, rx) = oneshot::channel();
+        let message = Message {
+            request,
+            tx,
+            token: self.depth.token(),
+        };
+
+        // Slots reserved by `poll_ready_n` are used before the slot reserved
+        // by `poll_ready`.
+        let sent = match self.reserved.pop() {
+            Some(mut reserved) => reserved.try_send(message),
+            None => self.tx.try_send(message),
+        };
 
-        match self.tx.try_send(Message { request, tx }) {
+        match sent {
             Err(e) => {
-                if e.is_closed() {
+                let closed = e.is_closed();
+                // The message never made it into the buffer.
+                e.into_inner().token.cancel();
+
+                if closed {
                     ResponseFuture::failed(self.worker.get_error_on_closed())
+                } else if self.fail_fast {
+                    // `poll_ready` reported ready regardless of room, as the
+                    // buffer fails fast.
+                    ResponseFuture::failed(Full::new().into())
                 } else {
                     // When `mpsc::Sender::poll_ready` returns `Ready`, a slot
                     // in the channel is reserved for the handle. Other `Sender`
@@ -162,8 +536,64 @@ where
                     panic!("buffer full; poll_ready must be called first");
                 }
             }
-            Ok(_) => ResponseFuture::new(rx),
+            Ok(_) => {
+                self.audit.check(self.depth.sent());
+                ResponseFuture::new(rx, self.worker.clone())
+            }
+        }
+    }
+}
+
+impl<T, Request> PollReadyN<Request> for Buffer<T, Request>
+where
+    T: Service<Request>,
+    T::Error: Into<Error>,
+{
+    fn poll_ready_n(&mut self, n: usize) -> Poll<usize, Self::Error> {
+        assert!(n > 0, "must reserve at least one call");
+        self.check_open()?;
+
+        // As with `poll_ready`, no room is reserved when failing fast.
+        if self.fail_fast {
+            return Ok(Async::Ready(n));
+        }
+
+        try_ready!(self
+            .tx
+            .poll_ready()
+            .map_err(|_| self.worker.get_error_on_closed()));
+
+        // Each `mpsc::Sender` reserves at most one slot, so additional slots
+        // are reserved by cloning the sender. Stop as soon as the channel is
+        // out of capacity rather than waiting for the whole batch.
+        while self.reserved.len() + 1 < n {
+            let mut tx = self.tx.clone();
+            match tx.poll_ready() {
+                Ok(Async::Ready(())) => self.reserved.push(tx),
+                Ok(Async::NotReady) => break,
+                Err(_) => return Err(self.worker.get_error_on_closed()),
+            }
+        }
+
+        Ok(Async::Ready(cmp::min(n, self.reserved.len() + 1)))
+    }
+}
+
+impl<T, Request> Unready<Request> for Buffer<T, Request>
+where
+    T: Service<Request>,
+    T::Error: Into<Error>,
+{
+    fn unready_reason(&self) -> Option<UnreadyReason> {
+        if self.worker.is_closing() {
+            return Some(UnreadyReason::Draining);
         }
+
+        // The buffer is only ever not ready when its channel is full, which
+        // the worker may know the cause of.
+        self.worker
+            .unready_reason()
+            .or(Some(UnreadyReason::QueueFull))
     }
 }
 
@@ -174,7 +604,14 @@ where
     fn clone(&self) -> Self {
         Self {
             tx: self.tx.clone(),
+            // Reservations belong to the handle that made them.
+            reserved: Vec::new(),
+            queues: self.queues.clone(),
             worker: self.worker.clone(),
+            audit: self.audit.clone(),
+            depth: self.depth.clone(),
+            fail_fast: self.fail_fast,
+            probe: self.probe.clone(),
         }
     }
 }
@@ -1,3 +1,4 @@
+use depth::Token;
 use error::ServiceError;
 use tokio_sync::oneshot;
 
@@ -6,6 +7,9 @@ use tokio_sync::oneshot;
 pub(crate) struct Message<Request, Fut> {
     pub(crate) request: Request,
     pub(crate) tx: Tx<Fut>,
+    /// Counts the message towards the depth of the queue until it is dropped
+    /// by the worker.
+    pub(crate) token: Token,
 }
 
 /// Response sender
@@ -1,10 +1,17 @@
-use error::{Closed, Error, ServiceError, SpawnError};
+use channel;
+use error::{Closed, Error, Panicked, ServiceError, SpawnError};
+use futures::task::{self, Task};
 use futures::{Async, Future, Poll, Stream};
-use message::Message;
+use message::{Message, Tx};
+use std::panic::{self, AssertUnwindSafe};
 use std::sync::{Arc, Mutex};
 use tokio_executor::TypedExecutor;
-use tokio_sync::mpsc;
 use tower_service::Service;
+use tower_util::UnreadyReason;
+
+/// Tells why the service is not ready, once a `Buffer` is told to forward
+/// the reasons of its service.
+pub(crate) type Probe<T> = Arc<Mutex<Option<fn(&T) -> Option<UnreadyReason>>>>;
 
 /// Task that handles processing the buffer. This type should not be used
 /// directly, instead `Buffer` requires an `Executor` that can accept this task.
@@ -19,8 +26,16 @@ where
     T::Error: Into<Error>,
 {
     current_message: Option<Message<Request, T::Future>>,
-    rx: mpsc::Receiver<Message<Request, T::Future>>,
+    /// The sender of the request being dispatched to the service. It is kept
+    /// here, rather than on the stack, so that it outlives a panic in the
+    /// service until the cause is recorded.
+    dispatching: Option<Tx<T::Future>>,
+    rx: channel::Receiver<Message<Request, T::Future>>,
+    /// Messages in the priority lanes, from the least to the most urgent,
+    /// which are dispatched before `rx`.
+    lanes: Vec<channel::Receiver<Message<Request, T::Future>>>,
     service: T,
+    probe: Probe<T>,
     finish: bool,
     failed: Option<ServiceError>,
     handle: Handle,
@@ -29,6 +44,22 @@ where
 /// Get the error out
 pub(crate) struct Handle {
     inner: Arc<Mutex<Option<ServiceError>>>,
+    drain: Arc<Mutex<DrainState>>,
+    /// Why the service is holding back the worker, if it can tell.
+    unready: Arc<Mutex<Option<UnreadyReason>>>,
+}
+
+/// Tracks draining the buffer, shared by the worker and the handles.
+#[derive(Default)]
+struct DrainState {
+    /// Set once the buffer should stop accepting requests.
+    closing: bool,
+    /// Set once the worker has finished, or is gone.
+    drained: bool,
+    /// The worker's task, notified when `closing` is set.
+    worker: Option<Task>,
+    /// The tasks waiting for the buffer to drain.
+    waiters: Vec<Task>,
 }
 
 /// This trait allows you to use either Tokio's threaded runtime's executor or the `current_thread`
@@ -54,7 +85,9 @@ where
 {
     pub(crate) fn spawn<E>(
         service: T,
-        rx: mpsc::Receiver<Message<Request, T::Future>>,
+        rx: channel::Receiver<Message<Request, T::Future>>,
+        lanes: Vec<channel::Receiver<Message<Request, T::Future>>>,
+        probe: Probe<T>,
         executor: &mut E,
     ) -> Result<Handle, Error>
     where
@@ -62,14 +95,19 @@ where
     {
         let handle = Handle {
             inner: Arc::new(Mutex::new(None)),
+            drain: Arc::new(Mutex::new(DrainState::default())),
+            unready: Arc::new(Mutex::new(None)),
         };
 
         let worker = Worker {
             current_message: None,
+            dispatching: None,
             finish: false,
             failed: None,
             rx,
+            lanes,
             service,
+            probe,
             handle: handle.clone(),
         };
 
@@ -95,15 +133,79 @@ where
             }
         }
 
+        // Requests in the priority lanes skip those waiting in `rx`.
+        if let Some(msg) = self.poll_lanes()? {
+            return Ok(Async::Ready(Some(msg)));
+        }
+
         // Get the next request
-        while let Some(mut msg) = try_ready!(self.rx.poll().map_err(|_| ())) {
+        while let Some(mut msg) = try_ready!(self.rx.poll()) {
             if msg.tx.poll_close()?.is_not_ready() {
                 return Ok(Async::Ready(Some(msg)));
             }
             // Otherwise, request is canceled, so pop the next one.
         }
 
-        Ok(Async::Ready(None))
+        // The last handle may have sent to a priority lane just before it
+        // was dropped.
+        Ok(Async::Ready(self.poll_lanes()?))
+    }
+
+    /// Return the next message from the most urgent priority lane that
+    /// hasn't been canceled, if one is available right away.
+    fn poll_lanes(&mut self) -> Result<Option<Message<Request, T::Future>>, ()> {
+        let mut i = self.lanes.len();
+
+        while i > 0 {
+            match self.lanes[i - 1].poll()? {
+                Async::Ready(Some(mut msg)) => {
+                    if msg.tx.poll_close()?.is_not_ready() {
+                        return Ok(Some(msg));
+                    }
+                    // Otherwise, request is canceled, so pop the next one.
+                }
+                Async::Ready(None) => {
+                    // Every sender is gone; the lane will never be used again.
+                    self.lanes.remove(i - 1);
+                    i -= 1;
+                }
+                Async::NotReady => i -= 1,
+            }
+        }
+
+        Ok(None)
+    }
+
+    /// Stops accepting requests once the buffer is closing, or else makes
+    /// sure the worker is notified when it is.
+    ///
+    /// Closing the channels lets the worker drain the requests already in
+    /// them, and then finish.
+    fn poll_closing(&mut self) {
+        let mut drain = self.handle.drain.lock().expect("buffer drain state");
+
+        if !drain.closing {
+            drain.worker = Some(task::current());
+            return;
+        }
+        drop(drain);
+
+        self.rx.close();
+        for rx in &mut self.lanes {
+            rx.close();
+        }
+    }
+
+    /// Records why the service is not ready, if it is not and it can tell,
+    /// for the `Buffer` handles to report.
+    fn set_unready(&self, not_ready: bool) {
+        let probe = *self.probe.lock().expect("buffer unready probe");
+        let reason = match probe {
+            Some(probe) if not_ready => probe(&self.service),
+            _ => None,
+        };
+
+        *self.handle.unready.lock().expect("buffer unready reason") = reason;
     }
 
     fn failed(&mut self, error: T::Error) {
@@ -132,8 +234,11 @@ where
         drop(inner);
 
         self.rx.close();
+        for rx in &mut self.lanes {
+            rx.close();
+        }
 
-        // By closing the mpsc::Receiver, we know that poll_next_msg will soon return Ready(None),
+        // By closing the channel::Receiver, we know that poll_next_msg will soon return Ready(None),
         // which will trigger the `self.finish == true` phase. We just need to make sure that any
         // requests that we receive before we've exhausted the receiver receive the error:
         self.failed = Some(error);
@@ -149,10 +254,32 @@ where
     type Error = ();
 
     fn poll(&mut self) -> Poll<(), ()> {
+        // If the service panics, the worker goes down with it. Record why
+        // before unwinding, so that the `Buffer` handles report the cause
+        // rather than just `Closed`.
+        match panic::catch_unwind(AssertUnwindSafe(|| self.poll_worker())) {
+            Ok(poll) => poll,
+            Err(payload) => {
+                let error = ServiceError::new(Panicked::new(&*payload).into());
+                self.handle.set_error(error);
+                panic::resume_unwind(payload)
+            }
+        }
+    }
+}
+
+impl<T, Request> Worker<T, Request>
+where
+    T: Service<Request>,
+    T::Error: Into<Error>,
+{
+    fn poll_worker(&mut self) -> Poll<(), ()> {
         if self.finish {
             return Ok(().into());
         }
 
+        self.poll_closing();
+
         loop {
             match try_ready!(self.poll_next_msg()) {
                 Some(msg) => {
@@ -162,15 +289,24 @@ where
                     }
 
                     // Wait for the service to be ready
-                    match self.service.poll_ready() {
+                    self.dispatching = Some(msg.tx);
+                    let ready = self.service.poll_ready();
+                    let tx = self.dispatching.take().expect("dispatching");
+                    let msg = Message { tx, ..msg };
+
+                    self.set_unready(ready.as_ref().map_or(false, Async::is_not_ready));
+
+                    match ready {
                         Ok(Async::Ready(())) => {
+                            self.dispatching = Some(msg.tx);
                             let response = self.service.call(msg.request);
+                            let tx = self.dispatching.take().expect("dispatching");
 
                             // Send the response future back to the sender.
                             //
                             // An error means the request had been canceled in-between
                             // our calls, the response future will just be dropped.
-                            let _ = msg.tx.send(Ok(response));
+                            let _ = tx.send(Ok(response));
                         }
                         Ok(Async::NotReady) => {
                             // Put out current message back in its slot.
@@ -197,7 +333,70 @@ where
     }
 }
 
+impl<T, Request> Drop for Worker<T, Request>
+where
+    T: Service<Request>,
+    T::Error: Into<Error>,
+{
+    fn drop(&mut self) {
+        // Whether the worker finished or went away, nothing is left to drain.
+        if let Ok(mut drain) = self.handle.drain.lock() {
+            drain.drained = true;
+            for waiter in drain.waiters.drain(..) {
+                waiter.notify();
+            }
+        }
+    }
+}
+
 impl Handle {
+    /// Stops the buffer from accepting requests, letting the worker drain the
+    /// requests already queued.
+    pub(crate) fn close(&self) {
+        let mut drain = self.drain.lock().expect("buffer drain state");
+        drain.closing = true;
+        if let Some(worker) = drain.worker.take() {
+            worker.notify();
+        }
+    }
+
+    /// Returns `true` once the buffer has stopped accepting requests.
+    pub(crate) fn is_closing(&self) -> bool {
+        self.drain.lock().expect("buffer drain state").closing
+    }
+
+    /// Returns why the service is holding back the worker, if it can tell.
+    pub(crate) fn unready_reason(&self) -> Option<UnreadyReason> {
+        *self.unready.lock().expect("buffer unready reason")
+    }
+
+    /// Returns `Ready` once the worker is done.
+    pub(crate) fn poll_drained(&self) -> Async<()> {
+        let mut drain = self.drain.lock().expect("buffer drain state");
+        if drain.drained {
+            return Async::Ready(());
+        }
+
+        // A task polling again is already registered.
+        if !drain.waiters.iter().any(Task::will_notify_current) {
+            drain.waiters.push(task::current());
+        }
+        Async::NotReady
+    }
+
+    /// Records the error that caused the worker to fail, unless one was
+    /// recorded already.
+    fn set_error(&self, error: ServiceError) {
+        // The lock is never held while calling into the service, so it is
+        // only poisoned if a handle panicked, in which case the error is
+        // lost.
+        if let Ok(mut inner) = self.inner.lock() {
+            if inner.is_none() {
+                *inner = Some(error);
+            }
+        }
+    }
+
     pub(crate) fn get_error_on_closed(&self) -> Error {
         self.inner
             .lock()
@@ -212,6 +411,8 @@ impl Clone for Handle {
     fn clone(&self) -> Handle {
         Handle {
             inner: self.inner.clone(),
+            drain: self.drain.clone(),
+            unready: self.unready.clone(),
         }
     }
 }
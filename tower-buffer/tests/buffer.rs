@@ -3,13 +3,16 @@ extern crate tokio_executor;
 extern crate tower_buffer;
 extern crate tower_mock;
 extern crate tower_service;
+extern crate tower_util;
 
 use futures::prelude::*;
 use tokio_executor::{SpawnError, TypedExecutor};
 use tower_buffer::*;
 use tower_service::*;
+use tower_util::{PollReadyN, Unready, UnreadyReason};
 
 use std::cell::RefCell;
+use std::rc::Rc;
 use std::thread;
 
 #[test]
@@ -110,6 +113,249 @@ fn when_inner_fails() {
     });
 }
 
+#[test]
+fn higher_priorities_are_dispatched_first() {
+    let (service, mut handle) = Mock::new();
+    let mut worker = Manual::default();
+    let mut bulk = Buffer::with_priority(service, 10, 3, &mut worker).unwrap();
+    let mut control = bulk.priority(1);
+    let mut health = bulk.priority(2);
+
+    handle.allow(0);
+
+    // The worker takes the first request out of the queue while it waits for
+    // the service, and the others queue up behind it.
+    let res1 = bulk.call("bulk1");
+    worker.poll();
+    let responses = vec![
+        res1,
+        bulk.call("bulk2"),
+        control.call("control"),
+        health.call("health"),
+    ];
+
+    handle.allow(4);
+    worker.poll();
+    for expected in &["bulk1", "health", "control", "bulk2"] {
+        let request = handle.next_request().unwrap();
+        assert_eq!(*request, *expected);
+        request.respond(*expected);
+    }
+
+    for (response, expected) in responses
+        .into_iter()
+        .zip(&["bulk1", "bulk2", "control", "health"])
+    {
+        assert_eq!(response.wait().unwrap(), *expected);
+    }
+}
+
+#[test]
+fn full_priority_lane_is_not_ready() {
+    let (service, mut handle) = Mock::new();
+    let mut worker = Manual::default();
+    let mut bulk = Buffer::with_priority(service, 1, 2, &mut worker).unwrap();
+    let mut urgent = bulk.priority(1);
+
+    handle.allow(0);
+
+    let res1 = urgent.call("urgent1");
+    worker.poll();
+    let res2 = urgent.call("urgent2");
+
+    // Room in the main queue does not make the full lane ready.
+    with_task(|| {
+        assert!(urgent.poll_ready().unwrap().is_not_ready());
+        assert!(bulk.poll_ready().unwrap().is_ready());
+    });
+    let res3 = bulk.call("bulk");
+
+    handle.allow(3);
+    worker.poll();
+    for expected in &["urgent1", "urgent2", "bulk"] {
+        let request = handle.next_request().unwrap();
+        assert_eq!(*request, *expected);
+        request.respond(*expected);
+    }
+
+    assert_eq!(res1.wait().unwrap(), "urgent1");
+    assert_eq!(res2.wait().unwrap(), "urgent2");
+    assert_eq!(res3.wait().unwrap(), "bulk");
+}
+
+#[test]
+fn fail_fast_when_full() {
+    let (service, mut handle) = Mock::new();
+    let mut worker = Manual::default();
+    let mut service = Buffer::with_executor(service, 1, &mut worker)
+        .unwrap()
+        .fail_fast();
+
+    handle.allow(0);
+
+    // The worker takes the first request out of the queue while it waits for
+    // the service, and the second one fills the queue.
+    let res1 = service.call("hello");
+    worker.poll();
+    with_task(|| {
+        assert!(service.poll_ready().unwrap().is_ready());
+    });
+    let res2 = service.call("hello2");
+
+    with_task(|| {
+        assert!(service.poll_ready().unwrap().is_ready());
+        assert_eq!(service.poll_ready_n(3).unwrap(), Async::Ready(3));
+    });
+    let err = service.call("hello3").wait().unwrap_err();
+    assert!(err.is::<error::Full>(), "unexpected error: {}", err);
+
+    handle.allow(2);
+    worker.poll();
+    for expected in &["hello", "hello2"] {
+        let request = handle.next_request().unwrap();
+        assert_eq!(*request, *expected);
+        request.respond(*expected);
+    }
+
+    assert_eq!(res1.wait().unwrap(), "hello");
+    assert_eq!(res2.wait().unwrap(), "hello2");
+}
+
+#[test]
+fn poll_ready_n_reserves_slots() {
+    let (service, mut handle) = Mock::new();
+    let mut worker = Manual::default();
+    let mut service = Buffer::with_executor(service, 3, &mut worker).unwrap();
+    let mut other = service.clone();
+
+    handle.allow(0);
+
+    with_task(|| {
+        assert_eq!(service.poll_ready_n(5).unwrap(), Async::Ready(3));
+        // The reserved slots are not left for other handles.
+        assert!(other.poll_ready().unwrap().is_not_ready());
+    });
+
+    let responses = (0..3).map(|_| service.call("hello")).collect::<Vec<_>>();
+
+    handle.allow(3);
+    worker.poll();
+    for response in responses {
+        handle.next_request().unwrap().respond("world");
+        assert_eq!(response.wait().unwrap(), "world");
+    }
+}
+
+#[test]
+#[cfg(not(feature = "audit-bounds"))]
+fn unbounded_is_always_ready() {
+    let (service, mut handle) = Mock::new();
+    let mut service = Buffer::unbounded_with_executor(service, &mut Exec).unwrap();
+
+    // The inner service is not ready, yet requests keep being accepted.
+    handle.allow(0);
+
+    let responses = (0..100)
+        .map(|_| {
+            with_task(|| {
+                assert!(service.poll_ready().unwrap().is_ready());
+            });
+            service.call("hello")
+        })
+        .collect::<Vec<_>>();
+
+    handle.allow(100);
+    for response in responses {
+        handle.next_request().unwrap().respond("world");
+        assert_eq!(response.wait().unwrap(), "world");
+    }
+}
+
+#[test]
+fn reports_queue_depth() {
+    let (service, mut handle) = Mock::new();
+    let mut worker = Manual::default();
+    let mut service = Buffer::with_executor(service, 10, &mut worker).unwrap();
+    let depth = service.queue_depth();
+
+    handle.allow(0);
+    let responses = vec![
+        service.call("one"),
+        service.call("two"),
+        service.call("three"),
+    ];
+    worker.poll();
+    assert_eq!(depth.current(), 3);
+    assert_eq!(depth.high_watermark(), 3);
+
+    // The worker gives up its count of a request once it dispatches it.
+    handle.allow(3);
+    worker.poll();
+    assert_eq!(depth.current(), 0);
+
+    for response in responses {
+        handle.next_request().unwrap().respond("world");
+        assert_eq!(response.wait().unwrap(), "world");
+    }
+
+    assert_eq!(depth.reset_high_watermark(), 3);
+    assert_eq!(depth.high_watermark(), 0);
+}
+
+#[test]
+fn drain_dispatches_queued_requests() {
+    let (mut service, mut handle) = new_service();
+
+    let res1 = service.call("hello");
+    let drain = service.drain();
+
+    // New requests are refused once the buffer is draining.
+    let err = service.call("hello2").wait().unwrap_err();
+    assert!(err.is::<error::Closed>(), "unexpected error: {:?}", err);
+
+    // Requests queued beforehand still make it to the service.
+    let request = handle.next_request().unwrap();
+    assert_eq!(*request, "hello");
+    request.respond("world");
+
+    assert_eq!(res1.wait().unwrap(), "world");
+    drain.wait().unwrap();
+}
+
+#[test]
+fn when_inner_panics() {
+    use std::error::Error as StdError;
+
+    struct Panics;
+    impl Service<&'static str> for Panics {
+        type Response = &'static str;
+        type Error = Box<StdError + Send + Sync>;
+        type Future = futures::future::FutureResult<Self::Response, Self::Error>;
+
+        fn poll_ready(&mut self) -> Poll<(), Self::Error> {
+            panic!("wedged");
+        }
+
+        fn call(&mut self, _: &'static str) -> Self::Future {
+            unreachable!("never ready");
+        }
+    }
+
+    let mut service = Buffer::with_executor(Panics, 10, &mut Exec).unwrap();
+    let res1 = service.call("hello");
+
+    // Both the request in flight and later ones see why the worker died.
+    let e = res1.wait().unwrap_err();
+    let e = e.downcast_ref::<error::ServiceError>().unwrap();
+    let panicked = e.source().unwrap().downcast_ref::<error::Panicked>();
+    assert_eq!(panicked.unwrap().message(), Some("wedged"));
+
+    with_task(|| {
+        let e = service.poll_ready().unwrap_err();
+        assert!(e.is::<error::ServiceError>());
+    });
+}
+
 #[test]
 fn poll_ready_when_worker_is_dropped_early() {
     let (service, _handle) = Mock::new();
@@ -146,11 +392,102 @@ fn response_future_when_worker_is_dropped_early() {
     response.wait().expect_err("res.wait");
 }
 
+#[test]
+#[cfg(feature = "audit-bounds")]
+fn audit_bounds_allow_full_buffer() {
+    let (service, mut handle) = Mock::new();
+    let mut worker = Manual::default();
+    let mut service = Buffer::with_executor(service, 10, &mut worker).unwrap();
+    handle.allow(0);
+
+    // The worker takes one request out of the channel while it waits for the
+    // inner service, and the channel holds the remaining `bound` requests.
+    let mut responses = vec![service.call("hello")];
+    worker.poll();
+    for _ in 0..10 {
+        with_task(|| assert!(service.poll_ready().unwrap().is_ready()));
+        responses.push(service.call("hello"));
+    }
+
+    with_task(|| assert!(service.poll_ready().unwrap().is_not_ready()));
+
+    // Requests that do not fit are not counted.
+    let mut shedding = service.clone().fail_fast();
+    for _ in 0..3 {
+        let err = shedding.call("hello").wait().unwrap_err();
+        assert!(err.is::<error::Full>(), "unexpected error: {}", err);
+    }
+
+    handle.allow(11);
+    worker.poll();
+    for _ in 0..11 {
+        handle.next_request().unwrap().respond("world");
+    }
+
+    for response in responses {
+        assert_eq!(response.wait().unwrap(), "world");
+    }
+}
+
+#[test]
+#[cfg(feature = "audit-bounds")]
+#[should_panic(expected = "unbounded buffer")]
+fn audit_bounds_reject_unbounded_buffer() {
+    let (service, _handle) = Mock::new();
+    let _: Result<Buffer<Mock, &'static str>, _> =
+        Buffer::unbounded_with_executor(service, &mut Exec);
+}
+
+#[test]
+fn reports_why_buffer_is_not_ready() {
+    let mut worker = Manual::default();
+    let mut service = Buffer::with_executor(RateLimited, 1, &mut worker).unwrap();
+
+    // The worker holds the first request, and the second fills the buffer.
+    let _res1 = service.call("hello1");
+    worker.poll();
+    let _res2 = service.call("hello2");
+
+    with_task(|| assert!(service.poll_ready().unwrap().is_not_ready()));
+    assert_eq!(service.unready_reason(), Some(UnreadyReason::QueueFull));
+
+    // Once told to, the worker records why the service holds it back.
+    let service = service.forward_unready();
+    worker.poll();
+    assert_eq!(service.unready_reason(), Some(UnreadyReason::RateLimited));
+
+    let _drain = service.drain();
+    assert_eq!(service.unready_reason(), Some(UnreadyReason::Draining));
+}
+
 type Mock = tower_mock::Mock<&'static str, &'static str>;
 type Handle = tower_mock::Handle<&'static str, &'static str>;
 
 struct Exec;
 
+/// Never ready, as its rate limit has been reached.
+struct RateLimited;
+
+impl Service<&'static str> for RateLimited {
+    type Response = &'static str;
+    type Error = &'static str;
+    type Future = futures::future::FutureResult<&'static str, &'static str>;
+
+    fn poll_ready(&mut self) -> Poll<(), Self::Error> {
+        Ok(Async::NotReady)
+    }
+
+    fn call(&mut self, _: &'static str) -> Self::Future {
+        unreachable!("never ready")
+    }
+}
+
+impl Unready<&'static str> for RateLimited {
+    fn unready_reason(&self) -> Option<UnreadyReason> {
+        Some(UnreadyReason::RateLimited)
+    }
+}
+
 impl<F> TypedExecutor<F> for Exec
 where
     F: Future<Item = (), Error = ()> + Send + 'static,
@@ -163,6 +500,31 @@ where
     }
 }
 
+/// Keeps the worker of a buffer, so that the test polls it by hand.
+#[derive(Clone, Default)]
+struct Manual(Rc<RefCell<Option<Box<Future<Item = (), Error = ()>>>>>);
+
+impl Manual {
+    /// Lets the worker do all the work it can.
+    fn poll(&mut self) {
+        let mut worker = self.0.borrow_mut();
+        let worker = worker.as_mut().expect("worker spawned");
+        with_task(|| {
+            let _ = worker.poll();
+        });
+    }
+}
+
+impl<F> TypedExecutor<F> for Manual
+where
+    F: Future<Item = (), Error = ()> + 'static,
+{
+    fn spawn(&mut self, fut: F) -> Result<(), SpawnError> {
+        *self.0.borrow_mut() = Some(Box::new(fut));
+        Ok(())
+    }
+}
+
 struct ExecFn<Func>(Func);
 
 impl<Func, F> TypedExecutor<F> for ExecFn<Func>
@@ -176,6 +538,71 @@ where
     }
 }
 
+#[test]
+fn express_requests_skip_the_queue() {
+    let (service, mut handle) = Mock::new();
+    let mut worker = Manual::default();
+    let mut service = Buffer::with_express_lane(service, 10, 1, &mut worker).unwrap();
+    let mut express = service.express_lane();
+
+    handle.allow(0);
+
+    // The worker holds on to the first request while it waits for the
+    // service, but the probe does not wait for the second.
+    let res1 = service.call("hello");
+    worker.poll();
+    let res2 = service.call("hello2");
+    let res3 = express.call("probe");
+
+    handle.allow(3);
+    worker.poll();
+
+    for expected in &["hello", "probe", "hello2"] {
+        let request = handle.next_request().unwrap();
+        assert_eq!(*request, *expected);
+        request.respond(*expected);
+    }
+
+    assert_eq!(res1.wait().unwrap(), "hello");
+    assert_eq!(res2.wait().unwrap(), "hello2");
+    assert_eq!(res3.wait().unwrap(), "probe");
+}
+
+#[test]
+fn full_buffer_admits_express_requests() {
+    let (service, mut handle) = Mock::new();
+    let mut worker = Manual::default();
+    let mut service = Buffer::with_express_lane(service, 1, 1, &mut worker).unwrap();
+    let mut express = service.express_lane();
+
+    handle.allow(0);
+
+    // The worker takes the first request out of the queue while it waits for
+    // the service, and the second one fills the queue.
+    let res1 = service.call("hello");
+    worker.poll();
+    let res2 = service.call("hello2");
+
+    // Only the express lane has room left.
+    with_task(|| {
+        assert!(service.poll_ready().unwrap().is_not_ready());
+        assert!(express.poll_ready().unwrap().is_ready());
+    });
+    let res3 = express.call("probe");
+
+    handle.allow(3);
+    worker.poll();
+    for expected in &["hello", "probe", "hello2"] {
+        let request = handle.next_request().unwrap();
+        assert_eq!(*request, *expected);
+        request.respond(*expected);
+    }
+
+    assert_eq!(res1.wait().unwrap(), "hello");
+    assert_eq!(res2.wait().unwrap(), "hello2");
+    assert_eq!(res3.wait().unwrap(), "probe");
+}
+
 fn new_service() -> (Buffer<Mock, &'static str>, Handle) {
     let (service, handle) = Mock::new();
     // bound is >0 here because clears_canceled_requests needs multiple outstanding requests
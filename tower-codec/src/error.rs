@@ -0,0 +1,21 @@
+//! Error types
+
+use std::error;
+
+pub(crate) type Error = Box<error::Error + Send + Sync>;
+
+pub(crate) mod never {
+    use std::{error, fmt};
+
+    /// An error that can never occur.
+    #[derive(Debug)]
+    pub enum Never {}
+
+    impl fmt::Display for Never {
+        fn fmt(&self, _: &mut fmt::Formatter) -> fmt::Result {
+            match *self {}
+        }
+    }
+
+    impl error::Error for Never {}
+}
@@ -0,0 +1,52 @@
+//! Future types
+
+use crate::error::Error;
+use crate::Decoder;
+use futures::{Async, Future, Poll};
+
+/// `Codec` response future
+#[derive(Debug)]
+pub struct ResponseFuture<F, D> {
+    state: State<F, D>,
+}
+
+#[derive(Debug)]
+enum State<F, D> {
+    Called(F, D),
+    Failed(Option<Error>),
+}
+
+impl<F, D> ResponseFuture<F, D> {
+    pub(crate) fn new(future: F, decoder: D) -> Self {
+        ResponseFuture {
+            state: State::Called(future, decoder),
+        }
+    }
+
+    pub(crate) fn failed(error: Error) -> Self {
+        ResponseFuture {
+            state: State::Failed(Some(error)),
+        }
+    }
+}
+
+impl<F, D> Future for ResponseFuture<F, D>
+where
+    F: Future,
+    F::Error: Into<Error>,
+    D: Decoder<F::Item>,
+    D::Error: Into<Error>,
+{
+    type Item = D::Item;
+    type Error = Error;
+
+    fn poll(&mut self) -> Poll<Self::Item, Self::Error> {
+        match self.state {
+            State::Called(ref mut future, ref mut decoder) => {
+                let response = try_ready!(future.poll().map_err(Into::into));
+                decoder.decode(response).map(Async::Ready).map_err(Into::into)
+            }
+            State::Failed(ref mut e) => Err(e.take().expect("polled after error")),
+        }
+    }
+}
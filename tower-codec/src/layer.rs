@@ -0,0 +1,44 @@
+use crate::error::{never::Never, Error};
+use crate::{Codec, Decoder, Encoder};
+use tower_layer::Layer;
+use tower_service::Service;
+
+/// Encodes requests and decodes responses around the inner service.
+///
+/// The service produced by this layer accepts a different request type than
+/// the service it wraps.
+#[derive(Debug, Clone)]
+pub struct CodecLayer<E, D> {
+    encoder: E,
+    decoder: D,
+}
+
+impl<E, D> CodecLayer<E, D> {
+    /// Create a new `CodecLayer` from an encoder and decoder pair.
+    pub fn new(encoder: E, decoder: D) -> Self {
+        CodecLayer { encoder, decoder }
+    }
+}
+
+impl<S, E, D, Request> Layer<S, Request> for CodecLayer<E, D>
+where
+    E: Encoder<Request> + Clone,
+    E::Error: Into<Error>,
+    S: Service<E::Item>,
+    S::Error: Into<Error>,
+    D: Decoder<S::Response> + Clone,
+    D::Error: Into<Error>,
+{
+    type Response = D::Item;
+    type Error = Error;
+    type LayerError = Never;
+    type Service = Codec<S, E, D>;
+
+    fn layer(&self, service: S) -> Result<Self::Service, Self::LayerError> {
+        Ok(Codec::new(
+            service,
+            self.encoder.clone(),
+            self.decoder.clone(),
+        ))
+    }
+}
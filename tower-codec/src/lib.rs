@@ -0,0 +1,141 @@
+//! Tower middleware that translates between typed requests and responses and
+//! the wire representation used by the inner service.
+//!
+//! Transports such as pipelined or multiplexed connections are usually
+//! expressed as a `Service` over a wire representation (e.g. `Service<Bytes>`).
+//! `Codec` wraps such a service with an [`Encoder`] for requests and a
+//! [`Decoder`] for responses, so the resulting service can be called with
+//! typed requests and yields typed responses.
+
+#![doc(html_root_url = "https://docs.rs/tower-codec/0.1.0")]
+#![deny(missing_debug_implementations, missing_docs)]
+#![cfg_attr(test, deny(warnings))]
+
+#[macro_use]
+extern crate futures;
+extern crate tower_layer;
+extern crate tower_service;
+
+pub mod error;
+pub mod future;
+mod layer;
+
+pub use crate::layer::CodecLayer;
+
+use crate::error::Error;
+use crate::future::ResponseFuture;
+use futures::Poll;
+use tower_service::Service;
+
+/// Encodes `Request` values into the wire representation.
+pub trait Encoder<Request> {
+    /// The encoded request.
+    type Item;
+
+    /// Error produced when a request cannot be encoded.
+    type Error;
+
+    /// Encode `request`.
+    fn encode(&mut self, request: Request) -> Result<Self::Item, Self::Error>;
+}
+
+/// Decodes `Response` values from the wire representation.
+pub trait Decoder<Response> {
+    /// The decoded response.
+    type Item;
+
+    /// Error produced when a response cannot be decoded.
+    type Error;
+
+    /// Decode `response`.
+    fn decode(&mut self, response: Response) -> Result<Self::Item, Self::Error>;
+}
+
+/// Encodes requests and decodes responses around an inner service.
+#[derive(Debug, Clone)]
+pub struct Codec<S, E, D> {
+    inner: S,
+    encoder: E,
+    decoder: D,
+}
+
+// ===== impl Codec =====
+
+impl<S, E, D> Codec<S, E, D> {
+    /// Creates a new `Codec` wrapping `inner`.
+    ///
+    /// `decoder` is cloned into each response future.
+    pub fn new(inner: S, encoder: E, decoder: D) -> Self {
+        Codec {
+            inner,
+            encoder,
+            decoder,
+        }
+    }
+
+    /// Get a reference to the inner service
+    pub fn get_ref(&self) -> &S {
+        &self.inner
+    }
+
+    /// Get a mutable reference to the inner service
+    pub fn get_mut(&mut self) -> &mut S {
+        &mut self.inner
+    }
+
+    /// Consume `self`, returning the inner service
+    pub fn into_inner(self) -> S {
+        self.inner
+    }
+}
+
+impl<S, E, D, Request> Service<Request> for Codec<S, E, D>
+where
+    E: Encoder<Request>,
+    E::Error: Into<Error>,
+    S: Service<E::Item>,
+    S::Error: Into<Error>,
+    D: Decoder<S::Response> + Clone,
+    D::Error: Into<Error>,
+{
+    type Response = D::Item;
+    type Error = Error;
+    type Future = ResponseFuture<S::Future, D>;
+
+    fn poll_ready(&mut self) -> Poll<(), Self::Error> {
+        self.inner.poll_ready().map_err(Into::into)
+    }
+
+    fn call(&mut self, request: Request) -> Self::Future {
+        match self.encoder.encode(request) {
+            Ok(item) => ResponseFuture::new(self.inner.call(item), self.decoder.clone()),
+            Err(e) => ResponseFuture::failed(e.into()),
+        }
+    }
+}
+
+// ===== impl Encoder / Decoder for closures =====
+
+impl<F, Request, T, E> Encoder<Request> for F
+where
+    F: FnMut(Request) -> Result<T, E>,
+{
+    type Item = T;
+    type Error = E;
+
+    fn encode(&mut self, request: Request) -> Result<T, E> {
+        self(request)
+    }
+}
+
+impl<F, Response, T, E> Decoder<Response> for F
+where
+    F: FnMut(Response) -> Result<T, E>,
+{
+    type Item = T;
+    type Error = E;
+
+    fn decode(&mut self, response: Response) -> Result<T, E> {
+        self(response)
+    }
+}
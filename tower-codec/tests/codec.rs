@@ -0,0 +1,56 @@
+extern crate futures;
+extern crate tower_codec;
+extern crate tower_mock;
+extern crate tower_service;
+
+use futures::future::{poll_fn, Future};
+use std::num::ParseIntError;
+use tower_codec::Codec;
+use tower_service::Service;
+
+#[test]
+fn encodes_request_and_decodes_response() {
+    let (mut service, mut handle) = new_service();
+
+    poll_fn(|| service.poll_ready()).wait().unwrap();
+    let response = service.call(41);
+
+    let request = handle.next_request().unwrap();
+    assert_eq!(*request, "41");
+    request.respond("42".to_string());
+
+    assert_eq!(response.wait().unwrap(), 42);
+}
+
+#[test]
+fn decode_error() {
+    let (mut service, mut handle) = new_service();
+
+    poll_fn(|| service.poll_ready()).wait().unwrap();
+    let response = service.call(1);
+
+    let request = handle.next_request().unwrap();
+    request.respond("not a number".to_string());
+
+    let err = response.wait().unwrap_err();
+    assert!(err.is::<ParseIntError>());
+}
+
+type Mock = tower_mock::Mock<String, String>;
+type Handle = tower_mock::Handle<String, String>;
+type Encode = fn(u32) -> Result<String, ParseIntError>;
+type Decode = fn(String) -> Result<u32, ParseIntError>;
+
+fn new_service() -> (Codec<Mock, Encode, Decode>, Handle) {
+    fn encode(request: u32) -> Result<String, ParseIntError> {
+        Ok(request.to_string())
+    }
+
+    fn decode(response: String) -> Result<u32, ParseIntError> {
+        response.parse()
+    }
+
+    let (service, handle) = Mock::new();
+    let service = Codec::new(service, encode as Encode, decode as Decode);
+    (service, handle)
+}
@@ -0,0 +1,139 @@
+use crate::error::Never;
+use crate::{Change, Discover};
+use futures::{Async, Future, Poll};
+use std::collections::{HashSet, VecDeque};
+use std::fmt;
+use std::net::SocketAddr;
+use std::time::Duration;
+use tower_service::Service;
+use tower_util::backoff::Delayed;
+
+/// Dynamic service discovery based on resolving a DNS name.
+///
+/// `Dns` resolves a name with a `resolver` service, which answers with the
+/// addresses the name currently resolves to, such as a [`Resolver`]
+/// resolving the A and AAAA, or SRV records of a [`Name`]. A service is
+/// created for each new address with `new_service`, and inserted; the
+/// services of addresses that are no longer part of the answer are removed.
+/// The name is resolved again once `interval` has elapsed.
+///
+/// If the resolution fails, the error is logged, and the services discovered
+/// so far are kept until the name is resolved again once `interval` has
+/// elapsed. Discovery thus never fails, so that a balancer is not torn down
+/// by a transient failure of DNS.
+///
+/// [`Resolver`]: struct.Resolver.html
+/// [`Name`]: enum.Name.html
+pub struct Dns<R, T, F, S>
+where
+    R: Service<T>,
+{
+    resolver: R,
+    target: T,
+    interval: Duration,
+    new_service: F,
+    state: State<R::Future>,
+    /// The addresses of the services discovered so far.
+    addrs: HashSet<SocketAddr>,
+    /// Changes that have yet to be yielded.
+    changes: VecDeque<Change<SocketAddr, S>>,
+}
+
+enum State<F> {
+    Idle,
+    Resolving(F),
+    Waiting(Delayed<()>),
+}
+
+impl<R, T, F, S> Dns<R, T, F, S>
+where
+    R: Service<T>,
+    R::Response: IntoIterator<Item = SocketAddr>,
+    T: Clone,
+    F: FnMut(SocketAddr) -> S,
+{
+    /// Discovers the services at the addresses `target` resolves to,
+    /// resolving it again every `interval`.
+    pub fn new(resolver: R, target: T, interval: Duration, new_service: F) -> Self {
+        Dns {
+            resolver,
+            target,
+            interval,
+            new_service,
+            state: State::Idle,
+            addrs: HashSet::new(),
+            changes: VecDeque::new(),
+        }
+    }
+
+    /// Queues the changes from the services discovered so far to the
+    /// services at `addrs`.
+    fn update(&mut self, addrs: R::Response) {
+        let addrs = addrs.into_iter().collect::<HashSet<_>>();
+
+        for removed in self.addrs.difference(&addrs) {
+            self.changes.push_back(Change::Remove(*removed));
+        }
+
+        for inserted in addrs.difference(&self.addrs) {
+            let service = (self.new_service)(*inserted);
+            self.changes.push_back(Change::Insert(*inserted, service));
+        }
+
+        self.addrs = addrs;
+    }
+}
+
+impl<R, T, F, S> Discover for Dns<R, T, F, S>
+where
+    R: Service<T>,
+    R::Response: IntoIterator<Item = SocketAddr>,
+    R::Error: fmt::Display,
+    T: Clone,
+    F: FnMut(SocketAddr) -> S,
+{
+    type Key = SocketAddr;
+    type Service = S;
+    type Error = Never;
+
+    fn poll(&mut self) -> Poll<Change<Self::Key, Self::Service>, Self::Error> {
+        loop {
+            if let Some(change) = self.changes.pop_front() {
+                return Ok(Async::Ready(change));
+            }
+
+            let state;
+
+            match self.state {
+                State::Idle => match self.resolver.poll_ready() {
+                    Ok(Async::Ready(())) => {
+                        state = State::Resolving(self.resolver.call(self.target.clone()));
+                    }
+                    Ok(Async::NotReady) => return Ok(Async::NotReady),
+                    Err(e) => {
+                        warn!("resolver failed: {}", e);
+                        state = State::Waiting(Delayed::new((), self.interval));
+                    }
+                },
+                State::Resolving(ref mut fut) => {
+                    match fut.poll() {
+                        Ok(Async::Ready(addrs)) => self.update(addrs),
+                        Ok(Async::NotReady) => return Ok(Async::NotReady),
+                        Err(e) => warn!("resolution failed: {}", e),
+                    }
+
+                    // Whether it failed or not, resolve the name again later.
+                    state = State::Waiting(Delayed::new((), self.interval));
+                }
+                State::Waiting(ref mut delay) => {
+                    if let Ok(Async::NotReady) = delay.poll() {
+                        return Ok(Async::NotReady);
+                    }
+                    state = State::Idle;
+                }
+            }
+
+            self.state = state;
+        }
+    }
+}
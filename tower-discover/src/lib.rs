@@ -4,16 +4,44 @@
 //! consumer. These services typically live on other servers and are accessible
 //! via the network; however, it is possible to discover services available in
 //! other processes or even in process.
+//!
+//! A `Discover` yields a `Change` each time an endpoint is inserted into, or
+//! removed from, the set of services, identified by a key. Balancers, such as
+//! those of `tower-balance`, track these changes to keep an up to date set of
+//! endpoints to dispatch requests to.
+//!
+//! Three implementations are provided:
+//!
+//! - `ServiceList` yields a fixed list of services once, keyed by their
+//!   position in the list.
+//! - `ServiceStream` yields the changes produced by a `Stream`.
+//! - `Dns` tracks the addresses a name resolves to, e.g. with a `Resolver`
+//!   looking up its A and AAAA, or SRV records, with the `dns` feature.
 
 #[macro_use]
 extern crate futures;
+#[cfg(feature = "dns")]
+#[macro_use]
+extern crate log;
 extern crate tower_service;
+#[cfg(feature = "dns")]
+extern crate tower_util;
+#[cfg(feature = "dns")]
+extern crate trust_dns_resolver;
 
+#[cfg(feature = "dns")]
+mod dns;
 mod error;
 mod list;
+#[cfg(feature = "dns")]
+mod resolver;
 mod stream;
 
+#[cfg(feature = "dns")]
+pub use crate::dns::Dns;
 pub use crate::list::ServiceList;
+#[cfg(feature = "dns")]
+pub use crate::resolver::{Name, ResolveFuture, Resolver};
 pub use crate::stream::ServiceStream;
 
 use futures::Poll;
@@ -22,7 +50,7 @@ use std::hash::Hash;
 /// Provide a uniform set of services able to satisfy a request.
 ///
 /// This set of services may be updated over time. On each change to the set, a
-/// new `NewServiceSet` is yielded by `Discover`.
+/// `Change` is yielded by `Discover`.
 ///
 /// See crate documentation for more details.
 pub trait Discover {
@@ -0,0 +1,108 @@
+use futures::{future, Future, Poll};
+use std::fmt;
+use std::net::SocketAddr;
+use tower_service::Service;
+use trust_dns_resolver::error::ResolveError;
+use trust_dns_resolver::AsyncResolver;
+
+/// A name to resolve with a `Resolver`.
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+pub enum Name {
+    /// A host name, resolved to the addresses of its A and AAAA records, with
+    /// the given port.
+    Host(String, u16),
+    /// A service name, such as `_http._tcp.example.com`, resolved to the
+    /// targets of its SRV records. Each target is resolved like a `Host`,
+    /// with the port of its record.
+    Srv(String),
+}
+
+/// Resolves `Name`s with DNS, for `Dns` to discover the services at the
+/// resulting addresses.
+///
+/// The `AsyncResolver` is built by the caller, who must also spawn the
+/// background task it comes with.
+#[derive(Clone)]
+pub struct Resolver {
+    inner: AsyncResolver,
+}
+
+/// The addresses a `Name` resolves to.
+pub struct ResolveFuture {
+    inner: Box<Future<Item = Vec<SocketAddr>, Error = ResolveError> + Send>,
+}
+
+// ===== impl Resolver =====
+
+impl Resolver {
+    /// Resolve names with `resolver`.
+    pub fn new(resolver: AsyncResolver) -> Self {
+        Resolver { inner: resolver }
+    }
+}
+
+impl Service<Name> for Resolver {
+    type Response = Vec<SocketAddr>;
+    type Error = ResolveError;
+    type Future = ResolveFuture;
+
+    fn poll_ready(&mut self) -> Poll<(), Self::Error> {
+        Ok(().into())
+    }
+
+    fn call(&mut self, name: Name) -> Self::Future {
+        let inner: Box<Future<Item = _, Error = _> + Send> = match name {
+            Name::Host(host, port) => Box::new(lookup_host(&self.inner, host, port)),
+            Name::Srv(name) => {
+                let resolver = self.inner.clone();
+                let addrs = self.inner.srv_lookup(name.as_str()).and_then(move |srv| {
+                    let hosts = srv
+                        .iter()
+                        .map(|record| {
+                            lookup_host(&resolver, record.target().to_utf8(), record.port())
+                        })
+                        .collect::<Vec<_>>();
+
+                    future::join_all(hosts).map(|addrs| addrs.into_iter().flatten().collect())
+                });
+                Box::new(addrs)
+            }
+        };
+
+        ResolveFuture { inner }
+    }
+}
+
+/// Resolves `host` to its A and AAAA records, with `port`.
+fn lookup_host(
+    resolver: &AsyncResolver,
+    host: String,
+    port: u16,
+) -> impl Future<Item = Vec<SocketAddr>, Error = ResolveError> + Send {
+    resolver
+        .lookup_ip(host.as_str())
+        .map(move |ips| ips.iter().map(|ip| SocketAddr::new(ip, port)).collect())
+}
+
+impl fmt::Debug for Resolver {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_struct("Resolver").finish()
+    }
+}
+
+// ===== impl ResolveFuture =====
+
+impl Future for ResolveFuture {
+    type Item = Vec<SocketAddr>;
+    type Error = ResolveError;
+
+    fn poll(&mut self) -> Poll<Self::Item, Self::Error> {
+        self.inner.poll()
+    }
+}
+
+impl fmt::Debug for ResolveFuture {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_struct("ResolveFuture").finish()
+    }
+}
@@ -4,6 +4,11 @@ use std::hash::Hash;
 use tower_service::Service;
 
 /// Dynamic service discovery based on a stream of service changes.
+///
+/// `ServiceStream` yields the changes of any `Stream` of `Change`s, such as
+/// the updates from a control plane's watch API, so that they may be used
+/// wherever a `Discover` is expected. Once the stream ends, no more changes
+/// are yielded.
 pub struct ServiceStream<S> {
     inner: futures::stream::Fuse<S>,
 }
@@ -0,0 +1,139 @@
+#![cfg(feature = "dns")]
+
+extern crate futures;
+extern crate tokio;
+extern crate tokio_mock_task;
+extern crate tower_discover;
+extern crate tower_mock;
+extern crate tower_service;
+extern crate trust_dns_resolver;
+
+use futures::Async;
+use std::net::SocketAddr;
+use std::time::Duration;
+use tokio::runtime::Runtime;
+use tokio_mock_task::MockTask;
+use tower_discover::{Change, Discover, Dns, Name, Resolver};
+use tower_mock::clock::MockClock;
+use tower_mock::Mock;
+use tower_service::Service;
+use trust_dns_resolver::config::{ResolverConfig, ResolverOpts};
+use trust_dns_resolver::AsyncResolver;
+
+type MockResolver = Mock<&'static str, Vec<SocketAddr>>;
+
+fn addr(s: &str) -> SocketAddr {
+    s.parse().unwrap()
+}
+
+/// Polls `dns` for the next change, as a comparable value.
+fn next<D>(task: &mut MockTask, dns: &mut D) -> Option<(&'static str, SocketAddr)>
+where
+    D: Discover<Key = SocketAddr, Service = SocketAddr>,
+    D::Error: ::std::fmt::Debug,
+{
+    match task.enter(|| dns.poll()).unwrap() {
+        Async::Ready(Change::Insert(key, svc)) => {
+            assert_eq!(key, svc);
+            Some(("insert", key))
+        }
+        Async::Ready(Change::Remove(key)) => Some(("remove", key)),
+        Async::NotReady => None,
+    }
+}
+
+#[test]
+fn tracks_resolved_addresses() {
+    let mut task = MockTask::new();
+    let (resolver, mut handle) = MockResolver::new();
+    let mut dns = Dns::new(resolver, "svc.local", Duration::from_secs(30), |addr| addr);
+
+    MockClock::new().enter(|clock| {
+        assert_eq!(next(&mut task, &mut dns), None);
+        let request = handle.next_request().unwrap();
+        assert_eq!(*request, "svc.local");
+        request.respond(vec![addr("10.0.0.1:80"), addr("10.0.0.2:80")]);
+
+        let mut inserted = vec![
+            next(&mut task, &mut dns).unwrap(),
+            next(&mut task, &mut dns).unwrap(),
+        ];
+        inserted.sort();
+        assert_eq!(
+            inserted,
+            vec![
+                ("insert", addr("10.0.0.1:80")),
+                ("insert", addr("10.0.0.2:80")),
+            ]
+        );
+
+        // The name is not resolved again until the interval has elapsed.
+        assert_eq!(next(&mut task, &mut dns), None);
+        assert!(task.enter(|| handle.poll_request()).unwrap().is_not_ready());
+
+        clock.advance(Duration::from_secs(30));
+        assert!(task.is_notified());
+        assert_eq!(next(&mut task, &mut dns), None);
+        handle
+            .next_request()
+            .unwrap()
+            .respond(vec![addr("10.0.0.2:80"), addr("10.0.0.3:80")]);
+
+        assert_eq!(
+            next(&mut task, &mut dns),
+            Some(("remove", addr("10.0.0.1:80")))
+        );
+        assert_eq!(
+            next(&mut task, &mut dns),
+            Some(("insert", addr("10.0.0.3:80")))
+        );
+        assert_eq!(next(&mut task, &mut dns), None);
+    });
+}
+
+#[test]
+fn keeps_addresses_when_resolution_fails() {
+    let mut task = MockTask::new();
+    let (resolver, mut handle) = MockResolver::new();
+    let mut dns = Dns::new(resolver, "svc.local", Duration::from_secs(30), |addr| addr);
+
+    MockClock::new().enter(|clock| {
+        assert_eq!(next(&mut task, &mut dns), None);
+        handle
+            .next_request()
+            .unwrap()
+            .respond(vec![addr("10.0.0.1:80")]);
+        assert_eq!(
+            next(&mut task, &mut dns),
+            Some(("insert", addr("10.0.0.1:80")))
+        );
+
+        clock.advance(Duration::from_secs(30));
+        assert_eq!(next(&mut task, &mut dns), None);
+        handle.next_request().unwrap().error("timed out");
+
+        // The error is not yielded, and the name is resolved again later.
+        assert_eq!(next(&mut task, &mut dns), None);
+        clock.advance(Duration::from_secs(30));
+        assert_eq!(next(&mut task, &mut dns), None);
+        handle
+            .next_request()
+            .unwrap()
+            .respond(vec![addr("10.0.0.1:80")]);
+        assert_eq!(next(&mut task, &mut dns), None);
+    });
+}
+
+#[test]
+fn resolves_host_with_port() {
+    let mut rt = Runtime::new().unwrap();
+    let (resolver, background) =
+        AsyncResolver::new(ResolverConfig::default(), ResolverOpts::default());
+    rt.spawn(background);
+    let mut resolver = Resolver::new(resolver);
+
+    // An address is resolved to itself, without querying a server.
+    let name = Name::Host("10.0.0.1".into(), 80);
+    let addrs = rt.block_on(resolver.call(name)).unwrap();
+    assert_eq!(addrs, vec![addr("10.0.0.1:80")]);
+}
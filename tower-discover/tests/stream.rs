@@ -0,0 +1,31 @@
+extern crate futures;
+extern crate tower_discover;
+extern crate tower_mock;
+
+use futures::{stream, Async};
+use tower_discover::{Change, Discover, ServiceStream};
+use tower_mock::Mock;
+
+type Svc = Mock<(), ()>;
+
+#[test]
+fn yields_changes_from_stream() {
+    let (svc, _handle) = Svc::new();
+    let changes = stream::iter_ok::<_, ()>(vec![Change::Insert("a", svc), Change::Remove("a")]);
+    let mut discover = ServiceStream::new::<&'static str, Svc, ()>(changes);
+
+    match discover.poll() {
+        Ok(Async::Ready(Change::Insert("a", _))) => {}
+        _ => panic!("expected the insertion of a"),
+    }
+    match discover.poll() {
+        Ok(Async::Ready(Change::Remove("a"))) => {}
+        _ => panic!("expected the removal of a"),
+    }
+
+    // The stream has ended.
+    match discover.poll() {
+        Ok(Async::NotReady) => {}
+        _ => panic!("expected no more changes"),
+    }
+}
@@ -0,0 +1,164 @@
+//! Annotating responses with how they were hedged.
+//!
+//! A `Hedge` hides the copies of requests it sends from its callers.
+//! `Hedge::annotated` and `HedgeLayer::annotated` wrap each response in an
+//! `Annotated`, telling whether a copy was sent, whether the copy answered,
+//! and how long the response took, so that callers and logging layers can
+//! explain slow requests.
+
+use crate::future::ResponseFuture;
+use crate::never::Never;
+use crate::{Error, Hedge, HedgeLayer, Policy};
+use futures::{Async, Future, Poll};
+use std::fmt;
+use std::time::Duration;
+use tokio_timer::clock;
+use tower_layer::Layer;
+use tower_service::Service;
+
+/// A response along with how it was obtained.
+#[derive(Clone, Debug)]
+pub struct Annotated<T> {
+    response: T,
+    hedged: bool,
+    answered_by_hedge: bool,
+    elapsed: Duration,
+}
+
+/// A `Hedge` that annotates its responses.
+#[derive(Clone, Debug)]
+pub struct AnnotatedHedge<S, P> {
+    hedge: Hedge<S, P>,
+}
+
+/// Hedges slow requests to the inner service, annotating their responses.
+#[derive(Clone, Debug)]
+pub struct AnnotatedHedgeLayer<P> {
+    layer: HedgeLayer<P>,
+}
+
+/// The `Future` returned by an `AnnotatedHedge` service.
+pub struct AnnotatedFuture<S, Request>
+where
+    S: Service<Request>,
+{
+    inner: ResponseFuture<S, Request>,
+}
+
+// ===== impl Annotated =====
+
+impl<T> Annotated<T> {
+    /// Returns a reference to the response.
+    pub fn response(&self) -> &T {
+        &self.response
+    }
+
+    /// Consumes `self`, returning the response.
+    pub fn into_response(self) -> T {
+        self.response
+    }
+
+    /// Whether a copy of the request was sent because the original was slow.
+    pub fn hedged(&self) -> bool {
+        self.hedged
+    }
+
+    /// Whether the response came from the copy rather than the original
+    /// request.
+    pub fn answered_by_hedge(&self) -> bool {
+        self.answered_by_hedge
+    }
+
+    /// The time from the original request being sent to the response.
+    pub fn elapsed(&self) -> Duration {
+        self.elapsed
+    }
+}
+
+// ===== impl AnnotatedHedge =====
+
+impl<S, P> AnnotatedHedge<S, P> {
+    pub(crate) fn new(hedge: Hedge<S, P>) -> Self {
+        AnnotatedHedge { hedge }
+    }
+}
+
+impl<S, P, Request> Service<Request> for AnnotatedHedge<S, P>
+where
+    S: Service<Request> + Clone,
+    S::Error: Into<Error>,
+    P: Policy<Request>,
+{
+    type Response = Annotated<S::Response>;
+    type Error = Error;
+    type Future = AnnotatedFuture<S, Request>;
+
+    fn poll_ready(&mut self) -> Poll<(), Self::Error> {
+        self.hedge.poll_ready()
+    }
+
+    fn call(&mut self, request: Request) -> Self::Future {
+        AnnotatedFuture {
+            inner: self.hedge.call(request),
+        }
+    }
+}
+
+// ===== impl AnnotatedHedgeLayer =====
+
+impl<P> AnnotatedHedgeLayer<P> {
+    pub(crate) fn new(layer: HedgeLayer<P>) -> Self {
+        AnnotatedHedgeLayer { layer }
+    }
+}
+
+impl<S, P, Request> Layer<S, Request> for AnnotatedHedgeLayer<P>
+where
+    S: Service<Request> + Clone,
+    S::Error: Into<Error>,
+    P: Policy<Request> + Clone,
+{
+    type Response = Annotated<S::Response>;
+    type Error = Error;
+    type LayerError = Never;
+    type Service = AnnotatedHedge<S, P>;
+
+    fn layer(&self, service: S) -> Result<Self::Service, Self::LayerError> {
+        let hedge = self.layer.layer(service)?;
+        Ok(AnnotatedHedge::new(hedge))
+    }
+}
+
+// ===== impl AnnotatedFuture =====
+
+impl<S, Request> Future for AnnotatedFuture<S, Request>
+where
+    S: Service<Request>,
+    S::Error: Into<Error>,
+{
+    type Item = Annotated<S::Response>;
+    type Error = Error;
+
+    fn poll(&mut self) -> Poll<Self::Item, Self::Error> {
+        let response = try_ready!(self.inner.poll());
+
+        Ok(Async::Ready(Annotated {
+            response,
+            hedged: self.inner.hedged(),
+            answered_by_hedge: self.inner.answered_by_hedge(),
+            elapsed: clock::now() - self.inner.started(),
+        }))
+    }
+}
+
+impl<S, Request> fmt::Debug for AnnotatedFuture<S, Request>
+where
+    S: Service<Request>,
+    S::Future: fmt::Debug,
+{
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_struct("AnnotatedFuture")
+            .field("inner", &self.inner)
+            .finish()
+    }
+}
@@ -0,0 +1,191 @@
+//! Future types
+
+use crate::latency::Latencies;
+use crate::Error;
+use futures::{Async, Future, Poll};
+use std::fmt;
+use std::sync::{Arc, Mutex};
+use std::time::Instant;
+use tokio_timer::{clock, Delay};
+use tower_service::Service;
+
+/// `Hedge` response future
+pub struct ResponseFuture<S, Request>
+where
+    S: Service<Request>,
+{
+    original: Option<S::Future>,
+    /// When the original request was sent, from which the latency of either
+    /// response is measured.
+    started: Instant,
+    hedge: State<S, Request>,
+    latencies: Arc<Mutex<Latencies>>,
+    /// Whether a copy of the request was sent.
+    hedged: bool,
+    /// Whether the response came from the copy rather than the original.
+    answered_by_hedge: bool,
+}
+
+enum State<S, Request>
+where
+    S: Service<Request>,
+{
+    /// Waiting for the original request to become slow.
+    Waiting(Delay, S, Request),
+    /// Waiting for the service to be ready for the hedged request.
+    Dispatching(S, Request),
+    /// The hedged request was sent.
+    Called(S::Future),
+    /// The request is not (or no longer) hedged.
+    None,
+}
+
+impl<S, Request> ResponseFuture<S, Request>
+where
+    S: Service<Request>,
+    S::Error: Into<Error>,
+{
+    pub(crate) fn new(
+        original: S::Future,
+        started: Instant,
+        hedge: Option<(Delay, S, Request)>,
+        latencies: Arc<Mutex<Latencies>>,
+    ) -> Self {
+        let hedge = match hedge {
+            Some((delay, service, request)) => State::Waiting(delay, service, request),
+            None => State::None,
+        };
+
+        ResponseFuture {
+            original: Some(original),
+            started,
+            hedge,
+            latencies,
+            hedged: false,
+            answered_by_hedge: false,
+        }
+    }
+
+    pub(crate) fn started(&self) -> Instant {
+        self.started
+    }
+
+    pub(crate) fn hedged(&self) -> bool {
+        self.hedged
+    }
+
+    pub(crate) fn answered_by_hedge(&self) -> bool {
+        self.answered_by_hedge
+    }
+
+    /// Sends the hedged request once the original is slow enough.
+    fn poll_hedge(&mut self) {
+        loop {
+            self.hedge = match ::std::mem::replace(&mut self.hedge, State::None) {
+                State::Waiting(mut delay, service, request) => match delay.poll() {
+                    Ok(Async::NotReady) => {
+                        self.hedge = State::Waiting(delay, service, request);
+                        return;
+                    }
+                    Ok(Async::Ready(())) => State::Dispatching(service, request),
+                    // Without a timer, the request is not hedged.
+                    Err(_) => return,
+                },
+                State::Dispatching(mut service, request) => match service.poll_ready() {
+                    Ok(Async::NotReady) => {
+                        self.hedge = State::Dispatching(service, request);
+                        return;
+                    }
+                    Ok(Async::Ready(())) => {
+                        self.hedged = true;
+                        State::Called(service.call(request))
+                    }
+                    // The original request may still succeed.
+                    Err(_) => return,
+                },
+                state => {
+                    self.hedge = state;
+                    return;
+                }
+            };
+        }
+    }
+}
+
+impl<S, Request> Future for ResponseFuture<S, Request>
+where
+    S: Service<Request>,
+    S::Error: Into<Error>,
+{
+    type Item = S::Response;
+    type Error = Error;
+
+    fn poll(&mut self) -> Poll<Self::Item, Self::Error> {
+        if let Some(mut future) = self.original.take() {
+            match future.poll() {
+                Ok(Async::Ready(response)) => {
+                    record(&self.latencies, self.started);
+                    return Ok(Async::Ready(response));
+                }
+                Ok(Async::NotReady) => self.original = Some(future),
+                Err(e) => {
+                    // A failed request is not hedged; only wait for a hedge
+                    // that has already been sent.
+                    match self.hedge {
+                        State::Called(..) => {}
+                        _ => return Err(e.into()),
+                    }
+                }
+            }
+        }
+
+        self.poll_hedge();
+
+        let failed = match self.hedge {
+            State::Called(ref mut future) => match future.poll() {
+                Ok(Async::Ready(response)) => {
+                    // The caller waited for the hedge since the original
+                    // request was sent.
+                    record(&self.latencies, self.started);
+                    self.answered_by_hedge = true;
+                    return Ok(Async::Ready(response));
+                }
+                Ok(Async::NotReady) => false,
+                Err(e) => {
+                    if self.original.is_none() {
+                        return Err(e.into());
+                    }
+                    true
+                }
+            },
+            _ => false,
+        };
+
+        if failed {
+            // The original request may still succeed.
+            self.hedge = State::None;
+        }
+
+        Ok(Async::NotReady)
+    }
+}
+
+impl<S, Request> fmt::Debug for ResponseFuture<S, Request>
+where
+    S: Service<Request>,
+    S::Future: fmt::Debug,
+{
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_struct("ResponseFuture")
+            .field("original", &self.original)
+            .finish()
+    }
+}
+
+fn record(latencies: &Mutex<Latencies>, started: Instant) {
+    let latency = clock::now() - started;
+
+    if let Ok(mut latencies) = latencies.lock() {
+        latencies.record(latency);
+    }
+}
@@ -0,0 +1,72 @@
+use std::cmp;
+use std::collections::VecDeque;
+use std::time::Duration;
+
+/// Tracks the latencies of recent requests, and the percentile of them past
+/// which requests are hedged.
+#[derive(Debug)]
+pub(crate) struct Latencies {
+    samples: VecDeque<Duration>,
+    window: usize,
+    min_samples: usize,
+    percentile: f64,
+    threshold: Option<Duration>,
+    /// Samples recorded since `threshold` was last computed.
+    stale: usize,
+}
+
+impl Latencies {
+    pub(crate) fn new(percentile: f64, min_samples: usize, window: usize) -> Self {
+        assert!(
+            percentile >= 0.0 && percentile <= 100.0,
+            "percentile must be within 0..=100"
+        );
+        assert!(window > 0, "window must be greater than zero");
+
+        Latencies {
+            samples: VecDeque::with_capacity(window),
+            window,
+            min_samples,
+            percentile,
+            threshold: None,
+            stale: 0,
+        }
+    }
+
+    /// Returns how long a request may take before it is hedged, or `None` if
+    /// too few requests have completed to tell.
+    pub(crate) fn threshold(&self) -> Option<Duration> {
+        self.threshold
+    }
+
+    pub(crate) fn record(&mut self, latency: Duration) {
+        if self.samples.len() == self.window {
+            self.samples.pop_front();
+        }
+        self.samples.push_back(latency);
+        self.stale += 1;
+
+        // Sorting the window for every request would be wasteful, so the
+        // threshold is only brought up to date every so often.
+        let interval = cmp::max(1, self.window / 16);
+        if self.stale >= interval || self.threshold.is_none() {
+            self.update();
+        }
+    }
+
+    fn update(&mut self) {
+        self.stale = 0;
+
+        if self.samples.len() < cmp::max(1, self.min_samples) {
+            self.threshold = None;
+            return;
+        }
+
+        let mut sorted: Vec<_> = self.samples.iter().cloned().collect();
+        sorted.sort();
+
+        let rank = (self.percentile / 100.0 * sorted.len() as f64).ceil() as usize;
+        let idx = cmp::min(rank.saturating_sub(1), sorted.len() - 1);
+        self.threshold = Some(sorted[idx]);
+    }
+}
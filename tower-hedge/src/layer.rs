@@ -0,0 +1,70 @@
+use crate::annotate::AnnotatedHedgeLayer;
+use crate::never::Never;
+use crate::{Error, Hedge, Policy};
+use tower_layer::Layer;
+use tower_service::Service;
+
+/// Hedges slow requests to the inner service.
+///
+/// Each service produced by the layer tracks the latencies of its own
+/// requests.
+#[derive(Debug, Clone)]
+pub struct HedgeLayer<P> {
+    policy: P,
+    percentile: f64,
+    min_samples: usize,
+    window: usize,
+}
+
+impl<P> HedgeLayer<P> {
+    /// Create a new `HedgeLayer`, hedging requests once they are slower than
+    /// `percentile` percent of recent requests.
+    pub fn new(policy: P, percentile: f64) -> Self {
+        HedgeLayer {
+            policy,
+            percentile,
+            min_samples: 10,
+            window: 1000,
+        }
+    }
+
+    /// Set how many requests must have completed before any request is
+    /// hedged.
+    pub fn min_samples(mut self, min_samples: usize) -> Self {
+        self.min_samples = min_samples;
+        self
+    }
+
+    /// Set how many of the most recent requests the percentile is computed
+    /// over.
+    pub fn window(mut self, window: usize) -> Self {
+        self.window = window;
+        self
+    }
+
+    /// Annotate the responses of the produced services with whether the
+    /// request was hedged, and how long the response took.
+    ///
+    /// See [`annotate`](../annotate/index.html) for details.
+    pub fn annotated(self) -> AnnotatedHedgeLayer<P> {
+        AnnotatedHedgeLayer::new(self)
+    }
+}
+
+impl<S, P, Request> Layer<S, Request> for HedgeLayer<P>
+where
+    S: Service<Request> + Clone,
+    S::Error: Into<Error>,
+    P: Policy<Request> + Clone,
+{
+    type Response = S::Response;
+    type Error = Error;
+    type LayerError = Never;
+    type Service = Hedge<S, P>;
+
+    fn layer(&self, service: S) -> Result<Self::Service, Self::LayerError> {
+        Ok(Hedge::new(service, self.policy.clone(), self.percentile)
+            .min_samples(self.min_samples)
+            .window(self.window))
+    }
+}
@@ -0,0 +1,204 @@
+//! Tower middleware that hedges slow requests.
+//!
+//! A few requests take much longer than the rest, often because of a slow
+//! replica, a garbage collection pause or a lost packet rather than anything
+//! about the request itself. `Hedge` tracks the latencies of recent requests,
+//! and once a request has been outstanding for longer than most of them, it
+//! sends a second copy of it. Whichever response arrives first is used, and
+//! the other request is cancelled by dropping its response future.
+//!
+//! Hedging trades a little extra load for much lower tail latency. It is
+//! only safe for requests that may be processed twice, which the `Policy`
+//! decides.
+
+#![doc(html_root_url = "https://docs.rs/tower-hedge/0.1.0")]
+#![deny(missing_debug_implementations, missing_docs)]
+#![cfg_attr(test, deny(warnings))]
+
+#[macro_use]
+extern crate futures;
+extern crate tokio_timer;
+extern crate tower_layer;
+extern crate tower_service;
+extern crate tower_util;
+
+pub mod annotate;
+pub mod future;
+mod latency;
+mod layer;
+mod never;
+
+pub use crate::layer::HedgeLayer;
+
+use crate::annotate::AnnotatedHedge;
+use crate::future::ResponseFuture;
+use crate::latency::Latencies;
+use futures::Poll;
+use std::sync::{Arc, Mutex};
+use tokio_timer::{clock, Delay};
+use tower_service::Service;
+use tower_util::{Unready, UnreadyReason};
+
+type Error = Box<::std::error::Error + Send + Sync>;
+
+/// Decides which requests may be hedged.
+pub trait Policy<Request> {
+    /// Returns a copy of `req` to send as a hedge, or `None` if it must not
+    /// be hedged, e.g. because processing it twice is not safe.
+    fn clone_request(&self, req: &Request) -> Option<Request>;
+}
+
+impl<F, Request> Policy<Request> for F
+where
+    F: Fn(&Request) -> Option<Request>,
+{
+    fn clone_request(&self, req: &Request) -> Option<Request> {
+        (self)(req)
+    }
+}
+
+/// Sends a second copy of requests that take longer than a percentile of
+/// recent requests.
+///
+/// The inner service is cloned for each hedged request, so that the copy can
+/// be sent once the service is ready again. No request is hedged until at
+/// least `min_samples` requests have completed (10 by default). The
+/// percentile is computed over the latest `window` requests (1000 by
+/// default), shared by all clones of the service.
+#[derive(Debug)]
+pub struct Hedge<S, P> {
+    inner: S,
+    policy: P,
+    latencies: Arc<Mutex<Latencies>>,
+    percentile: f64,
+    min_samples: usize,
+    window: usize,
+}
+
+// ===== impl Hedge =====
+
+impl<S, P> Hedge<S, P> {
+    /// Create a new `Hedge`, sending a copy of requests to `inner` once they
+    /// are slower than `percentile` percent of recent requests.
+    ///
+    /// # Panics
+    ///
+    /// This function panics if `percentile` is not within `0.0..=100.0`.
+    pub fn new(inner: S, policy: P, percentile: f64) -> Self {
+        Hedge {
+            inner,
+            policy,
+            latencies: Arc::new(Mutex::new(Latencies::new(percentile, 10, 1000))),
+            percentile,
+            min_samples: 10,
+            window: 1000,
+        }
+    }
+
+    /// Set how many requests must have completed before any request is
+    /// hedged.
+    pub fn min_samples(mut self, min_samples: usize) -> Self {
+        self.min_samples = min_samples;
+        self.reset_latencies()
+    }
+
+    /// Set how many of the most recent requests the percentile is computed
+    /// over.
+    ///
+    /// # Panics
+    ///
+    /// This function panics if `window` is 0.
+    pub fn window(mut self, window: usize) -> Self {
+        self.window = window;
+        self.reset_latencies()
+    }
+
+    fn reset_latencies(mut self) -> Self {
+        let latencies = Latencies::new(self.percentile, self.min_samples, self.window);
+        self.latencies = Arc::new(Mutex::new(latencies));
+        self
+    }
+
+    /// Annotate responses with whether the request was hedged, and how long
+    /// the response took.
+    ///
+    /// See [`annotate`](annotate/index.html) for details.
+    pub fn annotated(self) -> AnnotatedHedge<S, P> {
+        AnnotatedHedge::new(self)
+    }
+
+    /// Get a reference to the inner service
+    pub fn get_ref(&self) -> &S {
+        &self.inner
+    }
+
+    /// Get a mutable reference to the inner service
+    pub fn get_mut(&mut self) -> &mut S {
+        &mut self.inner
+    }
+
+    /// Consume `self`, returning the inner service
+    pub fn into_inner(self) -> S {
+        self.inner
+    }
+}
+
+impl<S, P, Request> Service<Request> for Hedge<S, P>
+where
+    S: Service<Request> + Clone,
+    S::Error: Into<Error>,
+    P: Policy<Request>,
+{
+    type Response = S::Response;
+    type Error = Error;
+    type Future = ResponseFuture<S, Request>;
+
+    fn poll_ready(&mut self) -> Poll<(), Self::Error> {
+        self.inner.poll_ready().map_err(Into::into)
+    }
+
+    fn call(&mut self, request: Request) -> Self::Future {
+        let threshold = self
+            .latencies
+            .lock()
+            .expect("hedge latencies poisoned")
+            .threshold();
+
+        let now = clock::now();
+        let hedge = threshold.and_then(|threshold| {
+            let copy = self.policy.clone_request(&request)?;
+            Some((Delay::new(now + threshold), self.inner.clone(), copy))
+        });
+
+        let future = self.inner.call(request);
+        ResponseFuture::new(future, now, hedge, self.latencies.clone())
+    }
+}
+
+impl<S, P, Request> Unready<Request> for Hedge<S, P>
+where
+    S: Unready<Request> + Clone,
+    S::Error: Into<Error>,
+    P: Policy<Request>,
+{
+    fn unready_reason(&self) -> Option<UnreadyReason> {
+        self.inner.unready_reason()
+    }
+}
+
+impl<S, P> Clone for Hedge<S, P>
+where
+    S: Clone,
+    P: Clone,
+{
+    fn clone(&self) -> Self {
+        Hedge {
+            inner: self.inner.clone(),
+            policy: self.policy.clone(),
+            latencies: self.latencies.clone(),
+            percentile: self.percentile,
+            min_samples: self.min_samples,
+            window: self.window,
+        }
+    }
+}
@@ -0,0 +1,106 @@
+extern crate futures;
+extern crate tokio;
+extern crate tower_hedge;
+extern crate tower_mock;
+extern crate tower_service;
+
+use futures::{future, Future};
+use std::thread;
+use tower_hedge::Hedge;
+use tower_service::Service;
+
+type Req = &'static str;
+type Mock = tower_mock::Mock<Req, &'static str>;
+
+fn copy(req: &Req) -> Option<Req> {
+    Some(*req)
+}
+
+#[test]
+fn hedges_slow_requests() {
+    let mut rt = tokio::runtime::current_thread::Runtime::new().unwrap();
+    let (service, mut handle) = Mock::new();
+    let mut service = Hedge::new(service, copy as fn(&Req) -> Option<Req>, 50.0).min_samples(1);
+
+    // A fast request sets the bar for hedging.
+    assert!(service.poll_ready().unwrap().is_ready());
+    let response = service.call("warm up");
+    handle.next_request().unwrap().respond("ok");
+    assert_eq!(rt.block_on(response).unwrap(), "ok");
+
+    assert!(service.poll_ready().unwrap().is_ready());
+    let response = service.call("hello");
+
+    let responder = thread::spawn(move || {
+        // The original request never completes...
+        let original = handle.next_request().unwrap();
+        assert_eq!(*original, "hello");
+
+        // ...so a copy is sent, which wins.
+        let hedge = handle.next_request().unwrap();
+        assert_eq!(*hedge, "hello");
+        hedge.respond("hedged");
+
+        original
+    });
+
+    assert_eq!(rt.block_on(response).unwrap(), "hedged");
+    drop(responder.join().unwrap());
+}
+
+#[test]
+fn does_not_hedge_without_samples() {
+    let mut rt = tokio::runtime::current_thread::Runtime::new().unwrap();
+    let (service, mut handle) = Mock::new();
+    let mut service = Hedge::new(service, copy as fn(&Req) -> Option<Req>, 50.0);
+
+    assert!(service.poll_ready().unwrap().is_ready());
+    let mut response = service.call("hello");
+
+    let request = handle.next_request().unwrap();
+    rt.block_on(future::lazy(|| {
+        assert!(response.poll().unwrap().is_not_ready());
+        Ok::<_, ()>(())
+    }))
+    .unwrap();
+
+    request.respond("world");
+    assert_eq!(rt.block_on(response).unwrap(), "world");
+
+    rt.block_on(future::lazy(|| {
+        assert!(handle.poll_request().unwrap().is_not_ready());
+        Ok::<_, ()>(())
+    }))
+    .unwrap();
+}
+
+#[test]
+fn annotates_hedged_responses() {
+    let mut rt = tokio::runtime::current_thread::Runtime::new().unwrap();
+    let (service, mut handle) = Mock::new();
+    let mut service = Hedge::new(service, copy as fn(&Req) -> Option<Req>, 50.0)
+        .min_samples(1)
+        .annotated();
+
+    assert!(service.poll_ready().unwrap().is_ready());
+    let response = service.call("warm up");
+    handle.next_request().unwrap().respond("ok");
+    let response = rt.block_on(response).unwrap();
+    assert!(!response.hedged());
+    assert!(!response.answered_by_hedge());
+
+    assert!(service.poll_ready().unwrap().is_ready());
+    let response = service.call("hello");
+
+    let responder = thread::spawn(move || {
+        let original = handle.next_request().unwrap();
+        handle.next_request().unwrap().respond("hedged");
+        original
+    });
+
+    let response = rt.block_on(response).unwrap();
+    assert!(response.hedged());
+    assert!(response.answered_by_hedge());
+    assert_eq!(*response.response(), "hedged");
+    drop(responder.join().unwrap());
+}
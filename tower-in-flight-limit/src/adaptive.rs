@@ -0,0 +1,502 @@
+//! An in-flight limit that adapts to the inner service.
+//!
+//! A static in-flight limit is hard to tune: too low and throughput suffers,
+//! too high and the inner service is overloaded. `AdaptiveInFlightLimit`
+//! instead adjusts its limit using additive-increase/multiplicative-decrease
+//! (AIMD): every successful response grows the limit by one over the course
+//! of a full window of requests, while every failure (an error, or a response
+//! slower than the configured latency threshold) shrinks it by a constant
+//! factor.
+//!
+//! Optionally, the limit can also back off when the local scheduler is
+//! saturated, so that the host itself is protected and not only the inner
+//! service. See [`Aimd::scheduler_load`](struct.Aimd.html#method.scheduler_load).
+
+use futures::task::{self, Task};
+use futures::{Async, Future, Poll};
+use std::fmt;
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+use tokio_timer::clock;
+use tower_layer::Layer;
+use tower_service::Service;
+use tower_util::{Unready, UnreadyReason};
+use {Error, Never};
+
+/// Configures how an `AdaptiveInFlightLimit` adjusts its limit.
+#[derive(Clone)]
+pub struct Aimd {
+    initial: usize,
+    min: usize,
+    max: usize,
+    decrease: f64,
+    latency: Option<Duration>,
+    scheduler: Option<Scheduler>,
+}
+
+/// Signals reported by the scheduler running the service.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct SchedulerLoad {
+    poll_duration: Duration,
+    budget_exhausted: u64,
+}
+
+#[derive(Clone)]
+struct Scheduler {
+    load: Arc<Fn() -> SchedulerLoad + Send + Sync>,
+    max_poll_duration: Duration,
+}
+
+/// Limits the number of in-flight requests, adapting the limit to the
+/// responses of the inner service.
+///
+/// Clones share the same limit.
+#[derive(Debug)]
+pub struct AdaptiveInFlightLimit<T> {
+    inner: T,
+    limit: Arc<Mutex<Limit>>,
+    acquired: bool,
+}
+
+/// Applies an adaptive in-flight limit to services.
+#[derive(Debug, Clone)]
+pub struct AdaptiveInFlightLimitLayer {
+    aimd: Aimd,
+}
+
+/// Future returned by `AdaptiveInFlightLimit`.
+#[derive(Debug)]
+pub struct ResponseFuture<T> {
+    inner: T,
+    limit: Arc<Mutex<Limit>>,
+    started: Instant,
+    released: bool,
+}
+
+#[derive(Debug)]
+struct Limit {
+    aimd: Aimd,
+    limit: f64,
+    in_flight: usize,
+    /// The scheduler's budget exhaustion count as of the last response.
+    budget_exhausted: u64,
+    waiters: Vec<Task>,
+}
+
+// ===== impl Aimd =====
+
+impl Aimd {
+    /// Create a new configuration.
+    ///
+    /// The limit starts at 10 and is kept between 1 and 1000. It is reduced
+    /// to 90% on failure, and responses are never considered too slow.
+    pub fn new() -> Self {
+        Aimd {
+            initial: 10,
+            min: 1,
+            max: 1000,
+            decrease: 0.9,
+            latency: None,
+            scheduler: None,
+        }
+    }
+
+    /// Set the limit to start with.
+    pub fn initial(mut self, initial: usize) -> Self {
+        self.initial = initial;
+        self
+    }
+
+    /// Set the lowest limit.
+    ///
+    /// # Panics
+    ///
+    /// This function panics if `min` is 0.
+    pub fn min(mut self, min: usize) -> Self {
+        assert!(min > 0, "the limit must allow at least one request");
+        self.min = min;
+        self
+    }
+
+    /// Set the highest limit.
+    pub fn max(mut self, max: usize) -> Self {
+        self.max = max;
+        self
+    }
+
+    /// Set the factor the limit is multiplied by on failure.
+    ///
+    /// # Panics
+    ///
+    /// This function panics if `decrease` is not between 0 and 1.
+    pub fn decrease_factor(mut self, decrease: f64) -> Self {
+        assert!(0.0 < decrease && decrease < 1.0);
+        self.decrease = decrease;
+        self
+    }
+
+    /// Checks that the limit starts between the lowest and highest limits.
+    ///
+    /// The setters may be called in any order, so the limits are only checked
+    /// once they are all set.
+    fn validate(&self) {
+        assert!(
+            self.min <= self.initial && self.initial <= self.max,
+            "the initial limit ({}) must be between the lowest ({}) and highest ({}) limits",
+            self.initial,
+            self.min,
+            self.max
+        );
+    }
+
+    /// Treat responses slower than `latency` as failures.
+    pub fn latency_threshold(mut self, latency: Duration) -> Self {
+        self.latency = Some(latency);
+        self
+    }
+
+    /// Treat responses completed while the local scheduler is saturated as
+    /// failures.
+    ///
+    /// `load` is called as each response completes, and should report the
+    /// recent poll duration of tasks and the total number of times tasks
+    /// have exhausted their budget. The scheduler is considered saturated if
+    /// tasks take longer than `max_poll_duration` to poll, or if any task
+    /// exhausted its budget since the last response.
+    ///
+    /// `load` is called while the limit is locked, so it should be cheap,
+    /// e.g. reading a few atomics updated by the executor.
+    pub fn scheduler_load<F>(mut self, max_poll_duration: Duration, load: F) -> Self
+    where
+        F: Fn() -> SchedulerLoad + Send + Sync + 'static,
+    {
+        self.scheduler = Some(Scheduler {
+            load: Arc::new(load),
+            max_poll_duration,
+        });
+        self
+    }
+}
+
+impl fmt::Debug for Aimd {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_struct("Aimd")
+            .field("initial", &self.initial)
+            .field("min", &self.min)
+            .field("max", &self.max)
+            .field("decrease", &self.decrease)
+            .field("latency", &self.latency)
+            .field(
+                "max_poll_duration",
+                &self.scheduler.as_ref().map(|s| s.max_poll_duration),
+            )
+            .finish()
+    }
+}
+
+impl Default for Aimd {
+    fn default() -> Self {
+        Aimd::new()
+    }
+}
+
+// ===== impl SchedulerLoad =====
+
+impl SchedulerLoad {
+    /// Create a new report of the scheduler's load.
+    ///
+    /// `poll_duration` is how long tasks recently took to be polled, and
+    /// `budget_exhausted` is the total number of times tasks have exhausted
+    /// their budget.
+    pub fn new(poll_duration: Duration, budget_exhausted: u64) -> Self {
+        SchedulerLoad {
+            poll_duration,
+            budget_exhausted,
+        }
+    }
+
+    /// Returns how long tasks recently took to be polled.
+    pub fn poll_duration(&self) -> Duration {
+        self.poll_duration
+    }
+
+    /// Returns the total number of times tasks have exhausted their budget.
+    pub fn budget_exhausted(&self) -> u64 {
+        self.budget_exhausted
+    }
+}
+
+// ===== impl AdaptiveInFlightLimit =====
+
+impl<T> AdaptiveInFlightLimit<T> {
+    /// Create a new adaptive in-flight limiter.
+    ///
+    /// # Panics
+    ///
+    /// This function panics if the initial limit of `aimd` is not between its
+    /// lowest and highest limits.
+    pub fn new<Request>(inner: T, aimd: Aimd) -> Self
+    where
+        T: Service<Request>,
+    {
+        aimd.validate();
+
+        let limit = Limit {
+            limit: clamp(aimd.initial as f64, &aimd),
+            aimd,
+            in_flight: 0,
+            budget_exhausted: 0,
+            waiters: Vec::new(),
+        };
+
+        AdaptiveInFlightLimit {
+            inner,
+            limit: Arc::new(Mutex::new(limit)),
+            acquired: false,
+        }
+    }
+
+    /// Returns the current limit.
+    pub fn current_limit(&self) -> usize {
+        self.lock().current()
+    }
+
+    /// Returns the number of requests currently in flight.
+    pub fn in_flight(&self) -> usize {
+        self.lock().in_flight
+    }
+
+    /// Get a reference to the inner service
+    pub fn get_ref(&self) -> &T {
+        &self.inner
+    }
+
+    /// Get a mutable reference to the inner service
+    pub fn get_mut(&mut self) -> &mut T {
+        &mut self.inner
+    }
+
+    fn lock(&self) -> ::std::sync::MutexGuard<Limit> {
+        self.limit.lock().expect("adaptive limit poisoned")
+    }
+}
+
+impl<S, Request> Service<Request> for AdaptiveInFlightLimit<S>
+where
+    S: Service<Request>,
+    S::Error: Into<Error>,
+{
+    type Response = S::Response;
+    type Error = Error;
+    type Future = ResponseFuture<S::Future>;
+
+    fn poll_ready(&mut self) -> Poll<(), Self::Error> {
+        if !self.acquired {
+            let mut limit = self.lock();
+
+            if limit.in_flight < limit.current() {
+                limit.in_flight += 1;
+            } else {
+                if !limit.waiters.iter().any(Task::will_notify_current) {
+                    limit.waiters.push(task::current());
+                }
+
+                // Each service waits from one task at a time, and the
+                // response futures holding the limit never wait.
+                #[cfg(feature = "audit-bounds")]
+                assert!(
+                    limit.waiters.len() <= Arc::strong_count(&self.limit),
+                    "adaptive limit has {} waiters, exceeding its {} handles",
+                    limit.waiters.len(),
+                    Arc::strong_count(&self.limit)
+                );
+                return Ok(Async::NotReady);
+            }
+
+            drop(limit);
+            self.acquired = true;
+        }
+
+        self.inner.poll_ready().map_err(Into::into)
+    }
+
+    fn call(&mut self, request: Request) -> Self::Future {
+        assert!(
+            self.acquired,
+            "max requests in-flight; poll_ready must be called first"
+        );
+        self.acquired = false;
+
+        ResponseFuture {
+            inner: self.inner.call(request),
+            limit: self.limit.clone(),
+            started: clock::now(),
+            released: false,
+        }
+    }
+}
+
+impl<S, Request> Unready<Request> for AdaptiveInFlightLimit<S>
+where
+    S: Unready<Request>,
+    S::Error: Into<Error>,
+{
+    fn unready_reason(&self) -> Option<UnreadyReason> {
+        if !self.acquired {
+            return Some(UnreadyReason::InFlightLimit);
+        }
+
+        self.inner.unready_reason()
+    }
+}
+
+impl<S> Clone for AdaptiveInFlightLimit<S>
+where
+    S: Clone,
+{
+    fn clone(&self) -> Self {
+        AdaptiveInFlightLimit {
+            inner: self.inner.clone(),
+            limit: self.limit.clone(),
+            acquired: false,
+        }
+    }
+}
+
+impl<S> Drop for AdaptiveInFlightLimit<S> {
+    fn drop(&mut self) {
+        if self.acquired {
+            if let Ok(mut limit) = self.limit.lock() {
+                limit.release(None);
+            }
+        }
+    }
+}
+
+// ===== impl AdaptiveInFlightLimitLayer =====
+
+impl AdaptiveInFlightLimitLayer {
+    /// Create a new layer, applying the limits of `aimd`.
+    ///
+    /// # Panics
+    ///
+    /// This function panics if the initial limit of `aimd` is not between its
+    /// lowest and highest limits.
+    pub fn new(aimd: Aimd) -> Self {
+        aimd.validate();
+        AdaptiveInFlightLimitLayer { aimd }
+    }
+}
+
+impl<S, Request> Layer<S, Request> for AdaptiveInFlightLimitLayer
+where
+    S: Service<Request>,
+    S::Error: Into<Error>,
+{
+    type Response = S::Response;
+    type Error = Error;
+    type LayerError = Never;
+    type Service = AdaptiveInFlightLimit<S>;
+
+    fn layer(&self, service: S) -> Result<Self::Service, Self::LayerError> {
+        Ok(AdaptiveInFlightLimit::new(service, self.aimd.clone()))
+    }
+}
+
+// ===== impl ResponseFuture =====
+
+impl<T> ResponseFuture<T> {
+    fn release(&mut self, success: bool) {
+        self.released = true;
+
+        if let Ok(mut limit) = self.limit.lock() {
+            limit.release(Some(success));
+        }
+    }
+}
+
+impl<T> Future for ResponseFuture<T>
+where
+    T: Future,
+    T::Error: Into<Error>,
+{
+    type Item = T::Item;
+    type Error = Error;
+
+    fn poll(&mut self) -> Poll<Self::Item, Self::Error> {
+        match self.inner.poll() {
+            Ok(Async::Ready(rsp)) => {
+                let elapsed = clock::now() - self.started;
+                let slow = self
+                    .limit
+                    .lock()
+                    .ok()
+                    .and_then(|limit| limit.aimd.latency)
+                    .map(|latency| elapsed > latency)
+                    .unwrap_or(false);
+
+                self.release(!slow);
+                Ok(Async::Ready(rsp))
+            }
+            Ok(Async::NotReady) => Ok(Async::NotReady),
+            Err(e) => {
+                self.release(false);
+                Err(e.into())
+            }
+        }
+    }
+}
+
+impl<T> Drop for ResponseFuture<T> {
+    fn drop(&mut self) {
+        if !self.released {
+            // The response was canceled, which says nothing about the health
+            // of the inner service.
+            if let Ok(mut limit) = self.limit.lock() {
+                limit.release(None);
+            }
+        }
+    }
+}
+
+// ===== impl Limit =====
+
+impl Limit {
+    fn current(&self) -> usize {
+        self.limit as usize
+    }
+
+    /// Releases an in-flight request, adjusting the limit by its outcome.
+    fn release(&mut self, success: Option<bool>) {
+        self.in_flight -= 1;
+
+        let success = success.map(|success| success && !self.is_saturated());
+        self.limit = match success {
+            Some(true) => clamp(self.limit + 1.0 / self.limit, &self.aimd),
+            Some(false) => clamp(self.limit * self.aimd.decrease, &self.aimd),
+            None => self.limit,
+        };
+
+        for task in self.waiters.drain(..) {
+            task.notify();
+        }
+    }
+
+    /// Returns `true` if the scheduler reports that the host is saturated.
+    fn is_saturated(&mut self) -> bool {
+        let scheduler = match self.aimd.scheduler {
+            Some(ref scheduler) => scheduler,
+            None => return false,
+        };
+
+        let load = (scheduler.load)();
+        let exhausted = load.budget_exhausted > self.budget_exhausted;
+        self.budget_exhausted = load.budget_exhausted;
+
+        exhausted || load.poll_duration > scheduler.max_poll_duration
+    }
+}
+
+fn clamp(limit: f64, aimd: &Aimd) -> f64 {
+    limit.max(aimd.min as f64).min(aimd.max as f64)
+}
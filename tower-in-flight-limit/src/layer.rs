@@ -1,3 +1,5 @@
+use std::sync::Arc;
+use tokio_sync::semaphore::Semaphore;
 use tower_layer::Layer;
 use tower_service::Service;
 use {Error, InFlightLimit, Never};
@@ -27,3 +29,37 @@ where
         Ok(InFlightLimit::new(service, self.max))
     }
 }
+
+/// Limits the number of in-flight requests across all services it produces.
+///
+/// Each service produced by `InFlightLimitLayer` has its own limit, so a stack
+/// built once per connection may exceed the limit once per connection. All
+/// services produced by a `GlobalInFlightLimitLayer` (and clones of the layer)
+/// draw from the same pool of permits instead.
+#[derive(Debug, Clone)]
+pub struct GlobalInFlightLimitLayer {
+    semaphore: Arc<Semaphore>,
+}
+
+impl GlobalInFlightLimitLayer {
+    pub fn new(max: usize) -> Self {
+        GlobalInFlightLimitLayer {
+            semaphore: Arc::new(Semaphore::new(max)),
+        }
+    }
+}
+
+impl<S, Request> Layer<S, Request> for GlobalInFlightLimitLayer
+where
+    S: Service<Request>,
+    S::Error: Into<Error>,
+{
+    type Response = S::Response;
+    type Error = Error;
+    type LayerError = Never;
+    type Service = InFlightLimit<S>;
+
+    fn layer(&self, service: S) -> Result<Self::Service, Self::LayerError> {
+        Ok(InFlightLimit::with_semaphore(service, self.semaphore.clone()))
+    }
+}
@@ -1,23 +1,38 @@
 //! Tower middleware that limits the maximum number of in-flight requests for a
 //! service.
+//!
+//! # Auditing bounds
+//!
+//! With the `audit-bounds` feature enabled, an `AdaptiveInFlightLimit` panics
+//! if more tasks wait for its limit than there are services sharing it. The
+//! services waiting for the semaphore of an `InFlightLimit` are queued by the
+//! semaphore itself, at most once for each service, and are not audited.
 
 #[macro_use]
 extern crate futures;
 extern crate tokio_sync;
+#[cfg(feature = "adaptive")]
+extern crate tokio_timer;
 extern crate tower_layer;
 extern crate tower_service;
+extern crate tower_util;
 
+#[cfg(feature = "adaptive")]
+pub mod adaptive;
 pub mod future;
 mod layer;
 mod never;
 
+#[cfg(feature = "adaptive")]
+pub use adaptive::{AdaptiveInFlightLimit, AdaptiveInFlightLimitLayer, Aimd, SchedulerLoad};
 use future::ResponseFuture;
-pub use layer::InFlightLimitLayer;
+pub use layer::{GlobalInFlightLimitLayer, InFlightLimitLayer};
 use never::Never;
 
 use tower_service::Service;
+use tower_util::{PollReadyN, Unready, UnreadyReason};
 
-use futures::Poll;
+use futures::{Async, Poll};
 use std::sync::Arc;
 use tokio_sync::semaphore::{self, Semaphore};
 
@@ -31,6 +46,8 @@ pub struct InFlightLimit<T> {
 struct Limit {
     semaphore: Arc<Semaphore>,
     permit: semaphore::Permit,
+    /// Permits acquired by `poll_ready_n` in addition to `permit`.
+    reserved: usize,
 }
 
 type Error = Box<dyn std::error::Error + Send + Sync>;
@@ -43,11 +60,18 @@ impl<T> InFlightLimit<T> {
     where
         T: Service<Request>,
     {
+        Self::with_semaphore(inner, Arc::new(Semaphore::new(max)))
+    }
+
+    /// Limit the requests in flight on `inner` by the permits of `semaphore`,
+    /// which may be shared with other services.
+    pub(crate) fn with_semaphore(inner: T, semaphore: Arc<Semaphore>) -> Self {
         InFlightLimit {
             inner,
             limit: Limit {
-                semaphore: Arc::new(Semaphore::new(max)),
+                semaphore,
                 permit: semaphore::Permit::new(),
+                reserved: 0,
             },
         }
     }
@@ -88,8 +112,13 @@ where
     }
 
     fn call(&mut self, request: Request) -> Self::Future {
-        // Make sure a permit has been acquired
-        if self
+        // Make sure a permit has been acquired, either by `poll_ready` or as
+        // part of a batch reserved by `poll_ready_n`.
+        let reserved = !self.limit.permit.is_acquired() && self.limit.reserved > 0;
+
+        if reserved {
+            self.limit.reserved -= 1;
+        } else if self
             .limit
             .permit
             .try_acquire(&self.limit.semaphore)
@@ -102,13 +131,72 @@ where
         let future = self.inner.call(request);
 
         // Forget the permit, the permit will be returned when
-        // `future::ResponseFuture` is dropped.
-        self.limit.permit.forget();
+        // `future::ResponseFuture` is dropped. Reserved permits have already
+        // been forgotten.
+        if !reserved {
+            self.limit.permit.forget();
+        }
 
         ResponseFuture::new(future, self.limit.semaphore.clone())
     }
 }
 
+impl<S, Request> PollReadyN<Request> for InFlightLimit<S>
+where
+    S: PollReadyN<Request>,
+    S::Error: Into<Error>,
+{
+    fn poll_ready_n(&mut self, n: usize) -> Poll<usize, Self::Error> {
+        assert!(n > 0, "must reserve at least one call");
+
+        try_ready!(self
+            .limit
+            .permit
+            .poll_acquire(&self.limit.semaphore)
+            .map_err(Error::from));
+
+        // Opportunistically grab as many additional permits as are
+        // immediately available, without waiting for more.
+        while self.limit.reserved + 1 < n {
+            let mut permit = semaphore::Permit::new();
+            if permit.try_acquire(&self.limit.semaphore).is_err() {
+                break;
+            }
+            permit.forget();
+            self.limit.reserved += 1;
+        }
+
+        let ready = match self.inner.poll_ready_n(self.limit.reserved + 1) {
+            Ok(Async::Ready(ready)) => ready,
+            Ok(Async::NotReady) => return Ok(Async::NotReady),
+            Err(e) => return Err(e.into()),
+        };
+
+        // Return any permits the inner service was unable to use.
+        let excess = self.limit.reserved + 1 - ready;
+        if excess > 0 {
+            self.limit.reserved -= excess;
+            self.limit.semaphore.add_permits(excess);
+        }
+
+        Ok(Async::Ready(ready))
+    }
+}
+
+impl<S, Request> Unready<Request> for InFlightLimit<S>
+where
+    S: Unready<Request>,
+    S::Error: Into<Error>,
+{
+    fn unready_reason(&self) -> Option<UnreadyReason> {
+        if !self.limit.permit.is_acquired() && self.limit.reserved == 0 {
+            return Some(UnreadyReason::InFlightLimit);
+        }
+
+        self.inner.unready_reason()
+    }
+}
+
 impl<S> Clone for InFlightLimit<S>
 where
     S: Clone,
@@ -119,6 +207,7 @@ where
             limit: Limit {
                 semaphore: self.limit.semaphore.clone(),
                 permit: semaphore::Permit::new(),
+                reserved: 0,
             },
         }
     }
@@ -127,5 +216,9 @@ where
 impl Drop for Limit {
     fn drop(&mut self) {
         self.permit.release(&self.semaphore);
+
+        if self.reserved > 0 {
+            self.semaphore.add_permits(self.reserved);
+        }
     }
 }
@@ -0,0 +1,115 @@
+#![cfg(feature = "adaptive")]
+
+extern crate futures;
+extern crate tokio_mock_task;
+extern crate tower_in_flight_limit;
+extern crate tower_service;
+extern crate tower_util;
+
+use futures::Future;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::Arc;
+use std::time::Duration;
+use tokio_mock_task::MockTask;
+use tower_in_flight_limit::{AdaptiveInFlightLimit, Aimd, SchedulerLoad};
+use tower_service::Service;
+use tower_util::service_fn;
+
+fn new_service(
+    aimd: Aimd,
+) -> AdaptiveInFlightLimit<impl Service<bool, Response = (), Error = &'static str> + Clone> {
+    // Requests are `true` if they should succeed.
+    let inner = service_fn(|ok: bool| if ok { Ok(()) } else { Err("failed") });
+    AdaptiveInFlightLimit::new(inner, aimd)
+}
+
+#[test]
+fn decreases_on_failure() {
+    let mut service = new_service(Aimd::new().initial(4).decrease_factor(0.5));
+    assert_eq!(service.current_limit(), 4);
+
+    assert!(service.poll_ready().unwrap().is_ready());
+    assert!(service.call(false).wait().is_err());
+    assert_eq!(service.current_limit(), 2);
+
+    assert!(service.poll_ready().unwrap().is_ready());
+    assert!(service.call(false).wait().is_err());
+    assert_eq!(service.current_limit(), 1);
+
+    // The limit never drops below the minimum.
+    assert!(service.poll_ready().unwrap().is_ready());
+    assert!(service.call(false).wait().is_err());
+    assert_eq!(service.current_limit(), 1);
+}
+
+#[test]
+fn increases_on_success() {
+    let mut service = new_service(Aimd::new().initial(1).max(2));
+
+    // A full window of successful requests increases the limit by one.
+    assert!(service.poll_ready().unwrap().is_ready());
+    assert!(service.call(true).wait().is_ok());
+    assert_eq!(service.current_limit(), 2);
+
+    for _ in 0..4 {
+        assert!(service.poll_ready().unwrap().is_ready());
+        assert!(service.call(true).wait().is_ok());
+    }
+    assert_eq!(service.current_limit(), 2);
+}
+
+#[test]
+fn limits_in_flight_requests() {
+    let mut task = MockTask::new();
+    let mut service = new_service(Aimd::new().initial(1));
+
+    task.enter(|| assert!(service.poll_ready().unwrap().is_ready()));
+    let rsp = service.call(true);
+    assert_eq!(service.in_flight(), 1);
+
+    let mut other = service.clone();
+    task.enter(|| assert!(other.poll_ready().unwrap().is_not_ready()));
+
+    rsp.wait().unwrap();
+    assert!(task.is_notified());
+    task.enter(|| assert!(other.poll_ready().unwrap().is_ready()));
+}
+
+#[test]
+fn decreases_when_scheduler_is_saturated() {
+    let exhausted = Arc::new(AtomicUsize::new(0));
+    let load = {
+        let exhausted = exhausted.clone();
+        move || {
+            let exhausted = exhausted.load(Ordering::SeqCst) as u64;
+            SchedulerLoad::new(Duration::from_millis(1), exhausted)
+        }
+    };
+
+    let aimd = Aimd::new()
+        .initial(4)
+        .decrease_factor(0.5)
+        .scheduler_load(Duration::from_millis(10), load);
+    let mut service = new_service(aimd);
+
+    assert!(service.poll_ready().unwrap().is_ready());
+    assert!(service.call(true).wait().is_ok());
+    assert_eq!(service.current_limit(), 4);
+
+    // A task exhausted its budget, so even a successful response backs off.
+    exhausted.fetch_add(1, Ordering::SeqCst);
+    assert!(service.poll_ready().unwrap().is_ready());
+    assert!(service.call(true).wait().is_ok());
+    assert_eq!(service.current_limit(), 2);
+
+    // The count didn't change since, so the scheduler has recovered.
+    assert!(service.poll_ready().unwrap().is_ready());
+    assert!(service.call(true).wait().is_ok());
+    assert_eq!(service.current_limit(), 2);
+}
+
+#[test]
+#[should_panic]
+fn initial_limit_must_be_within_bounds() {
+    new_service(Aimd::new().initial(20).max(10));
+}
@@ -1,11 +1,14 @@
 extern crate futures;
 extern crate tokio_mock_task;
 extern crate tower_in_flight_limit;
+extern crate tower_layer;
 extern crate tower_mock;
 extern crate tower_service;
+extern crate tower_util;
 
 use tower_in_flight_limit::InFlightLimit;
 use tower_service::Service;
+use tower_util::{service_fn, PollReadyN, Unready, UnreadyReason};
 
 use futures::future::{poll_fn, Future};
 use tokio_mock_task::MockTask;
@@ -291,6 +294,51 @@ fn multi_waiters() {
     assert!(task3.is_notified());
 }
 
+#[test]
+fn poll_ready_n_reserves_available_permits() {
+    let mut task = MockTask::new();
+
+    let inner = service_fn(|req: &'static str| Ok::<_, ()>(req));
+    let mut service = InFlightLimit::new(inner, 3);
+
+    // Only three permits exist, so only three calls can be reserved.
+    let ready = task.enter(|| assert_ready!(service.poll_ready_n(5)));
+    assert_eq!(ready, 3);
+
+    let r1 = service.call("hello 1");
+    let r2 = service.call("hello 2");
+    let r3 = service.call("hello 3");
+
+    task.enter(|| assert_not_ready!(service.poll_ready()));
+
+    assert_eq!(r1.wait().unwrap(), "hello 1");
+    assert!(task.is_notified());
+
+    let ready = task.enter(|| assert_ready!(service.poll_ready_n(5)));
+    assert_eq!(ready, 1);
+
+    drop((r2, r3));
+}
+
+#[test]
+fn reports_unready_reason() {
+    let mut task = MockTask::new();
+
+    let inner = service_fn(|req: &'static str| Ok::<_, ()>(req));
+    let mut s1 = InFlightLimit::new(inner, 1);
+    let mut s2 = s1.clone();
+
+    task.enter(|| assert_ready!(s1.poll_ready()));
+    let r1 = s1.call("hello");
+
+    task.enter(|| assert_not_ready!(s2.poll_ready()));
+    assert_eq!(s2.unready_reason(), Some(UnreadyReason::InFlightLimit));
+
+    r1.wait().unwrap();
+    task.enter(|| assert_ready!(s2.poll_ready()));
+    assert_eq!(s2.unready_reason(), None);
+}
+
 type Mock = tower_mock::Mock<&'static str, &'static str>;
 type Handle = tower_mock::Handle<&'static str, &'static str>;
 
@@ -299,3 +347,36 @@ fn new_service(max: usize) -> (InFlightLimit<Mock>, Handle) {
     let service = InFlightLimit::new(service, max);
     (service, handle)
 }
+
+#[test]
+fn global_limit_is_shared_across_layered_services() {
+    use tower_in_flight_limit::GlobalInFlightLimitLayer;
+    use tower_layer::Layer;
+
+    let mut task = MockTask::new();
+
+    let layer = GlobalInFlightLimitLayer::new(1);
+    let mut a = layer
+        .layer(service_fn(|req: &'static str| Ok::<_, ()>(req)))
+        .unwrap();
+    let mut b = layer
+        .layer(service_fn(|req: &'static str| Ok::<_, ()>(req)))
+        .unwrap();
+
+    task.enter(|| {
+        assert_ready!(a.poll_ready());
+    });
+    let r1 = a.call("hello");
+
+    // `b` draws from the same permits as `a`.
+    task.enter(|| {
+        assert_not_ready!(b.poll_ready());
+    });
+
+    assert_eq!(r1.wait().unwrap(), "hello");
+    assert!(task.is_notified());
+
+    task.enter(|| {
+        assert_ready!(b.poll_ready());
+    });
+}
@@ -0,0 +1,294 @@
+//! Shedding requests that cannot complete before their deadline.
+//!
+//! A request whose caller gives up before it completes is wasted work, and
+//! only adds to the load of an already busy service. `DeadlineShed` tracks
+//! how long requests to the inner service typically take, as a percentile of
+//! the latencies of recent requests, and fails requests with
+//! `error::DeadlineTooShort` without calling the inner service when the time
+//! remaining until their deadline is shorter than that.
+//!
+//! Requests carry deadlines by implementing `tower_timeout::deadline::HasDeadline`,
+//! e.g. by being wrapped in a `WithDeadline`. Requests without a deadline are
+//! never shed, and requests are not shed until enough have completed to tell
+//! the typical latency.
+
+use error::{DeadlineTooShort, Error, Never};
+use futures::{Async, Future, Poll};
+use std::cmp;
+use std::collections::VecDeque;
+use std::fmt;
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+use tokio_timer::clock;
+use tower_layer::Layer;
+use tower_service::Service;
+use tower_timeout::deadline::HasDeadline;
+
+/// Sheds requests whose remaining deadline is shorter than the typical
+/// latency of the inner service.
+///
+/// Clones share the latencies they observe.
+#[derive(Debug, Clone)]
+pub struct DeadlineShed<S> {
+    inner: S,
+    latencies: Arc<Mutex<Latencies>>,
+    percentile: f64,
+    min_samples: usize,
+    window: usize,
+}
+
+/// Wraps services in `DeadlineShed` middleware.
+#[derive(Debug, Clone)]
+pub struct DeadlineShedLayer {
+    percentile: f64,
+    min_samples: usize,
+    window: usize,
+}
+
+/// Future for the `DeadlineShed` service.
+pub struct DeadlineFuture<F> {
+    state: Result<(F, Instant), ()>,
+    latencies: Arc<Mutex<Latencies>>,
+}
+
+/// Tracks the latencies of recent requests, and the percentile of them that
+/// requests are expected to take.
+#[derive(Debug)]
+struct Latencies {
+    samples: VecDeque<Duration>,
+    window: usize,
+    min_samples: usize,
+    percentile: f64,
+    expected: Option<Duration>,
+    /// Samples recorded since `expected` was last computed.
+    stale: usize,
+}
+
+// ===== impl DeadlineShed =====
+
+impl<S> DeadlineShed<S> {
+    /// Wraps `inner`, shedding requests whose remaining deadline is shorter
+    /// than the `percentile` (within `0..=100`) of recent latencies.
+    ///
+    /// By default, the percentile is computed over the last 1000 requests,
+    /// once at least 10 have completed.
+    pub fn new(inner: S, percentile: f64) -> Self {
+        DeadlineShed {
+            inner,
+            latencies: Arc::new(Mutex::new(Latencies::new(percentile, 10, 1000))),
+            percentile,
+            min_samples: 10,
+            window: 1000,
+        }
+    }
+
+    /// Set how many requests must have completed before any are shed.
+    pub fn min_samples(mut self, min_samples: usize) -> Self {
+        self.min_samples = min_samples;
+        self.reset_latencies()
+    }
+
+    /// Set how many of the most recent requests the percentile is computed
+    /// over.
+    pub fn window(mut self, window: usize) -> Self {
+        self.window = window;
+        self.reset_latencies()
+    }
+
+    fn reset_latencies(mut self) -> Self {
+        let latencies = Latencies::new(self.percentile, self.min_samples, self.window);
+        self.latencies = Arc::new(Mutex::new(latencies));
+        self
+    }
+
+    /// Get a reference to the inner service
+    pub fn get_ref(&self) -> &S {
+        &self.inner
+    }
+
+    /// Get a mutable reference to the inner service
+    pub fn get_mut(&mut self) -> &mut S {
+        &mut self.inner
+    }
+
+    /// Consume `self`, returning the inner service
+    pub fn into_inner(self) -> S {
+        self.inner
+    }
+}
+
+impl<S, Req> Service<Req> for DeadlineShed<S>
+where
+    S: Service<Req>,
+    S::Error: Into<Error>,
+    Req: HasDeadline,
+{
+    type Response = S::Response;
+    type Error = Error;
+    type Future = DeadlineFuture<S::Future>;
+
+    fn poll_ready(&mut self) -> Poll<(), Self::Error> {
+        self.inner.poll_ready().map_err(Into::into)
+    }
+
+    fn call(&mut self, req: Req) -> Self::Future {
+        let expected = self.latencies.lock().expect("latencies").expected;
+
+        let shed = match (req.deadline(), expected) {
+            (Some(deadline), Some(expected)) => deadline.remaining() < expected,
+            _ => false,
+        };
+
+        let state = if shed {
+            Err(())
+        } else {
+            Ok((self.inner.call(req), clock::now()))
+        };
+
+        DeadlineFuture {
+            state,
+            latencies: self.latencies.clone(),
+        }
+    }
+}
+
+// ===== impl DeadlineShedLayer =====
+
+impl DeadlineShedLayer {
+    /// Creates a new layer, shedding requests whose remaining deadline is
+    /// shorter than the `percentile` (within `0..=100`) of recent latencies.
+    ///
+    /// See [`DeadlineShed::new`](struct.DeadlineShed.html#method.new).
+    pub fn new(percentile: f64) -> Self {
+        DeadlineShedLayer {
+            percentile,
+            min_samples: 10,
+            window: 1000,
+        }
+    }
+
+    /// Set how many requests must have completed before any are shed.
+    pub fn min_samples(mut self, min_samples: usize) -> Self {
+        self.min_samples = min_samples;
+        self
+    }
+
+    /// Set how many of the most recent requests the percentile is computed
+    /// over.
+    pub fn window(mut self, window: usize) -> Self {
+        self.window = window;
+        self
+    }
+}
+
+impl<S, Req> Layer<S, Req> for DeadlineShedLayer
+where
+    S: Service<Req>,
+    S::Error: Into<Error>,
+    Req: HasDeadline,
+{
+    type Response = S::Response;
+    type Error = Error;
+    type LayerError = Never;
+    type Service = DeadlineShed<S>;
+
+    fn layer(&self, service: S) -> Result<Self::Service, Self::LayerError> {
+        let service = DeadlineShed::new(service, self.percentile)
+            .min_samples(self.min_samples)
+            .window(self.window);
+        Ok(service)
+    }
+}
+
+// ===== impl DeadlineFuture =====
+
+impl<F> Future for DeadlineFuture<F>
+where
+    F: Future,
+    F::Error: Into<Error>,
+{
+    type Item = F::Item;
+    type Error = Error;
+
+    fn poll(&mut self) -> Poll<Self::Item, Self::Error> {
+        match self.state {
+            Ok((ref mut fut, started)) => {
+                let result = match fut.poll() {
+                    Ok(Async::NotReady) => return Ok(Async::NotReady),
+                    result => result,
+                };
+
+                // Failed requests count too: a service that is slow to fail
+                // is just as unlikely to answer in time.
+                if let Ok(mut latencies) = self.latencies.lock() {
+                    latencies.record(clock::now() - started);
+                }
+
+                result.map_err(Into::into)
+            }
+            Err(()) => Err(DeadlineTooShort::new().into()),
+        }
+    }
+}
+
+impl<F> fmt::Debug for DeadlineFuture<F>
+where
+    // bounds for future-proofing...
+    F: fmt::Debug,
+{
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str("DeadlineFuture")
+    }
+}
+
+// ===== impl Latencies =====
+
+impl Latencies {
+    fn new(percentile: f64, min_samples: usize, window: usize) -> Self {
+        assert!(
+            percentile >= 0.0 && percentile <= 100.0,
+            "percentile must be within 0..=100"
+        );
+        assert!(window > 0, "window must be greater than zero");
+
+        Latencies {
+            samples: VecDeque::with_capacity(window),
+            window,
+            min_samples,
+            percentile,
+            expected: None,
+            stale: 0,
+        }
+    }
+
+    fn record(&mut self, latency: Duration) {
+        if self.samples.len() == self.window {
+            self.samples.pop_front();
+        }
+        self.samples.push_back(latency);
+        self.stale += 1;
+
+        // Sorting the window for every request would be wasteful, so the
+        // percentile is only brought up to date every so often.
+        let interval = cmp::max(1, self.window / 16);
+        if self.stale >= interval || self.expected.is_none() {
+            self.update();
+        }
+    }
+
+    fn update(&mut self) {
+        self.stale = 0;
+
+        if self.samples.len() < cmp::max(1, self.min_samples) {
+            self.expected = None;
+            return;
+        }
+
+        let mut sorted: Vec<_> = self.samples.iter().cloned().collect();
+        sorted.sort();
+
+        let rank = (self.percentile / 100.0 * sorted.len() as f64).ceil() as usize;
+        let idx = cmp::min(rank.saturating_sub(1), sorted.len() - 1);
+        self.expected = Some(sorted[idx]);
+    }
+}
@@ -5,9 +5,23 @@ use std::fmt;
 pub(crate) type Error = Box<dyn std::error::Error + Send + Sync>;
 pub(crate) use self::never::Never;
 
-/// An error returned by `Overload` when the underlying service
+/// An error returned by `LoadShed` when the underlying service
 /// is not ready to handle any requests at the time of being
 /// called.
+///
+/// Layers and metric collectors further up the stack can tell shed requests
+/// apart from other failures by downcasting the error:
+///
+/// ```
+/// # extern crate tower_load_shed;
+/// # use tower_load_shed::error::Overloaded;
+/// # fn main() {
+/// # let err: Box<std::error::Error + Send + Sync> = "boom".into();
+/// if err.is::<Overloaded>() {
+///     // The request never reached the inner service.
+/// }
+/// # }
+/// ```
 pub struct Overloaded {
     _p: (),
 }
@@ -32,6 +46,35 @@ impl fmt::Display for Overloaded {
 
 impl std::error::Error for Overloaded {}
 
+/// An error returned by `DeadlineShed` when the time remaining until a
+/// request's deadline is shorter than the inner service typically takes.
+///
+/// The request never reached the inner service.
+pub struct DeadlineTooShort {
+    _p: (),
+}
+
+impl DeadlineTooShort {
+    #[cfg(feature = "deadline")]
+    pub(crate) fn new() -> Self {
+        DeadlineTooShort { _p: () }
+    }
+}
+
+impl fmt::Debug for DeadlineTooShort {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str("DeadlineTooShort")
+    }
+}
+
+impl fmt::Display for DeadlineTooShort {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str("deadline too short for the service's typical latency")
+    }
+}
+
+impl std::error::Error for DeadlineTooShort {}
+
 pub(crate) mod never {
     use std::{error, fmt};
 
@@ -2,18 +2,31 @@ use tower_layer::Layer;
 use tower_service::Service;
 
 use error::{Error, Never};
-use LoadShed;
+use {LoadShed, OnShed};
 
 /// A `tower-layer` to wrap services in `LoadShed` middleware.
-#[derive(Debug)]
+#[derive(Debug, Clone, Default)]
 pub struct LoadShedLayer {
-    _p: (),
+    dry_run: Option<OnShed>,
 }
 
 impl LoadShedLayer {
     /// Creates a new layer.
     pub fn new() -> Self {
-        LoadShedLayer { _p: () }
+        LoadShedLayer { dry_run: None }
+    }
+
+    /// Creates a new layer whose services never shed load, but call
+    /// `on_shed` each time a request would have been shed.
+    ///
+    /// See [`LoadShed::dry_run`](struct.LoadShed.html#method.dry_run).
+    pub fn dry_run<F>(on_shed: F) -> Self
+    where
+        F: Fn() + Send + Sync + 'static,
+    {
+        LoadShedLayer {
+            dry_run: Some(OnShed::new(on_shed)),
+        }
     }
 }
 
@@ -28,6 +41,6 @@ where
     type Service = LoadShed<S>;
 
     fn layer(&self, service: S) -> Result<Self::Service, Self::LayerError> {
-        Ok(LoadShed::new(service))
+        Ok(LoadShed::with_dry_run(service, self.dry_run.clone()))
     }
 }
@@ -2,15 +2,43 @@
 #![deny(missing_debug_implementations)]
 #![deny(missing_docs)]
 
-//! tower-load-shed
+//! Tower middleware for shedding load when inner services aren't ready.
+//!
+//! Rather than waiting for the inner service to have capacity, `LoadShed`
+//! fails requests immediately with `error::Overloaded`. This is useful in
+//! proxies, where rejecting a request early is preferable to queueing it.
+//!
+//! # Dry runs
+//!
+//! Shedding load is destructive, so it is useful to see how often a service
+//! would shed before enabling it. `LoadShed::dry_run` and
+//! `LoadShedLayer::dry_run` never shed requests: they wait for the inner
+//! service to be ready like any other service, and call a hook whenever a
+//! request would have been shed instead, e.g. to count it in metrics.
+//!
+//! # Deadlines
+//!
+//! With the `deadline` feature, enabled by default, `deadline::DeadlineShed`
+//! sheds requests whose deadline leaves too little time for the inner service
+//! to complete them. See [`deadline`](deadline/index.html) for details.
 
 extern crate futures;
+#[cfg(feature = "deadline")]
+extern crate tokio_timer;
 extern crate tower_layer;
 extern crate tower_service;
+#[cfg(feature = "deadline")]
+extern crate tower_timeout;
+extern crate tower_util;
 
 use futures::Poll;
+use std::fmt;
+use std::sync::Arc;
 use tower_service::Service;
+use tower_util::{Unready, UnreadyReason};
 
+#[cfg(feature = "deadline")]
+pub mod deadline;
 pub mod error;
 mod future;
 mod layer;
@@ -24,8 +52,17 @@ pub use self::layer::LoadShedLayer;
 pub struct LoadShed<S> {
     inner: S,
     is_ready: bool,
+    /// Set in dry runs, where requests are not shed.
+    dry_run: Option<OnShed>,
+    /// Whether the request waiting for the inner service in a dry run has
+    /// already been reported as shed.
+    would_shed: bool,
 }
 
+/// A hook called in dry runs when a request would have been shed.
+#[derive(Clone)]
+pub(crate) struct OnShed(Arc<Fn() + Send + Sync>);
+
 // ===== impl LoadShed =====
 
 impl<S> LoadShed<S> {
@@ -34,8 +71,45 @@ impl<S> LoadShed<S> {
         LoadShed {
             inner,
             is_ready: false,
+            dry_run: None,
+            would_shed: false,
+        }
+    }
+
+    /// Wraps a service in `LoadShed` middleware that never sheds load, but
+    /// calls `on_shed` each time a request would have been shed.
+    ///
+    /// The returned service is only ready once the inner service is.
+    pub fn dry_run<F>(inner: S, on_shed: F) -> Self
+    where
+        F: Fn() + Send + Sync + 'static,
+    {
+        Self::with_dry_run(inner, Some(OnShed::new(on_shed)))
+    }
+
+    pub(crate) fn with_dry_run(inner: S, dry_run: Option<OnShed>) -> Self {
+        LoadShed {
+            inner,
+            is_ready: false,
+            dry_run,
+            would_shed: false,
         }
     }
+
+    /// Get a reference to the inner service
+    pub fn get_ref(&self) -> &S {
+        &self.inner
+    }
+
+    /// Get a mutable reference to the inner service
+    pub fn get_mut(&mut self) -> &mut S {
+        &mut self.inner
+    }
+
+    /// Consume `self`, returning the inner service
+    pub fn into_inner(self) -> S {
+        self.inner
+    }
 }
 
 impl<S, Req> Service<Req> for LoadShed<S>
@@ -48,6 +122,21 @@ where
     type Future = ResponseFuture<S::Future>;
 
     fn poll_ready(&mut self) -> Poll<(), Self::Error> {
+        if let Some(ref on_shed) = self.dry_run {
+            let ready = self.inner.poll_ready().map_err(Into::into)?;
+
+            // A request is only reported once, however often its caller
+            // polls before the inner service is ready.
+            if ready.is_ready() {
+                self.would_shed = false;
+            } else if !self.would_shed {
+                self.would_shed = true;
+                on_shed.call();
+            }
+
+            return Ok(ready);
+        }
+
         // We check for readiness here, so that we can know in `call` if
         // the inner service is overloaded or not.
         self.is_ready = self.inner.poll_ready().map_err(Into::into)?.is_ready();
@@ -58,6 +147,10 @@ where
     }
 
     fn call(&mut self, req: Req) -> Self::Future {
+        if self.dry_run.is_some() {
+            return ResponseFuture::called(self.inner.call(req));
+        }
+
         if self.is_ready {
             // readiness only counts once, you need to check again!
             self.is_ready = false;
@@ -68,6 +161,16 @@ where
     }
 }
 
+impl<S, Req> Unready<Req> for LoadShed<S>
+where
+    S: Unready<Req>,
+    S::Error: Into<Error>,
+{
+    fn unready_reason(&self) -> Option<UnreadyReason> {
+        self.inner.unready_reason()
+    }
+}
+
 impl<S: Clone> Clone for LoadShed<S> {
     fn clone(&self) -> Self {
         LoadShed {
@@ -75,6 +178,29 @@ impl<S: Clone> Clone for LoadShed<S> {
             // new clones shouldn't carry the readiness state, as a cloneable
             // inner service likely tracks readiness per clone.
             is_ready: false,
+            dry_run: self.dry_run.clone(),
+            would_shed: false,
         }
     }
 }
+
+// ===== impl OnShed =====
+
+impl OnShed {
+    pub(crate) fn new<F>(f: F) -> Self
+    where
+        F: Fn() + Send + Sync + 'static,
+    {
+        OnShed(Arc::new(f))
+    }
+
+    fn call(&self) {
+        (self.0)()
+    }
+}
+
+impl fmt::Debug for OnShed {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_tuple("OnShed").finish()
+    }
+}
@@ -0,0 +1,51 @@
+#![cfg(feature = "deadline")]
+
+extern crate futures;
+extern crate tokio;
+extern crate tower_load_shed;
+extern crate tower_mock;
+extern crate tower_service;
+extern crate tower_timeout;
+
+use std::time::Duration;
+use tower_load_shed::deadline::DeadlineShed;
+use tower_load_shed::error::DeadlineTooShort;
+use tower_service::Service;
+use tower_timeout::deadline::{Deadline, WithDeadline};
+
+type Mock = tower_mock::Mock<WithDeadline<&'static str>, &'static str>;
+
+#[test]
+fn sheds_requests_that_cannot_complete_in_time() {
+    let mut rt = tokio::runtime::current_thread::Runtime::new().unwrap();
+    let (service, mut handle) = Mock::new();
+    let mut service = DeadlineShed::new(service, 50.0).min_samples(1);
+
+    // Until a request has completed, nothing is shed...
+    assert!(service.poll_ready().unwrap().is_ready());
+    let request = WithDeadline::new("hello", Some(Deadline::after(Duration::from_millis(1))));
+    let response = service.call(request);
+    let request = handle.next_request().unwrap();
+
+    let responder = std::thread::spawn(move || {
+        std::thread::sleep(Duration::from_millis(50));
+        request.respond("world");
+    });
+
+    assert_eq!(rt.block_on(response).unwrap(), "world");
+    responder.join().unwrap();
+
+    // ...then requests with too little time left are shed...
+    assert!(service.poll_ready().unwrap().is_ready());
+    let request = WithDeadline::new("hello", Some(Deadline::after(Duration::from_millis(10))));
+    let err = rt.block_on(service.call(request)).unwrap_err();
+    assert!(err.is::<DeadlineTooShort>());
+
+    // ...while those with enough time, or without a deadline, are not.
+    for deadline in vec![Some(Deadline::after(Duration::from_secs(10))), None] {
+        assert!(service.poll_ready().unwrap().is_ready());
+        let response = service.call(WithDeadline::new("hello", deadline));
+        handle.next_request().unwrap().respond("world");
+        assert_eq!(rt.block_on(response).unwrap(), "world");
+    }
+}
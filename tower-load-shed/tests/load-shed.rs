@@ -4,6 +4,8 @@ extern crate tower_mock;
 extern crate tower_service;
 
 use futures::Future;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::Arc;
 use tower_load_shed::LoadShed;
 use tower_service::Service;
 
@@ -46,6 +48,49 @@ fn when_not_ready() {
     assert!(err.is::<tower_load_shed::error::Overloaded>());
 }
 
+#[test]
+fn recovers_once_ready() {
+    let (mut service, mut handle) = new_service();
+
+    handle.allow(0);
+
+    with_task(|| assert!(service.poll_ready().unwrap().is_ready()));
+    let err = service.call("hello").wait().unwrap_err();
+    assert!(err.is::<tower_load_shed::error::Overloaded>());
+
+    handle.allow(1);
+
+    with_task(|| assert!(service.poll_ready().unwrap().is_ready()));
+    let response = service.call("hello");
+    handle.next_request().unwrap().respond("world");
+    assert_eq!(response.wait().unwrap(), "world");
+}
+
+#[test]
+fn dry_run() {
+    let (service, mut handle) = Mock::new();
+    let shed = Arc::new(AtomicUsize::new(0));
+    let counter = shed.clone();
+    let mut service = LoadShed::dry_run(service, move || {
+        counter.fetch_add(1, Ordering::SeqCst);
+    });
+
+    handle.allow(0);
+
+    // The request waits rather than being shed, and is reported once.
+    with_task(|| assert!(service.poll_ready().unwrap().is_not_ready()));
+    with_task(|| assert!(service.poll_ready().unwrap().is_not_ready()));
+    assert_eq!(shed.load(Ordering::SeqCst), 1);
+
+    handle.allow(1);
+
+    with_task(|| assert!(service.poll_ready().unwrap().is_ready()));
+    let response = service.call("hello");
+    handle.next_request().unwrap().respond("world");
+    assert_eq!(response.wait().unwrap(), "world");
+    assert_eq!(shed.load(Ordering::SeqCst), 1);
+}
+
 type Mock = tower_mock::Mock<&'static str, &'static str>;
 type Handle = tower_mock::Handle<&'static str, &'static str>;
 
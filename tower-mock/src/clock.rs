@@ -0,0 +1,161 @@
+//! A mock clock and timer, for testing time-based middleware deterministically.
+//!
+//! Tower's time-based middleware reads the time with `tokio_timer::clock::now`
+//! and waits with `tokio_timer::Delay`, both of which use the clock and timer
+//! set for the current thread. `MockClock::enter` sets a clock that only
+//! moves when told to, along with a timer driven by it, so that tests can
+//! advance time explicitly instead of sleeping:
+//!
+//! ```
+//! # extern crate futures;
+//! # extern crate tokio_mock_task;
+//! # extern crate tokio_timer;
+//! # extern crate tower_mock;
+//! # use futures::Future;
+//! # use std::time::Duration;
+//! # use tokio_mock_task::MockTask;
+//! # use tokio_timer::{clock, Delay};
+//! # use tower_mock::clock::MockClock;
+//! # fn main() {
+//! let mut task = MockTask::new();
+//!
+//! MockClock::new().enter(|handle| {
+//!     let mut sleep = Delay::new(clock::now() + Duration::from_secs(60));
+//!     assert!(task.enter(|| sleep.poll()).unwrap().is_not_ready());
+//!
+//!     handle.advance(Duration::from_secs(60));
+//!     assert!(task.is_notified());
+//!     assert!(task.enter(|| sleep.poll()).unwrap().is_ready());
+//! });
+//! # }
+//! ```
+
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+use tokio_executor::park::{Park, Unpark};
+use tokio_timer::clock::{self, Clock, Now};
+use tokio_timer::{self, Timer};
+
+/// A clock that only moves when advanced by its `Handle`.
+#[derive(Debug)]
+pub struct MockClock {
+    time: MockTime,
+}
+
+/// Advances the time of a `MockClock`, firing the timers that expire.
+#[derive(Debug)]
+pub struct Handle {
+    timer: Timer<MockPark, MockNow>,
+    time: MockTime,
+}
+
+/// The time shared by a `MockClock`, its `Handle` and their timer.
+#[derive(Debug, Clone)]
+struct MockTime(Arc<Mutex<Instant>>);
+
+#[derive(Debug)]
+struct MockNow(MockTime);
+
+#[derive(Debug)]
+struct MockPark(MockTime);
+
+#[derive(Debug)]
+struct MockUnpark;
+
+// ===== impl MockClock =====
+
+impl MockClock {
+    /// Creates a clock starting at the current time.
+    pub fn new() -> Self {
+        MockClock {
+            time: MockTime(Arc::new(Mutex::new(Instant::now()))),
+        }
+    }
+
+    /// Runs `f` with `self` as the current clock and timer.
+    ///
+    /// # Panics
+    ///
+    /// Panics if called from within an executor.
+    pub fn enter<F, R>(&mut self, f: F) -> R
+    where
+        F: FnOnce(&mut Handle) -> R,
+    {
+        let clock = Clock::new_with_now(MockNow(self.time.clone()));
+        let timer = Timer::new_with_now(MockPark(self.time.clone()), MockNow(self.time.clone()));
+        let timer_handle = timer.handle();
+        let mut handle = Handle {
+            timer,
+            time: self.time.clone(),
+        };
+
+        let mut enter = ::tokio_executor::enter().expect("mock clock entered from an executor");
+        tokio_timer::with_default(&timer_handle, &mut enter, |enter| {
+            clock::with_default(&clock, enter, |_| f(&mut handle))
+        })
+    }
+}
+
+impl Default for MockClock {
+    fn default() -> Self {
+        MockClock::new()
+    }
+}
+
+// ===== impl Handle =====
+
+impl Handle {
+    /// Returns the current time of the clock.
+    pub fn now(&self) -> Instant {
+        self.time.now()
+    }
+
+    /// Moves the clock forward by `duration`, and notifies the tasks waiting
+    /// on timers that have expired.
+    pub fn advance(&mut self, duration: Duration) {
+        self.time.advance(duration);
+        self.timer
+            .turn(Some(Duration::from_millis(0)))
+            .expect("mock timer turn");
+    }
+}
+
+// ===== impl MockTime =====
+
+impl MockTime {
+    fn now(&self) -> Instant {
+        *self.0.lock().expect("mock time")
+    }
+
+    fn advance(&self, duration: Duration) {
+        *self.0.lock().expect("mock time") += duration;
+    }
+}
+
+impl Now for MockNow {
+    fn now(&self) -> Instant {
+        (self.0).now()
+    }
+}
+
+impl Park for MockPark {
+    type Unpark = MockUnpark;
+    type Error = ();
+
+    fn unpark(&self) -> Self::Unpark {
+        MockUnpark
+    }
+
+    fn park(&mut self) -> Result<(), Self::Error> {
+        Ok(())
+    }
+
+    fn park_timeout(&mut self, _: Duration) -> Result<(), Self::Error> {
+        // Time only moves when the handle is advanced.
+        Ok(())
+    }
+}
+
+impl Unpark for MockUnpark {
+    fn unpark(&self) {}
+}
@@ -1,11 +1,17 @@
 //! Mock `Service` that can be used in tests.
 
 extern crate futures;
+extern crate tokio_executor;
 extern crate tokio_sync;
+extern crate tokio_timer;
 extern crate tower_service;
 
+pub mod clock;
 pub mod error;
 pub mod future;
+pub mod make;
+
+pub use make::{MakeHandle, MakeMock};
 
 use error::Error;
 use future::ResponseFuture;
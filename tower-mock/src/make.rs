@@ -0,0 +1,151 @@
+//! Mock `MakeService` that can be used in tests.
+//!
+//! Middleware that creates services, such as reconnecting clients, pools and
+//! balancers, must be tested against the ways service construction behaves:
+//! construction that is slow, that fails, or that is not ready to begin. A
+//! `MakeMock` hands every target it is called with to its `MakeHandle`, which
+//! decides when, and whether, the service is constructed.
+
+use error::Error;
+use future::ResponseFuture;
+use futures::future::{self, Future};
+use futures::{Async, Poll};
+use tower_service::Service;
+use {Handle, Mock, Request};
+
+use std::ops;
+
+/// A mock `MakeService`, creating services of type `S` for targets of type `T`.
+#[derive(Debug)]
+pub struct MakeMock<T, S> {
+    inner: Mock<T, S>,
+}
+
+/// Handle to the `MakeMock`.
+#[derive(Debug)]
+pub struct MakeHandle<T, S> {
+    inner: Handle<T, S>,
+}
+
+/// A pending construction of a service for a target.
+#[derive(Debug)]
+pub struct Construction<T, S> {
+    request: Request<T, S>,
+}
+
+// ===== impl MakeMock =====
+
+impl<T, S> MakeMock<T, S> {
+    /// Create a new `MakeMock` and `MakeHandle` pair.
+    pub fn new() -> (Self, MakeHandle<T, S>) {
+        let (inner, handle) = Mock::new();
+        (MakeMock { inner }, MakeHandle { inner: handle })
+    }
+}
+
+impl<T, S> Service<T> for MakeMock<T, S> {
+    type Response = S;
+    type Error = Error;
+    type Future = ResponseFuture<S>;
+
+    fn poll_ready(&mut self) -> Poll<(), Self::Error> {
+        self.inner.poll_ready()
+    }
+
+    fn call(&mut self, target: T) -> Self::Future {
+        self.inner.call(target)
+    }
+}
+
+impl<T, S> Clone for MakeMock<T, S> {
+    fn clone(&self) -> Self {
+        MakeMock {
+            inner: self.inner.clone(),
+        }
+    }
+}
+
+// ===== impl MakeHandle =====
+
+impl<T, S> MakeHandle<T, S> {
+    /// Asynchronously gets the next construction
+    pub fn poll_construction(&mut self) -> Poll<Option<Construction<T, S>>, Error> {
+        let poll = self.inner.poll_request()?;
+        Ok(poll.map(|request| request.map(|request| Construction { request })))
+    }
+
+    /// Synchronously gets the next construction.
+    ///
+    /// This function blocks the current thread until a target is received.
+    pub fn next_construction(&mut self) -> Option<Construction<T, S>> {
+        future::poll_fn(|| self.poll_construction()).wait().unwrap()
+    }
+
+    /// Asserts that the next target requested is `expected`, returning its
+    /// construction.
+    ///
+    /// This function blocks the current thread until a target is received.
+    pub fn expect_target(&mut self, expected: T) -> Construction<T, S>
+    where
+        T: PartialEq + ::std::fmt::Debug,
+    {
+        let construction = self
+            .next_construction()
+            .expect("the MakeMock was dropped");
+        assert_eq!(*construction, expected, "unexpected target requested");
+        construction
+    }
+
+    /// Asserts that no target has been requested since the last one was
+    /// taken from the handle.
+    pub fn assert_no_construction(&mut self)
+    where
+        T: ::std::fmt::Debug,
+    {
+        let poll = future::lazy(|| Ok::<_, ()>(self.poll_construction()))
+            .wait()
+            .unwrap()
+            .expect("failed to poll for constructions");
+
+        if let Async::Ready(Some(construction)) = poll {
+            panic!("unexpected target requested: {:?}", construction.target());
+        }
+    }
+
+    /// Allow a certain number of services to be constructed
+    pub fn allow(&mut self, num: u64) {
+        self.inner.allow(num);
+    }
+
+    /// Make the next `poll_ready` of the `MakeMock` fail with the given error.
+    pub fn error<E: Into<Error>>(&mut self, e: E) {
+        self.inner.error(e);
+    }
+}
+
+// ===== impl Construction =====
+
+impl<T, S> Construction<T, S> {
+    /// Returns the target the service is constructed for.
+    pub fn target(&self) -> &T {
+        &self.request
+    }
+
+    /// Complete the construction with `service`.
+    pub fn resolve(self, service: S) {
+        self.request.respond(service)
+    }
+
+    /// Fail the construction with `err`.
+    pub fn fail<E: Into<Error>>(self, err: E) {
+        self.request.error(err)
+    }
+}
+
+impl<T, S> ops::Deref for Construction<T, S> {
+    type Target = T;
+
+    fn deref(&self) -> &T {
+        self.target()
+    }
+}
@@ -0,0 +1,54 @@
+extern crate futures;
+extern crate tower_mock;
+extern crate tower_service;
+
+use futures::Future;
+use tower_mock::{MakeMock, Mock};
+use tower_service::Service;
+
+type Svc = Mock<&'static str, &'static str>;
+
+#[test]
+fn resolves_construction_on_demand() {
+    let (mut make, mut handle) = MakeMock::<&'static str, Svc>::new();
+
+    assert!(make.poll_ready().unwrap().is_ready());
+    let mut svc = make.call("a");
+
+    let construction = handle.expect_target("a");
+    with_task(|| assert!(svc.poll().unwrap().is_not_ready()));
+
+    let (inner, _inner_handle) = Mock::new();
+    construction.resolve(inner);
+    assert!(svc.wait().is_ok());
+
+    handle.assert_no_construction();
+}
+
+#[test]
+fn fails_construction() {
+    let (mut make, mut handle) = MakeMock::<&'static str, Svc>::new();
+
+    assert!(make.poll_ready().unwrap().is_ready());
+    let svc = make.call("a");
+
+    handle.next_construction().unwrap().fail("refused");
+    assert_eq!(svc.wait().unwrap_err().to_string(), "refused");
+}
+
+#[test]
+fn not_ready() {
+    let (mut make, mut handle) = MakeMock::<&'static str, Svc>::new();
+
+    handle.allow(0);
+    with_task(|| assert!(make.poll_ready().unwrap().is_not_ready()));
+
+    handle.allow(1);
+    with_task(|| assert!(make.poll_ready().unwrap().is_ready()));
+}
+
+// Helper to run some code within context of a task
+fn with_task<F: FnOnce() -> U, U>(f: F) -> U {
+    use futures::future::lazy;
+    lazy(|| Ok::<_, ()>(f())).wait().unwrap()
+}
@@ -0,0 +1,78 @@
+use crate::Rate;
+use std::cmp;
+use std::time::{Duration, Instant};
+
+/// The remaining calls of a rate, kept independently of any one service.
+#[derive(Debug)]
+pub(crate) struct Bucket {
+    rate: Rate,
+    until: Instant,
+    rem: u64,
+    slice: u32,
+    last_used: Instant,
+}
+
+impl Bucket {
+    pub(crate) fn new(rate: Rate, now: Instant) -> Self {
+        Bucket {
+            rate,
+            until: now + rate.slice(),
+            rem: rate.capacity(),
+            slice: 0,
+            last_used: now,
+        }
+    }
+
+    pub(crate) fn rate(&self) -> Rate {
+        self.rate
+    }
+
+    /// Returns the time of the next refill.
+    pub(crate) fn until(&self) -> Instant {
+        self.until
+    }
+
+    /// Returns `true` if the bucket has not been used for long enough that
+    /// it is full again, and for at least `idle_timeout`.
+    pub(crate) fn is_idle(&self, idle_timeout: Duration, now: Instant) -> bool {
+        now >= self.last_used + cmp::max(idle_timeout, self.rate.per())
+    }
+
+    /// Takes a call from the bucket, returning `false` if none remain.
+    pub(crate) fn try_acquire(&mut self, rate: Rate, now: Instant) -> bool {
+        self.last_used = now;
+
+        if rate != self.rate {
+            // The rate was changed through the handle. Keep what remains of
+            // the current period, but never more than the new rate allows.
+            self.rem = cmp::min(self.rem, rate.capacity());
+            self.until = cmp::min(self.until, now + rate.slice());
+            self.slice = 0;
+            self.rate = rate;
+        }
+
+        while now >= self.until && self.rem < rate.capacity() {
+            self.rem += rate.refill(self.slice);
+            self.slice = (self.slice + 1) % rate.slices();
+            self.until += rate.slice();
+        }
+
+        if now >= self.until {
+            self.until = now + rate.slice();
+        }
+
+        self.rem = cmp::min(self.rem, rate.capacity());
+
+        if self.rem == 0 {
+            return false;
+        }
+
+        self.rem -= 1;
+        true
+    }
+
+    /// Returns a call taken by `try_acquire` that was never made.
+    pub(crate) fn release(&mut self) {
+        self.rem = cmp::min(self.rem + 1, self.rate.capacity());
+    }
+}
@@ -0,0 +1,44 @@
+//! Weighing requests against the rate limit
+
+/// Determines how much of the rate limit a request consumes.
+///
+/// By default every request costs a single token. Expensive requests, such as
+/// batch queries, can be made to consume more of the limit than cheap ones.
+///
+/// `Cost` is implemented for any `Fn(&Request) -> u64`.
+pub trait Cost<Request> {
+    /// Returns the number of tokens consumed by `request`.
+    fn cost(&self, request: &Request) -> u64;
+
+    /// Returns the number of tokens consumed by every request, if it is the
+    /// same for all requests.
+    ///
+    /// Calls can only be reserved in bulk with `PollReadyN` when their cost is
+    /// known up front. By default, it is not.
+    fn fixed_cost(&self) -> Option<u64> {
+        None
+    }
+}
+
+/// Every request costs a single token.
+#[derive(Debug, Copy, Clone, Default)]
+pub struct Unit;
+
+impl<Request> Cost<Request> for Unit {
+    fn cost(&self, _: &Request) -> u64 {
+        1
+    }
+
+    fn fixed_cost(&self) -> Option<u64> {
+        Some(1)
+    }
+}
+
+impl<F, Request> Cost<Request> for F
+where
+    F: Fn(&Request) -> u64,
+{
+    fn cost(&self, request: &Request) -> u64 {
+        self(request)
+    }
+}
@@ -1,7 +1,27 @@
-use std::error;
+//! Error types
+
+use std::{error, fmt};
 
 pub(crate) type Error = Box<error::Error + Send + Sync>;
 
+/// Error returned when a request exceeds the rate of its key.
+#[derive(Debug)]
+pub struct RateLimited(());
+
+impl RateLimited {
+    pub(crate) fn new() -> Self {
+        RateLimited(())
+    }
+}
+
+impl fmt::Display for RateLimited {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str("rate limit exceeded")
+    }
+}
+
+impl error::Error for RateLimited {}
+
 pub(crate) mod never {
     use std::{error, fmt};
 
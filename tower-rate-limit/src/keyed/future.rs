@@ -0,0 +1,32 @@
+//! Future types
+
+use crate::error::{Error, RateLimited};
+use futures::{Future, Poll};
+
+/// Response future returned by `KeyedRateLimit`.
+#[derive(Debug)]
+pub struct ResponseFuture<T> {
+    inner: Option<T>,
+}
+
+impl<T> ResponseFuture<T> {
+    pub(crate) fn new(inner: Option<T>) -> ResponseFuture<T> {
+        ResponseFuture { inner }
+    }
+}
+
+impl<T> Future for ResponseFuture<T>
+where
+    T: Future,
+    Error: From<T::Error>,
+{
+    type Item = T::Item;
+    type Error = Error;
+
+    fn poll(&mut self) -> Poll<Self::Item, Self::Error> {
+        match self.inner {
+            Some(ref mut inner) => inner.poll().map_err(Into::into),
+            None => Err(RateLimited::new().into()),
+        }
+    }
+}
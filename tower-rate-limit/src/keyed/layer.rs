@@ -0,0 +1,66 @@
+use super::{Handle, KeyedRateLimit};
+use crate::error::{never::Never, Error};
+use crate::Rate;
+use std::hash::Hash;
+use std::time::Duration;
+use tower_layer::Layer;
+use tower_service::Service;
+
+/// Rate limits requests per key.
+///
+/// Every service produced by this layer shares the same `Handle`, so rates
+/// set through `handle()` apply to all of them.
+#[derive(Debug)]
+pub struct KeyedRateLimitLayer<K, F>
+where
+    K: Hash + Eq,
+{
+    handle: Handle<K>,
+    key: F,
+    idle_timeout: Duration,
+}
+
+impl<K, F> KeyedRateLimitLayer<K, F>
+where
+    K: Hash + Eq,
+{
+    /// Limit each key to `num` requests `per` period by default.
+    pub fn new(num: u64, per: Duration, key: F) -> Self {
+        KeyedRateLimitLayer {
+            handle: Handle::new(Rate::new(num, per)),
+            key,
+            idle_timeout: Duration::from_secs(0),
+        }
+    }
+
+    /// Keep the state of idle keys for at least `idle_timeout`.
+    ///
+    /// See [`KeyedRateLimit::idle_timeout`](struct.KeyedRateLimit.html#method.idle_timeout).
+    pub fn idle_timeout(mut self, idle_timeout: Duration) -> Self {
+        self.idle_timeout = idle_timeout;
+        self
+    }
+
+    /// Returns a handle that updates the rates of the produced services.
+    pub fn handle(&self) -> Handle<K> {
+        self.handle.clone()
+    }
+}
+
+impl<S, K, F, Request> Layer<S, Request> for KeyedRateLimitLayer<K, F>
+where
+    S: Service<Request>,
+    Error: From<S::Error>,
+    K: Hash + Eq,
+    F: Fn(&Request) -> K + Clone,
+{
+    type Response = S::Response;
+    type Error = Error;
+    type LayerError = Never;
+    type Service = KeyedRateLimit<S, K, F>;
+
+    fn layer(&self, service: S) -> Result<Self::Service, Self::LayerError> {
+        let service = KeyedRateLimit::with_handle(service, self.handle.clone(), self.key.clone());
+        Ok(service.idle_timeout(self.idle_timeout))
+    }
+}
@@ -0,0 +1,241 @@
+//! Rate limiting requests by a key extracted from each request.
+//!
+//! Every key is limited independently. Keys use a default `Rate` unless an
+//! override has been registered for them through a `Handle`, which may be
+//! done at any time, e.g. to tighten the limit for a single abusive client.
+
+pub mod future;
+mod layer;
+
+pub use self::layer::KeyedRateLimitLayer;
+
+use self::future::ResponseFuture;
+use crate::bucket::Bucket;
+use crate::error::Error;
+use crate::Rate;
+use futures::Poll;
+use tokio_timer::clock;
+use tower_service::Service;
+
+use std::cmp;
+use std::collections::HashMap;
+use std::hash::Hash;
+use std::sync::{Arc, RwLock};
+use std::time::{Duration, Instant};
+
+/// Enforces a rate limit per key on the requests the underlying service
+/// receives.
+///
+/// Unlike `RateLimit`, the key of a request is not known until the request is
+/// received, so requests exceeding the rate of their key fail with
+/// `error::RateLimited` rather than applying backpressure.
+///
+/// The state kept for a key is evicted once the key has been idle for its
+/// rate's period (or the configured `idle_timeout`, if longer), at which point
+/// its quota would have been fully replenished anyway.
+#[derive(Debug)]
+pub struct KeyedRateLimit<T, K, F>
+where
+    K: Hash + Eq,
+{
+    inner: T,
+    key: F,
+    handle: Handle<K>,
+    buckets: HashMap<K, Bucket>,
+    idle_timeout: Duration,
+    next_sweep: Instant,
+}
+
+/// Updates the rates used by a `KeyedRateLimit` at runtime.
+#[derive(Debug)]
+pub struct Handle<K>
+where
+    K: Hash + Eq,
+{
+    rates: Arc<RwLock<Rates<K>>>,
+}
+
+#[derive(Debug)]
+struct Rates<K>
+where
+    K: Hash + Eq,
+{
+    default: Rate,
+    overrides: HashMap<K, Rate>,
+}
+
+// ===== impl KeyedRateLimit =====
+
+impl<T, K, F> KeyedRateLimit<T, K, F>
+where
+    K: Hash + Eq,
+{
+    /// Create a new keyed rate limiter.
+    ///
+    /// `key` is used to extract the key of each request, which is limited to
+    /// `default` unless an override is set through `handle()`.
+    pub fn new<Request>(inner: T, default: Rate, key: F) -> Self
+    where
+        T: Service<Request>,
+        F: Fn(&Request) -> K,
+    {
+        Self::with_handle(inner, Handle::new(default), key)
+    }
+
+    pub(crate) fn with_handle(inner: T, handle: Handle<K>, key: F) -> Self {
+        KeyedRateLimit {
+            inner,
+            key,
+            handle,
+            buckets: HashMap::new(),
+            idle_timeout: Duration::from_secs(0),
+            next_sweep: clock::now(),
+        }
+    }
+
+    /// Keep the state of idle keys for at least `idle_timeout`.
+    ///
+    /// By default, a key's state is evicted as soon as it has been idle for
+    /// the period of its rate.
+    pub fn idle_timeout(mut self, idle_timeout: Duration) -> Self {
+        self.idle_timeout = idle_timeout;
+        self
+    }
+
+    /// Returns the number of keys for which state is currently kept.
+    pub fn tracked_keys(&self) -> usize {
+        self.buckets.len()
+    }
+
+    /// Evicts the buckets of keys that have been idle long enough to be full.
+    ///
+    /// Sweeps happen at most once per idle timeout (or the default rate's
+    /// period), so their cost is amortized over many calls.
+    fn evict_idle(&mut self, now: Instant) {
+        if now < self.next_sweep {
+            return;
+        }
+
+        let idle_timeout = self.idle_timeout;
+        self.buckets.retain(|_, bucket| !bucket.is_idle(idle_timeout, now));
+
+        let per = self.handle.default_rate().per();
+        self.next_sweep = now + cmp::max(idle_timeout, per);
+    }
+
+    /// Returns a handle that updates the rates of this limiter.
+    pub fn handle(&self) -> Handle<K> {
+        self.handle.clone()
+    }
+
+    /// Get a reference to the inner service
+    pub fn get_ref(&self) -> &T {
+        &self.inner
+    }
+
+    /// Get a mutable reference to the inner service
+    pub fn get_mut(&mut self) -> &mut T {
+        &mut self.inner
+    }
+
+    /// Consume `self`, returning the inner service
+    pub fn into_inner(self) -> T {
+        self.inner
+    }
+}
+
+impl<T, K, F, Request> Service<Request> for KeyedRateLimit<T, K, F>
+where
+    T: Service<Request>,
+    Error: From<T::Error>,
+    K: Hash + Eq,
+    F: Fn(&Request) -> K,
+{
+    type Response = T::Response;
+    type Error = Error;
+    type Future = ResponseFuture<T::Future>;
+
+    fn poll_ready(&mut self) -> Poll<(), Self::Error> {
+        self.inner.poll_ready().map_err(Into::into)
+    }
+
+    fn call(&mut self, request: Request) -> Self::Future {
+        let key = (self.key)(&request);
+        let rate = self.handle.rate(&key);
+        let now = clock::now();
+
+        self.evict_idle(now);
+
+        let allowed = self
+            .buckets
+            .entry(key)
+            .or_insert_with(|| Bucket::new(rate, now))
+            .try_acquire(rate, now);
+
+        if allowed {
+            ResponseFuture::new(Some(self.inner.call(request)))
+        } else {
+            ResponseFuture::new(None)
+        }
+    }
+}
+
+// ===== impl Handle =====
+
+impl<K> Handle<K>
+where
+    K: Hash + Eq,
+{
+    pub(crate) fn new(default: Rate) -> Self {
+        let rates = Rates {
+            default,
+            overrides: HashMap::new(),
+        };
+
+        Handle {
+            rates: Arc::new(RwLock::new(rates)),
+        }
+    }
+
+    /// Returns the rate that applies to `key`.
+    pub fn rate(&self, key: &K) -> Rate {
+        let rates = self.rates.read().expect("keyed rate limit rates poisoned");
+        rates.overrides.get(key).cloned().unwrap_or(rates.default)
+    }
+
+    /// Returns the rate of keys without an override.
+    pub fn default_rate(&self) -> Rate {
+        let rates = self.rates.read().expect("keyed rate limit rates poisoned");
+        rates.default
+    }
+
+    /// Limit `key` to `rate` instead of the default rate.
+    pub fn set(&self, key: K, rate: Rate) {
+        let mut rates = self.rates.write().expect("keyed rate limit rates poisoned");
+        rates.overrides.insert(key, rate);
+    }
+
+    /// Remove the override for `key`, so that it falls back to the default
+    /// rate. Returns the override, if there was one.
+    pub fn remove(&self, key: &K) -> Option<Rate> {
+        let mut rates = self.rates.write().expect("keyed rate limit rates poisoned");
+        rates.overrides.remove(key)
+    }
+
+    /// Change the rate of all keys without an override.
+    pub fn set_default(&self, rate: Rate) {
+        let mut rates = self.rates.write().expect("keyed rate limit rates poisoned");
+        rates.default = rate;
+    }
+}
+
+impl<K> Clone for Handle<K>
+where
+    K: Hash + Eq,
+{
+    fn clone(&self) -> Self {
+        Handle {
+            rates: self.rates.clone(),
+        }
+    }
+}
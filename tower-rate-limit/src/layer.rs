@@ -1,22 +1,138 @@
+use crate::bucket::Bucket;
+use crate::cost::{Cost, Unit};
 use crate::error::{never::Never, Error};
-use crate::{Rate, RateLimit};
+use crate::{Rate, RateLimit, SharedRateLimit};
+use std::sync::{Arc, Mutex};
 use std::time::Duration;
+use tokio_timer::{clock, timer};
 use tower_layer::Layer;
 use tower_service::Service;
 
 #[derive(Debug)]
-pub struct RateLimitLayer {
+pub struct RateLimitLayer<C = Unit> {
     rate: Rate,
+    cost: C,
+    timer: timer::Handle,
 }
 
 impl RateLimitLayer {
     pub fn new(num: u64, per: Duration) -> Self {
         let rate = Rate::new(num, per);
-        RateLimitLayer { rate }
+        RateLimitLayer {
+            rate,
+            cost: Unit,
+            timer: timer::Handle::default(),
+        }
+    }
+
+    /// Rate limit with a token bucket holding up to `burst` requests, refilled
+    /// at a rate of `num` requests `per` period.
+    ///
+    /// See [`RateLimit::token_bucket`](struct.RateLimit.html#method.token_bucket).
+    pub fn token_bucket(num: u64, per: Duration, burst: u64) -> Self {
+        let rate = Rate::new(num, per).token_bucket(burst);
+        RateLimitLayer {
+            rate,
+            cost: Unit,
+            timer: timer::Handle::default(),
+        }
+    }
+
+    /// Rate limit over a sliding window of `per`, rather than fixed periods.
+    ///
+    /// See [`RateLimit::sliding_window`](struct.RateLimit.html#method.sliding_window).
+    pub fn sliding_window(num: u64, per: Duration) -> Self {
+        let rate = Rate::new(num, per).sliding_window();
+        RateLimitLayer {
+            rate,
+            cost: Unit,
+            timer: timer::Handle::default(),
+        }
+    }
+}
+
+impl<C> RateLimitLayer<C> {
+    /// Refill the rate in `slices` equal increments over each period.
+    ///
+    /// See [`Rate::refill_slices`](struct.Rate.html#method.refill_slices).
+    pub fn refill_slices(mut self, slices: u32) -> Self {
+        self.rate = self.rate.refill_slices(slices);
+        self
+    }
+
+    /// Weigh each request by `cost` rather than counting every request once.
+    ///
+    /// See [`RateLimit::with_cost`](struct.RateLimit.html#method.with_cost).
+    pub fn with_cost<D>(self, cost: D) -> RateLimitLayer<D> {
+        RateLimitLayer {
+            rate: self.rate,
+            cost,
+            timer: self.timer,
+        }
+    }
+
+    /// Wait for the limit to be lifted with `timer`, rather than the timer of
+    /// the runtime that polls the services.
+    ///
+    /// See [`RateLimit::with_timer`](struct.RateLimit.html#method.with_timer).
+    pub fn with_timer(mut self, timer: timer::Handle) -> Self {
+        self.timer = timer;
+        self
+    }
+}
+
+impl<S, C, Request> Layer<S, Request> for RateLimitLayer<C>
+where
+    S: Service<Request>,
+    C: Cost<Request> + Clone,
+    Error: From<S::Error>,
+{
+    type Response = S::Response;
+    type Error = Error;
+    type LayerError = Never;
+    type Service = RateLimit<S, C>;
+
+    fn layer(&self, service: S) -> Result<Self::Service, Self::LayerError> {
+        let service = RateLimit::new(service, self.rate)
+            .with_cost(self.cost.clone())
+            .with_timer(self.timer.clone());
+        Ok(service)
+    }
+}
+
+/// Rate limits requests across all services it produces.
+///
+/// Each service produced by `RateLimitLayer` has its own quota. All services
+/// produced by a `SharedRateLimitLayer` (and clones of the layer) draw from
+/// the same quota instead.
+#[derive(Debug, Clone)]
+pub struct SharedRateLimitLayer {
+    bucket: Arc<Mutex<Bucket>>,
+    timer: timer::Handle,
+}
+
+impl SharedRateLimitLayer {
+    /// Limit the services produced by this layer, and by its clones, to `num`
+    /// requests `per` period in total.
+    pub fn new(num: u64, per: Duration) -> Self {
+        let bucket = Bucket::new(Rate::new(num, per), clock::now());
+        SharedRateLimitLayer {
+            bucket: Arc::new(Mutex::new(bucket)),
+            timer: timer::Handle::default(),
+        }
+    }
+
+    /// Wait for the limit to be lifted with `timer`, rather than the timer of
+    /// the runtime that polls the services.
+    ///
+    /// See [`SharedRateLimit::with_timer`](struct.SharedRateLimit.html#method.with_timer).
+    pub fn with_timer(mut self, timer: timer::Handle) -> Self {
+        self.timer = timer;
+        self
     }
 }
 
-impl<S, Request> Layer<S, Request> for RateLimitLayer
+impl<S, Request> Layer<S, Request> for SharedRateLimitLayer
 where
     S: Service<Request>,
     Error: From<S::Error>,
@@ -24,9 +140,11 @@ where
     type Response = S::Response;
     type Error = Error;
     type LayerError = Never;
-    type Service = RateLimit<S>;
+    type Service = SharedRateLimit<S>;
 
     fn layer(&self, service: S) -> Result<Self::Service, Self::LayerError> {
-        Ok(RateLimit::new(service, self.rate))
+        let service = SharedRateLimit::with_bucket(service, self.bucket.clone())
+            .with_timer(self.timer.clone());
+        Ok(service)
     }
 }
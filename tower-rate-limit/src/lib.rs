@@ -6,34 +6,60 @@ extern crate futures;
 extern crate tokio_timer;
 extern crate tower_layer;
 extern crate tower_service;
+extern crate tower_util;
 
+mod bucket;
+pub mod cost;
 pub mod error;
 pub mod future;
+pub mod keyed;
 mod layer;
 mod rate;
+mod shared;
 
-pub use crate::layer::RateLimitLayer;
+pub use crate::cost::Cost;
+pub use crate::keyed::{KeyedRateLimit, KeyedRateLimitLayer};
+pub use crate::layer::{RateLimitLayer, SharedRateLimitLayer};
 pub use crate::rate::Rate;
+pub use crate::shared::SharedRateLimit;
 
+use crate::cost::Unit;
 use crate::error::Error;
 use crate::future::ResponseFuture;
 use futures::{Future, Poll};
-use tokio_timer::{clock, Delay};
+use tokio_timer::{clock, timer, Delay};
 use tower_service::Service;
+use tower_util::{PollReadyN, Unready, UnreadyReason};
 
+use std::cmp;
+use std::collections::VecDeque;
+use std::mem;
 use std::time::Instant;
 
 #[derive(Debug)]
-pub struct RateLimit<T> {
+pub struct RateLimit<T, C = Unit> {
     inner: T,
     rate: Rate,
+    cost: C,
     state: State,
+    /// Index of the next refill slice within the current period.
+    slice: u32,
+    /// Tokens consumed beyond what was left in the current period, which
+    /// must be paid off by the following refills.
+    debt: u64,
+    /// Times and costs of the requests made in the current window, when the
+    /// rate is a sliding window.
+    window: VecDeque<(Instant, u64)>,
+    /// Total cost of the requests in `window`.
+    in_window: u64,
+    timer: timer::Handle,
 }
 
 #[derive(Debug)]
 enum State {
     // The service has hit its limit
     Limited(Delay),
+    // `until` is the time of the next refill.
     Ready { until: Instant, rem: u64 },
 }
 
@@ -44,17 +70,104 @@ impl<T> RateLimit<T> {
         T: Service<Request>,
     {
         let state = State::Ready {
-            until: clock::now(),
-            rem: rate.num(),
+            until: clock::now() + rate.slice(),
+            rem: rate.capacity(),
         };
 
         RateLimit {
             inner,
             rate,
+            cost: Unit,
             state: state,
+            slice: 0,
+            debt: 0,
+            window: VecDeque::new(),
+            in_window: 0,
+            timer: timer::Handle::default(),
         }
     }
 
+    /// Create a new sliding window rate limiter.
+    ///
+    /// A fixed window allows up to twice the rate around the boundary of two
+    /// periods. With a sliding window, at most `num` requests are made in
+    /// any span of `per`, at the cost of remembering the time of each of the
+    /// last `num` requests.
+    pub fn sliding_window<Request>(inner: T, rate: Rate) -> Self
+    where
+        T: Service<Request>,
+    {
+        RateLimit::new(inner, rate.sliding_window())
+    }
+
+    /// Create a new token bucket rate limiter.
+    ///
+    /// Up to `burst` requests may be made at once, while in the long term
+    /// requests are limited to `rate`. Unlike a fixed window, the bucket is
+    /// refilled one request at a time, so short bursts are smoothed out
+    /// without letting through more than `rate` on average.
+    ///
+    /// # Panics
+    ///
+    /// This function panics if `burst` is 0.
+    pub fn token_bucket<Request>(inner: T, rate: Rate, burst: u64) -> Self
+    where
+        T: Service<Request>,
+    {
+        RateLimit::new(inner, rate.token_bucket(burst))
+    }
+}
+
+impl<T, C> RateLimit<T, C> {
+    /// Weigh each request by `cost` rather than counting every request once.
+    ///
+    /// A request is let through as long as any of the limit remains, even if
+    /// it costs more than what is left. The excess is then paid off by
+    /// the following refills before any other request is allowed.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// # extern crate tower_rate_limit;
+    /// # extern crate tower_service;
+    /// # use std::time::Duration;
+    /// # use tower_rate_limit::{Rate, RateLimit};
+    /// # use tower_service::Service;
+    /// # fn main() {}
+    /// fn limit_items<S>(svc: S) -> impl Service<Vec<u32>>
+    /// where
+    ///     S: Service<Vec<u32>>,
+    ///     Box<std::error::Error + Send + Sync>: From<S::Error>,
+    /// {
+    ///     // Each item in a batch counts as a request.
+    ///     let rate = Rate::new(100, Duration::from_secs(1));
+    ///     RateLimit::new(svc, rate).with_cost(|batch: &Vec<u32>| batch.len() as u64)
+    /// }
+    /// ```
+    pub fn with_cost<D>(self, cost: D) -> RateLimit<T, D> {
+        RateLimit {
+            inner: self.inner,
+            rate: self.rate,
+            cost,
+            state: self.state,
+            slice: self.slice,
+            debt: self.debt,
+            window: self.window,
+            in_window: self.in_window,
+            timer: self.timer,
+        }
+    }
+
+    /// Wait for the limit to be lifted with `timer`, rather than the timer of
+    /// the runtime that polls the service.
+    ///
+    /// This allows a stack to be driven by a custom runtime or a nested
+    /// executor that does not set a default timer.
+    pub fn with_timer(mut self, timer: timer::Handle) -> Self {
+        self.timer = timer;
+        self
+    }
+
     /// Get a reference to the inner service
     pub fn get_ref(&self) -> &T {
         &self.inner
@@ -69,11 +182,123 @@ impl<T> RateLimit<T> {
     pub fn into_inner(self) -> T {
         self.inner
     }
+
+    /// Wait until the limit is lifted, returning the number of calls that may
+    /// be made in the current period.
+    fn poll_rate(&mut self) -> Poll<u64, Error> {
+        loop {
+            let refilled_at = match self.state {
+                State::Ready { rem, .. } => return Ok(rem.into()),
+                State::Limited(ref mut sleep) => {
+                    try_ready!(sleep.poll());
+                    sleep.deadline()
+                }
+            };
+
+            let rem = if self.rate.is_sliding() {
+                let rem = self.slide(refilled_at);
+                if rem == 0 {
+                    // An expensive request is still in the window.
+                    let (oldest, _) = *self.window.front().expect("window is not empty");
+                    self.state = State::Limited(self.timer.delay(oldest + self.rate.per()));
+                    continue;
+                }
+                rem
+            } else {
+                let refill = self.next_refill();
+                if refill <= self.debt {
+                    // The refill only pays off part of an expensive request.
+                    self.debt -= refill;
+                    let sleep = self.timer.delay(refilled_at + self.rate.slice());
+                    self.state = State::Limited(sleep);
+                    continue;
+                }
+                refill - mem::replace(&mut self.debt, 0)
+            };
+
+            self.state = State::Ready {
+                until: refilled_at + self.rate.slice(),
+                rem,
+            };
+        }
+    }
+
+    /// Drops requests that have left the window as of `now`, returning the
+    /// number of requests that may be made.
+    fn slide(&mut self, now: Instant) -> u64 {
+        while let Some(&(at, cost)) = self.window.front() {
+            if at + self.rate.per() > now {
+                break;
+            }
+            self.window.pop_front();
+            self.in_window -= cost;
+        }
+
+        self.rate.num().saturating_sub(self.in_window)
+    }
+
+    /// Records a request costing `cost` made at `now` in the sliding window.
+    fn call_sliding(&mut self, now: Instant, cost: u64) -> State {
+        let rem = self.slide(now);
+        debug_assert!(rem > 0, "sliding window exceeded");
+
+        // Free requests take up nothing of the window, and would let it
+        // grow without bound.
+        if cost > 0 {
+            self.window.push_back((now, cost));
+            self.in_window += cost;
+        }
+
+        // Every request in the window costs at least 1, and all but the last
+        // one fit in the rate.
+        #[cfg(feature = "audit-bounds")]
+        assert!(
+            self.window.len() as u64 <= self.rate.num(),
+            "sliding window holds {} requests, exceeding its rate of {}",
+            self.window.len(),
+            self.rate.num()
+        );
+
+        if rem > cost {
+            State::Ready {
+                until: now,
+                rem: rem - cost,
+            }
+        } else {
+            // Wait until the oldest request leaves the window.
+            let (oldest, _) = *self.window.front().expect("window is not empty");
+            State::Limited(self.timer.delay(oldest + self.rate.per()))
+        }
+    }
+
+    /// Returns the number of requests replenished by the next slice.
+    fn next_refill(&mut self) -> u64 {
+        let refill = self.rate.refill(self.slice);
+        self.slice = (self.slice + 1) % self.rate.slices();
+        refill
+    }
+
+    /// Replenishes `rem` for every slice that has elapsed as of `now`.
+    fn refill(&mut self, mut until: Instant, mut rem: u64, now: Instant) -> (Instant, u64) {
+        while now >= until && rem < self.rate.capacity() {
+            rem += self.next_refill();
+            until += self.rate.slice();
+        }
+
+        if now >= until {
+            // The limit is already at capacity, so there's nothing to catch
+            // up on. Start timing the next slice from now.
+            until = now + self.rate.slice();
+        }
+
+        (until, cmp::min(rem, self.rate.capacity()))
+    }
 }
 
-impl<S, Request> Service<Request> for RateLimit<S>
+impl<S, C, Request> Service<Request> for RateLimit<S, C>
 where
     S: Service<Request>,
+    C: Cost<Request>,
     Error: From<S::Error>,
 {
     type Response = S::Response;
@@ -81,40 +306,39 @@ where
     type Future = ResponseFuture<S::Future>;
 
     fn poll_ready(&mut self) -> Poll<(), Self::Error> {
-        match self.state {
-            State::Ready { .. } => return self.inner.poll_ready().map_err(Into::into),
-            State::Limited(ref mut sleep) => {
-                try_ready!(sleep.poll());
-            }
-        }
-
-        self.state = State::Ready {
-            until: clock::now() + self.rate.per(),
-            rem: self.rate.num(),
-        };
+        try_ready!(self.poll_rate());
 
         self.inner.poll_ready().map_err(Into::into)
     }
 
     fn call(&mut self, request: Request) -> Self::Future {
+        let cost = self.cost.cost(&request);
+
         match self.state {
+            State::Ready { .. } if self.rate.is_sliding() => {
+                self.state = self.call_sliding(clock::now(), cost);
+
+                let inner = self.inner.call(request);
+                ResponseFuture::new(inner)
+            }
             State::Ready { mut until, mut rem } => {
                 let now = clock::now();
 
-                // If the period has elapsed, reset it.
+                // If any slices of the period have elapsed, refill them.
                 if now >= until {
-                    until = now + self.rate.per();
-                    let rem = self.rate.num();
-
-                    self.state = State::Ready { until, rem }
+                    let (next_until, next_rem) = self.refill(until, rem, now);
+                    until = next_until;
+                    rem = next_rem;
                 }
 
-                if rem > 1 {
-                    rem -= 1;
+                if rem > cost {
+                    rem -= cost;
                     self.state = State::Ready { until, rem };
                 } else {
-                    // The service is disabled until further notice
-                    let sleep = Delay::new(until);
+                    // The service is disabled until further notice, and any
+                    // tokens consumed beyond `rem` are owed to later refills.
+                    self.debt = cost - rem;
+                    let sleep = self.timer.delay(until);
                     self.state = State::Limited(sleep);
                 }
 
@@ -126,3 +350,43 @@ where
         }
     }
 }
+
+impl<S, C, Request> Unready<Request> for RateLimit<S, C>
+where
+    S: Unready<Request>,
+    C: Cost<Request>,
+    Error: From<S::Error>,
+{
+    fn unready_reason(&self) -> Option<UnreadyReason> {
+        match self.state {
+            State::Limited(..) => Some(UnreadyReason::RateLimited),
+            State::Ready { .. } => self.inner.unready_reason(),
+        }
+    }
+}
+
+impl<S, C, Request> PollReadyN<Request> for RateLimit<S, C>
+where
+    S: PollReadyN<Request>,
+    C: Cost<Request>,
+    Error: From<S::Error>,
+{
+    /// Reserves as many calls as the remaining limit lets through.
+    ///
+    /// A call is let through as long as any of the limit remains, so `rem`
+    /// tokens allow for `rem / cost` calls, rounded up. When requests are
+    /// weighed by a cost function, the cost of the next calls is not known,
+    /// and a single call is reserved.
+    fn poll_ready_n(&mut self, n: usize) -> Poll<usize, Self::Error> {
+        assert!(n > 0, "must reserve at least one call");
+
+        let rem = try_ready!(self.poll_rate());
+        let n = match self.cost.fixed_cost() {
+            Some(0) => n,
+            Some(cost) => cmp::min(n as u64, (rem + cost - 1) / cost) as usize,
+            None => 1,
+        };
+
+        self.inner.poll_ready_n(n).map_err(Into::into)
+    }
+}
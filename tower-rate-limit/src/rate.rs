@@ -1,9 +1,12 @@
 use std::time::Duration;
 
-#[derive(Debug, Copy, Clone)]
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
 pub struct Rate {
     num: u64,
     per: Duration,
+    slices: u32,
+    capacity: u64,
+    sliding: bool,
 }
 
 impl Rate {
@@ -16,7 +19,59 @@ impl Rate {
         assert!(num > 0);
         assert!(per > Duration::from_millis(0));
 
-        Rate { num, per }
+        Rate {
+            num,
+            per,
+            slices: 1,
+            capacity: num,
+            sliding: false,
+        }
+    }
+
+    /// Refill the rate in `slices` equal increments over each period, rather
+    /// than all at once when the period elapses.
+    ///
+    /// With a single slice (the default), callers that are limited all wake
+    /// up together when the period ends. Using more slices spreads the refill
+    /// over the period, smoothing out bursts at the window boundary.
+    ///
+    /// # Panics
+    ///
+    /// This function panics if `slices` is 0 or greater than the number of
+    /// requests allowed per period.
+    pub fn refill_slices(mut self, slices: u32) -> Self {
+        assert!(slices > 0);
+        assert!(u64::from(slices) <= self.num);
+
+        self.slices = slices;
+        self
+    }
+
+    /// Turn the rate into a token bucket holding up to `burst` requests.
+    ///
+    /// The bucket is refilled one request at a time, at a rate of `num`
+    /// requests per period.
+    ///
+    /// # Panics
+    ///
+    /// This function panics if `burst` is 0, or if `num` does not fit in a
+    /// `u32`.
+    pub(crate) fn token_bucket(mut self, burst: u64) -> Self {
+        assert!(burst > 0);
+        assert!(self.num <= u64::from(::std::u32::MAX));
+
+        self.slices = self.num as u32;
+        self.capacity = burst;
+        self
+    }
+
+    /// Limit requests over a sliding window rather than fixed periods.
+    ///
+    /// At any point in time, at most `num` requests will have been made in
+    /// the preceding `per`.
+    pub(crate) fn sliding_window(mut self) -> Self {
+        self.sliding = true;
+        self
     }
 
     pub(crate) fn num(&self) -> u64 {
@@ -26,4 +81,33 @@ impl Rate {
     pub(crate) fn per(&self) -> Duration {
         self.per
     }
+
+    pub(crate) fn is_sliding(&self) -> bool {
+        self.sliding
+    }
+
+    /// The maximum number of requests that may be made at once.
+    pub(crate) fn capacity(&self) -> u64 {
+        self.capacity
+    }
+
+    pub(crate) fn slices(&self) -> u32 {
+        self.slices
+    }
+
+    /// The amount of time between refills.
+    pub(crate) fn slice(&self) -> Duration {
+        self.per / self.slices
+    }
+
+    /// The number of requests replenished by the `idx`th slice of a period.
+    ///
+    /// The amounts are distributed so that the slices of a period always add
+    /// up to `num`.
+    pub(crate) fn refill(&self, idx: u32) -> u64 {
+        let slices = u64::from(self.slices);
+        let idx = u64::from(idx % self.slices);
+
+        (self.num * (idx + 1)) / slices - (self.num * idx) / slices
+    }
 }
@@ -0,0 +1,152 @@
+use crate::bucket::Bucket;
+use crate::error::Error;
+use crate::future::ResponseFuture;
+use crate::Rate;
+use futures::{Async, Future, Poll};
+use tokio_timer::{clock, timer, Delay};
+use tower_service::Service;
+use tower_util::{Unready, UnreadyReason};
+
+use std::sync::{Arc, Mutex};
+
+/// Enforces a rate limit shared by all clones of the service.
+///
+/// Each `RateLimit` has its own quota, so e.g. a client stack built once per
+/// connection may exceed the rate once per connection. The quota of a
+/// `SharedRateLimit` is kept behind an `Arc`, so that the service can be
+/// cloned for each connection while all clones draw from it together.
+///
+/// A call is taken from the quota when the service becomes ready. If the
+/// service is dropped before the call is made, it is returned.
+#[derive(Debug)]
+pub struct SharedRateLimit<T> {
+    inner: T,
+    bucket: Arc<Mutex<Bucket>>,
+    timer: timer::Handle,
+    sleep: Option<Delay>,
+    acquired: bool,
+}
+
+impl<T> SharedRateLimit<T> {
+    /// Create a new rate limiter, whose clones share `rate`.
+    pub fn new<Request>(inner: T, rate: Rate) -> Self
+    where
+        T: Service<Request>,
+    {
+        let bucket = Bucket::new(rate, clock::now());
+        Self::with_bucket(inner, Arc::new(Mutex::new(bucket)))
+    }
+
+    pub(crate) fn with_bucket(inner: T, bucket: Arc<Mutex<Bucket>>) -> Self {
+        SharedRateLimit {
+            inner,
+            bucket,
+            timer: timer::Handle::default(),
+            sleep: None,
+            acquired: false,
+        }
+    }
+
+    /// Wait for the limit to be lifted with `timer`, rather than the timer of
+    /// the runtime that polls the service.
+    ///
+    /// See [`RateLimit::with_timer`](struct.RateLimit.html#method.with_timer).
+    pub fn with_timer(mut self, timer: timer::Handle) -> Self {
+        self.timer = timer;
+        self
+    }
+
+    /// Get a reference to the inner service
+    pub fn get_ref(&self) -> &T {
+        &self.inner
+    }
+
+    /// Get a mutable reference to the inner service
+    pub fn get_mut(&mut self) -> &mut T {
+        &mut self.inner
+    }
+
+    /// Wait until a call has been taken from the shared quota.
+    fn poll_acquire(&mut self) -> Poll<(), Error> {
+        loop {
+            if let Some(ref mut sleep) = self.sleep {
+                try_ready!(sleep.poll());
+            }
+
+            let mut bucket = self.bucket.lock().expect("shared rate limit poisoned");
+            let rate = bucket.rate();
+
+            if bucket.try_acquire(rate, clock::now()) {
+                self.sleep = None;
+                self.acquired = true;
+                return Ok(Async::Ready(()));
+            }
+
+            // Other clones may take the refill first, in which case this
+            // clone will go back to sleep.
+            self.sleep = Some(self.timer.delay(bucket.until()));
+        }
+    }
+}
+
+impl<S, Request> Service<Request> for SharedRateLimit<S>
+where
+    S: Service<Request>,
+    Error: From<S::Error>,
+{
+    type Response = S::Response;
+    type Error = Error;
+    type Future = ResponseFuture<S::Future>;
+
+    fn poll_ready(&mut self) -> Poll<(), Self::Error> {
+        if !self.acquired {
+            try_ready!(self.poll_acquire());
+        }
+
+        self.inner.poll_ready().map_err(Into::into)
+    }
+
+    fn call(&mut self, request: Request) -> Self::Future {
+        assert!(
+            self.acquired,
+            "service not ready; poll_ready must be called first"
+        );
+        self.acquired = false;
+
+        ResponseFuture::new(self.inner.call(request))
+    }
+}
+
+impl<S, Request> Unready<Request> for SharedRateLimit<S>
+where
+    S: Unready<Request>,
+    Error: From<S::Error>,
+{
+    fn unready_reason(&self) -> Option<UnreadyReason> {
+        if !self.acquired {
+            return Some(UnreadyReason::RateLimited);
+        }
+
+        self.inner.unready_reason()
+    }
+}
+
+impl<S> Clone for SharedRateLimit<S>
+where
+    S: Clone,
+{
+    fn clone(&self) -> Self {
+        SharedRateLimit::with_bucket(self.inner.clone(), self.bucket.clone())
+            .with_timer(self.timer.clone())
+    }
+}
+
+impl<S> Drop for SharedRateLimit<S> {
+    fn drop(&mut self) {
+        if self.acquired {
+            if let Ok(mut bucket) = self.bucket.lock() {
+                bucket.release();
+            }
+        }
+    }
+}
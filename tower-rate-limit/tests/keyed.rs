@@ -0,0 +1,115 @@
+extern crate futures;
+extern crate tokio;
+extern crate tokio_timer;
+extern crate tower_mock;
+extern crate tower_rate_limit;
+extern crate tower_service;
+
+use futures::{future, Future};
+use tower_rate_limit::error::RateLimited;
+use tower_rate_limit::*;
+use tower_service::*;
+
+use std::time::{Duration, Instant};
+
+type Mock = tower_mock::Mock<(&'static str, &'static str), &'static str>;
+type Handle = tower_mock::Handle<(&'static str, &'static str), &'static str>;
+type Key = fn(&(&'static str, &'static str)) -> &'static str;
+
+fn new_service(rate: Rate) -> (KeyedRateLimit<Mock, &'static str, Key>, Handle) {
+    let (service, handle) = Mock::new();
+    let key: Key = |req| req.0;
+    let service = KeyedRateLimit::new(service, rate, key);
+    (service, handle)
+}
+
+fn call(
+    rt: &mut tokio::runtime::current_thread::Runtime,
+    service: &mut KeyedRateLimit<Mock, &'static str, Key>,
+    handle: &mut Handle,
+    req: (&'static str, &'static str),
+) -> bool {
+    let response = rt
+        .block_on(future::lazy(|| {
+            assert!(service.poll_ready().unwrap().is_ready());
+            Ok::<_, ()>(service.call(req))
+        }))
+        .unwrap();
+
+    rt.block_on(future::lazy(|| {
+        if let futures::Async::Ready(Some(request)) = handle.poll_request().unwrap() {
+            request.respond("ok");
+        }
+        Ok::<_, ()>(())
+    }))
+    .unwrap();
+
+    match response.wait() {
+        Ok(_) => true,
+        Err(e) => {
+            assert!(e.is::<RateLimited>());
+            false
+        }
+    }
+}
+
+#[test]
+fn keys_are_limited_independently() {
+    let mut rt = tokio::runtime::current_thread::Runtime::new().unwrap();
+    let (mut service, mut handle) = new_service(Rate::new(1, Duration::from_millis(100)));
+
+    assert!(call(&mut rt, &mut service, &mut handle, ("a", "hello")));
+    assert!(!call(&mut rt, &mut service, &mut handle, ("a", "hello")));
+    assert!(call(&mut rt, &mut service, &mut handle, ("b", "hello")));
+
+    rt.block_on(tokio_timer::Delay::new(
+        Instant::now() + Duration::from_millis(100),
+    ))
+    .unwrap();
+
+    assert!(call(&mut rt, &mut service, &mut handle, ("a", "hello")));
+}
+
+#[test]
+fn overrides_apply_at_runtime() {
+    let mut rt = tokio::runtime::current_thread::Runtime::new().unwrap();
+    let (mut service, mut handle) = new_service(Rate::new(3, Duration::from_millis(100)));
+    let rates = service.handle();
+
+    assert!(call(&mut rt, &mut service, &mut handle, ("a", "hello")));
+
+    // Tighten the limit of `a` only.
+    rates.set("a", Rate::new(1, Duration::from_millis(100)));
+    assert!(!call(&mut rt, &mut service, &mut handle, ("a", "hello")));
+    assert!(call(&mut rt, &mut service, &mut handle, ("b", "hello")));
+    assert!(call(&mut rt, &mut service, &mut handle, ("b", "hello")));
+
+    // Falling back to the default restores the larger quota.
+    assert!(rates.remove(&"a").is_some());
+    rt.block_on(tokio_timer::Delay::new(
+        Instant::now() + Duration::from_millis(100),
+    ))
+    .unwrap();
+
+    assert!(call(&mut rt, &mut service, &mut handle, ("a", "hello")));
+    assert!(call(&mut rt, &mut service, &mut handle, ("a", "hello")));
+}
+
+#[test]
+fn idle_keys_are_evicted() {
+    let mut rt = tokio::runtime::current_thread::Runtime::new().unwrap();
+    let (mut service, mut handle) = new_service(Rate::new(1, Duration::from_millis(100)));
+
+    assert!(call(&mut rt, &mut service, &mut handle, ("a", "hello")));
+    assert!(call(&mut rt, &mut service, &mut handle, ("b", "hello")));
+    assert_eq!(service.tracked_keys(), 2);
+
+    rt.block_on(tokio_timer::Delay::new(
+        Instant::now() + Duration::from_millis(100),
+    ))
+    .unwrap();
+
+    // `a` and `b` are swept, leaving only `c`.
+    assert!(call(&mut rt, &mut service, &mut handle, ("c", "hello")));
+    assert_eq!(service.tracked_keys(), 1);
+}
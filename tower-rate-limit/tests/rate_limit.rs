@@ -4,10 +4,12 @@ extern crate tokio_timer;
 extern crate tower_mock;
 extern crate tower_rate_limit;
 extern crate tower_service;
+extern crate tower_util;
 
-use futures::future;
+use futures::{future, Async};
 use tower_rate_limit::*;
 use tower_service::*;
+use tower_util::{AlwaysReady, PollReadyN};
 
 use std::time::{Duration, Instant};
 
@@ -55,6 +57,261 @@ fn reaching_capacity() {
     assert_eq!(response.unwrap(), "done");
 }
 
+#[test]
+fn refill_slices() {
+    let mut rt = tokio::runtime::current_thread::Runtime::new().unwrap();
+    let rate = Rate::new(2, from_millis(100)).refill_slices(2);
+    let (mut service, mut handle) = new_service(rate);
+
+    for _ in 0..2 {
+        assert!(service.poll_ready().unwrap().is_ready());
+        let response = service.call("hello");
+        handle.next_request().unwrap().respond("world");
+        assert_eq!(rt.block_on(response).unwrap(), "world");
+    }
+
+    rt.block_on(future::lazy(|| {
+        assert!(service.poll_ready().unwrap().is_not_ready());
+        Ok::<_, ()>(())
+    }))
+    .unwrap();
+
+    // Only half of the period needs to elapse before a slice is refilled.
+    rt.block_on(tokio_timer::Delay::new(
+        Instant::now() + Duration::from_millis(50),
+    ))
+    .unwrap();
+
+    let poll_ready = rt.block_on(future::lazy(|| service.poll_ready()));
+    assert!(poll_ready.unwrap().is_ready());
+
+    let response = service.call("three");
+    handle.next_request().unwrap().respond("done");
+    assert_eq!(rt.block_on(response).unwrap(), "done");
+
+    // The slice only refilled a single request.
+    rt.block_on(future::lazy(|| {
+        assert!(service.poll_ready().unwrap().is_not_ready());
+        Ok::<_, ()>(())
+    }))
+    .unwrap();
+}
+
+#[test]
+fn token_bucket_burst() {
+    let mut rt = tokio::runtime::current_thread::Runtime::new().unwrap();
+    let (service, mut handle) = Mock::new();
+    let mut service = RateLimit::token_bucket(service, Rate::new(2, from_millis(100)), 3);
+
+    // The full burst is available immediately.
+    for _ in 0..3 {
+        assert!(service.poll_ready().unwrap().is_ready());
+        let response = service.call("hello");
+        handle.next_request().unwrap().respond("world");
+        assert_eq!(rt.block_on(response).unwrap(), "world");
+    }
+
+    rt.block_on(future::lazy(|| {
+        assert!(service.poll_ready().unwrap().is_not_ready());
+        Ok::<_, ()>(())
+    }))
+    .unwrap();
+
+    // A single token is added every `per / num`.
+    rt.block_on(tokio_timer::Delay::new(
+        Instant::now() + Duration::from_millis(50),
+    ))
+    .unwrap();
+
+    let poll_ready = rt.block_on(future::lazy(|| service.poll_ready()));
+    assert!(poll_ready.unwrap().is_ready());
+
+    let response = service.call("four");
+    handle.next_request().unwrap().respond("done");
+    assert_eq!(rt.block_on(response).unwrap(), "done");
+
+    rt.block_on(future::lazy(|| {
+        assert!(service.poll_ready().unwrap().is_not_ready());
+        Ok::<_, ()>(())
+    }))
+    .unwrap();
+}
+
+#[test]
+fn sliding_window() {
+    let mut rt = tokio::runtime::current_thread::Runtime::new().unwrap();
+    let (service, mut handle) = Mock::new();
+    let mut service = RateLimit::sliding_window(service, Rate::new(2, from_millis(100)));
+
+    assert!(service.poll_ready().unwrap().is_ready());
+    let response = service.call("one");
+    handle.next_request().unwrap().respond("world");
+    assert_eq!(rt.block_on(response).unwrap(), "world");
+
+    rt.block_on(tokio_timer::Delay::new(Instant::now() + from_millis(60)))
+        .unwrap();
+
+    assert!(service.poll_ready().unwrap().is_ready());
+    let response = service.call("two");
+    handle.next_request().unwrap().respond("world");
+    assert_eq!(rt.block_on(response).unwrap(), "world");
+
+    rt.block_on(future::lazy(|| {
+        assert!(service.poll_ready().unwrap().is_not_ready());
+        Ok::<_, ()>(())
+    }))
+    .unwrap();
+
+    // Once the first request leaves the window, a single request may be made.
+    let poll_ready = rt.block_on(future::poll_fn(|| service.poll_ready()));
+    assert!(poll_ready.is_ok());
+
+    let response = service.call("three");
+    handle.next_request().unwrap().respond("world");
+    assert_eq!(rt.block_on(response).unwrap(), "world");
+
+    // Unlike a fixed window, the second request is still in the window.
+    rt.block_on(future::lazy(|| {
+        assert!(service.poll_ready().unwrap().is_not_ready());
+        Ok::<_, ()>(())
+    }))
+    .unwrap();
+}
+
+#[test]
+fn weighted_requests() {
+    let mut rt = tokio::runtime::current_thread::Runtime::new().unwrap();
+    let (service, mut handle) = new_service(Rate::new(5, from_millis(100)));
+    let mut service = service.with_cost(|request: &&'static str| request.len() as u64);
+
+    assert!(service.poll_ready().unwrap().is_ready());
+    let response = service.call("abc");
+    handle.next_request().unwrap().respond("world");
+    assert_eq!(rt.block_on(response).unwrap(), "world");
+
+    // Only 2 tokens remain, but the request is still let through.
+    assert!(service.poll_ready().unwrap().is_ready());
+    let response = service.call("def");
+    handle.next_request().unwrap().respond("world");
+    assert_eq!(rt.block_on(response).unwrap(), "world");
+
+    rt.block_on(future::lazy(|| {
+        assert!(service.poll_ready().unwrap().is_not_ready());
+        Ok::<_, ()>(())
+    }))
+    .unwrap();
+
+    // The overdrawn token is taken out of the next period, leaving 4.
+    let poll_ready = rt.block_on(future::poll_fn(|| service.poll_ready()));
+    assert!(poll_ready.is_ok());
+
+    let response = service.call("ghij");
+    handle.next_request().unwrap().respond("world");
+    assert_eq!(rt.block_on(response).unwrap(), "world");
+
+    rt.block_on(future::lazy(|| {
+        assert!(service.poll_ready().unwrap().is_not_ready());
+        Ok::<_, ()>(())
+    }))
+    .unwrap();
+}
+
+#[test]
+fn shared_between_clones() {
+    let mut rt = tokio::runtime::current_thread::Runtime::new().unwrap();
+    let (service, mut handle) = Mock::new();
+    let mut service = SharedRateLimit::new(service, Rate::new(1, from_millis(100)));
+    let mut other = service.clone();
+
+    assert!(service.poll_ready().unwrap().is_ready());
+    let response = service.call("one");
+    handle.next_request().unwrap().respond("world");
+    assert_eq!(rt.block_on(response).unwrap(), "world");
+
+    // The clone draws from the quota the first call used up.
+    rt.block_on(future::lazy(|| {
+        assert!(other.poll_ready().unwrap().is_not_ready());
+        Ok::<_, ()>(())
+    }))
+    .unwrap();
+
+    let poll_ready = rt.block_on(future::poll_fn(|| other.poll_ready()));
+    assert!(poll_ready.is_ok());
+
+    let response = other.call("two");
+    handle.next_request().unwrap().respond("world");
+    assert_eq!(rt.block_on(response).unwrap(), "world");
+
+    rt.block_on(future::lazy(|| {
+        assert!(service.poll_ready().unwrap().is_not_ready());
+        Ok::<_, ()>(())
+    }))
+    .unwrap();
+
+    let poll_ready = rt.block_on(future::poll_fn(|| other.poll_ready()));
+    assert!(poll_ready.is_ok());
+
+    // A clone dropped after becoming ready returns its call right away.
+    drop(other);
+    rt.block_on(future::lazy(|| {
+        assert!(service.poll_ready().unwrap().is_ready());
+        Ok::<_, ()>(())
+    }))
+    .unwrap();
+}
+
+#[test]
+fn poll_ready_n_reserves_remaining_calls() {
+    let mut rt = tokio::runtime::current_thread::Runtime::new().unwrap();
+    let mut service = RateLimit::new(AlwaysReady::new(Echo), Rate::new(3, from_millis(100)));
+
+    // Only as many calls as the limit lets through are reserved.
+    assert_eq!(service.poll_ready_n(5).unwrap(), Async::Ready(3));
+    for _ in 0..3 {
+        assert_eq!(rt.block_on(service.call("hello")).unwrap(), "hello");
+    }
+
+    rt.block_on(future::lazy(|| {
+        assert!(service.poll_ready().unwrap().is_not_ready());
+        Ok::<_, ()>(())
+    }))
+    .unwrap();
+}
+
+#[test]
+fn poll_ready_n_reserves_one_weighted_call() {
+    let mut rt = tokio::runtime::current_thread::Runtime::new().unwrap();
+    let service = RateLimit::new(AlwaysReady::new(Echo), Rate::new(5, from_millis(100)));
+    let mut service = service.with_cost(|request: &&'static str| request.len() as u64);
+
+    // The cost of the next calls is not known up front.
+    assert_eq!(service.poll_ready_n(5).unwrap(), Async::Ready(1));
+    assert_eq!(rt.block_on(service.call("abcdef")).unwrap(), "abcdef");
+
+    rt.block_on(future::lazy(|| {
+        assert!(service.poll_ready().unwrap().is_not_ready());
+        Ok::<_, ()>(())
+    }))
+    .unwrap();
+}
+
+/// Responds with the request.
+struct Echo;
+
+impl Service<&'static str> for Echo {
+    type Response = &'static str;
+    type Error = Box<::std::error::Error + Send + Sync>;
+    type Future = future::FutureResult<Self::Response, Self::Error>;
+
+    fn poll_ready(&mut self) -> futures::Poll<(), Self::Error> {
+        Ok(Async::Ready(()))
+    }
+
+    fn call(&mut self, request: &'static str) -> Self::Future {
+        future::ok(request)
+    }
+}
+
 type Mock = tower_mock::Mock<&'static str, &'static str>;
 type Handle = tower_mock::Handle<&'static str, &'static str>;
 
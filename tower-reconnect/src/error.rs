@@ -0,0 +1,74 @@
+//! Error types
+
+use std::fmt;
+
+/// Errors produced by `Reconnect`.
+pub(crate) type Error = Box<::std::error::Error + Send + Sync>;
+
+/// An error when the connection of a `Reconnect` was lost, returned by
+/// `poll_ready` if it fails on disconnect.
+///
+/// Its `source` is the error of the lost connection, if there was one: a
+/// connection replaced because a probe timed out has none.
+#[derive(Debug)]
+pub struct Disconnected {
+    source: Option<Error>,
+}
+
+/// An error when `Reconnect` has given up connecting, because attempts kept
+/// failing for longer than its `max_attempts` or `max_elapsed` allow, or
+/// because its `ReconnectPolicy` decided to.
+///
+/// It is terminal: once it is returned, no more attempts are made.
+#[derive(Debug)]
+pub struct GaveUp {
+    attempts: usize,
+}
+
+// ===== impl Disconnected =====
+
+impl Disconnected {
+    pub(crate) fn new(source: Option<Error>) -> Self {
+        Disconnected { source }
+    }
+}
+
+impl fmt::Display for Disconnected {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self.source {
+            Some(ref source) => write!(f, "connection lost: {}", source),
+            None => f.write_str("connection lost"),
+        }
+    }
+}
+
+impl std::error::Error for Disconnected {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        self.source.as_ref().map(|source| &**source as _)
+    }
+}
+
+// ===== impl GaveUp =====
+
+impl GaveUp {
+    pub(crate) fn new(attempts: usize) -> Self {
+        GaveUp { attempts }
+    }
+
+    /// Returns the number of consecutive attempts that failed.
+    pub fn attempts(&self) -> usize {
+        self.attempts
+    }
+}
+
+impl fmt::Display for GaveUp {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(
+            f,
+            "gave up connecting after {} failed attempts",
+            self.attempts
+        )
+    }
+}
+
+impl std::error::Error for GaveUp {}
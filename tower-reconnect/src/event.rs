@@ -0,0 +1,66 @@
+//! Observing the connection of a `Reconnect`.
+//!
+//! `Reconnect::on_event` registers a callback that is passed an `Event` each
+//! time the connection changes, e.g. to log transitions or to export the
+//! connection state as a metric, without wrapping the `MakeService`.
+
+use std::fmt;
+use std::sync::Arc;
+
+/// A change in the connection of a `Reconnect`.
+///
+/// Attempts to connect are numbered from 1, and counted again from 1 once a
+/// connection is established.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Event {
+    /// An attempt to connect was started.
+    Connecting {
+        /// The number of the attempt.
+        attempt: usize,
+    },
+    /// An attempt to connect succeeded.
+    Connected {
+        /// The number of the attempt.
+        attempt: usize,
+    },
+    /// An attempt to connect failed.
+    ///
+    /// It is followed by the next attempt, possibly after backing off, unless
+    /// `Reconnect` gives up.
+    ConnectFailed {
+        /// The number of the attempt.
+        attempt: usize,
+    },
+    /// The established connection failed, and will be replaced.
+    Disconnected,
+    /// Too many attempts to connect failed, and no more are made.
+    GaveUp {
+        /// The number of attempts made.
+        attempts: usize,
+    },
+}
+
+/// A callback registered with `on_event`.
+#[derive(Clone)]
+pub(crate) struct OnEvent(Arc<Fn(Event) + Send + Sync>);
+
+// ===== impl OnEvent =====
+
+impl OnEvent {
+    pub(crate) fn new<F>(f: F) -> Self
+    where
+        F: Fn(Event) + Send + Sync + 'static,
+    {
+        OnEvent(Arc::new(f))
+    }
+
+    pub(crate) fn emit(&self, event: Event) {
+        (self.0)(event)
+    }
+}
+
+impl fmt::Debug for OnEvent {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_tuple("OnEvent").finish()
+    }
+}
@@ -0,0 +1,125 @@
+use crate::error::Error;
+use crate::event::{Event, OnEvent};
+use crate::never::Never;
+use crate::target::{Shared, TargetHandle};
+use crate::Reconnect;
+use std::sync::Arc;
+use std::time::Duration;
+use tower_layer::Layer;
+use tower_service::Service;
+use tower_util::backoff::Backoff;
+
+/// Reconnects to `target` through the wrapped `MakeService`.
+///
+/// The layer wraps a `MakeService`, rather than a service, turning it into a
+/// service that connects on demand. It is therefore the innermost layer of a
+/// stack built with `ServiceBuilder::build_service(make_service)`, and the
+/// layers added before it wrap the connection as a whole.
+///
+/// All services built by the layer, and by its clones, share the same target,
+/// changed through [`target_handle`](#method.target_handle).
+#[derive(Debug, Clone)]
+pub struct ReconnectLayer<Target> {
+    target: Arc<Shared<Target>>,
+    backoff: Option<Backoff>,
+    max_attempts: Option<usize>,
+    max_elapsed: Option<Duration>,
+    on_event: Option<OnEvent>,
+    fail_on_disconnect: bool,
+}
+
+impl<Target> ReconnectLayer<Target> {
+    /// Create a layer connecting to `target`.
+    pub fn new(target: Target) -> Self {
+        Self::with_target_handle(TargetHandle::new(Arc::new(Shared::new(target))))
+    }
+
+    /// Create a layer connecting to the target of `handle`, shared with the
+    /// services it was obtained from.
+    pub fn with_target_handle(handle: TargetHandle<Target>) -> Self {
+        ReconnectLayer {
+            target: handle.into_shared(),
+            backoff: None,
+            max_attempts: None,
+            max_elapsed: None,
+            on_event: None,
+            fail_on_disconnect: false,
+        }
+    }
+
+    /// Returns a handle to change the target of the services built by the
+    /// layer, including those built from now on.
+    ///
+    /// See [`target`](target/index.html) for details.
+    pub fn target_handle(&self) -> TargetHandle<Target> {
+        TargetHandle::new(self.target.clone())
+    }
+
+    /// Wait between failed attempts to connect.
+    ///
+    /// See [`Reconnect::with_backoff`](struct.Reconnect.html#method.with_backoff).
+    pub fn with_backoff(mut self, backoff: Backoff) -> Self {
+        self.backoff = Some(backoff);
+        self
+    }
+
+    /// Give up once `max_attempts` consecutive attempts to connect failed.
+    ///
+    /// See [`Reconnect::max_attempts`](struct.Reconnect.html#method.max_attempts).
+    pub fn max_attempts(mut self, max_attempts: usize) -> Self {
+        self.max_attempts = Some(max_attempts);
+        self
+    }
+
+    /// Give up once attempts to connect have kept failing for `max_elapsed`.
+    ///
+    /// See [`Reconnect::max_elapsed`](struct.Reconnect.html#method.max_elapsed).
+    pub fn max_elapsed(mut self, max_elapsed: Duration) -> Self {
+        self.max_elapsed = Some(max_elapsed);
+        self
+    }
+
+    /// Fail `poll_ready` when the connection is lost, rather than waiting for
+    /// a new one.
+    ///
+    /// See [`Reconnect::fail_on_disconnect`](struct.Reconnect.html#method.fail_on_disconnect).
+    pub fn fail_on_disconnect(mut self) -> Self {
+        self.fail_on_disconnect = true;
+        self
+    }
+
+    /// Call `f` with each `Event` in the lifecycle of the connections of the
+    /// produced services.
+    ///
+    /// See [`event`](event/index.html) for details.
+    pub fn on_event<F>(mut self, f: F) -> Self
+    where
+        F: Fn(Event) + Send + Sync + 'static,
+    {
+        self.on_event = Some(OnEvent::new(f));
+        self
+    }
+}
+
+impl<M, Target, S, Request> Layer<M, Request> for ReconnectLayer<Target>
+where
+    M: Service<Target, Response = S>,
+    S: Service<Request>,
+    Error: From<M::Error> + From<S::Error>,
+    Target: Clone,
+{
+    type Response = S::Response;
+    type Error = Error;
+    type LayerError = Never;
+    type Service = Reconnect<M, Target>;
+
+    fn layer(&self, mk_service: M) -> Result<Self::Service, Self::LayerError> {
+        let mut reconnect = Reconnect::with_shared_target(mk_service, self.target.clone());
+        reconnect.backoff = self.backoff.clone();
+        reconnect.max_attempts = self.max_attempts;
+        reconnect.max_elapsed = self.max_elapsed;
+        reconnect.on_event = self.on_event.clone();
+        reconnect.fail_on_disconnect = self.fail_on_disconnect;
+        Ok(reconnect)
+    }
+}
@@ -1,18 +1,45 @@
 extern crate futures;
 #[macro_use]
 extern crate log;
+extern crate rand;
+extern crate tokio_timer;
+extern crate tower_layer;
 extern crate tower_service;
 extern crate tower_util;
 
+pub mod error;
+pub mod event;
 pub mod future;
+mod layer;
+mod never;
+pub mod policy;
+mod probe;
+pub mod replay;
+pub mod state;
+pub mod target;
 
+pub use crate::layer::ReconnectLayer;
+pub use crate::policy::ReconnectPolicy;
+pub use crate::replay::Replay;
+
+use crate::error::{Disconnected, Error, GaveUp};
+use crate::event::{Event, OnEvent};
 use crate::future::ResponseFuture;
+use crate::policy::Action;
+use crate::probe::Probe;
+use crate::state::{ConnectionState, StateHandle};
+use crate::target::{Shared, TargetHandle, Watch};
 
 use futures::{Async, Future, Poll};
+use tokio_timer::clock;
 use tower_service::Service;
+use tower_util::backoff::{Backoff, Delayed};
 use tower_util::MakeService;
 
 use std::fmt;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::Arc;
+use std::time::{Duration, Instant};
 
 pub struct Reconnect<M, Target>
 where
@@ -20,16 +47,37 @@ where
 {
     mk_service: M,
     state: State<M::Future, M::Response>,
-    target: Target,
+    target: Arc<Shared<Target>>,
+    /// Watches `target` for calls to `TargetHandle::reconnect_now`.
+    watch: Watch,
+    backoff: Option<Backoff>,
+    max_attempts: Option<usize>,
+    max_elapsed: Option<Duration>,
+    /// The number of consecutive attempts to connect that failed.
+    failures: usize,
+    /// When the first of the consecutive failed attempts failed.
+    failing_since: Option<Instant>,
+    /// The number of connections established, identifying the current one.
+    connections: usize,
+    /// The number of the connection that is up, or 0 while none is, shared
+    /// with the response futures of a `Replay`.
+    live: Arc<AtomicUsize>,
+    on_event: Option<OnEvent>,
+    probe: Option<Probe<M::Response>>,
+    policy: Option<Box<ReconnectPolicy + Send>>,
+    state_handle: StateHandle,
+    fail_on_disconnect: bool,
 }
 
-type Error = Box<::std::error::Error + Send + Sync>;
-
 #[derive(Debug)]
 enum State<F, S> {
     Idle,
     Connecting(F),
     Connected(S),
+    /// Waiting before the next attempt to connect.
+    Backoff(Delayed<()>),
+    /// Gave up connecting.
+    Failed,
 }
 
 impl<M, Target> Reconnect<M, Target>
@@ -43,29 +91,255 @@ where
         Error: From<M::Error> + From<S::Error>,
         Target: Clone,
     {
+        Self::with_shared_target(mk_service, Arc::new(Shared::new(target)))
+    }
+
+    /// Create a `Reconnect` connecting to a target shared with other
+    /// services.
+    pub(crate) fn with_shared_target(mk_service: M, target: Arc<Shared<Target>>) -> Self {
         Reconnect {
             mk_service,
             state: State::Idle,
+            watch: target.watch(),
             target,
+            backoff: None,
+            max_attempts: None,
+            max_elapsed: None,
+            failures: 0,
+            failing_since: None,
+            connections: 0,
+            live: Arc::new(AtomicUsize::new(0)),
+            on_event: None,
+            probe: None,
+            policy: None,
+            state_handle: StateHandle::new(),
+            fail_on_disconnect: false,
+        }
+    }
+
+    /// Returns the state of the connection.
+    ///
+    /// See [`state`](state/index.html) for details.
+    pub fn state(&self) -> ConnectionState {
+        match self.state {
+            State::Idle => ConnectionState::Idle,
+            State::Connecting(_) | State::Backoff(_) => ConnectionState::Connecting,
+            State::Connected(_) => ConnectionState::Connected,
+            State::Failed => ConnectionState::Failed,
+        }
+    }
+
+    /// Returns a handle reporting the state of the connection.
+    ///
+    /// See [`state`](state/index.html) for details.
+    pub fn state_handle(&self) -> StateHandle {
+        self.state_handle.clone()
+    }
+
+    /// Returns a handle to change the target of the following connections.
+    ///
+    /// See [`target`](target/index.html) for details.
+    pub fn target_handle(&self) -> TargetHandle<Target> {
+        TargetHandle::new(self.target.clone())
+    }
+
+    /// Wait between failed attempts to connect, rather than trying again
+    /// right away.
+    ///
+    /// The delays are computed by `backoff`, and grow with every consecutive
+    /// failure until a connection is established. Adding jitter to them keeps
+    /// many clients of a backend that went down from reconnecting in
+    /// lockstep.
+    pub fn with_backoff(mut self, backoff: Backoff) -> Self {
+        self.backoff = Some(backoff);
+        self
+    }
+
+    /// Give up once `max_attempts` consecutive attempts to connect failed.
+    ///
+    /// The error of the last attempt is returned by `poll_ready` as usual, and
+    /// every later call fails with `error::GaveUp`, so that callers waiting
+    /// for a target that is gone for good are not left waiting forever.
+    pub fn max_attempts(mut self, max_attempts: usize) -> Self {
+        self.max_attempts = Some(max_attempts);
+        self
+    }
+
+    /// Give up once attempts to connect have kept failing for `max_elapsed`,
+    /// counted from the first failure.
+    ///
+    /// Attempts are still made until one fails after `max_elapsed`, which is
+    /// then reported like hitting [`max_attempts`](#method.max_attempts).
+    /// Both limits may be set, in which case the first one reached applies.
+    pub fn max_elapsed(mut self, max_elapsed: Duration) -> Self {
+        self.max_elapsed = Some(max_elapsed);
+        self
+    }
+
+    /// Returns `true` if too many attempts to connect failed.
+    fn should_give_up(&self) -> bool {
+        let too_many = self.max_attempts.map_or(false, |max| self.failures >= max);
+        let too_long = match (self.max_elapsed, self.failing_since) {
+            (Some(max), Some(since)) => clock::now() - since >= max,
+            _ => false,
+        };
+
+        too_many || too_long
+    }
+
+    /// Call `f` with each `Event` in the lifecycle of the connection.
+    ///
+    /// See [`event`](event/index.html) for details.
+    pub fn on_event<F>(mut self, f: F) -> Self
+    where
+        F: Fn(Event) + Send + Sync + 'static,
+    {
+        self.on_event = Some(OnEvent::new(f));
+        self
+    }
+
+    /// Fail `poll_ready` with `error::Disconnected` when the connection is
+    /// lost, rather than waiting for a new one.
+    ///
+    /// By default, a caller waiting for the `Reconnect` to become ready keeps
+    /// waiting while it reconnects, as long as the limits on reconnecting
+    /// allow, and its request is then sent on the new connection. Failing
+    /// instead lets callers fail over, or give up, right away. The next call
+    /// to `poll_ready` starts reconnecting.
+    ///
+    /// Either way, requests in flight on the lost connection complete as the
+    /// connection's service decides, usually with an error. To send them
+    /// again on the new connection, wrap the `Reconnect` in a
+    /// [`Replay`](replay/index.html).
+    pub fn fail_on_disconnect(mut self) -> Self {
+        self.fail_on_disconnect = true;
+        self
+    }
+
+    /// Decide what to do after an attempt to connect failed with `policy`.
+    ///
+    /// The policy replaces the settings of `with_backoff`, `max_attempts` and
+    /// `max_elapsed`. See [`policy`](policy/index.html) for details.
+    pub fn with_policy<P>(mut self, policy: P) -> Self
+    where
+        P: ReconnectPolicy + Send + 'static,
+    {
+        self.policy = Some(Box::new(policy));
+        self
+    }
+
+    /// Check the established connection every `interval` by sending the
+    /// request returned by `request`, rather than waiting for a request of a
+    /// caller to fail on a dead connection.
+    ///
+    /// A probe fails if the service returns an error, or if it does not
+    /// respond within `interval`, in which case the connection is replaced.
+    /// The responses to successful probes are discarded.
+    ///
+    /// Probes are sent by `poll_ready` once the service is ready, and it does
+    /// not report being ready to the caller until the probe has succeeded,
+    /// so that no request is sent on a connection that was not checked within
+    /// `interval`. A connection left idle is thus probed before it is used
+    /// again. The task that last polled the `Reconnect` is notified when a
+    /// probe is due. A timer must be available.
+    pub fn with_probe<Request, F>(mut self, interval: Duration, request: F) -> Self
+    where
+        M::Response: Service<Request>,
+        <M::Response as Service<Request>>::Future: Send + 'static,
+        Error: From<<M::Response as Service<Request>>::Error>,
+        F: Fn() -> Request + Send + Sync + 'static,
+    {
+        self.probe = Some(Probe::new(interval, request));
+        self
+    }
+
+    /// Marks the current connection as lost.
+    fn disconnected(&self) {
+        self.live.store(0, Ordering::SeqCst);
+        self.emit(Event::Disconnected);
+    }
+
+    fn emit(&self, event: Event) {
+        if let Some(ref on_event) = self.on_event {
+            on_event.emit(event);
+        }
+    }
+
+    /// Returns the state to enter after an attempt to connect failed with
+    /// `error`.
+    fn failed(&mut self, error: &Error) -> State<M::Future, M::Response> {
+        self.failures += 1;
+        if self.failing_since.is_none() {
+            self.failing_since = Some(clock::now());
+        }
+        self.emit(Event::ConnectFailed {
+            attempt: self.failures,
+        });
+
+        let action = match self.policy {
+            Some(ref mut policy) => policy.on_failure(self.failures, &**error),
+            None => self.default_action(),
+        };
+
+        match action {
+            Action::Retry => State::Idle,
+            Action::RetryAfter(delay) => {
+                trace!("backing off for {:?}", delay);
+                State::Backoff(Delayed::new((), delay))
+            }
+            Action::GiveUp => {
+                warn!("giving up after {} failed attempts", self.failures);
+                self.emit(Event::GaveUp {
+                    attempts: self.failures,
+                });
+                State::Failed
+            }
+        }
+    }
+
+    /// Returns the `Action` configured with `with_backoff`, `max_attempts`
+    /// and `max_elapsed`.
+    fn default_action(&mut self) -> Action {
+        if self.should_give_up() {
+            return Action::GiveUp;
+        }
+
+        match self.backoff {
+            Some(ref mut backoff) => {
+                Action::RetryAfter(backoff.next_delay(&mut rand::thread_rng()))
+            }
+            None => Action::Retry,
         }
     }
 }
 
-impl<M, Target, S, Request> Service<Request> for Reconnect<M, Target>
+impl<M, Target, S> Reconnect<M, Target>
 where
     M: Service<Target, Response = S>,
-    S: Service<Request>,
-    Error: From<M::Error> + From<S::Error>,
-    Target: Clone,
 {
-    type Response = S::Response;
-    type Error = Error;
-    type Future = ResponseFuture<S::Future>;
-
-    fn poll_ready(&mut self) -> Poll<(), Self::Error> {
-        let ret;
+    /// Drives the connection until the connected service is ready.
+    fn poll_connection<Request>(&mut self) -> Poll<(), Error>
+    where
+        S: Service<Request>,
+        Error: From<M::Error> + From<S::Error>,
+        Target: Clone,
+    {
+        let error: Error;
         let mut state;
 
+        if self.target.poll_reconnect(&mut self.watch) {
+            trace!("poll_ready; reconnecting now");
+            if let State::Connected(_) = self.state {
+                self.disconnected();
+            }
+            self.state = State::Idle;
+            self.failures = 0;
+            self.failing_since = None;
+            if let Some(ref mut backoff) = self.backoff {
+                backoff.reset();
+            }
+        }
+
         loop {
             match self.state {
                 State::Idle => {
@@ -78,7 +352,10 @@ where
                         }
                     }
 
-                    let fut = self.mk_service.make_service(self.target.clone());
+                    self.emit(Event::Connecting {
+                        attempt: self.failures + 1,
+                    });
+                    let fut = self.mk_service.make_service(self.target.get());
                     self.state = State::Connecting(fut);
                     continue;
                 }
@@ -86,6 +363,19 @@ where
                     trace!("poll_ready; connecting");
                     match f.poll() {
                         Ok(Async::Ready(service)) => {
+                            self.emit(Event::Connected {
+                                attempt: self.failures + 1,
+                            });
+                            self.failures = 0;
+                            self.failing_since = None;
+                            self.connections += 1;
+                            self.live.store(self.connections, Ordering::SeqCst);
+                            if let Some(ref mut backoff) = self.backoff {
+                                backoff.reset();
+                            }
+                            if let Some(ref mut probe) = self.probe {
+                                probe.reset();
+                            }
                             state = State::Connected(service);
                         }
                         Ok(Async::NotReady) => {
@@ -94,16 +384,43 @@ where
                         }
                         Err(e) => {
                             trace!("poll_ready; error");
-                            state = State::Idle;
-                            ret = Err(e.into());
+                            error = e.into();
                             break;
                         }
                     }
                 }
                 State::Connected(ref mut inner) => {
                     trace!("poll_ready; connected");
+                    let probe_failed = match self.probe {
+                        Some(ref mut probe) => probe.poll_failed(),
+                        None => false,
+                    };
+
+                    if probe_failed {
+                        trace!("poll_ready; probe failed");
+                        self.disconnected();
+                        self.state = State::Idle;
+                        if self.fail_on_disconnect {
+                            return Err(Disconnected::new(None).into());
+                        }
+                        continue;
+                    }
+
                     match inner.poll_ready() {
                         Ok(Async::Ready(_)) => {
+                            if let Some(ref mut probe) = self.probe {
+                                if probe.poll_due() {
+                                    trace!("poll_ready; sending probe");
+                                    probe.send(inner);
+                                    continue;
+                                }
+
+                                if probe.is_pending() {
+                                    trace!("poll_ready; waiting for probe");
+                                    return Ok(Async::NotReady);
+                                }
+                            }
+
                             trace!("poll_ready; ready");
                             return Ok(Async::Ready(()));
                         }
@@ -111,19 +428,53 @@ where
                             trace!("poll_ready; not ready");
                             return Ok(Async::NotReady);
                         }
-                        Err(_) => {
+                        Err(e) => {
                             trace!("poll_ready; error");
+                            self.disconnected();
+                            if self.fail_on_disconnect {
+                                self.state = State::Idle;
+                                return Err(Disconnected::new(Some(e.into())).into());
+                            }
                             state = State::Idle;
                         }
                     }
                 }
+                State::Backoff(ref mut delay) => {
+                    trace!("poll_ready; backing off");
+                    if let Ok(Async::NotReady) = delay.poll() {
+                        return Ok(Async::NotReady);
+                    }
+                    state = State::Idle;
+                }
+                State::Failed => {
+                    trace!("poll_ready; gave up");
+                    return Err(GaveUp::new(self.failures).into());
+                }
             }
 
             self.state = state;
         }
 
-        self.state = state;
-        ret
+        self.state = self.failed(&error);
+        Err(error)
+    }
+}
+
+impl<M, Target, S, Request> Service<Request> for Reconnect<M, Target>
+where
+    M: Service<Target, Response = S>,
+    S: Service<Request>,
+    Error: From<M::Error> + From<S::Error>,
+    Target: Clone,
+{
+    type Response = S::Response;
+    type Error = Error;
+    type Future = ResponseFuture<S::Future>;
+
+    fn poll_ready(&mut self) -> Poll<(), Self::Error> {
+        let ready = self.poll_connection::<Request>();
+        self.state_handle.set(self.state());
+        ready
     }
 
     fn call(&mut self, request: Request) -> Self::Future {
@@ -149,6 +500,16 @@ where
             .field("mk_service", &self.mk_service)
             .field("state", &self.state)
             .field("target", &self.target)
+            .field("backoff", &self.backoff)
+            .field("max_attempts", &self.max_attempts)
+            .field("max_elapsed", &self.max_elapsed)
+            .field("failures", &self.failures)
+            .field("failing_since", &self.failing_since)
+            .field("connections", &self.connections)
+            .field("on_event", &self.on_event)
+            .field("probe", &self.probe)
+            .field("policy", &self.policy.is_some())
+            .field("fail_on_disconnect", &self.fail_on_disconnect)
             .finish()
     }
 }
@@ -0,0 +1,45 @@
+//! Deciding what to do when connecting fails.
+//!
+//! By default, `Reconnect` tries again after any error, waiting and giving up
+//! as configured with `with_backoff`, `max_attempts` and `max_elapsed`. A
+//! `ReconnectPolicy` set with `Reconnect::with_policy` makes this decision
+//! instead, e.g. to give up right away on errors that trying again will not
+//! fix, such as failing to authenticate, while retrying dial errors.
+
+use std::error::Error;
+use std::time::Duration;
+
+/// What `Reconnect` should do after an attempt to connect failed.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Action {
+    /// Try again right away.
+    Retry,
+    /// Try again once the given delay has elapsed.
+    RetryAfter(Duration),
+    /// Give up: no more attempts are made, and `poll_ready` fails with
+    /// `error::GaveUp` from then on.
+    GiveUp,
+}
+
+/// Decides what `Reconnect` does after an attempt to connect failed.
+///
+/// This is implemented for closures taking the same arguments as
+/// `on_failure`.
+pub trait ReconnectPolicy {
+    /// Returns the `Action` to take after the attempt numbered `attempt`
+    /// failed with `error`.
+    ///
+    /// Attempts are numbered from 1, and counted again from 1 once a
+    /// connection is established, so that policies may compute their delays
+    /// from `attempt` alone.
+    fn on_failure(&mut self, attempt: usize, error: &(Error + Send + Sync + 'static)) -> Action;
+}
+
+impl<F> ReconnectPolicy for F
+where
+    F: FnMut(usize, &(Error + Send + Sync + 'static)) -> Action,
+{
+    fn on_failure(&mut self, attempt: usize, error: &(Error + Send + Sync + 'static)) -> Action {
+        self(attempt, error)
+    }
+}
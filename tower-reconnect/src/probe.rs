@@ -0,0 +1,113 @@
+use crate::error::Error;
+use futures::{Async, Future};
+use std::fmt;
+use std::time::Duration;
+use tokio_timer::{clock, Delay};
+use tower_service::Service;
+
+/// The response to a probe, erased so that `Reconnect` does not depend on the
+/// type of requests.
+type ProbeFuture = Box<Future<Item = (), Error = Error> + Send>;
+
+/// Periodically checks the established connection of a `Reconnect`.
+pub(crate) struct Probe<S> {
+    interval: Duration,
+    send: Box<Fn(&mut S) -> ProbeFuture + Send + Sync>,
+    /// When the next probe is due. The probe in flight fails if it has not
+    /// completed by then.
+    next: Option<Delay>,
+    pending: Option<ProbeFuture>,
+}
+
+impl<S> Probe<S> {
+    pub(crate) fn new<Request, F>(interval: Duration, request: F) -> Self
+    where
+        S: Service<Request>,
+        S::Future: Send + 'static,
+        Error: From<S::Error>,
+        F: Fn() -> Request + Send + Sync + 'static,
+    {
+        let send = move |service: &mut S| -> ProbeFuture {
+            let response = service.call(request());
+            Box::new(response.map(|_| ()).map_err(Error::from))
+        };
+
+        Probe {
+            interval,
+            send: Box::new(send),
+            next: None,
+            pending: None,
+        }
+    }
+
+    /// Returns `true` if the probe in flight failed or timed out.
+    pub(crate) fn poll_failed(&mut self) -> bool {
+        let result = match self.pending {
+            Some(ref mut pending) => pending.poll(),
+            None => return false,
+        };
+
+        match result {
+            Ok(Async::Ready(())) => {
+                trace!("probe succeeded");
+                self.pending = None;
+                return false;
+            }
+            Ok(Async::NotReady) => {
+                if !self.is_due() {
+                    return false;
+                }
+                debug!("probe timed out");
+            }
+            Err(e) => debug!("probe failed: {}", e),
+        }
+
+        self.pending = None;
+        true
+    }
+
+    /// Returns `true` if a probe should be sent.
+    pub(crate) fn poll_due(&mut self) -> bool {
+        self.pending.is_none() && self.is_due()
+    }
+
+    /// Returns `true` if a probe is in flight.
+    pub(crate) fn is_pending(&self) -> bool {
+        self.pending.is_some()
+    }
+
+    /// Sends a probe on `service`, which must be ready.
+    pub(crate) fn send(&mut self, service: &mut S) {
+        self.pending = Some((self.send)(service));
+        self.next = Some(Delay::new(clock::now() + self.interval));
+    }
+
+    /// Forgets about the previous connection.
+    pub(crate) fn reset(&mut self) {
+        self.next = None;
+        self.pending = None;
+    }
+
+    fn is_due(&mut self) -> bool {
+        let interval = self.interval;
+        let next = self
+            .next
+            .get_or_insert_with(|| Delay::new(clock::now() + interval));
+
+        // Without a timer, no probes are sent.
+        match next.poll() {
+            Ok(Async::Ready(())) => true,
+            _ => false,
+        }
+    }
+}
+
+impl<S> fmt::Debug for Probe<S> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_struct("Probe")
+            .field("interval", &self.interval)
+            .field("next", &self.next)
+            .field("pending", &self.pending.is_some())
+            .finish()
+    }
+}
@@ -0,0 +1,306 @@
+//! Replaying requests lost with a connection.
+//!
+//! When the connection of a `Reconnect` is lost, the requests in flight on it
+//! usually fail with it. Wrapping the `Reconnect` in a `Replay` parks these
+//! requests instead, and sends them again once a new connection is
+//! established.
+//!
+//! A copy of each request is kept until its response arrives, so requests
+//! must be `Clone`. Up to `limit` requests are parked at once; the requests
+//! failing beyond that fail as usual, as do all parked requests if the
+//! `Reconnect` gives up.
+//!
+//! Only requests failing once the `Reconnect` has found their connection
+//! lost, e.g. when polled for readiness or by a probe, are parked. A request
+//! failing while its connection is still up fails right away with its error,
+//! as does one failing before the loss of its connection is noticed.
+//!
+//! Parked requests are replayed by `poll_ready`, before the `Replay` reports
+//! being ready, so callers waiting for the new connection are served after
+//! them. The task that last polled the `Replay` is notified when a request
+//! is parked.
+//!
+//! With the `audit-bounds` feature enabled, parking more than `limit`
+//! requests panics.
+//!
+//! Requests queued in front of the `Reconnect`, e.g. in a `Buffer`, wait for
+//! the new connection unless `Reconnect::fail_on_disconnect` is set.
+
+use crate::error::{Disconnected, Error, GaveUp};
+use crate::future::ResponseFuture as ReconnectFuture;
+use crate::Reconnect;
+
+use futures::sync::oneshot;
+use futures::task::{self, Task};
+use futures::{Async, Future, Poll};
+use tower_service::Service;
+
+use std::collections::VecDeque;
+use std::fmt;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::{Arc, Mutex};
+
+/// Replays requests lost with the connection of a `Reconnect`.
+///
+/// See the [module documentation](index.html) for details.
+pub struct Replay<M, Target, Request>
+where
+    M: Service<Target>,
+    M::Response: Service<Request>,
+{
+    inner: Reconnect<M, Target>,
+    parked: Arc<Mutex<Parked<Request, InnerFuture<M, Target, Request>>>>,
+}
+
+/// The response future of a `Replay`.
+pub struct ResponseFuture<Request, F> {
+    state: State<Request, F>,
+    /// The connection the request was sent on.
+    connection: usize,
+    /// The connection that is up, or 0 while none is.
+    live: Arc<AtomicUsize>,
+    parked: Arc<Mutex<Parked<Request, F>>>,
+}
+
+type InnerFuture<M, Target, Request> =
+    ReconnectFuture<<<M as Service<Target>>::Response as Service<Request>>::Future>;
+
+enum State<Request, F> {
+    Called { future: F, request: Option<Request> },
+    Parked(oneshot::Receiver<Result<F, Error>>),
+    Replayed(F),
+}
+
+/// The requests waiting to be replayed.
+struct Parked<Request, F> {
+    limit: usize,
+    requests: VecDeque<Lost<Request, F>>,
+    /// The task to notify when a request is parked.
+    task: Option<Task>,
+}
+
+/// A request that failed because its connection was lost.
+struct Lost<Request, F> {
+    request: Request,
+    error: Error,
+    tx: oneshot::Sender<Result<F, Error>>,
+}
+
+// ===== impl Replay =====
+
+impl<M, Target, Request> Replay<M, Target, Request>
+where
+    M: Service<Target>,
+    M::Response: Service<Request>,
+{
+    /// Replay up to `limit` requests lost with the connection of `inner`.
+    pub fn new(inner: Reconnect<M, Target>, limit: usize) -> Self {
+        let parked = Parked {
+            limit,
+            requests: VecDeque::new(),
+            task: None,
+        };
+
+        Replay {
+            inner,
+            parked: Arc::new(Mutex::new(parked)),
+        }
+    }
+
+    /// Returns a reference to the inner `Reconnect`.
+    pub fn get_ref(&self) -> &Reconnect<M, Target> {
+        &self.inner
+    }
+
+    /// Fails the parked requests with their own errors.
+    fn fail_parked(&self) {
+        let mut parked = self.parked.lock().expect("replay state");
+        for lost in parked.requests.drain(..) {
+            let _ = lost.tx.send(Err(lost.error));
+        }
+    }
+}
+
+impl<M, Target, S, Request> Service<Request> for Replay<M, Target, Request>
+where
+    M: Service<Target, Response = S>,
+    S: Service<Request>,
+    Error: From<M::Error> + From<S::Error>,
+    Target: Clone,
+    Request: Clone,
+{
+    type Response = S::Response;
+    type Error = Error;
+    type Future = ResponseFuture<Request, ReconnectFuture<S::Future>>;
+
+    fn poll_ready(&mut self) -> Poll<(), Self::Error> {
+        loop {
+            match self.inner.poll_ready() {
+                Ok(Async::Ready(())) => {}
+                Ok(Async::NotReady) => {
+                    self.parked.lock().expect("replay state").task = Some(task::current());
+                    return Ok(Async::NotReady);
+                }
+                Err(e) => {
+                    if e.is::<GaveUp>() {
+                        self.fail_parked();
+                    }
+                    return Err(e);
+                }
+            }
+
+            let lost = {
+                let mut parked = self.parked.lock().expect("replay state");
+                parked.task = Some(task::current());
+                match parked.requests.pop_front() {
+                    Some(lost) => lost,
+                    None => return Ok(Async::Ready(())),
+                }
+            };
+
+            if lost.tx.is_canceled() {
+                continue;
+            }
+
+            trace!("replaying request on the new connection");
+            let future = self.inner.call(lost.request);
+            let _ = lost.tx.send(Ok(future));
+        }
+    }
+
+    fn call(&mut self, request: Request) -> Self::Future {
+        let future = self.inner.call(request.clone());
+
+        ResponseFuture {
+            state: State::Called {
+                future,
+                request: Some(request),
+            },
+            connection: self.inner.connections,
+            live: self.inner.live.clone(),
+            parked: self.parked.clone(),
+        }
+    }
+}
+
+impl<M, Target, Request> Drop for Replay<M, Target, Request>
+where
+    M: Service<Target>,
+    M::Response: Service<Request>,
+{
+    fn drop(&mut self) {
+        self.fail_parked();
+    }
+}
+
+impl<M, Target, Request> fmt::Debug for Replay<M, Target, Request>
+where
+    M: Service<Target> + fmt::Debug,
+    M::Future: fmt::Debug,
+    M::Response: Service<Request> + fmt::Debug,
+    Target: fmt::Debug,
+{
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let parked = self.parked.lock().expect("replay state");
+        f.debug_struct("Replay")
+            .field("inner", &self.inner)
+            .field("limit", &parked.limit)
+            .field("parked", &parked.requests.len())
+            .finish()
+    }
+}
+
+// ===== impl ResponseFuture =====
+
+impl<Request, F> Future for ResponseFuture<Request, F>
+where
+    F: Future<Error = Error>,
+{
+    type Item = F::Item;
+    type Error = Error;
+
+    fn poll(&mut self) -> Poll<Self::Item, Self::Error> {
+        loop {
+            let state = match self.state {
+                State::Called {
+                    ref mut future,
+                    ref mut request,
+                } => {
+                    let error = match future.poll() {
+                        Err(error) => error,
+                        ready => return ready,
+                    };
+
+                    if self.live.load(Ordering::SeqCst) == self.connection {
+                        // The connection the request failed on is still up.
+                        return Err(error);
+                    }
+
+                    match park(&self.parked, request.take(), error) {
+                        Ok(rx) => State::Parked(rx),
+                        Err(error) => return Err(error),
+                    }
+                }
+                State::Parked(ref mut rx) => match rx.poll() {
+                    Ok(Async::Ready(Ok(future))) => State::Replayed(future),
+                    Ok(Async::Ready(Err(error))) => return Err(error),
+                    Ok(Async::NotReady) => return Ok(Async::NotReady),
+                    Err(_) => return Err(Disconnected::new(None).into()),
+                },
+                State::Replayed(ref mut future) => return future.poll(),
+            };
+
+            self.state = state;
+        }
+    }
+}
+
+/// Parks a request that failed with `error`, unless too many are parked
+/// already, in which case the error is returned.
+fn park<Request, F>(
+    parked: &Mutex<Parked<Request, F>>,
+    request: Option<Request>,
+    error: Error,
+) -> Result<oneshot::Receiver<Result<F, Error>>, Error> {
+    let request = match request {
+        Some(request) => request,
+        None => return Err(error),
+    };
+
+    let mut parked = parked.lock().expect("replay state");
+    if parked.requests.len() >= parked.limit {
+        debug!("too many requests parked; failing request");
+        return Err(error);
+    }
+
+    let (tx, rx) = oneshot::channel();
+    parked.requests.push_back(Lost { request, error, tx });
+
+    #[cfg(feature = "audit-bounds")]
+    assert!(
+        parked.requests.len() <= parked.limit,
+        "replay parks {} requests, exceeding its limit of {}",
+        parked.requests.len(),
+        parked.limit
+    );
+    if let Some(task) = parked.task.take() {
+        task.notify();
+    }
+
+    Ok(rx)
+}
+
+impl<Request, F> fmt::Debug for ResponseFuture<Request, F> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let state = match self.state {
+            State::Called { .. } => "Called",
+            State::Parked(_) => "Parked",
+            State::Replayed(_) => "Replayed",
+        };
+
+        f.debug_struct("ResponseFuture")
+            .field("state", &state)
+            .field("connection", &self.connection)
+            .finish()
+    }
+}
@@ -0,0 +1,65 @@
+//! Reporting the state of the connection of a `Reconnect`.
+//!
+//! `Reconnect::state` returns the current `ConnectionState`. Since the
+//! `Reconnect` is usually out of reach once it is wrapped by other
+//! middleware, `Reconnect::state_handle` returns a `StateHandle` that reports
+//! it too, e.g. for a health endpoint. Transitions are reported by
+//! [`event`](../event/index.html) instead.
+
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::Arc;
+
+/// The state of the connection of a `Reconnect`.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum ConnectionState {
+    /// No connection has been attempted since the last one was lost.
+    Idle,
+    /// Connecting, or waiting to try again after an attempt failed.
+    Connecting,
+    /// A connection is established.
+    Connected,
+    /// Gave up connecting.
+    Failed,
+}
+
+/// Reports the state of the connection of a `Reconnect`.
+///
+/// The state is updated each time the `Reconnect` is polled.
+#[derive(Clone, Debug)]
+pub struct StateHandle {
+    state: Arc<AtomicUsize>,
+}
+
+// ===== impl ConnectionState =====
+
+impl ConnectionState {
+    fn from_usize(n: usize) -> Self {
+        match n {
+            0 => ConnectionState::Idle,
+            1 => ConnectionState::Connecting,
+            2 => ConnectionState::Connected,
+            3 => ConnectionState::Failed,
+            _ => unreachable!("invalid connection state: {}", n),
+        }
+    }
+}
+
+// ===== impl StateHandle =====
+
+impl StateHandle {
+    pub(crate) fn new() -> Self {
+        StateHandle {
+            state: Arc::new(AtomicUsize::new(ConnectionState::Idle as usize)),
+        }
+    }
+
+    /// Returns the state of the connection as of the last time the
+    /// `Reconnect` was polled.
+    pub fn get(&self) -> ConnectionState {
+        ConnectionState::from_usize(self.state.load(Ordering::SeqCst))
+    }
+
+    pub(crate) fn set(&self, state: ConnectionState) {
+        self.state.store(state as usize, Ordering::SeqCst);
+    }
+}
@@ -0,0 +1,158 @@
+//! Changing the target of a `Reconnect` at runtime.
+//!
+//! `Reconnect::target_handle` returns a `TargetHandle`, which replaces the
+//! target used for the following connections, e.g. when a backend's address
+//! changes on a configuration reload. The established connection is kept
+//! until it fails, unless `reconnect_now` is called.
+//!
+//! The services built by a `ReconnectLayer` share a target, so that
+//! `ReconnectLayer::target_handle` changes the target of all of them. A
+//! layer may also be given the handle of a target shared with other services
+//! with `ReconnectLayer::with_target_handle`.
+
+use futures::task::AtomicTask;
+use std::fmt;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::{Arc, Mutex, Weak};
+
+/// Changes the target of the `Reconnect` services sharing it.
+pub struct TargetHandle<Target> {
+    shared: Arc<Shared<Target>>,
+}
+
+/// The target of `Reconnect` services, shared with their `TargetHandle`s.
+pub(crate) struct Shared<Target> {
+    target: Mutex<Target>,
+    /// The number of calls to `reconnect_now`. Each `Reconnect` drops its
+    /// connection once it sees the number change.
+    reconnects: AtomicUsize,
+    /// The tasks polling the `Reconnect` services, notified by
+    /// `reconnect_now`. Services that were dropped are pruned as watches are
+    /// added.
+    tasks: Mutex<Vec<Weak<AtomicTask>>>,
+}
+
+/// Watches a `Shared` target for calls to `reconnect_now`, on behalf of a
+/// single `Reconnect`.
+#[derive(Debug)]
+pub(crate) struct Watch {
+    task: Arc<AtomicTask>,
+    /// The number of calls to `reconnect_now` seen so far.
+    seen: usize,
+}
+
+// ===== impl TargetHandle =====
+
+impl<Target> TargetHandle<Target> {
+    pub(crate) fn new(shared: Arc<Shared<Target>>) -> Self {
+        TargetHandle { shared }
+    }
+
+    pub(crate) fn into_shared(self) -> Arc<Shared<Target>> {
+        self.shared
+    }
+
+    /// Returns the current target.
+    pub fn get(&self) -> Target
+    where
+        Target: Clone,
+    {
+        self.shared.get()
+    }
+
+    /// Connect to `target` from the next attempt on.
+    pub fn set(&self, target: Target) {
+        *self.shared.target.lock().unwrap() = target;
+    }
+
+    /// Drop the current connections, and connect again right away, without
+    /// waiting for a backoff delay.
+    ///
+    /// This also makes services that gave up connecting try again.
+    pub fn reconnect_now(&self) {
+        self.shared.reconnects.fetch_add(1, Ordering::SeqCst);
+
+        let tasks = self.shared.tasks.lock().unwrap();
+        for task in tasks.iter().filter_map(Weak::upgrade) {
+            task.notify();
+        }
+    }
+}
+
+impl<Target> Clone for TargetHandle<Target> {
+    fn clone(&self) -> Self {
+        TargetHandle {
+            shared: self.shared.clone(),
+        }
+    }
+}
+
+impl<Target> fmt::Debug for TargetHandle<Target>
+where
+    Target: fmt::Debug,
+{
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_struct("TargetHandle")
+            .field("shared", &self.shared)
+            .finish()
+    }
+}
+
+// ===== impl Shared =====
+
+impl<Target> Shared<Target> {
+    pub(crate) fn new(target: Target) -> Self {
+        Shared {
+            target: Mutex::new(target),
+            reconnects: AtomicUsize::new(0),
+            tasks: Mutex::new(Vec::new()),
+        }
+    }
+
+    /// Start watching for calls to `reconnect_now` made from now on.
+    pub(crate) fn watch(&self) -> Watch {
+        let task = Arc::new(AtomicTask::new());
+
+        let mut tasks = self.tasks.lock().unwrap();
+        tasks.retain(|task| task.upgrade().is_some());
+        tasks.push(Arc::downgrade(&task));
+
+        Watch {
+            task,
+            seen: self.reconnects.load(Ordering::SeqCst),
+        }
+    }
+
+    pub(crate) fn get(&self) -> Target
+    where
+        Target: Clone,
+    {
+        self.target.lock().unwrap().clone()
+    }
+
+    /// Returns `true` if `reconnect_now` was called since the last call with
+    /// `watch`, registering the current task to be notified when it is.
+    pub(crate) fn poll_reconnect(&self, watch: &mut Watch) -> bool {
+        watch.task.register();
+
+        let reconnects = self.reconnects.load(Ordering::SeqCst);
+        if reconnects == watch.seen {
+            return false;
+        }
+
+        watch.seen = reconnects;
+        true
+    }
+}
+
+impl<Target> fmt::Debug for Shared<Target>
+where
+    Target: fmt::Debug,
+{
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_struct("Shared")
+            .field("target", &self.target)
+            .field("reconnects", &self.reconnects)
+            .finish()
+    }
+}
@@ -0,0 +1,431 @@
+extern crate futures;
+extern crate tokio_mock_task;
+extern crate tower_layer;
+extern crate tower_mock;
+extern crate tower_reconnect;
+extern crate tower_service;
+extern crate tower_util;
+
+use futures::{Async, Future};
+use std::error::Error;
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+use tokio_mock_task::MockTask;
+use tower_layer::Layer;
+use tower_mock::clock::MockClock;
+use tower_mock::{MakeMock, Mock};
+use tower_reconnect::error::{Disconnected, GaveUp};
+use tower_reconnect::event::Event;
+use tower_reconnect::policy::Action;
+use tower_reconnect::state::ConnectionState;
+use tower_reconnect::{Reconnect, ReconnectLayer, Replay};
+use tower_service::Service;
+use tower_util::backoff::Backoff;
+
+type Svc = Mock<&'static str, &'static str>;
+
+#[test]
+fn backs_off_between_failed_attempts() {
+    let mut task = MockTask::new();
+    let (make, mut handle) = MakeMock::<&'static str, Svc>::new();
+    let backoff = Backoff::new(Duration::from_secs(1), Duration::from_secs(10));
+    let mut service = Reconnect::new::<Svc, &'static str>(make, "a").with_backoff(backoff);
+
+    MockClock::new().enter(|clock| {
+        assert!(task.enter(|| service.poll_ready()).unwrap().is_not_ready());
+        handle.expect_target("a").fail("refused");
+
+        let err = task.enter(|| service.poll_ready()).unwrap_err();
+        assert_eq!(err.to_string(), "refused");
+
+        // No attempt is made until the delay has elapsed.
+        assert!(task.enter(|| service.poll_ready()).unwrap().is_not_ready());
+        handle.assert_no_construction();
+
+        clock.advance(Duration::from_secs(1));
+        assert!(task.is_notified());
+        assert!(task.enter(|| service.poll_ready()).unwrap().is_not_ready());
+
+        let (inner, _inner_handle) = Mock::new();
+        handle.expect_target("a").resolve(inner);
+        assert!(task.enter(|| service.poll_ready()).unwrap().is_ready());
+    });
+}
+
+#[test]
+fn gives_up_after_max_attempts() {
+    let mut task = MockTask::new();
+    let (make, mut handle) = MakeMock::<&'static str, Svc>::new();
+    let mut service = Reconnect::new::<Svc, &'static str>(make, "a").max_attempts(2);
+
+    for _ in 0..2 {
+        assert!(task.enter(|| service.poll_ready()).unwrap().is_not_ready());
+        handle.expect_target("a").fail("refused");
+
+        let err = task.enter(|| service.poll_ready()).unwrap_err();
+        assert_eq!(err.to_string(), "refused");
+    }
+
+    let err = task.enter(|| service.poll_ready()).unwrap_err();
+    assert_eq!(err.downcast_ref::<GaveUp>().unwrap().attempts(), 2);
+    handle.assert_no_construction();
+}
+
+#[test]
+fn gives_up_after_max_elapsed() {
+    let mut task = MockTask::new();
+    let (make, mut handle) = MakeMock::<&'static str, Svc>::new();
+    let mut service =
+        Reconnect::new::<Svc, &'static str>(make, "a").max_elapsed(Duration::from_secs(5));
+
+    MockClock::new().enter(|clock| {
+        for _ in 0..2 {
+            assert!(task.enter(|| service.poll_ready()).unwrap().is_not_ready());
+            handle.expect_target("a").fail("refused");
+
+            let err = task.enter(|| service.poll_ready()).unwrap_err();
+            assert_eq!(err.to_string(), "refused");
+
+            clock.advance(Duration::from_secs(5));
+        }
+
+        let err = task.enter(|| service.poll_ready()).unwrap_err();
+        assert_eq!(err.downcast_ref::<GaveUp>().unwrap().attempts(), 2);
+        handle.assert_no_construction();
+    });
+}
+
+#[test]
+fn connects_to_updated_target() {
+    let mut task = MockTask::new();
+    let (make, mut handle) = MakeMock::<&'static str, Svc>::new();
+    let mut service = Reconnect::new::<Svc, &'static str>(make, "a");
+    let target = service.target_handle();
+
+    assert!(task.enter(|| service.poll_ready()).unwrap().is_not_ready());
+    let (inner, mut inner_handle) = Mock::new();
+    handle.expect_target("a").resolve(inner);
+    assert!(task.enter(|| service.poll_ready()).unwrap().is_ready());
+
+    // The established connection is kept...
+    target.set("b");
+    assert_eq!(target.get(), "b");
+    assert!(task.enter(|| service.poll_ready()).unwrap().is_ready());
+    handle.assert_no_construction();
+
+    // ...until it fails.
+    inner_handle.error("broken");
+    assert!(task.enter(|| service.poll_ready()).unwrap().is_not_ready());
+    let (inner, _inner_handle) = Mock::new();
+    handle.expect_target("b").resolve(inner);
+    assert!(task.enter(|| service.poll_ready()).unwrap().is_ready());
+
+    // Or until reconnecting is requested.
+    target.set("c");
+    target.reconnect_now();
+    assert!(task.is_notified());
+    assert!(task.enter(|| service.poll_ready()).unwrap().is_not_ready());
+    handle.expect_target("c");
+}
+
+#[test]
+fn layer_target_handle_changes_target_of_all_services() {
+    let mut task_a = MockTask::new();
+    let mut task_b = MockTask::new();
+    let (make, mut handle) = MakeMock::<&'static str, Svc>::new();
+    let layer = ReconnectLayer::new("a");
+    let target = layer.target_handle();
+
+    let mut a = Layer::<_, &'static str>::layer(&layer, make.clone()).unwrap();
+    let mut b = Layer::<_, &'static str>::layer(&layer, make).unwrap();
+
+    assert!(task_a.enter(|| a.poll_ready()).unwrap().is_not_ready());
+    let (inner, _inner_a) = Mock::new();
+    handle.expect_target("a").resolve(inner);
+    assert!(task_a.enter(|| a.poll_ready()).unwrap().is_ready());
+
+    assert!(task_b.enter(|| b.poll_ready()).unwrap().is_not_ready());
+    let (inner, _inner_b) = Mock::new();
+    handle.expect_target("a").resolve(inner);
+    assert!(task_b.enter(|| b.poll_ready()).unwrap().is_ready());
+
+    // Both services reconnect to the new target.
+    target.set("b");
+    target.reconnect_now();
+    assert!(task_a.is_notified());
+    assert!(task_b.is_notified());
+
+    assert!(task_a.enter(|| a.poll_ready()).unwrap().is_not_ready());
+    handle.expect_target("b");
+    assert!(task_b.enter(|| b.poll_ready()).unwrap().is_not_ready());
+    handle.expect_target("b");
+}
+
+#[test]
+fn layer_shares_target_handle() {
+    let mut task = MockTask::new();
+    let (make, mut handle) = MakeMock::<&'static str, Svc>::new();
+    let service = Reconnect::new::<Svc, &'static str>(make.clone(), "a");
+    let layer = ReconnectLayer::with_target_handle(service.target_handle());
+    assert_eq!(layer.target_handle().get(), "a");
+
+    service.target_handle().set("b");
+    let mut built = Layer::<_, &'static str>::layer(&layer, make).unwrap();
+    assert!(task.enter(|| built.poll_ready()).unwrap().is_not_ready());
+    handle.expect_target("b");
+}
+
+#[test]
+fn reconnects_when_probe_fails() {
+    let mut task = MockTask::new();
+    let (make, mut handle) = MakeMock::<&'static str, Svc>::new();
+    let mut service = Reconnect::new::<Svc, &'static str>(make, "a")
+        .with_probe(Duration::from_secs(10), || "ping");
+
+    MockClock::new().enter(|clock| {
+        assert!(task.enter(|| service.poll_ready()).unwrap().is_not_ready());
+        let (inner, mut inner_handle) = Mock::new();
+        handle.expect_target("a").resolve(inner);
+        assert!(task.enter(|| service.poll_ready()).unwrap().is_ready());
+
+        clock.advance(Duration::from_secs(10));
+        assert!(task.is_notified());
+        assert!(task.enter(|| service.poll_ready()).unwrap().is_not_ready());
+
+        let probe = inner_handle.next_request().unwrap();
+        assert_eq!(*probe, "ping");
+        probe.error("dead");
+
+        assert!(task.is_notified());
+        assert!(task.enter(|| service.poll_ready()).unwrap().is_not_ready());
+        handle.expect_target("a");
+    });
+}
+
+#[test]
+fn waits_for_probe_of_idle_connection() {
+    let mut task = MockTask::new();
+    let (make, mut handle) = MakeMock::<&'static str, Svc>::new();
+    let mut service = Reconnect::new::<Svc, &'static str>(make, "a")
+        .with_probe(Duration::from_secs(10), || "ping");
+
+    MockClock::new().enter(|clock| {
+        assert!(task.enter(|| service.poll_ready()).unwrap().is_not_ready());
+        let (inner, mut inner_handle) = Mock::new();
+        handle.expect_target("a").resolve(inner);
+        assert!(task.enter(|| service.poll_ready()).unwrap().is_ready());
+
+        // The connection is left idle past the interval, so it is probed
+        // before the next caller may use it.
+        clock.advance(Duration::from_secs(60));
+        assert!(task.enter(|| service.poll_ready()).unwrap().is_not_ready());
+
+        let probe = inner_handle.next_request().unwrap();
+        assert_eq!(*probe, "ping");
+        assert!(task.enter(|| service.poll_ready()).unwrap().is_not_ready());
+
+        probe.respond("pong");
+        assert!(task.is_notified());
+        assert!(task.enter(|| service.poll_ready()).unwrap().is_ready());
+        handle.assert_no_construction();
+    });
+}
+
+#[test]
+fn policy_gives_up_on_fatal_errors() {
+    let mut task = MockTask::new();
+    let (make, mut handle) = MakeMock::<&'static str, Svc>::new();
+    let mut service = Reconnect::new::<Svc, &'static str>(make, "a").with_policy(
+        |_: usize, err: &(Error + Send + Sync + 'static)| {
+            if err.to_string() == "unauthorized" {
+                Action::GiveUp
+            } else {
+                Action::Retry
+            }
+        },
+    );
+
+    for &err in &["refused", "unauthorized"] {
+        assert!(task.enter(|| service.poll_ready()).unwrap().is_not_ready());
+        handle.expect_target("a").fail(err);
+        assert!(task.enter(|| service.poll_ready()).is_err());
+    }
+
+    let err = task.enter(|| service.poll_ready()).unwrap_err();
+    assert_eq!(err.downcast_ref::<GaveUp>().unwrap().attempts(), 2);
+    handle.assert_no_construction();
+}
+
+#[test]
+fn reports_connection_events() {
+    let mut task = MockTask::new();
+    let events = Arc::new(Mutex::new(Vec::new()));
+    let recorded = events.clone();
+    let (make, mut handle) = MakeMock::<&'static str, Svc>::new();
+    let mut service = Reconnect::new::<Svc, &'static str>(make, "a")
+        .on_event(move |event| recorded.lock().unwrap().push(event));
+
+    assert!(task.enter(|| service.poll_ready()).unwrap().is_not_ready());
+    handle.expect_target("a").fail("refused");
+    assert!(task.enter(|| service.poll_ready()).is_err());
+
+    assert!(task.enter(|| service.poll_ready()).unwrap().is_not_ready());
+    let (inner, mut inner_handle) = Mock::new();
+    handle.expect_target("a").resolve(inner);
+    assert!(task.enter(|| service.poll_ready()).unwrap().is_ready());
+
+    inner_handle.error("broken");
+    assert!(task.enter(|| service.poll_ready()).unwrap().is_not_ready());
+
+    assert_eq!(
+        *events.lock().unwrap(),
+        vec![
+            Event::Connecting { attempt: 1 },
+            Event::ConnectFailed { attempt: 1 },
+            Event::Connecting { attempt: 2 },
+            Event::Connected { attempt: 2 },
+            Event::Disconnected,
+            Event::Connecting { attempt: 1 },
+        ]
+    );
+}
+
+#[test]
+fn reports_connection_state() {
+    let mut task = MockTask::new();
+    let (make, mut handle) = MakeMock::<&'static str, Svc>::new();
+    let mut service = Reconnect::new::<Svc, &'static str>(make, "a").max_attempts(1);
+    let state = service.state_handle();
+    assert_eq!(state.get(), ConnectionState::Idle);
+
+    assert!(task.enter(|| service.poll_ready()).unwrap().is_not_ready());
+    assert_eq!(state.get(), ConnectionState::Connecting);
+
+    let (inner, mut inner_handle) = Mock::new();
+    handle.expect_target("a").resolve(inner);
+    assert!(task.enter(|| service.poll_ready()).unwrap().is_ready());
+    assert_eq!(state.get(), ConnectionState::Connected);
+    assert_eq!(service.state(), ConnectionState::Connected);
+
+    inner_handle.error("broken");
+    assert!(task.enter(|| service.poll_ready()).unwrap().is_not_ready());
+    handle.expect_target("a").fail("refused");
+    assert!(task.enter(|| service.poll_ready()).is_err());
+    assert_eq!(state.get(), ConnectionState::Failed);
+}
+
+#[test]
+fn fails_on_disconnect() {
+    let mut task = MockTask::new();
+    let (make, mut handle) = MakeMock::<&'static str, Svc>::new();
+    let mut service = Reconnect::new::<Svc, &'static str>(make, "a").fail_on_disconnect();
+
+    assert!(task.enter(|| service.poll_ready()).unwrap().is_not_ready());
+    let (inner, mut inner_handle) = Mock::new();
+    handle.expect_target("a").resolve(inner);
+    assert!(task.enter(|| service.poll_ready()).unwrap().is_ready());
+
+    inner_handle.error("broken");
+    let err = task.enter(|| service.poll_ready()).unwrap_err();
+    assert!(err.is::<Disconnected>(), "unexpected error: {:?}", err);
+    assert_eq!(err.to_string(), "connection lost: broken");
+
+    // Reconnecting starts once the service is polled again.
+    assert!(task.enter(|| service.poll_ready()).unwrap().is_not_ready());
+    handle.expect_target("a");
+}
+
+#[test]
+fn replays_requests_lost_with_connection() {
+    let mut task = MockTask::new();
+    let mut caller = MockTask::new();
+    let (make, mut handle) = MakeMock::<&'static str, Svc>::new();
+    let mut service = Replay::new(Reconnect::new::<Svc, &'static str>(make, "a"), 1);
+
+    assert!(task.enter(|| service.poll_ready()).unwrap().is_not_ready());
+    let (inner, mut inner_handle) = Mock::new();
+    handle.expect_target("a").resolve(inner);
+    assert!(task.enter(|| service.poll_ready()).unwrap().is_ready());
+
+    let mut first = service.call("hello");
+    assert!(task.enter(|| service.poll_ready()).unwrap().is_ready());
+    let mut second = service.call("world");
+
+    inner_handle.next_request().unwrap().error("broken");
+    inner_handle.next_request().unwrap().error("broken");
+    inner_handle.error("broken");
+
+    // The connection is found lost...
+    assert!(task.enter(|| service.poll_ready()).unwrap().is_not_ready());
+
+    // ...so the failed requests are parked, but only one may be.
+    assert!(caller.enter(|| first.poll()).unwrap().is_not_ready());
+    assert!(task.is_notified());
+    let err = caller.enter(|| second.poll()).unwrap_err();
+    assert_eq!(err.to_string(), "broken");
+
+    let (inner, mut inner_handle) = Mock::new();
+    handle.expect_target("a").resolve(inner);
+    assert!(task.enter(|| service.poll_ready()).unwrap().is_ready());
+
+    let replayed = inner_handle.next_request().unwrap();
+    assert_eq!(*replayed, "hello");
+    replayed.respond("hi");
+
+    assert!(caller.is_notified());
+    assert_eq!(caller.enter(|| first.poll()).unwrap(), Async::Ready("hi"));
+}
+
+#[test]
+fn does_not_replay_requests_failing_on_live_connection() {
+    let mut task = MockTask::new();
+    let mut caller = MockTask::new();
+    let (make, mut handle) = MakeMock::<&'static str, Svc>::new();
+    let mut service = Replay::new(Reconnect::new::<Svc, &'static str>(make, "a"), 1);
+
+    assert!(task.enter(|| service.poll_ready()).unwrap().is_not_ready());
+    let (inner, mut inner_handle) = Mock::new();
+    handle.expect_target("a").resolve(inner);
+    assert!(task.enter(|| service.poll_ready()).unwrap().is_ready());
+
+    let mut response = service.call("hello");
+    inner_handle.next_request().unwrap().error("bad request");
+
+    // The connection is still up, so the request fails with its error right
+    // away, without the `Replay` being polled again.
+    let err = caller.enter(|| response.poll()).unwrap_err();
+    assert_eq!(err.to_string(), "bad request");
+    handle.assert_no_construction();
+}
+
+#[test]
+fn does_not_park_requests_failing_on_live_connection() {
+    let mut task = MockTask::new();
+    let mut caller = MockTask::new();
+    let (make, mut handle) = MakeMock::<&'static str, Svc>::new();
+    let mut service = Replay::new(Reconnect::new::<Svc, &'static str>(make, "a"), 1);
+
+    assert!(task.enter(|| service.poll_ready()).unwrap().is_not_ready());
+    let (inner, mut inner_handle) = Mock::new();
+    handle.expect_target("a").resolve(inner);
+    assert!(task.enter(|| service.poll_ready()).unwrap().is_ready());
+
+    let mut rejected = service.call("bad");
+    assert!(task.enter(|| service.poll_ready()).unwrap().is_ready());
+    let mut lost = service.call("hello");
+
+    inner_handle.next_request().unwrap().error("bad request");
+    let err = caller.enter(|| rejected.poll()).unwrap_err();
+    assert_eq!(err.to_string(), "bad request");
+
+    // The rejected request did not take the only park slot.
+    inner_handle.next_request().unwrap().error("broken");
+    inner_handle.error("broken");
+    assert!(task.enter(|| service.poll_ready()).unwrap().is_not_ready());
+    assert!(caller.enter(|| lost.poll()).unwrap().is_not_ready());
+
+    let (inner, mut inner_handle) = Mock::new();
+    handle.expect_target("a").resolve(inner);
+    assert!(task.enter(|| service.poll_ready()).unwrap().is_ready());
+    assert_eq!(*inner_handle.next_request().unwrap(), "hello");
+}
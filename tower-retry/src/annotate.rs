@@ -0,0 +1,148 @@
+//! Annotating responses with how they were retried.
+//!
+//! A `Retry` hides the attempts it makes from its callers. When a request is
+//! slow, it is often useful to know whether it was retried. `Retry::annotated`
+//! and `RetryLayer::annotated` wrap each response in an `Annotated`, carrying
+//! the number of attempts made and the total time taken, so that callers and
+//! logging layers can explain slow requests.
+
+use futures::{Async, Future, Poll};
+use std::time::{Duration, Instant};
+use tokio_timer::clock;
+use tower_layer::Layer;
+use tower_service::Service;
+
+use never::Never;
+use {Policy, ResponseFuture, Retry, RetryLayer};
+
+/// A response along with how it was obtained.
+#[derive(Clone, Debug)]
+pub struct Annotated<T> {
+    response: T,
+    attempts: usize,
+    elapsed: Duration,
+}
+
+/// A `Retry` that annotates its responses.
+#[derive(Clone, Debug)]
+pub struct AnnotatedRetry<P, S> {
+    retry: Retry<P, S>,
+}
+
+/// Retry requests based on a policy, annotating their responses.
+#[derive(Debug)]
+pub struct AnnotatedRetryLayer<P> {
+    layer: RetryLayer<P>,
+}
+
+/// The `Future` returned by an `AnnotatedRetry` service.
+#[derive(Debug)]
+pub struct AnnotatedFuture<P, S, Request>
+where
+    P: Policy<Request, S::Response, S::Error>,
+    S: Service<Request>,
+{
+    inner: ResponseFuture<P, S, Request>,
+    started: Instant,
+}
+
+// ===== impl Annotated =====
+
+impl<T> Annotated<T> {
+    /// Returns a reference to the response.
+    pub fn response(&self) -> &T {
+        &self.response
+    }
+
+    /// Consumes `self`, returning the response.
+    pub fn into_response(self) -> T {
+        self.response
+    }
+
+    /// The number of times the request was sent to the inner service.
+    ///
+    /// This is `1` if the request was not retried.
+    pub fn attempts(&self) -> usize {
+        self.attempts
+    }
+
+    /// The time from the request being issued to the final response,
+    /// including all attempts and the time spent deciding to retry.
+    pub fn elapsed(&self) -> Duration {
+        self.elapsed
+    }
+}
+
+// ===== impl AnnotatedRetry =====
+
+impl<P, S> AnnotatedRetry<P, S> {
+    pub(crate) fn new(retry: Retry<P, S>) -> Self {
+        AnnotatedRetry { retry }
+    }
+}
+
+impl<P, S, Request> Service<Request> for AnnotatedRetry<P, S>
+where
+    P: Policy<Request, S::Response, S::Error> + Clone,
+    S: Service<Request> + Clone,
+{
+    type Response = Annotated<S::Response>;
+    type Error = S::Error;
+    type Future = AnnotatedFuture<P, S, Request>;
+
+    fn poll_ready(&mut self) -> Poll<(), Self::Error> {
+        self.retry.poll_ready()
+    }
+
+    fn call(&mut self, request: Request) -> Self::Future {
+        AnnotatedFuture {
+            started: clock::now(),
+            inner: self.retry.call(request),
+        }
+    }
+}
+
+// ===== impl AnnotatedRetryLayer =====
+
+impl<P> AnnotatedRetryLayer<P> {
+    pub(crate) fn new(layer: RetryLayer<P>) -> Self {
+        AnnotatedRetryLayer { layer }
+    }
+}
+
+impl<P, S, Request> Layer<S, Request> for AnnotatedRetryLayer<P>
+where
+    S: Service<Request> + Clone,
+    P: Policy<Request, S::Response, S::Error> + Clone,
+{
+    type Response = Annotated<S::Response>;
+    type Error = S::Error;
+    type LayerError = Never;
+    type Service = AnnotatedRetry<P, S>;
+
+    fn layer(&self, service: S) -> Result<Self::Service, Self::LayerError> {
+        let retry = self.layer.layer(service)?;
+        Ok(AnnotatedRetry::new(retry))
+    }
+}
+
+// ===== impl AnnotatedFuture =====
+
+impl<P, S, Request> Future for AnnotatedFuture<P, S, Request>
+where
+    P: Policy<Request, S::Response, S::Error> + Clone,
+    S: Service<Request> + Clone,
+{
+    type Item = Annotated<S::Response>;
+    type Error = S::Error;
+
+    fn poll(&mut self) -> Poll<Self::Item, Self::Error> {
+        let response = try_ready!(self.inner.poll());
+
+        Ok(Async::Ready(Annotated {
+            response,
+            attempts: self.inner.attempts(),
+            elapsed: clock::now() - self.started,
+        }))
+    }
+}
@@ -0,0 +1,163 @@
+//! Retrying with exponential backoff.
+
+use rand;
+use std::fmt;
+use std::time::Duration;
+use tower_util::backoff::{Backoff, Delayed, Jitter};
+
+use clone::{CloneRequest, Cloned, NotCloned};
+use Policy;
+
+/// A `Policy` retrying errors with exponentially increasing delays.
+///
+/// Errors for which `retryable` returns `true` are retried, at most
+/// `max_retries` times (3 by default). The first retry waits for `base`, and
+/// every further retry waits `multiplier` times longer than the previous one
+/// (2 by default), but never longer than `max`. Responses are never retried.
+///
+/// The delays are computed by a [`Backoff`], and may be randomized by setting
+/// its [`Jitter`] with `jitter`.
+///
+/// Requests are copied as set by `C`, with `Clone` by default. See
+/// [`clone`](../clone/index.html) for requests that cannot be cloned.
+///
+/// [`Backoff`]: ../../tower_util/backoff/struct.Backoff.html
+/// [`Jitter`]: ../../tower_util/backoff/enum.Jitter.html
+///
+/// # Example
+///
+/// ```
+/// # extern crate tower_retry;
+/// use std::time::Duration;
+/// use tower_retry::{ExponentialBackoff, RetryLayer};
+///
+/// # fn main() {
+/// let policy = ExponentialBackoff::new(
+///     Duration::from_millis(50),
+///     Duration::from_secs(2),
+///     |err: &std::io::Error| err.kind() == std::io::ErrorKind::ConnectionReset,
+/// )
+/// .max_retries(5);
+///
+/// let layer = RetryLayer::new(policy);
+/// # drop(layer);
+/// # }
+/// ```
+#[derive(Clone)]
+pub struct ExponentialBackoff<F, C = Cloned> {
+    retryable: F,
+    backoff: Backoff,
+    retries_left: usize,
+    clone: C,
+}
+
+/// The `Future` returned by `ExponentialBackoff::retry`.
+pub type BackoffFuture<F, C> = Delayed<ExponentialBackoff<F, C>>;
+
+// ===== impl ExponentialBackoff =====
+
+impl<F> ExponentialBackoff<F> {
+    /// Create a new policy retrying errors for which `retryable` returns
+    /// `true`, waiting from `base` up to `max` between attempts.
+    ///
+    /// # Panics
+    ///
+    /// This function panics if `base` is greater than `max`.
+    pub fn new(base: Duration, max: Duration, retryable: F) -> Self {
+        ExponentialBackoff {
+            retryable,
+            backoff: Backoff::new(base, max),
+            retries_left: 3,
+            clone: Cloned,
+        }
+    }
+}
+
+impl<F, C> ExponentialBackoff<F, C> {
+    /// Set the factor each delay is multiplied by for the next retry.
+    ///
+    /// # Panics
+    ///
+    /// This function panics if `multiplier` is less than 1.
+    pub fn multiplier(mut self, multiplier: f64) -> Self {
+        self.backoff = self.backoff.multiplier(multiplier);
+        self
+    }
+
+    /// Set the jitter applied to the delays (none by default).
+    pub fn jitter(mut self, jitter: Jitter) -> Self {
+        self.backoff = self.backoff.jitter(jitter);
+        self
+    }
+
+    /// Set the maximum number of times a request is retried.
+    pub fn max_retries(mut self, max_retries: usize) -> Self {
+        self.retries_left = max_retries;
+        self
+    }
+
+    /// Never copy requests, e.g. to wrap the policy in a `Rebuild`.
+    pub fn not_cloned(self) -> ExponentialBackoff<F, NotCloned> {
+        ExponentialBackoff {
+            retryable: self.retryable,
+            backoff: self.backoff,
+            retries_left: self.retries_left,
+            clone: NotCloned,
+        }
+    }
+
+    /// Returns the delay before the next retry, and the policy to use after
+    /// it.
+    fn backoff(&self) -> (Duration, Self)
+    where
+        F: Clone,
+        C: Clone,
+    {
+        // Policies are cloned for every request, so each retry draws from the
+        // thread's generator rather than one stored in the policy, which would
+        // give every request the same sequence of delays.
+        let mut backoff = self.backoff.clone();
+        let delay = backoff.next_delay(&mut rand::thread_rng());
+
+        let policy = ExponentialBackoff {
+            retryable: self.retryable.clone(),
+            backoff,
+            retries_left: self.retries_left - 1,
+            clone: self.clone.clone(),
+        };
+
+        (delay, policy)
+    }
+}
+
+impl<F, C, Req, Res, E> Policy<Req, Res, E> for ExponentialBackoff<F, C>
+where
+    F: Fn(&E) -> bool + Clone,
+    C: CloneRequest<Req> + Clone,
+{
+    type Future = BackoffFuture<F, C>;
+
+    fn retry(&self, _: &Req, result: Result<&Res, &E>) -> Option<Self::Future> {
+        match result {
+            Err(err) if self.retries_left > 0 && (self.retryable)(err) => {
+                let (delay, policy) = self.backoff();
+
+                Some(Delayed::new(policy, delay))
+            }
+            _ => None,
+        }
+    }
+
+    fn clone_request(&self, req: &Req) -> Option<Req> {
+        self.clone.clone_request(req)
+    }
+}
+
+impl<F, C> fmt::Debug for ExponentialBackoff<F, C> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_struct("ExponentialBackoff")
+            .field("backoff", &self.backoff)
+            .field("retries_left", &self.retries_left)
+            .finish()
+    }
+}
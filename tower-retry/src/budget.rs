@@ -1,14 +1,22 @@
 //! A retry "budget" for allowing only a certain amount of retries over time.
+//!
+//! Unbounded retries amplify outages: when a backend starts failing, every
+//! client multiplies its load. A `Budget` shared by all requests of a client
+//! limits retries to a fraction of the requests made, and `Budgeted` applies
+//! it to any retry `Policy`.
 
 use std::fmt;
 use std::sync::{
     atomic::{AtomicIsize, Ordering},
-    Mutex,
+    Arc, Mutex,
 };
 use std::time::{Duration, Instant};
 
+use futures::{Async, Future, Poll};
 use tokio_timer::clock;
 
+use Policy;
+
 /// Represents a "budget" for retrying requests.
 ///
 /// This is useful for limiting the amount of retries a service can perform
@@ -26,6 +34,51 @@ pub struct Overdrawn {
     _inner: (),
 }
 
+/// A `Policy` that only retries requests while the `Budget` allows it.
+///
+/// Every request that succeeds deposits into the budget, and every retry the
+/// wrapped policy asks for must first be withdrawn from it. Clones share
+/// the same budget, so it applies across all requests of a client.
+///
+/// # Example
+///
+/// ```
+/// # extern crate futures;
+/// # extern crate tower_retry;
+/// # use std::sync::Arc;
+/// # use tower_retry::Policy;
+/// use tower_retry::budget::{Budget, Budgeted};
+///
+/// # #[derive(Clone)]
+/// # struct Always;
+/// # impl<E> Policy<String, String, E> for Always {
+/// #     type Future = futures::future::FutureResult<Self, ()>;
+/// #     fn retry(&self, _: &String, _: Result<&String, &E>) -> Option<Self::Future> {
+/// #         Some(futures::future::ok(Always))
+/// #     }
+/// #     fn clone_request(&self, req: &String) -> Option<String> {
+/// #         Some(req.clone())
+/// #     }
+/// # }
+/// # fn main() {
+/// let budget = Arc::new(Budget::default());
+/// let policy = Budgeted::new(Always, budget);
+/// # drop(policy);
+/// # }
+/// ```
+#[derive(Clone, Debug)]
+pub struct Budgeted<P> {
+    policy: P,
+    budget: Arc<Budget>,
+}
+
+/// The `Future` returned by `Budgeted::retry`.
+#[derive(Debug)]
+pub struct BudgetedFuture<F> {
+    inner: F,
+    budget: Option<Arc<Budget>>,
+}
+
 #[derive(Debug)]
 struct Bucket {
     generation: Mutex<Generation>,
@@ -148,6 +201,92 @@ impl fmt::Debug for Budget {
     }
 }
 
+// ===== impl Budgeted =====
+
+impl<P> Budgeted<P> {
+    /// Limit the retries of `policy` to what `budget` allows.
+    pub fn new(policy: P, budget: Arc<Budget>) -> Self {
+        Budgeted { policy, budget }
+    }
+
+    /// Returns the budget retries are withdrawn from.
+    pub fn budget(&self) -> &Arc<Budget> {
+        &self.budget
+    }
+
+    /// Get a reference to the inner policy
+    pub fn get_ref(&self) -> &P {
+        &self.policy
+    }
+
+    /// Withdraws the retry decided by the inner policy from the budget, or
+    /// deposits into it if the request is complete and `succeeded`.
+    fn withdraw<F>(&self, succeeded: bool, retry: Option<F>) -> Option<BudgetedFuture<F>> {
+        let retry = match retry {
+            Some(retry) => retry,
+            None => {
+                // Failed requests earn no retries, lest a failing backend
+                // keep the budget full.
+                if succeeded {
+                    self.budget.deposit();
+                }
+                return None;
+            }
+        };
+
+        if self.budget.withdraw().is_err() {
+            return None;
+        }
+
+        Some(BudgetedFuture {
+            inner: retry,
+            budget: Some(self.budget.clone()),
+        })
+    }
+}
+
+impl<P, Req, Res, E> Policy<Req, Res, E> for Budgeted<P>
+where
+    P: Policy<Req, Res, E>,
+{
+    type Future = BudgetedFuture<P::Future>;
+
+    fn retry(&self, req: &Req, result: Result<&Res, &E>) -> Option<Self::Future> {
+        self.withdraw(result.is_ok(), self.policy.retry(req, result))
+    }
+
+    fn retry_since(
+        &self,
+        req: &Req,
+        result: Result<&Res, &E>,
+        started: Instant,
+    ) -> Option<Self::Future> {
+        let retry = self.policy.retry_since(req, result, started);
+        self.withdraw(result.is_ok(), retry)
+    }
+
+    fn clone_request(&self, req: &Req) -> Option<Req> {
+        self.policy.clone_request(req)
+    }
+}
+
+// ===== impl BudgetedFuture =====
+
+impl<F> Future for BudgetedFuture<F>
+where
+    F: Future<Error = ()>,
+{
+    type Item = Budgeted<F::Item>;
+    type Error = ();
+
+    fn poll(&mut self) -> Poll<Self::Item, ()> {
+        let policy = try_ready!(self.inner.poll());
+        let budget = self.budget.take().expect("polled after complete");
+
+        Ok(Async::Ready(Budgeted::new(policy, budget)))
+    }
+}
+
 // ===== impl Bucket =====
 
 impl Bucket {
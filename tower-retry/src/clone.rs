@@ -0,0 +1,33 @@
+//! Copying requests for the policies of this crate.
+//!
+//! `ExponentialBackoff` and `RetryAfter` decide *whether* to retry, and copy
+//! requests with `Clone` by default. Requests that are not `Clone` are
+//! retried by wrapping the policy in a [`Rebuild`](../rebuild/index.html),
+//! which builds them instead, after opting out of cloning with `not_cloned`.
+
+/// How a policy copies a request before it is sent, so that it can be
+/// retried.
+pub trait CloneRequest<Req> {
+    /// Returns a copy of `req`, or `None` if it cannot be copied.
+    fn clone_request(&self, req: &Req) -> Option<Req>;
+}
+
+/// Copies requests with `Clone`.
+#[derive(Clone, Copy, Debug, Default)]
+pub struct Cloned;
+
+/// Never copies requests, leaving it to a wrapping policy.
+#[derive(Clone, Copy, Debug, Default)]
+pub struct NotCloned;
+
+impl<Req: Clone> CloneRequest<Req> for Cloned {
+    fn clone_request(&self, req: &Req) -> Option<Req> {
+        Some(req.clone())
+    }
+}
+
+impl<Req> CloneRequest<Req> for NotCloned {
+    fn clone_request(&self, _: &Req) -> Option<Req> {
+        None
+    }
+}
@@ -0,0 +1,104 @@
+//! Bounding the total time spent retrying a request.
+//!
+//! Limiting the number of attempts does not bound how long a request takes,
+//! since each attempt, and each delay between attempts, may take a while.
+//! `Deadline` wraps a `Policy` and stops retrying once a total time has
+//! elapsed since the first attempt was made, however many attempts that
+//! allows, so that retries respect the end-to-end deadline of the request.
+//! The time of the first attempt is recorded by `Retry`, and passed on
+//! through `Policy::retry_since`.
+//!
+//! An attempt that is already in flight when the time is up is not
+//! cancelled; wrap the `Retry` in a `Timeout` for that.
+
+use futures::{Async, Future, Poll};
+use std::time::{Duration, Instant};
+use tokio_timer::clock;
+
+use Policy;
+
+/// A `Policy` that stops retrying once `budget` has elapsed since the first
+/// attempt.
+#[derive(Clone, Debug)]
+pub struct Deadline<P> {
+    policy: P,
+    budget: Duration,
+}
+
+/// The `Future` returned by `Deadline::retry`.
+#[derive(Debug)]
+pub struct DeadlineFuture<F> {
+    inner: F,
+    budget: Duration,
+}
+
+// ===== impl Deadline =====
+
+impl<P> Deadline<P> {
+    /// Retry as decided by `policy`, until `budget` has elapsed since the
+    /// first attempt.
+    pub fn new(policy: P, budget: Duration) -> Self {
+        Deadline { policy, budget }
+    }
+
+    /// Get a reference to the inner policy
+    pub fn get_ref(&self) -> &P {
+        &self.policy
+    }
+
+    /// Consume `self`, returning the inner policy
+    pub fn into_inner(self) -> P {
+        self.policy
+    }
+}
+
+impl<P, Req, Res, E> Policy<Req, Res, E> for Deadline<P>
+where
+    P: Policy<Req, Res, E>,
+{
+    type Future = DeadlineFuture<P::Future>;
+
+    /// Without the time of the first attempt, the time spent on the request
+    /// is not known, and the inner policy alone decides.
+    fn retry(&self, req: &Req, result: Result<&Res, &E>) -> Option<Self::Future> {
+        self.retry_since(req, result, clock::now())
+    }
+
+    fn retry_since(
+        &self,
+        req: &Req,
+        result: Result<&Res, &E>,
+        started: Instant,
+    ) -> Option<Self::Future> {
+        if clock::now() - started >= self.budget {
+            return None;
+        }
+
+        self.policy
+            .retry_since(req, result, started)
+            .map(|inner| DeadlineFuture {
+                inner,
+                budget: self.budget,
+            })
+    }
+
+    fn clone_request(&self, req: &Req) -> Option<Req> {
+        self.policy.clone_request(req)
+    }
+}
+
+// ===== impl DeadlineFuture =====
+
+impl<F> Future for DeadlineFuture<F>
+where
+    F: Future<Error = ()>,
+{
+    type Item = Deadline<F::Item>;
+    type Error = ();
+
+    fn poll(&mut self) -> Poll<Self::Item, ()> {
+        let policy = try_ready!(self.inner.poll());
+
+        Ok(Async::Ready(Deadline::new(policy, self.budget)))
+    }
+}
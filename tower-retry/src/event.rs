@@ -0,0 +1,67 @@
+//! Observing how requests are retried.
+//!
+//! `Retry::on_event` and `RetryLayer::on_event` register a callback that is
+//! passed an `Event` at each step of a request's lifecycle, e.g. to count
+//! attempts and retries in metrics, without wrapping the `Policy`.
+
+use std::fmt;
+use std::sync::Arc;
+
+/// A step in the lifecycle of a request sent through a `Retry`.
+///
+/// Attempts are numbered from 1, so the first retry is attempt 2.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Event {
+    /// An attempt was sent to the inner service.
+    AttemptStarted {
+        /// The number of the attempt.
+        attempt: usize,
+    },
+    /// An attempt failed with an error from the inner service.
+    ///
+    /// Responses that the policy classifies as failures are not reported
+    /// here, but are followed by `RetryScheduled` if they are retried.
+    AttemptFailed {
+        /// The number of the attempt.
+        attempt: usize,
+    },
+    /// The policy decided to retry the request after an attempt.
+    ///
+    /// The next attempt is started once the policy's future completes and
+    /// the inner service is ready.
+    RetryScheduled {
+        /// The number of the attempt being retried.
+        attempt: usize,
+    },
+    /// An attempt failed, and the request is not retried again, either
+    /// because the policy declined or because the request cannot be cloned.
+    RetriesExhausted {
+        /// The number of attempts made.
+        attempts: usize,
+    },
+}
+
+/// A callback registered with `on_event`, shared by clones of a `Retry`.
+#[derive(Clone)]
+pub(crate) struct OnEvent(Arc<Fn(Event) + Send + Sync>);
+
+// ===== impl OnEvent =====
+
+impl OnEvent {
+    pub(crate) fn new<F>(f: F) -> Self
+    where
+        F: Fn(Event) + Send + Sync + 'static,
+    {
+        OnEvent(Arc::new(f))
+    }
+
+    pub(crate) fn emit(&self, event: Event) {
+        (self.0)(event)
+    }
+}
+
+impl fmt::Debug for OnEvent {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_tuple("OnEvent").finish()
+    }
+}
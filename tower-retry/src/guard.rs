@@ -0,0 +1,127 @@
+//! Guarding against requests changing between retries.
+//!
+//! A retried request is expected to mean the same as the original. A
+//! `clone_request` implementation that mutates the request (or drops part of
+//! it, such as a body that was already consumed) silently re-sends something
+//! else. `Guarded` wraps a `Policy` and, in builds with debug assertions
+//! enabled, hashes each request with a user-provided hook and asserts that
+//! every clone hashes the same as the request it was cloned from. Since each
+//! retry is cloned from the previous one, every attempt is checked against
+//! the original request.
+//!
+//! In release builds, `Guarded` only forwards to the wrapped policy.
+
+use futures::{Async, Future, Poll};
+use std::fmt;
+use std::time::Instant;
+
+use Policy;
+
+/// A `Policy` asserting that cloned requests are identical to the original.
+#[derive(Clone)]
+pub struct Guarded<P, H> {
+    policy: P,
+    hash: H,
+}
+
+/// The `Future` returned by `Guarded::retry`.
+#[derive(Debug)]
+pub struct GuardedFuture<F, H> {
+    inner: F,
+    hash: Option<H>,
+}
+
+// ===== impl Guarded =====
+
+impl<P, H> Guarded<P, H> {
+    /// Guard the requests cloned by `policy`, comparing them by `hash`.
+    ///
+    /// `hash` should cover everything that affects the meaning of a request,
+    /// e.g. its method, URI, headers and body.
+    pub fn new(policy: P, hash: H) -> Self {
+        Guarded { policy, hash }
+    }
+
+    /// Get a reference to the inner policy
+    pub fn get_ref(&self) -> &P {
+        &self.policy
+    }
+
+    /// Consume `self`, returning the inner policy
+    pub fn into_inner(self) -> P {
+        self.policy
+    }
+
+    fn wrap<F>(&self, inner: F) -> GuardedFuture<F, H>
+    where
+        H: Clone,
+    {
+        GuardedFuture {
+            inner,
+            hash: Some(self.hash.clone()),
+        }
+    }
+}
+
+impl<P, H, Req, Res, E> Policy<Req, Res, E> for Guarded<P, H>
+where
+    P: Policy<Req, Res, E>,
+    H: Fn(&Req) -> u64 + Clone,
+{
+    type Future = GuardedFuture<P::Future, H>;
+
+    fn retry(&self, req: &Req, result: Result<&Res, &E>) -> Option<Self::Future> {
+        self.policy.retry(req, result).map(|inner| self.wrap(inner))
+    }
+
+    fn retry_since(
+        &self,
+        req: &Req,
+        result: Result<&Res, &E>,
+        started: Instant,
+    ) -> Option<Self::Future> {
+        self.policy
+            .retry_since(req, result, started)
+            .map(|inner| self.wrap(inner))
+    }
+
+    fn clone_request(&self, req: &Req) -> Option<Req> {
+        let clone = self.policy.clone_request(req)?;
+
+        debug_assert_eq!(
+            (self.hash)(req),
+            (self.hash)(&clone),
+            "clone_request returned a request different from the original"
+        );
+
+        Some(clone)
+    }
+}
+
+impl<P, H> fmt::Debug for Guarded<P, H>
+where
+    P: fmt::Debug,
+{
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_struct("Guarded")
+            .field("policy", &self.policy)
+            .finish()
+    }
+}
+
+// ===== impl GuardedFuture =====
+
+impl<F, H> Future for GuardedFuture<F, H>
+where
+    F: Future<Error = ()>,
+{
+    type Item = Guarded<F::Item, H>;
+    type Error = ();
+
+    fn poll(&mut self) -> Poll<Self::Item, ()> {
+        let policy = try_ready!(self.inner.poll());
+        let hash = self.hash.take().expect("polled after complete");
+
+        Ok(Async::Ready(Guarded::new(policy, hash)))
+    }
+}
@@ -0,0 +1,111 @@
+//! Only retrying requests that are safe to send more than once.
+//!
+//! A request that failed may still have been processed, e.g. if the
+//! connection was lost before the response was received, so retrying it can
+//! apply it twice. Request types implement `Idempotent` to tell whether this
+//! is safe, e.g. from an HTTP request's method, and `OnlyIdempotent` wraps a
+//! `Policy` so that it never retries requests that are not idempotent. Such
+//! requests are still sent through the same `Retry`, but only once.
+//!
+//! `RetryLayer::only_idempotent` applies `OnlyIdempotent` to the layer's
+//! policy.
+
+use futures::{Async, Future, Poll};
+use std::time::Instant;
+
+use Policy;
+
+/// Tells whether a request may safely be sent more than once.
+pub trait Idempotent {
+    /// Returns `true` if processing this request more than once has the same
+    /// effect as processing it once.
+    fn is_idempotent(&self) -> bool;
+}
+
+/// A `Policy` that never retries requests that are not `Idempotent`.
+#[derive(Clone, Debug)]
+pub struct OnlyIdempotent<P> {
+    policy: P,
+}
+
+/// The `Future` returned by `OnlyIdempotent::retry`.
+#[derive(Debug)]
+pub struct OnlyIdempotentFuture<F> {
+    inner: F,
+}
+
+// ===== impl OnlyIdempotent =====
+
+impl<P> OnlyIdempotent<P> {
+    /// Retry idempotent requests as decided by `policy`.
+    pub fn new(policy: P) -> Self {
+        OnlyIdempotent { policy }
+    }
+
+    /// Get a reference to the inner policy
+    pub fn get_ref(&self) -> &P {
+        &self.policy
+    }
+
+    /// Consume `self`, returning the inner policy
+    pub fn into_inner(self) -> P {
+        self.policy
+    }
+}
+
+impl<P, Req, Res, E> Policy<Req, Res, E> for OnlyIdempotent<P>
+where
+    P: Policy<Req, Res, E>,
+    Req: Idempotent,
+{
+    type Future = OnlyIdempotentFuture<P::Future>;
+
+    fn retry(&self, req: &Req, result: Result<&Res, &E>) -> Option<Self::Future> {
+        if !req.is_idempotent() {
+            return None;
+        }
+
+        self.policy
+            .retry(req, result)
+            .map(|inner| OnlyIdempotentFuture { inner })
+    }
+
+    fn retry_since(
+        &self,
+        req: &Req,
+        result: Result<&Res, &E>,
+        started: Instant,
+    ) -> Option<Self::Future> {
+        if !req.is_idempotent() {
+            return None;
+        }
+
+        self.policy
+            .retry_since(req, result, started)
+            .map(|inner| OnlyIdempotentFuture { inner })
+    }
+
+    fn clone_request(&self, req: &Req) -> Option<Req> {
+        // Without a clone, `Retry` does not retry the request at all.
+        if !req.is_idempotent() {
+            return None;
+        }
+
+        self.policy.clone_request(req)
+    }
+}
+
+// ===== impl OnlyIdempotentFuture =====
+
+impl<F> Future for OnlyIdempotentFuture<F>
+where
+    F: Future<Error = ()>,
+{
+    type Item = OnlyIdempotent<F::Item>;
+    type Error = ();
+
+    fn poll(&mut self) -> Poll<Self::Item, ()> {
+        let policy = try_ready!(self.inner.poll());
+        Ok(Async::Ready(OnlyIdempotent { policy }))
+    }
+}
@@ -6,18 +6,40 @@
 
 #[macro_use]
 extern crate futures;
+extern crate rand;
 extern crate tokio_timer;
 extern crate tower_layer;
 extern crate tower_service;
+extern crate tower_timeout;
+extern crate tower_util;
 
 use futures::{Async, Future, Poll};
 use tower_layer::Layer;
 use tower_service::Service;
+use tower_util::{Unready, UnreadyReason};
 
+pub mod annotate;
+pub mod backoff;
 pub mod budget;
+pub mod clone;
+pub mod deadline;
+pub mod event;
+pub mod guard;
+pub mod idempotent;
 mod never;
+pub mod rebuild;
+pub mod retry_after;
+pub mod timeout;
 
+pub use backoff::ExponentialBackoff;
+
+use annotate::{AnnotatedRetry, AnnotatedRetryLayer};
+use event::{Event, OnEvent};
+use idempotent::OnlyIdempotent;
 use never::Never;
+use std::time::{Duration, Instant};
+use timeout::RetryWithTimeoutLayer;
+use tokio_timer::clock;
 
 /// A "retry policy" to classify if a request should be retried.
 ///
@@ -79,6 +101,21 @@ pub trait Policy<Req, Res, E>: Sized {
     /// If the returned `Future` errors, the request will **not** be retried
     /// after all.
     fn retry(&self, req: &Req, result: Result<&Res, &E>) -> Option<Self::Future>;
+    /// Check the policy if a request, whose first attempt was made at
+    /// `_started`, should be retried.
+    ///
+    /// `Retry` calls this rather than `retry`, so that policies may bound the
+    /// time spent on a request, as [`Deadline`](deadline/struct.Deadline.html)
+    /// does. By default, the time is ignored and `retry` is called. Policies
+    /// wrapping another policy should pass the time on to it.
+    fn retry_since(
+        &self,
+        req: &Req,
+        result: Result<&Res, &E>,
+        _started: Instant,
+    ) -> Option<Self::Future> {
+        self.retry(req, result)
+    }
     /// Tries to clone a request before being passed to the inner service.
     ///
     /// If the request cannot be cloned, return `None`.
@@ -92,12 +129,14 @@ pub trait Policy<Req, Res, E>: Sized {
 pub struct Retry<P, S> {
     policy: P,
     service: S,
+    on_event: Option<OnEvent>,
 }
 
 /// Retry requests based on a policy
 #[derive(Debug)]
 pub struct RetryLayer<P> {
     policy: P,
+    on_event: Option<OnEvent>,
 }
 
 /// The `Future` returned by a `Retry` service.
@@ -110,6 +149,9 @@ where
     request: Option<Request>,
     retry: Retry<P, S>,
     state: State<S::Future, P::Future, S::Response, S::Error>,
+    attempts: usize,
+    /// When the first attempt was made.
+    started: Instant,
 }
 
 #[derive(Debug)]
@@ -127,7 +169,49 @@ enum State<F, P, R, E> {
 impl<P> RetryLayer<P> {
     /// Create a new `RetryLayer` from a retry policy
     pub fn new(policy: P) -> Self {
-        RetryLayer { policy }
+        RetryLayer {
+            policy,
+            on_event: None,
+        }
+    }
+
+    /// Call `f` with each `Event` in the lifecycle of requests sent through
+    /// the produced services.
+    ///
+    /// See [`event`](event/index.html) for details.
+    pub fn on_event<F>(mut self, f: F) -> Self
+    where
+        F: Fn(Event) + Send + Sync + 'static,
+    {
+        self.on_event = Some(OnEvent::new(f));
+        self
+    }
+
+    /// Annotate the responses of the produced services with the number of
+    /// attempts made and the total time taken.
+    ///
+    /// See [`annotate`](annotate/index.html) for details.
+    pub fn annotated(self) -> AnnotatedRetryLayer<P> {
+        AnnotatedRetryLayer::new(self)
+    }
+
+    /// Never retry requests that are not
+    /// [`Idempotent`](idempotent/trait.Idempotent.html).
+    ///
+    /// See [`idempotent`](idempotent/index.html) for details.
+    pub fn only_idempotent(self) -> RetryLayer<OnlyIdempotent<P>> {
+        RetryLayer {
+            policy: OnlyIdempotent::new(self.policy),
+            on_event: self.on_event,
+        }
+    }
+
+    /// Fail each attempt made by the produced services that takes longer
+    /// than `timeout`.
+    ///
+    /// See [`timeout`](timeout/index.html) for details.
+    pub fn with_attempt_timeout(self, timeout: Duration) -> RetryWithTimeoutLayer<P> {
+        RetryWithTimeoutLayer::new(self, timeout)
     }
 }
 
@@ -143,7 +227,10 @@ where
 
     fn layer(&self, service: S) -> Result<Self::Service, Self::LayerError> {
         let policy = self.policy.clone();
-        Ok(Retry::new(policy, service))
+        Ok(Retry {
+            on_event: self.on_event.clone(),
+            ..Retry::new(policy, service)
+        })
     }
 }
 
@@ -156,7 +243,46 @@ impl<P, S> Retry<P, S> {
         P: Policy<Request, S::Response, S::Error> + Clone,
         S: Service<Request> + Clone,
     {
-        Retry { policy, service }
+        Retry {
+            policy,
+            service,
+            on_event: None,
+        }
+    }
+
+    /// Call `f` with each `Event` in the lifecycle of requests sent through
+    /// this service.
+    ///
+    /// See [`event`](event/index.html) for details.
+    pub fn on_event<F>(mut self, f: F) -> Self
+    where
+        F: Fn(Event) + Send + Sync + 'static,
+    {
+        self.on_event = Some(OnEvent::new(f));
+        self
+    }
+
+    /// Annotate responses with the number of attempts made and the total
+    /// time taken.
+    ///
+    /// See [`annotate`](annotate/index.html) for details.
+    pub fn annotated(self) -> AnnotatedRetry<P, S> {
+        AnnotatedRetry::new(self)
+    }
+
+    fn emit(&self, event: Event) {
+        if let Some(ref on_event) = self.on_event {
+            on_event.emit(event);
+        }
+    }
+
+    /// Completes a request with the result of its last attempt.
+    fn finish<T, E>(&self, attempts: usize, result: Result<T, E>) -> Poll<T, E> {
+        if result.is_err() {
+            self.emit(Event::RetriesExhausted { attempts });
+        }
+
+        result.map(Async::Ready)
     }
 }
 
@@ -175,17 +301,42 @@ where
 
     fn call(&mut self, request: Request) -> Self::Future {
         let cloned = self.policy.clone_request(&request);
+        let started = clock::now();
         let future = self.service.call(request);
+        self.emit(Event::AttemptStarted { attempt: 1 });
         ResponseFuture {
             request: cloned,
             retry: self.clone(),
             state: State::Called(future),
+            attempts: 1,
+            started,
         }
     }
 }
 
+impl<P, S, Request> Unready<Request> for Retry<P, S>
+where
+    P: Policy<Request, S::Response, S::Error> + Clone,
+    S: Unready<Request> + Clone,
+{
+    fn unready_reason(&self) -> Option<UnreadyReason> {
+        self.service.unready_reason()
+    }
+}
+
 // ===== impl ResponseFuture =====
 
+impl<P, S, Request> ResponseFuture<P, S, Request>
+where
+    P: Policy<Request, S::Response, S::Error>,
+    S: Service<Request>,
+{
+    /// The number of times the request has been sent to the inner service.
+    pub(crate) fn attempts(&self) -> usize {
+        self.attempts
+    }
+}
+
 impl<P, S, Request> Future for ResponseFuture<P, S, Request>
 where
     P: Policy<Request, S::Response, S::Error> + Clone,
@@ -201,17 +352,32 @@ where
                     let result = match future.poll() {
                         Ok(Async::NotReady) => return Ok(Async::NotReady),
                         Ok(Async::Ready(res)) => Ok(res),
-                        Err(err) => Err(err),
+                        Err(err) => {
+                            self.retry.emit(Event::AttemptFailed {
+                                attempt: self.attempts,
+                            });
+                            Err(err)
+                        }
                     };
 
-                    if let Some(ref req) = self.request {
-                        match self.retry.policy.retry(req, result.as_ref()) {
-                            Some(checking) => State::Checking(checking, Some(result)),
-                            None => return result.map(Async::Ready),
+                    let checking = match self.request {
+                        Some(ref req) => {
+                            self.retry
+                                .policy
+                                .retry_since(req, result.as_ref(), self.started)
                         }
-                    } else {
                         // request wasn't cloned, so no way to retry it
-                        return result.map(Async::Ready);
+                        None => None,
+                    };
+
+                    match checking {
+                        Some(checking) => {
+                            self.retry.emit(Event::RetryScheduled {
+                                attempt: self.attempts,
+                            });
+                            State::Checking(checking, Some(result))
+                        }
+                        None => return self.retry.finish(self.attempts, result),
                     }
                 }
                 State::Checking(ref mut future, ref mut result) => {
@@ -221,10 +387,8 @@ where
                         Err(()) => {
                             // if Policy::retry() fails, return the original
                             // result...
-                            return result
-                                .take()
-                                .expect("polled after complete")
-                                .map(Async::Ready);
+                            let result = result.take().expect("polled after complete");
+                            return self.retry.finish(self.attempts, result);
                         }
                     };
                     self.retry.policy = policy;
@@ -237,6 +401,10 @@ where
                         .take()
                         .expect("retrying requires cloned request");
                     self.request = self.retry.policy.clone_request(&req);
+                    self.attempts += 1;
+                    self.retry.emit(Event::AttemptStarted {
+                        attempt: self.attempts,
+                    });
                     State::Called(self.retry.service.call(req))
                 }
             };
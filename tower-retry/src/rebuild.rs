@@ -0,0 +1,120 @@
+//! Retrying requests that cannot be cloned.
+//!
+//! `Policy::clone_request` copies a request before it is sent, so that it is
+//! still around if it has to be retried. Some requests cannot be copied,
+//! e.g. those with a streaming body that is consumed as it is sent. They can
+//! often be built again, though: by opening the body's source again, or from
+//! parts of the request that are cheap to keep around.
+//!
+//! `Rebuild` wraps a `Policy` deciding *whether* to retry, and uses a
+//! user-provided factory instead of the policy's `clone_request` to build
+//! the request for each attempt.
+
+use futures::{Async, Future, Poll};
+use std::fmt;
+use std::time::Instant;
+
+use Policy;
+
+/// A `Policy` building the request for each attempt with a factory.
+#[derive(Clone)]
+pub struct Rebuild<P, F> {
+    policy: P,
+    factory: F,
+}
+
+/// The `Future` returned by `Rebuild::retry`.
+#[derive(Debug)]
+pub struct RebuildFuture<T, F> {
+    inner: T,
+    factory: Option<F>,
+}
+
+// ===== impl Rebuild =====
+
+impl<P, F> Rebuild<P, F> {
+    /// Retry requests as decided by `policy`, building each attempt with
+    /// `factory`.
+    ///
+    /// `factory` is given the request of the previous attempt, and returns
+    /// `None` if the request cannot be built again, in which case it is not
+    /// retried. The `clone_request` of `policy` is never called.
+    pub fn new(policy: P, factory: F) -> Self {
+        Rebuild { policy, factory }
+    }
+
+    /// Get a reference to the inner policy
+    pub fn get_ref(&self) -> &P {
+        &self.policy
+    }
+
+    /// Consume `self`, returning the inner policy
+    pub fn into_inner(self) -> P {
+        self.policy
+    }
+
+    fn wrap<T>(&self, inner: T) -> RebuildFuture<T, F>
+    where
+        F: Clone,
+    {
+        RebuildFuture {
+            inner,
+            factory: Some(self.factory.clone()),
+        }
+    }
+}
+
+impl<P, F, Req, Res, E> Policy<Req, Res, E> for Rebuild<P, F>
+where
+    P: Policy<Req, Res, E>,
+    F: Fn(&Req) -> Option<Req> + Clone,
+{
+    type Future = RebuildFuture<P::Future, F>;
+
+    fn retry(&self, req: &Req, result: Result<&Res, &E>) -> Option<Self::Future> {
+        self.policy.retry(req, result).map(|inner| self.wrap(inner))
+    }
+
+    fn retry_since(
+        &self,
+        req: &Req,
+        result: Result<&Res, &E>,
+        started: Instant,
+    ) -> Option<Self::Future> {
+        self.policy
+            .retry_since(req, result, started)
+            .map(|inner| self.wrap(inner))
+    }
+
+    fn clone_request(&self, req: &Req) -> Option<Req> {
+        (self.factory)(req)
+    }
+}
+
+impl<P, F> fmt::Debug for Rebuild<P, F>
+where
+    P: fmt::Debug,
+{
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_struct("Rebuild")
+            .field("policy", &self.policy)
+            .finish()
+    }
+}
+
+// ===== impl RebuildFuture =====
+
+impl<T, F> Future for RebuildFuture<T, F>
+where
+    T: Future<Error = ()>,
+{
+    type Item = Rebuild<T::Item, F>;
+    type Error = ();
+
+    fn poll(&mut self) -> Poll<Self::Item, ()> {
+        let policy = try_ready!(self.inner.poll());
+        let factory = self.factory.take().expect("polled after complete");
+
+        Ok(Async::Ready(Rebuild::new(policy, factory)))
+    }
+}
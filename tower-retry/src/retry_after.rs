@@ -0,0 +1,132 @@
+//! Retrying after a delay chosen by the response.
+//!
+//! Servers often tell clients how long to wait before trying again, e.g.
+//! with the `Retry-After` header of an HTTP 503 response, or a backoff hint
+//! in an error. `RetryAfter` is a `Policy` that reads this delay from the
+//! response or error, and waits that long before retrying.
+
+use std::cmp;
+use std::fmt;
+use std::time::Duration;
+use tower_util::backoff::Delayed;
+
+use clone::{CloneRequest, Cloned, NotCloned};
+use Policy;
+
+/// A `Policy` retrying after a delay computed from the response or error.
+///
+/// `delay` is called with the request and the result of each attempt. If it
+/// returns `Some`, the request is retried after the returned delay, which is
+/// capped at `max_delay` so a server cannot stall the client indefinitely.
+/// Requests are retried at most `max_retries` times (3 by default).
+///
+/// Requests are copied as set by `C`, with `Clone` by default. See
+/// [`clone`](../clone/index.html) for requests that cannot be cloned.
+///
+/// # Example
+///
+/// ```
+/// # extern crate tower_retry;
+/// use std::time::Duration;
+/// use tower_retry::retry_after::RetryAfter;
+/// use tower_retry::RetryLayer;
+///
+/// struct Response {
+///     status: u16,
+///     retry_after: Option<u64>,
+/// }
+///
+/// # fn main() {
+/// let policy = RetryAfter::new(
+///     Duration::from_secs(30),
+///     |_: &String, result: Result<&Response, &std::io::Error>| match result {
+///         Ok(res) if res.status == 503 => res.retry_after.map(Duration::from_secs),
+///         _ => None,
+///     },
+/// );
+///
+/// let layer = RetryLayer::new(policy);
+/// # drop(layer);
+/// # }
+/// ```
+#[derive(Clone)]
+pub struct RetryAfter<F, C = Cloned> {
+    delay: F,
+    max_delay: Duration,
+    retries_left: usize,
+    clone: C,
+}
+
+/// The `Future` returned by `RetryAfter::retry`.
+pub type RetryAfterFuture<F, C> = Delayed<RetryAfter<F, C>>;
+
+// ===== impl RetryAfter =====
+
+impl<F> RetryAfter<F> {
+    /// Create a new policy retrying whenever `delay` returns a delay, waiting
+    /// at most `max_delay` before each retry.
+    pub fn new(max_delay: Duration, delay: F) -> Self {
+        RetryAfter {
+            delay,
+            max_delay,
+            retries_left: 3,
+            clone: Cloned,
+        }
+    }
+}
+
+impl<F, C> RetryAfter<F, C> {
+    /// Set the maximum number of times a request is retried.
+    pub fn max_retries(mut self, max_retries: usize) -> Self {
+        self.retries_left = max_retries;
+        self
+    }
+
+    /// Never copy requests, e.g. to wrap the policy in a `Rebuild`.
+    pub fn not_cloned(self) -> RetryAfter<F, NotCloned> {
+        RetryAfter {
+            delay: self.delay,
+            max_delay: self.max_delay,
+            retries_left: self.retries_left,
+            clone: NotCloned,
+        }
+    }
+}
+
+impl<F, C, Req, Res, E> Policy<Req, Res, E> for RetryAfter<F, C>
+where
+    F: Fn(&Req, Result<&Res, &E>) -> Option<Duration> + Clone,
+    C: CloneRequest<Req> + Clone,
+{
+    type Future = RetryAfterFuture<F, C>;
+
+    fn retry(&self, req: &Req, result: Result<&Res, &E>) -> Option<Self::Future> {
+        if self.retries_left == 0 {
+            return None;
+        }
+
+        let delay = cmp::min((self.delay)(req, result)?, self.max_delay);
+
+        let policy = RetryAfter {
+            delay: self.delay.clone(),
+            max_delay: self.max_delay,
+            retries_left: self.retries_left - 1,
+            clone: self.clone.clone(),
+        };
+
+        Some(Delayed::new(policy, delay))
+    }
+
+    fn clone_request(&self, req: &Req) -> Option<Req> {
+        self.clone.clone_request(req)
+    }
+}
+
+impl<F, C> fmt::Debug for RetryAfter<F, C> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_struct("RetryAfter")
+            .field("max_delay", &self.max_delay)
+            .field("retries_left", &self.retries_left)
+            .finish()
+    }
+}
@@ -0,0 +1,83 @@
+//! Bounding each attempt with a timeout.
+//!
+//! A timeout applied around a `Retry` bounds the request as a whole, while a
+//! timeout applied to the service inside it bounds every attempt on its own.
+//! `Retry::with_attempt_timeout` and `RetryLayer::with_attempt_timeout`
+//! build the latter, so the timeout restarts with every attempt regardless
+//! of how the rest of the stack is ordered. For an overall deadline as well,
+//! wrap the resulting service in a `Timeout` of its own.
+//!
+//! A timed out attempt fails with [`Elapsed`](struct.Elapsed.html), which
+//! the policy may choose to retry. `is_elapsed` also finds it when it was
+//! wrapped by middleware between the `Retry` and the timeout:
+//!
+//! ```
+//! # extern crate tower_retry;
+//! # use tower_retry::timeout::is_elapsed;
+//! # fn main() {
+//! # let err: Box<std::error::Error + Send + Sync> = "boom".into();
+//! let retryable = is_elapsed(&*err);
+//! # drop(retryable);
+//! # }
+//! ```
+
+use std::time::Duration;
+use tower_layer::Layer;
+use tower_service::Service;
+use tower_timeout::Timeout;
+
+use never::Never;
+use {Policy, Retry, RetryLayer};
+
+pub use tower_timeout::error::{is_elapsed, Elapsed};
+
+type Error = Box<::std::error::Error + Send + Sync>;
+
+/// A `Retry` bounding each attempt with a timeout.
+pub type RetryWithTimeout<P, S> = Retry<P, Timeout<S>>;
+
+/// Retry requests based on a policy, bounding each attempt with a timeout.
+#[derive(Debug)]
+pub struct RetryWithTimeoutLayer<P> {
+    layer: RetryLayer<P>,
+    timeout: Duration,
+}
+
+// ===== impl RetryWithTimeout =====
+
+impl<P, S> Retry<P, Timeout<S>> {
+    /// Retry the inner service depending on `policy`, failing each attempt
+    /// that takes longer than `timeout`.
+    pub fn with_attempt_timeout<Request>(policy: P, service: S, timeout: Duration) -> Self
+    where
+        P: Policy<Request, S::Response, Error> + Clone,
+        S: Service<Request> + Clone,
+        Error: From<S::Error>,
+    {
+        Retry::new(policy, Timeout::new(service, timeout))
+    }
+}
+
+// ===== impl RetryWithTimeoutLayer =====
+
+impl<P> RetryWithTimeoutLayer<P> {
+    pub(crate) fn new(layer: RetryLayer<P>, timeout: Duration) -> Self {
+        RetryWithTimeoutLayer { layer, timeout }
+    }
+}
+
+impl<P, S, Request> Layer<S, Request> for RetryWithTimeoutLayer<P>
+where
+    S: Service<Request> + Clone,
+    Error: From<S::Error>,
+    P: Policy<Request, S::Response, Error> + Clone,
+{
+    type Response = S::Response;
+    type Error = Error;
+    type LayerError = Never;
+    type Service = RetryWithTimeout<P, S>;
+
+    fn layer(&self, service: S) -> Result<Self::Service, Self::LayerError> {
+        self.layer.layer(Timeout::new(service, self.timeout))
+    }
+}
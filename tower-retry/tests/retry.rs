@@ -1,10 +1,25 @@
 extern crate futures;
+extern crate tokio;
 extern crate tower_mock;
 extern crate tower_retry;
 extern crate tower_service;
 
 use futures::{future, Future};
-use tower_retry::Policy;
+use std::collections::hash_map::DefaultHasher;
+use std::hash::{Hash, Hasher};
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::{Arc, Mutex};
+use std::thread;
+use std::time::{Duration, Instant};
+use tower_retry::budget::{Budget, Budgeted};
+use tower_retry::deadline::Deadline;
+use tower_retry::event::Event;
+use tower_retry::guard::Guarded;
+use tower_retry::idempotent::{Idempotent, OnlyIdempotent};
+use tower_retry::rebuild::Rebuild;
+use tower_retry::retry_after::RetryAfter;
+use tower_retry::timeout::Elapsed;
+use tower_retry::{ExponentialBackoff, Policy};
 use tower_service::Service;
 
 #[test]
@@ -53,6 +68,35 @@ fn retry_limit() {
     assert_eq!(fut.wait().unwrap_err().to_string(), "retry 3");
 }
 
+#[test]
+fn retry_events() {
+    let events = Arc::new(Mutex::new(Vec::new()));
+    let (service, mut handle) = new_service(Limit(1));
+    let recorded = events.clone();
+    let mut service = service.on_event(move |event| recorded.lock().unwrap().push(event));
+
+    assert!(service.poll_ready().unwrap().is_ready());
+    let mut fut = service.call("hello");
+
+    handle.next_request().unwrap().error("retry 1");
+    assert_not_ready(&mut fut);
+
+    handle.next_request().unwrap().error("retry 2");
+    assert_eq!(fut.wait().unwrap_err().to_string(), "retry 2");
+
+    assert_eq!(
+        *events.lock().unwrap(),
+        vec![
+            Event::AttemptStarted { attempt: 1 },
+            Event::AttemptFailed { attempt: 1 },
+            Event::RetryScheduled { attempt: 1 },
+            Event::AttemptStarted { attempt: 2 },
+            Event::AttemptFailed { attempt: 2 },
+            Event::RetriesExhausted { attempts: 2 },
+        ]
+    );
+}
+
 #[test]
 fn retry_error_inspection() {
     let (mut service, mut handle) = new_service(UnlessErr("reject"));
@@ -102,6 +146,343 @@ fn success_with_cannot_clone() {
     assert_eq!(fut.wait().unwrap(), "world");
 }
 
+#[test]
+fn annotated_attempts() {
+    let (service, mut handle) = new_service(RetryErrors);
+    let mut service = service.annotated();
+
+    assert!(service.poll_ready().unwrap().is_ready());
+    let mut fut = service.call("hello");
+
+    let req1 = handle.next_request().unwrap();
+    req1.error("retry me");
+
+    assert_not_ready(&mut fut);
+
+    let req2 = handle.next_request().unwrap();
+    req2.respond("world");
+
+    let rsp = fut.wait().unwrap();
+    assert_eq!(rsp.attempts(), 2);
+    assert_eq!(*rsp.response(), "world");
+
+    assert!(service.poll_ready().unwrap().is_ready());
+    let fut = service.call("hello");
+    handle.next_request().unwrap().respond("world");
+    assert_eq!(fut.wait().unwrap().attempts(), 1);
+}
+
+#[test]
+fn guarded_retry() {
+    let (mut service, mut handle) = new_service(Guarded::new(RetryErrors, hash));
+
+    assert!(service.poll_ready().unwrap().is_ready());
+    let mut fut = service.call("hello");
+
+    handle.next_request().unwrap().error("retry me");
+    assert_not_ready(&mut fut);
+
+    let req2 = handle.next_request().unwrap();
+    assert_eq!(*req2, "hello");
+    req2.respond("world");
+
+    assert_eq!(fut.wait().unwrap(), "world");
+}
+
+#[test]
+fn rebuild_request() {
+    let built = Arc::new(AtomicUsize::new(0));
+    let count = built.clone();
+    let factory = move |req: &Req| {
+        count.fetch_add(1, Ordering::SeqCst);
+        Some(*req)
+    };
+    let (mut service, mut handle) = new_service(Rebuild::new(NoClone(1), factory));
+
+    assert!(service.poll_ready().unwrap().is_ready());
+    let mut fut = service.call("hello");
+
+    let req1 = handle.next_request().unwrap();
+    assert_eq!(*req1, "hello");
+    req1.error("retry 1");
+
+    assert_not_ready(&mut fut);
+
+    let req2 = handle.next_request().unwrap();
+    assert_eq!(*req2, "hello");
+    req2.error("retry 2");
+
+    assert_eq!(fut.wait().unwrap_err().to_string(), "retry 2");
+    // Built once before each attempt that could be retried.
+    assert_eq!(built.load(Ordering::SeqCst), 2);
+}
+
+#[test]
+#[cfg(debug_assertions)]
+#[should_panic(expected = "clone_request returned a request different from the original")]
+fn guarded_mutated_request() {
+    let (mut service, _handle) = new_service(Guarded::new(Mutates, hash));
+
+    assert!(service.poll_ready().unwrap().is_ready());
+    let _ = service.call("hello");
+}
+
+#[test]
+fn budgeted_retry() {
+    // The budget allows a single retry, and deposits don't add any.
+    let budget = Arc::new(Budget::new(Duration::from_secs(1), 1, 0.0));
+    let (mut service, mut handle) = new_service(Budgeted::new(RetryErrors, budget));
+
+    assert!(service.poll_ready().unwrap().is_ready());
+    let mut fut = service.call("hello");
+
+    handle.next_request().unwrap().error("retry 1");
+    assert_not_ready(&mut fut);
+
+    handle.next_request().unwrap().error("retry 2");
+    assert_eq!(fut.wait().unwrap_err().to_string(), "retry 2");
+}
+
+#[test]
+fn budget_deposits_only_successes() {
+    // Every deposit allows a single retry, and there is no reserve.
+    let budget = Arc::new(Budget::new(Duration::from_secs(1), 0, 1.0));
+    let (mut service, mut handle) = new_service(Budgeted::new(RetryErrors, budget));
+
+    // Neither failed requests nor overdrawn retries fill the budget...
+    for _ in 0..2 {
+        assert!(service.poll_ready().unwrap().is_ready());
+        let fut = service.call("hello");
+        handle.next_request().unwrap().error("no retry");
+        assert_eq!(fut.wait().unwrap_err().to_string(), "no retry");
+    }
+
+    // ...while successful requests do.
+    assert!(service.poll_ready().unwrap().is_ready());
+    let fut = service.call("hello");
+    handle.next_request().unwrap().respond("world");
+    assert_eq!(fut.wait().unwrap(), "world");
+
+    assert!(service.poll_ready().unwrap().is_ready());
+    let mut fut = service.call("hello");
+    handle.next_request().unwrap().error("retry 1");
+    assert_not_ready(&mut fut);
+    handle.next_request().unwrap().respond("world");
+    assert_eq!(fut.wait().unwrap(), "world");
+}
+
+#[test]
+fn exponential_backoff() {
+    let mut rt = tokio::runtime::current_thread::Runtime::new().unwrap();
+    let policy = ExponentialBackoff::new(
+        Duration::from_millis(10),
+        Duration::from_millis(15),
+        |err: &InnerError| *err != "fatal",
+    )
+    .max_retries(2);
+
+    // Responses and errors that aren't retryable are not retried.
+    assert!(Policy::<Req, Res, InnerError>::retry(&policy, &"hello", Ok(&"world")).is_none());
+    assert!(Policy::<Req, Res, _>::retry(&policy, &"hello", Err(&"fatal")).is_none());
+
+    let started = Instant::now();
+    let retry = Policy::<Req, Res, _>::retry(&policy, &"hello", Err(&"again")).unwrap();
+    let policy = rt.block_on(retry).unwrap();
+    assert!(started.elapsed() >= Duration::from_millis(10));
+
+    // The delay doubles, but is capped at the max.
+    let started = Instant::now();
+    let retry = Policy::<Req, Res, _>::retry(&policy, &"hello", Err(&"again")).unwrap();
+    let policy = rt.block_on(retry).unwrap();
+    assert!(started.elapsed() >= Duration::from_millis(15));
+
+    // Out of retries.
+    assert!(Policy::<Req, Res, _>::retry(&policy, &"hello", Err(&"again")).is_none());
+}
+
+#[test]
+fn backoff_rebuilds_requests() {
+    // Not `Clone`, so only `Rebuild` can copy it.
+    #[derive(Debug, PartialEq)]
+    struct Body(&'static str);
+
+    let mut rt = tokio::runtime::current_thread::Runtime::new().unwrap();
+    let backoff = ExponentialBackoff::new(
+        Duration::from_millis(1),
+        Duration::from_millis(1),
+        |_: &InnerError| true,
+    )
+    .not_cloned();
+    assert!(Policy::<Body, Res, InnerError>::clone_request(&backoff, &Body("hello")).is_none());
+
+    let policy = Rebuild::new(backoff, |req: &Body| Some(Body(req.0)));
+    let copy = Policy::<Body, Res, InnerError>::clone_request(&policy, &Body("hello"));
+    assert_eq!(copy, Some(Body("hello")));
+
+    let retry = Policy::<Body, Res, _>::retry(&policy, &Body("hello"), Err(&"again")).unwrap();
+    let policy = rt.block_on(retry).unwrap();
+    let copy = Policy::<Body, Res, InnerError>::clone_request(&policy, &Body("hello"));
+    assert_eq!(copy, Some(Body("hello")));
+}
+
+#[test]
+fn retry_after() {
+    let mut rt = tokio::runtime::current_thread::Runtime::new().unwrap();
+    let policy = RetryAfter::new(
+        Duration::from_millis(20),
+        |_: &Req, result: Result<&Res, &InnerError>| match result {
+            Ok(&"busy") => Some(Duration::from_millis(10)),
+            Ok(&"very busy") => Some(Duration::from_secs(60)),
+            _ => None,
+        },
+    )
+    .max_retries(2);
+
+    assert!(Policy::<Req, Res, InnerError>::retry(&policy, &"hello", Ok(&"world")).is_none());
+
+    // The delay comes from the response.
+    let started = Instant::now();
+    let retry = Policy::<Req, Res, InnerError>::retry(&policy, &"hello", Ok(&"busy")).unwrap();
+    let policy = rt.block_on(retry).unwrap();
+    assert!(started.elapsed() >= Duration::from_millis(10));
+
+    // But is capped at the max.
+    let started = Instant::now();
+    let retry = Policy::<Req, Res, InnerError>::retry(&policy, &"hello", Ok(&"very busy"));
+    let policy = rt.block_on(retry.unwrap()).unwrap();
+    let elapsed = started.elapsed();
+    assert!(elapsed >= Duration::from_millis(20));
+    assert!(elapsed < Duration::from_secs(60));
+
+    // Out of retries.
+    assert!(Policy::<Req, Res, InnerError>::retry(&policy, &"hello", Ok(&"busy")).is_none());
+}
+
+#[test]
+fn deadline() {
+    let mut rt = tokio::runtime::current_thread::Runtime::new().unwrap();
+    let deadline = Deadline::new(Limit(100), Duration::from_millis(30));
+    let policy = Rebuild::new(deadline, |req: &Req| Some(*req));
+    let err: Error = "retry".into();
+
+    // The clock starts with the first attempt, which `Retry` passes on
+    // through the policies wrapping `Deadline`...
+    let started = Instant::now();
+    let retry = policy.retry_since(&"hello", Err(&err), started).unwrap();
+    let policy = rt.block_on(retry).unwrap();
+
+    // ...and retries stop once it runs out, although the inner policy would
+    // keep going.
+    thread::sleep(Duration::from_millis(30));
+    assert!(policy.retry_since(&"hello", Err(&err), started).is_none());
+}
+
+#[test]
+fn deadline_bounds_retries() {
+    let policy = Deadline::new(Limit(100), Duration::from_millis(30));
+    let (mut service, mut handle) = new_service(policy);
+
+    assert!(service.poll_ready().unwrap().is_ready());
+    let mut fut = service.call("hello");
+
+    handle.next_request().unwrap().error("retry 1");
+    assert_not_ready(&mut fut);
+
+    thread::sleep(Duration::from_millis(30));
+    handle.next_request().unwrap().error("retry 2");
+    assert_eq!(fut.wait().unwrap_err().to_string(), "retry 2");
+}
+
+#[test]
+fn attempt_timeout() {
+    let mut rt = tokio::runtime::current_thread::Runtime::new().unwrap();
+    let (service, mut handle) = Mock::new();
+    let mut service =
+        tower_retry::Retry::with_attempt_timeout(Limit(1), service, Duration::from_millis(20));
+
+    assert!(service.poll_ready().unwrap().is_ready());
+    let fut = service.call("hello");
+
+    let responder = thread::spawn(move || {
+        // The first attempt never completes, and times out...
+        let req1 = handle.next_request().unwrap();
+        assert_eq!(*req1, "hello");
+
+        // ...while the second is given a fresh timeout.
+        let req2 = handle.next_request().unwrap();
+        assert_eq!(*req2, "hello");
+        req2.respond("world");
+
+        req1
+    });
+
+    assert_eq!(rt.block_on(fut).unwrap(), "world");
+    drop(responder.join().unwrap());
+}
+
+#[test]
+fn attempt_timeout_exhausted() {
+    let mut rt = tokio::runtime::current_thread::Runtime::new().unwrap();
+    let (service, mut handle) = Mock::new();
+    let mut service =
+        tower_retry::Retry::with_attempt_timeout(Limit(0), service, Duration::from_millis(20));
+
+    assert!(service.poll_ready().unwrap().is_ready());
+    let fut = service.call("hello");
+    let req = handle.next_request().unwrap();
+
+    let err = rt.block_on(fut).unwrap_err();
+    assert!(err.is::<Elapsed>());
+    drop(req);
+}
+
+#[test]
+fn only_idempotent() {
+    #[derive(Clone, Copy, Debug, PartialEq)]
+    enum Method {
+        Get,
+        Post,
+    }
+
+    impl Idempotent for Method {
+        fn is_idempotent(&self) -> bool {
+            *self == Method::Get
+        }
+    }
+
+    #[derive(Clone)]
+    struct RetryAll;
+
+    impl Policy<Method, Res, Error> for RetryAll {
+        type Future = future::FutureResult<Self, ()>;
+        fn retry(&self, _: &Method, result: Result<&Res, &Error>) -> Option<Self::Future> {
+            result.err().map(|_| future::ok(RetryAll))
+        }
+
+        fn clone_request(&self, req: &Method) -> Option<Method> {
+            Some(*req)
+        }
+    }
+
+    let (service, mut handle) = tower_mock::Mock::<Method, Res>::new();
+    let mut service = tower_retry::Retry::new(OnlyIdempotent::new(RetryAll), service);
+
+    assert!(service.poll_ready().unwrap().is_ready());
+    let mut fut = service.call(Method::Get);
+    handle.next_request().unwrap().error("retry me");
+    assert_not_ready(&mut fut);
+    handle.next_request().unwrap().respond("world");
+    assert_eq!(fut.wait().unwrap(), "world");
+
+    // Requests that are not idempotent still go through, but only once.
+    assert!(service.poll_ready().unwrap().is_ready());
+    let fut = service.call(Method::Post);
+    let req = handle.next_request().unwrap();
+    assert_eq!(*req, Method::Post);
+    req.error("fatal");
+    assert_eq!(fut.wait().unwrap_err().to_string(), "fatal");
+}
+
 type Req = &'static str;
 type Res = &'static str;
 type InnerError = &'static str;
@@ -179,6 +560,45 @@ impl Policy<Req, Res, Error> for CannotClone {
     }
 }
 
+/// Retries errors a limited number of times, but cannot clone requests.
+#[derive(Clone)]
+struct NoClone(usize);
+
+impl Policy<Req, Res, Error> for NoClone {
+    type Future = future::FutureResult<Self, ()>;
+    fn retry(&self, _: &Req, result: Result<&Res, &Error>) -> Option<Self::Future> {
+        if result.is_err() && self.0 > 0 {
+            Some(future::ok(NoClone(self.0 - 1)))
+        } else {
+            None
+        }
+    }
+
+    fn clone_request(&self, _req: &Req) -> Option<Req> {
+        None
+    }
+}
+
+#[derive(Clone)]
+struct Mutates;
+
+impl Policy<Req, Res, Error> for Mutates {
+    type Future = future::FutureResult<Self, ()>;
+    fn retry(&self, _: &Req, _: Result<&Res, &Error>) -> Option<Self::Future> {
+        None
+    }
+
+    fn clone_request(&self, _req: &Req) -> Option<Req> {
+        Some("mutated")
+    }
+}
+
+fn hash(req: &Req) -> u64 {
+    let mut hasher = DefaultHasher::new();
+    req.hash(&mut hasher);
+    hasher.finish()
+}
+
 fn new_service<P: Policy<Req, Res, Error> + Clone>(
     policy: P,
 ) -> (tower_retry::Retry<P, Mock>, Handle) {
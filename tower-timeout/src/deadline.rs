@@ -0,0 +1,253 @@
+//! Propagating deadlines across layers and services.
+//!
+//! A request is often served by sending further requests to other services,
+//! each through its own stack of middleware with its own timeouts. Unless
+//! they know about the original caller's deadline, those nested requests may
+//! outlive it, wasting work that nobody waits for anymore.
+//!
+//! `WithDeadline` carries a `Deadline` along with a request, and
+//! `DeadlineTimeout` times out requests once their deadline passes, shrinking
+//! its own timeout to the time remaining. Nested requests carry the same
+//! deadline by building them with `WithDeadline::map`, or with the deadline
+//! of the request being served.
+
+use crate::error::Error;
+use crate::future::ResponseFuture;
+use futures::Poll;
+use never::Never;
+use std::cmp;
+use std::time::{Duration, Instant};
+use tokio_timer::{clock, timer};
+use tower_layer::Layer;
+use tower_service::Service;
+
+/// The point in time by which a request must have completed.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub struct Deadline(Instant);
+
+/// Requests that may carry a `Deadline`.
+pub trait HasDeadline {
+    /// Returns the deadline of the request, if it has one.
+    fn deadline(&self) -> Option<Deadline>;
+}
+
+/// A request along with its deadline.
+#[derive(Debug, Clone)]
+pub struct WithDeadline<R> {
+    request: R,
+    deadline: Option<Deadline>,
+}
+
+/// Times out requests once their deadline passes.
+///
+/// Each request times out after the configured timeout, or once its
+/// deadline passes if that is sooner. Requests whose deadline has already
+/// passed fail with `error::Elapsed` without being sent to the inner service.
+#[derive(Debug, Clone)]
+pub struct DeadlineTimeout<T> {
+    inner: T,
+    timeout: Option<Duration>,
+    timer: timer::Handle,
+}
+
+/// Times out requests once their deadline passes via the supplied inner
+/// service.
+#[derive(Debug, Clone)]
+pub struct DeadlineTimeoutLayer {
+    timeout: Option<Duration>,
+    timer: timer::Handle,
+}
+
+// ===== impl Deadline =====
+
+impl Deadline {
+    /// Returns a deadline at `instant`.
+    pub fn at(instant: Instant) -> Self {
+        Deadline(instant)
+    }
+
+    /// Returns a deadline `timeout` from now.
+    pub fn after(timeout: Duration) -> Self {
+        Deadline(clock::now() + timeout)
+    }
+
+    /// Returns the point in time of the deadline.
+    pub fn instant(&self) -> Instant {
+        self.0
+    }
+
+    /// Returns the time left until the deadline, or zero if it has passed.
+    pub fn remaining(&self) -> Duration {
+        let now = clock::now();
+
+        if self.0 > now {
+            self.0 - now
+        } else {
+            Duration::from_secs(0)
+        }
+    }
+
+    /// Returns `true` if the deadline has passed.
+    pub fn is_elapsed(&self) -> bool {
+        self.0 <= clock::now()
+    }
+}
+
+// ===== impl WithDeadline =====
+
+impl<R> WithDeadline<R> {
+    /// Attaches `deadline` to `request`.
+    ///
+    /// Requests without a deadline are only bounded by the timeouts of the
+    /// services they are sent through.
+    pub fn new(request: R, deadline: Option<Deadline>) -> Self {
+        WithDeadline { request, deadline }
+    }
+
+    /// Returns a reference to the request.
+    pub fn get_ref(&self) -> &R {
+        &self.request
+    }
+
+    /// Returns a mutable reference to the request.
+    pub fn get_mut(&mut self) -> &mut R {
+        &mut self.request
+    }
+
+    /// Consumes `self`, returning the request.
+    pub fn into_inner(self) -> R {
+        self.request
+    }
+
+    /// Maps the request with `f`, keeping the deadline, e.g. to build a
+    /// nested request from the request being served.
+    pub fn map<F, U>(self, f: F) -> WithDeadline<U>
+    where
+        F: FnOnce(R) -> U,
+    {
+        WithDeadline {
+            request: f(self.request),
+            deadline: self.deadline,
+        }
+    }
+}
+
+impl<R> HasDeadline for WithDeadline<R> {
+    fn deadline(&self) -> Option<Deadline> {
+        self.deadline
+    }
+}
+
+// ===== impl DeadlineTimeout =====
+
+impl<T> DeadlineTimeout<T> {
+    /// Creates a new `DeadlineTimeout`, timing out requests after `timeout`
+    /// at the latest.
+    ///
+    /// If `timeout` is `None`, requests are only timed out once their
+    /// deadline passes.
+    pub fn new(inner: T, timeout: Option<Duration>) -> Self {
+        DeadlineTimeout {
+            inner,
+            timeout,
+            timer: timer::Handle::default(),
+        }
+    }
+
+    /// Time out requests with `timer`, rather than the timer of the runtime that
+    /// polls the responses.
+    ///
+    /// See [`Timeout::with_timer`](../struct.Timeout.html#method.with_timer).
+    pub fn with_timer(mut self, timer: timer::Handle) -> Self {
+        self.timer = timer;
+        self
+    }
+
+    /// Get a reference to the inner service
+    pub fn get_ref(&self) -> &T {
+        &self.inner
+    }
+
+    /// Get a mutable reference to the inner service
+    pub fn get_mut(&mut self) -> &mut T {
+        &mut self.inner
+    }
+
+    /// Consume `self`, returning the inner service
+    pub fn into_inner(self) -> T {
+        self.inner
+    }
+}
+
+impl<S, Request> Service<Request> for DeadlineTimeout<S>
+where
+    S: Service<Request>,
+    Request: HasDeadline,
+    Error: From<S::Error>,
+{
+    type Response = S::Response;
+    type Error = Error;
+    type Future = ResponseFuture<S::Future>;
+
+    fn poll_ready(&mut self) -> Poll<(), Self::Error> {
+        self.inner.poll_ready().map_err(Into::into)
+    }
+
+    fn call(&mut self, request: Request) -> Self::Future {
+        let deadline = request.deadline();
+
+        if deadline.map(|d| d.is_elapsed()).unwrap_or(false) {
+            return ResponseFuture::elapsed();
+        }
+
+        let remaining = deadline.map(|d| d.remaining());
+        let timeout = match (self.timeout, remaining) {
+            (Some(timeout), Some(remaining)) => Some(cmp::min(timeout, remaining)),
+            (timeout, remaining) => timeout.or(remaining),
+        };
+
+        let response = self.inner.call(request);
+        let timer = &self.timer;
+        let sleep = timeout.map(|timeout| timer.delay(clock::now() + timeout));
+
+        ResponseFuture::new(response, sleep)
+    }
+}
+
+// ===== impl DeadlineTimeoutLayer =====
+
+impl DeadlineTimeoutLayer {
+    /// Create a layer timing out requests after `timeout` at the latest, or
+    /// once their deadline passes if that is sooner.
+    pub fn new(timeout: Option<Duration>) -> Self {
+        DeadlineTimeoutLayer {
+            timeout,
+            timer: timer::Handle::default(),
+        }
+    }
+
+    /// Time out requests with `timer`, rather than the timer of the runtime that
+    /// polls the responses.
+    ///
+    /// See [`DeadlineTimeout::with_timer`](struct.DeadlineTimeout.html#method.with_timer).
+    pub fn with_timer(mut self, timer: timer::Handle) -> Self {
+        self.timer = timer;
+        self
+    }
+}
+
+impl<S, Request> Layer<S, Request> for DeadlineTimeoutLayer
+where
+    S: Service<Request>,
+    Request: HasDeadline,
+    Error: From<S::Error>,
+{
+    type Response = S::Response;
+    type Error = Error;
+    type LayerError = Never;
+    type Service = DeadlineTimeout<S>;
+
+    fn layer(&self, service: S) -> Result<Self::Service, Self::LayerError> {
+        Ok(DeadlineTimeout::new(service, self.timeout).with_timer(self.timer.clone()))
+    }
+}
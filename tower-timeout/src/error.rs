@@ -5,6 +5,10 @@ use std::{error, fmt};
 pub(crate) type Error = Box<error::Error + Send + Sync>;
 
 /// The timeout elapsed.
+///
+/// Middleware further up the stack may box this error, or wrap it in an
+/// error of their own, such as a `Buffer`'s `ServiceError`. Use `is_elapsed`
+/// to tell timeouts apart from other failures regardless.
 #[derive(Debug)]
 pub struct Elapsed(pub(super) ());
 
@@ -15,3 +19,43 @@ impl fmt::Display for Elapsed {
 }
 
 impl error::Error for Elapsed {}
+
+/// The request waited too long for the service to become ready.
+#[derive(Debug)]
+pub struct QueueElapsed(pub(super) ());
+
+impl fmt::Display for QueueElapsed {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.pad("request timed out waiting for capacity")
+    }
+}
+
+impl error::Error for QueueElapsed {}
+
+/// Returns `true` if `err` is, or was caused by, an `Elapsed` error.
+///
+/// The chain of `source`s of `err` is searched, so this works through
+/// boxing and wrapping, e.g. in a retry policy or when recording metrics:
+///
+/// ```
+/// # extern crate tower_timeout;
+/// # use tower_timeout::error::is_elapsed;
+/// # fn main() {
+/// # let err: Box<std::error::Error + Send + Sync> = "boom".into();
+/// if is_elapsed(&*err) {
+///     // The request timed out.
+/// }
+/// # }
+/// ```
+pub fn is_elapsed(err: &(error::Error + 'static)) -> bool {
+    let mut next = Some(err);
+
+    while let Some(err) = next {
+        if err.is::<Elapsed>() {
+            return true;
+        }
+        next = err.source();
+    }
+
+    false
+}
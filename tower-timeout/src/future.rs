@@ -2,18 +2,32 @@
 
 use crate::error::{Elapsed, Error};
 use futures::{Async, Future, Poll};
-use tokio_timer::Delay;
+use std::fmt;
+use std::time::{Duration, Instant};
+use tokio_timer::{clock, Delay};
 
 /// `Timeout` response future
 #[derive(Debug)]
 pub struct ResponseFuture<T> {
-    response: T,
-    sleep: Delay,
+    /// `None` if the request timed out before it was sent.
+    response: Option<T>,
+    /// `None` if the request has no timeout.
+    sleep: Option<Delay>,
 }
 
 impl<T> ResponseFuture<T> {
-    pub(crate) fn new(response: T, sleep: Delay) -> Self {
-        ResponseFuture { response, sleep }
+    pub(crate) fn new(response: T, sleep: Option<Delay>) -> Self {
+        ResponseFuture {
+            response: Some(response),
+            sleep,
+        }
+    }
+
+    pub(crate) fn elapsed() -> Self {
+        ResponseFuture {
+            response: None,
+            sleep: None,
+        }
     }
 }
 
@@ -26,16 +40,88 @@ where
     type Error = Error;
 
     fn poll(&mut self) -> Poll<Self::Item, Self::Error> {
+        let response = match self.response {
+            Some(ref mut response) => response,
+            None => return Err(Elapsed(()).into()),
+        };
+
         // First, try polling the future
-        match self.response.poll()? {
+        match response.poll()? {
             Async::Ready(v) => return Ok(Async::Ready(v)),
             Async::NotReady => {}
         }
 
         // Now check the sleep
-        match self.sleep.poll()? {
+        let sleep = match self.sleep {
+            Some(ref mut sleep) => sleep,
+            None => return Ok(Async::NotReady),
+        };
+
+        match sleep.poll()? {
             Async::NotReady => Ok(Async::NotReady),
             Async::Ready(_) => Err(Elapsed(()).into()),
         }
     }
 }
+
+/// `SoftTimeout` response future
+pub struct SoftTimeoutFuture<T, C> {
+    response: T,
+    started: Instant,
+    /// `None` once the callback was called, or if the timer failed.
+    slow: Option<(Delay, C)>,
+}
+
+impl<T, C> SoftTimeoutFuture<T, C> {
+    pub(crate) fn new(response: T, started: Instant, sleep: Delay, on_slow: C) -> Self {
+        SoftTimeoutFuture {
+            response,
+            started,
+            slow: Some((sleep, on_slow)),
+        }
+    }
+}
+
+impl<T, C> Future for SoftTimeoutFuture<T, C>
+where
+    T: Future,
+    C: FnOnce(Duration),
+{
+    type Item = T::Item;
+    type Error = T::Error;
+
+    fn poll(&mut self) -> Poll<Self::Item, Self::Error> {
+        if let Async::Ready(v) = self.response.poll()? {
+            return Ok(Async::Ready(v));
+        }
+
+        let elapsed = match self.slow {
+            Some((ref mut sleep, _)) => match sleep.poll() {
+                Ok(Async::NotReady) => return Ok(Async::NotReady),
+                Ok(Async::Ready(())) => true,
+                // Without a timer, slow requests go unreported.
+                Err(_) => false,
+            },
+            None => return Ok(Async::NotReady),
+        };
+
+        let (_, on_slow) = self.slow.take().expect("slow request callback");
+        if elapsed {
+            on_slow(clock::now() - self.started);
+        }
+
+        Ok(Async::NotReady)
+    }
+}
+
+impl<T, C> fmt::Debug for SoftTimeoutFuture<T, C>
+where
+    T: fmt::Debug,
+{
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_struct("SoftTimeoutFuture")
+            .field("response", &self.response)
+            .field("started", &self.started)
+            .finish()
+    }
+}
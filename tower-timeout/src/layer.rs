@@ -1,18 +1,32 @@
 use crate::{Error, Timeout};
 use never::Never;
 use std::time::Duration;
+use tokio_timer::timer;
 use tower_layer::Layer;
 use tower_service::Service;
 /// Applies a timeout to requests via the supplied inner service.
 #[derive(Debug)]
 pub struct TimeoutLayer {
     timeout: Duration,
+    timer: timer::Handle,
 }
 
 impl TimeoutLayer {
     /// Create a timeout from a duration
     pub fn new(timeout: Duration) -> Self {
-        TimeoutLayer { timeout }
+        TimeoutLayer {
+            timeout,
+            timer: timer::Handle::default(),
+        }
+    }
+
+    /// Time out requests with `timer`, rather than the timer of the runtime
+    /// that polls the responses.
+    ///
+    /// See [`Timeout::with_timer`](struct.Timeout.html#method.with_timer).
+    pub fn with_timer(mut self, timer: timer::Handle) -> Self {
+        self.timer = timer;
+        self
     }
 }
 
@@ -27,6 +41,6 @@ where
     type Service = Timeout<S>;
 
     fn layer(&self, service: S) -> Result<Self::Service, Self::LayerError> {
-        Ok(Timeout::new(service, self.timeout))
+        Ok(Timeout::new(service, self.timeout).with_timer(self.timer.clone()))
     }
 }
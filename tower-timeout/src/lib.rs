@@ -11,20 +11,32 @@ extern crate futures;
 extern crate tokio_timer;
 extern crate tower_layer;
 extern crate tower_service;
+extern crate tower_util;
 
+pub mod deadline;
 pub mod error;
 pub mod future;
 mod layer;
 mod never;
+mod queue;
+mod request;
+mod soft;
+mod total;
 
+pub use crate::deadline::{DeadlineTimeout, DeadlineTimeoutLayer};
 pub use crate::layer::TimeoutLayer;
+pub use crate::queue::{QueueTimeout, QueueTimeoutLayer};
+pub use crate::request::{RequestTimeout, RequestTimeoutLayer};
+pub use crate::soft::{SoftTimeout, SoftTimeoutLayer};
+pub use crate::total::{TotalTimeout, TotalTimeoutLayer};
 
 use crate::error::Error;
 use crate::future::ResponseFuture;
 use futures::Poll;
-use tokio_timer::{clock, Delay};
+use tokio_timer::{clock, timer};
 
 use tower_service::Service;
+use tower_util::{Unready, UnreadyReason};
 
 use std::time::Duration;
 
@@ -33,6 +45,7 @@ use std::time::Duration;
 pub struct Timeout<T> {
     inner: T,
     timeout: Duration,
+    timer: timer::Handle,
 }
 
 // ===== impl Timeout =====
@@ -40,7 +53,21 @@ pub struct Timeout<T> {
 impl<T> Timeout<T> {
     /// Creates a new Timeout
     pub fn new(inner: T, timeout: Duration) -> Self {
-        Timeout { inner, timeout }
+        Timeout {
+            inner,
+            timeout,
+            timer: timer::Handle::default(),
+        }
+    }
+
+    /// Time out requests with `timer`, rather than the timer of the runtime
+    /// that polls the responses.
+    ///
+    /// This allows a stack to be driven by a custom runtime or a nested
+    /// executor that does not set a default timer.
+    pub fn with_timer(mut self, timer: timer::Handle) -> Self {
+        self.timer = timer;
+        self
     }
 }
 
@@ -59,8 +86,18 @@ where
 
     fn call(&mut self, request: Request) -> Self::Future {
         let response = self.inner.call(request);
-        let sleep = Delay::new(clock::now() + self.timeout);
+        let sleep = self.timer.delay(clock::now() + self.timeout);
+
+        ResponseFuture::new(response, Some(sleep))
+    }
+}
 
-        ResponseFuture::new(response, sleep)
+impl<S, Request> Unready<Request> for Timeout<S>
+where
+    S: Unready<Request>,
+    Error: From<S::Error>,
+{
+    fn unready_reason(&self) -> Option<UnreadyReason> {
+        self.inner.unready_reason()
     }
 }
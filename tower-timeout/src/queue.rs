@@ -0,0 +1,163 @@
+use crate::error::{Error, QueueElapsed};
+use futures::future::MapErr;
+use futures::{Async, Future, Poll};
+use never::Never;
+use std::time::Duration;
+use tokio_timer::{clock, timer, Delay};
+use tower_layer::Layer;
+use tower_service::Service;
+use tower_util::{Unready, UnreadyReason};
+
+/// Limits how long a request may wait for the inner service to become ready.
+///
+/// The clock starts once the inner service first reports that it is not
+/// ready, e.g. because a `Buffer` is full or an in-flight limit is reached.
+/// If it has not become ready within the timeout, `poll_ready` fails with
+/// `error::QueueElapsed`, so that the caller gives up rather than dispatching
+/// work whose caller may have moved on already. Once the service is polled
+/// again, the clock starts over.
+///
+/// Unlike `Timeout`, the time spent processing a request once it has been
+/// dispatched is not limited.
+///
+/// This also serves as a readiness watchdog: a wedged inner service, such as
+/// a `Buffer` whose worker has stopped making progress, would otherwise leave
+/// callers waiting in `poll_ready` forever. With a `QueueTimeout`, they get a
+/// `QueueElapsed` error instead, and may fail over to another service.
+#[derive(Debug)]
+pub struct QueueTimeout<T> {
+    inner: T,
+    timeout: Duration,
+    timer: timer::Handle,
+    waiting: Option<Delay>,
+}
+
+/// Limits how long requests may wait for services to become ready.
+#[derive(Debug, Clone)]
+pub struct QueueTimeoutLayer {
+    timeout: Duration,
+    timer: timer::Handle,
+}
+
+// ===== impl QueueTimeout =====
+
+impl<T> QueueTimeout<T> {
+    /// Creates a new `QueueTimeout`
+    pub fn new(inner: T, timeout: Duration) -> Self {
+        QueueTimeout {
+            inner,
+            timeout,
+            timer: timer::Handle::default(),
+            waiting: None,
+        }
+    }
+
+    /// Time out waiting for readiness with `timer`, rather than the timer of
+    /// the runtime that polls the service.
+    ///
+    /// See [`Timeout::with_timer`](struct.Timeout.html#method.with_timer).
+    pub fn with_timer(mut self, timer: timer::Handle) -> Self {
+        self.timer = timer;
+        self
+    }
+
+    /// Get a reference to the inner service
+    pub fn get_ref(&self) -> &T {
+        &self.inner
+    }
+
+    /// Get a mutable reference to the inner service
+    pub fn get_mut(&mut self) -> &mut T {
+        &mut self.inner
+    }
+
+    /// Consume `self`, returning the inner service
+    pub fn into_inner(self) -> T {
+        self.inner
+    }
+}
+
+impl<S, Request> Service<Request> for QueueTimeout<S>
+where
+    S: Service<Request>,
+    Error: From<S::Error>,
+{
+    type Response = S::Response;
+    type Error = Error;
+    type Future = MapErr<S::Future, fn(S::Error) -> Error>;
+
+    fn poll_ready(&mut self) -> Poll<(), Self::Error> {
+        match self.inner.poll_ready() {
+            Ok(Async::NotReady) => {}
+            ready => {
+                self.waiting = None;
+                return ready.map_err(Into::into);
+            }
+        }
+
+        let timeout = self.timeout;
+        let timer = &self.timer;
+        let elapsed = self
+            .waiting
+            .get_or_insert_with(|| timer.delay(clock::now() + timeout))
+            .poll()?;
+
+        if elapsed.is_ready() {
+            self.waiting = None;
+            return Err(QueueElapsed(()).into());
+        }
+
+        Ok(Async::NotReady)
+    }
+
+    fn call(&mut self, request: Request) -> Self::Future {
+        let map_err: fn(S::Error) -> Error = Error::from;
+        self.inner.call(request).map_err(map_err)
+    }
+}
+
+impl<S, Request> Unready<Request> for QueueTimeout<S>
+where
+    S: Unready<Request>,
+    Error: From<S::Error>,
+{
+    fn unready_reason(&self) -> Option<UnreadyReason> {
+        self.inner.unready_reason()
+    }
+}
+
+// ===== impl QueueTimeoutLayer =====
+
+impl QueueTimeoutLayer {
+    /// Create a queue timeout from a duration
+    pub fn new(timeout: Duration) -> Self {
+        QueueTimeoutLayer {
+            timeout,
+            timer: timer::Handle::default(),
+        }
+    }
+
+    /// Time out waiting for readiness with `timer`, rather than the timer of
+    /// the runtime that polls the services.
+    ///
+    /// See [`QueueTimeout::with_timer`](struct.QueueTimeout.html#method.with_timer).
+    pub fn with_timer(mut self, timer: timer::Handle) -> Self {
+        self.timer = timer;
+        self
+    }
+}
+
+impl<S, Request> Layer<S, Request> for QueueTimeoutLayer
+where
+    S: Service<Request>,
+    Error: From<S::Error>,
+{
+    type Response = S::Response;
+    type Error = Error;
+    type LayerError = Never;
+    type Service = QueueTimeout<S>;
+
+    fn layer(&self, service: S) -> Result<Self::Service, Self::LayerError> {
+        Ok(QueueTimeout::new(service, self.timeout).with_timer(self.timer.clone()))
+    }
+}
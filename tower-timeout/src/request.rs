@@ -0,0 +1,129 @@
+use crate::error::Error;
+use crate::future::ResponseFuture;
+use futures::Poll;
+use never::Never;
+use std::time::Duration;
+use tokio_timer::{clock, timer};
+use tower_layer::Layer;
+use tower_service::Service;
+
+/// Applies a timeout computed from each request.
+///
+/// `timeout` is called with every request before it is dispatched, and the
+/// response fails with `error::Elapsed` if it does not complete within the
+/// returned duration, e.g. so that requests can carry their own deadlines.
+/// Requests for which it returns `None` are not timed out.
+#[derive(Debug, Clone)]
+pub struct RequestTimeout<T, F> {
+    inner: T,
+    timeout: F,
+    timer: timer::Handle,
+}
+
+/// Applies timeouts computed from each request via the supplied inner
+/// service.
+#[derive(Debug, Clone)]
+pub struct RequestTimeoutLayer<F> {
+    timeout: F,
+    timer: timer::Handle,
+}
+
+// ===== impl RequestTimeout =====
+
+impl<T, F> RequestTimeout<T, F> {
+    /// Creates a new `RequestTimeout`
+    pub fn new(inner: T, timeout: F) -> Self {
+        RequestTimeout {
+            inner,
+            timeout,
+            timer: timer::Handle::default(),
+        }
+    }
+
+    /// Time out requests with `timer`, rather than the timer of the runtime that
+    /// polls the responses.
+    ///
+    /// See [`Timeout::with_timer`](struct.Timeout.html#method.with_timer).
+    pub fn with_timer(mut self, timer: timer::Handle) -> Self {
+        self.timer = timer;
+        self
+    }
+
+    /// Get a reference to the inner service
+    pub fn get_ref(&self) -> &T {
+        &self.inner
+    }
+
+    /// Get a mutable reference to the inner service
+    pub fn get_mut(&mut self) -> &mut T {
+        &mut self.inner
+    }
+
+    /// Consume `self`, returning the inner service
+    pub fn into_inner(self) -> T {
+        self.inner
+    }
+}
+
+impl<S, F, Request> Service<Request> for RequestTimeout<S, F>
+where
+    S: Service<Request>,
+    F: Fn(&Request) -> Option<Duration>,
+    Error: From<S::Error>,
+{
+    type Response = S::Response;
+    type Error = Error;
+    type Future = ResponseFuture<S::Future>;
+
+    fn poll_ready(&mut self) -> Poll<(), Self::Error> {
+        self.inner.poll_ready().map_err(Into::into)
+    }
+
+    fn call(&mut self, request: Request) -> Self::Future {
+        let timer = &self.timer;
+        let sleep = (self.timeout)(&request).map(|timeout| timer.delay(clock::now() + timeout));
+        let response = self.inner.call(request);
+
+        ResponseFuture::new(response, sleep)
+    }
+}
+
+// ===== impl RequestTimeoutLayer =====
+
+impl<F> RequestTimeoutLayer<F> {
+    /// Create a layer timing out requests after the duration returned by
+    /// `timeout`
+    pub fn new(timeout: F) -> Self {
+        RequestTimeoutLayer {
+            timeout,
+            timer: timer::Handle::default(),
+        }
+    }
+
+    /// Time out requests with `timer`, rather than the timer of the runtime that
+    /// polls the responses.
+    ///
+    /// See [`RequestTimeout::with_timer`](struct.RequestTimeout.html#method.with_timer).
+    pub fn with_timer(mut self, timer: timer::Handle) -> Self {
+        self.timer = timer;
+        self
+    }
+}
+
+impl<S, F, Request> Layer<S, Request> for RequestTimeoutLayer<F>
+where
+    S: Service<Request>,
+    F: Fn(&Request) -> Option<Duration> + Clone,
+    Error: From<S::Error>,
+{
+    type Response = S::Response;
+    type Error = Error;
+    type LayerError = Never;
+    type Service = RequestTimeout<S, F>;
+
+    fn layer(&self, service: S) -> Result<Self::Service, Self::LayerError> {
+        let service =
+            RequestTimeout::new(service, self.timeout.clone()).with_timer(self.timer.clone());
+        Ok(service)
+    }
+}
@@ -0,0 +1,152 @@
+use crate::future::SoftTimeoutFuture;
+use futures::Poll;
+use never::Never;
+use std::time::Duration;
+use tokio_timer::{clock, timer};
+use tower_layer::Layer;
+use tower_service::Service;
+
+/// Reports requests that take longer than a threshold, without cancelling
+/// them.
+///
+/// `on_slow` is called with every request before it is dispatched, and
+/// returns a callback to run if the request is still in progress once
+/// `threshold` has elapsed. The callback is passed the time elapsed since the
+/// request was dispatched, and typically captures a summary of the request,
+/// so that slow requests can be logged or counted. The response is awaited
+/// as usual either way.
+///
+/// # Example
+///
+/// ```
+/// # extern crate tower_timeout;
+/// # use std::time::Duration;
+/// # use tower_timeout::SoftTimeoutLayer;
+/// # fn main() {
+/// let layer = SoftTimeoutLayer::new(Duration::from_secs(1), |req: &String| {
+///     let summary = req.clone();
+///     move |elapsed: Duration| println!("{} is slow ({:?})", summary, elapsed)
+/// });
+/// # drop(layer);
+/// # }
+/// ```
+#[derive(Debug, Clone)]
+pub struct SoftTimeout<T, F> {
+    inner: T,
+    threshold: Duration,
+    on_slow: F,
+    timer: timer::Handle,
+}
+
+/// Applies `SoftTimeout` to services, each reporting its slow requests with
+/// callbacks returned by the same `on_slow` hook.
+#[derive(Debug, Clone)]
+pub struct SoftTimeoutLayer<F> {
+    threshold: Duration,
+    on_slow: F,
+    timer: timer::Handle,
+}
+
+// ===== impl SoftTimeout =====
+
+impl<T, F> SoftTimeout<T, F> {
+    /// Creates a new `SoftTimeout`
+    pub fn new(inner: T, threshold: Duration, on_slow: F) -> Self {
+        SoftTimeout {
+            inner,
+            threshold,
+            on_slow,
+            timer: timer::Handle::default(),
+        }
+    }
+
+    /// Report slow requests with `timer`, rather than the timer of the runtime that
+    /// polls the responses.
+    ///
+    /// See [`Timeout::with_timer`](struct.Timeout.html#method.with_timer).
+    pub fn with_timer(mut self, timer: timer::Handle) -> Self {
+        self.timer = timer;
+        self
+    }
+
+    /// Get a reference to the inner service
+    pub fn get_ref(&self) -> &T {
+        &self.inner
+    }
+
+    /// Get a mutable reference to the inner service
+    pub fn get_mut(&mut self) -> &mut T {
+        &mut self.inner
+    }
+
+    /// Consume `self`, returning the inner service
+    pub fn into_inner(self) -> T {
+        self.inner
+    }
+}
+
+impl<S, F, C, Request> Service<Request> for SoftTimeout<S, F>
+where
+    S: Service<Request>,
+    F: Fn(&Request) -> C,
+    C: FnOnce(Duration),
+{
+    type Response = S::Response;
+    type Error = S::Error;
+    type Future = SoftTimeoutFuture<S::Future, C>;
+
+    fn poll_ready(&mut self) -> Poll<(), Self::Error> {
+        self.inner.poll_ready()
+    }
+
+    fn call(&mut self, request: Request) -> Self::Future {
+        let on_slow = (self.on_slow)(&request);
+        let response = self.inner.call(request);
+
+        let started = clock::now();
+        let sleep = self.timer.delay(started + self.threshold);
+
+        SoftTimeoutFuture::new(response, started, sleep, on_slow)
+    }
+}
+
+// ===== impl SoftTimeoutLayer =====
+
+impl<F> SoftTimeoutLayer<F> {
+    /// Create a layer reporting requests slower than `threshold` with the
+    /// callbacks returned by `on_slow`
+    pub fn new(threshold: Duration, on_slow: F) -> Self {
+        SoftTimeoutLayer {
+            threshold,
+            on_slow,
+            timer: timer::Handle::default(),
+        }
+    }
+
+    /// Report slow requests with `timer`, rather than the timer of the runtime that
+    /// polls the responses.
+    ///
+    /// See [`SoftTimeout::with_timer`](struct.SoftTimeout.html#method.with_timer).
+    pub fn with_timer(mut self, timer: timer::Handle) -> Self {
+        self.timer = timer;
+        self
+    }
+}
+
+impl<S, F, C, Request> Layer<S, Request> for SoftTimeoutLayer<F>
+where
+    S: Service<Request>,
+    F: Fn(&Request) -> C + Clone,
+    C: FnOnce(Duration),
+{
+    type Response = S::Response;
+    type Error = S::Error;
+    type LayerError = Never;
+    type Service = SoftTimeout<S, F>;
+
+    fn layer(&self, service: S) -> Result<Self::Service, Self::LayerError> {
+        let service = SoftTimeout::new(service, self.threshold, self.on_slow.clone())
+            .with_timer(self.timer.clone());
+        Ok(service)
+    }
+}
@@ -0,0 +1,166 @@
+use crate::error::{Elapsed, Error};
+use crate::future::ResponseFuture;
+use futures::{Async, Future, Poll};
+use never::Never;
+use std::time::Duration;
+use tokio_timer::{clock, timer, Delay};
+use tower_layer::Layer;
+use tower_service::Service;
+
+/// Limits the total time taken by requests, including the time spent waiting
+/// for the inner service to become ready.
+///
+/// `Timeout` starts the clock once a request is dispatched, so time spent in
+/// `poll_ready`, e.g. waiting for a rate limit or for capacity in a `Buffer`,
+/// is not bounded. `TotalTimeout` starts the clock as soon as the caller
+/// polls for readiness to send a request, and the same timeout keeps running
+/// once the request is dispatched. If it elapses before the service is ready,
+/// `poll_ready` fails with `error::Elapsed`; if it elapses afterwards, the
+/// response does.
+///
+/// Requests dispatched without polling for readiness first are only timed
+/// from the moment they are dispatched.
+#[derive(Debug)]
+pub struct TotalTimeout<T> {
+    inner: T,
+    timeout: Duration,
+    timer: timer::Handle,
+    /// The clock of the next request, started by `poll_ready`.
+    sleep: Option<Delay>,
+}
+
+/// Limits the total time taken by requests, including the time spent waiting
+/// for services to become ready.
+#[derive(Debug, Clone)]
+pub struct TotalTimeoutLayer {
+    timeout: Duration,
+    timer: timer::Handle,
+}
+
+// ===== impl TotalTimeout =====
+
+impl<T> TotalTimeout<T> {
+    /// Creates a new `TotalTimeout`
+    pub fn new(inner: T, timeout: Duration) -> Self {
+        TotalTimeout {
+            inner,
+            timeout,
+            timer: timer::Handle::default(),
+            sleep: None,
+        }
+    }
+
+    /// Time out requests with `timer`, rather than the timer of the runtime
+    /// that polls the service and the responses.
+    ///
+    /// See [`Timeout::with_timer`](struct.Timeout.html#method.with_timer).
+    pub fn with_timer(mut self, timer: timer::Handle) -> Self {
+        self.timer = timer;
+        self
+    }
+
+    /// Get a reference to the inner service
+    pub fn get_ref(&self) -> &T {
+        &self.inner
+    }
+
+    /// Get a mutable reference to the inner service
+    pub fn get_mut(&mut self) -> &mut T {
+        &mut self.inner
+    }
+
+    /// Consume `self`, returning the inner service
+    pub fn into_inner(self) -> T {
+        self.inner
+    }
+}
+
+impl<T: Clone> Clone for TotalTimeout<T> {
+    fn clone(&self) -> Self {
+        // The clock of a request being sent through `self` is not shared.
+        TotalTimeout::new(self.inner.clone(), self.timeout).with_timer(self.timer.clone())
+    }
+}
+
+impl<S, Request> Service<Request> for TotalTimeout<S>
+where
+    S: Service<Request>,
+    Error: From<S::Error>,
+{
+    type Response = S::Response;
+    type Error = Error;
+    type Future = ResponseFuture<S::Future>;
+
+    fn poll_ready(&mut self) -> Poll<(), Self::Error> {
+        if self.sleep.is_none() {
+            self.sleep = Some(self.timer.delay(clock::now() + self.timeout));
+        }
+
+        match self.inner.poll_ready() {
+            Ok(Async::Ready(())) => return Ok(Async::Ready(())),
+            Ok(Async::NotReady) => {}
+            Err(e) => {
+                // The next request gets a clock of its own.
+                self.sleep = None;
+                return Err(e.into());
+            }
+        }
+
+        let elapsed = self.sleep.as_mut().expect("total timeout").poll()?;
+        if elapsed.is_ready() {
+            self.sleep = None;
+            return Err(Elapsed(()).into());
+        }
+
+        Ok(Async::NotReady)
+    }
+
+    fn call(&mut self, request: Request) -> Self::Future {
+        let response = self.inner.call(request);
+
+        let timeout = self.timeout;
+        let timer = &self.timer;
+        let sleep = self
+            .sleep
+            .take()
+            .unwrap_or_else(|| timer.delay(clock::now() + timeout));
+
+        ResponseFuture::new(response, Some(sleep))
+    }
+}
+
+// ===== impl TotalTimeoutLayer =====
+
+impl TotalTimeoutLayer {
+    /// Create a total timeout from a duration
+    pub fn new(timeout: Duration) -> Self {
+        TotalTimeoutLayer {
+            timeout,
+            timer: timer::Handle::default(),
+        }
+    }
+
+    /// Time out requests with `timer`, rather than the timer of the runtime
+    /// that polls the services and the responses.
+    ///
+    /// See [`TotalTimeout::with_timer`](struct.TotalTimeout.html#method.with_timer).
+    pub fn with_timer(mut self, timer: timer::Handle) -> Self {
+        self.timer = timer;
+        self
+    }
+}
+
+impl<S, Request> Layer<S, Request> for TotalTimeoutLayer
+where
+    S: Service<Request>,
+    Error: From<S::Error>,
+{
+    type Response = S::Response;
+    type Error = Error;
+    type LayerError = Never;
+    type Service = TotalTimeout<S>;
+
+    fn layer(&self, service: S) -> Result<Self::Service, Self::LayerError> {
+        Ok(TotalTimeout::new(service, self.timeout).with_timer(self.timer.clone()))
+    }
+}
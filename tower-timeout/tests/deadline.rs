@@ -0,0 +1,67 @@
+extern crate futures;
+extern crate tokio;
+extern crate tower_mock;
+extern crate tower_service;
+extern crate tower_timeout;
+
+use std::time::{Duration, Instant};
+use tower_service::Service;
+use tower_timeout::deadline::{Deadline, HasDeadline, WithDeadline};
+use tower_timeout::error::Elapsed;
+use tower_timeout::DeadlineTimeout;
+
+type Req = WithDeadline<&'static str>;
+type Mock = tower_mock::Mock<Req, &'static str>;
+
+#[test]
+fn shrinks_timeout_to_deadline() {
+    let mut rt = tokio::runtime::current_thread::Runtime::new().unwrap();
+    let (service, mut handle) = Mock::new();
+    let mut service = DeadlineTimeout::new(service, Some(Duration::from_secs(60)));
+
+    let deadline = Deadline::after(Duration::from_millis(20));
+    let started = Instant::now();
+
+    assert!(service.poll_ready().unwrap().is_ready());
+    let response = service.call(WithDeadline::new("hello", Some(deadline)));
+
+    // The deadline is passed on to the inner service.
+    let request = handle.next_request().unwrap();
+    assert_eq!(request.deadline(), Some(deadline));
+
+    let err = rt.block_on(response).unwrap_err();
+    assert!(err.is::<Elapsed>());
+    assert!(started.elapsed() < Duration::from_secs(60));
+    drop(request);
+}
+
+#[test]
+fn elapsed_deadline_is_not_sent() {
+    let mut rt = tokio::runtime::current_thread::Runtime::new().unwrap();
+    let (service, mut handle) = Mock::new();
+    let mut service = DeadlineTimeout::new(service, None);
+
+    let deadline = Deadline::at(Instant::now());
+
+    assert!(service.poll_ready().unwrap().is_ready());
+    let response = service.call(WithDeadline::new("hello", Some(deadline)));
+
+    let err = rt.block_on(response).unwrap_err();
+    assert!(err.is::<Elapsed>());
+
+    rt.block_on(futures::future::lazy(|| {
+        assert!(handle.poll_request().unwrap().is_not_ready());
+        Ok::<_, ()>(())
+    }))
+    .unwrap();
+}
+
+#[test]
+fn nested_requests_keep_deadline() {
+    let deadline = Deadline::after(Duration::from_secs(1));
+    let request = WithDeadline::new("hello", Some(deadline));
+
+    let nested = request.map(|req| req.len());
+    assert_eq!(*nested.get_ref(), 5);
+    assert_eq!(nested.deadline(), Some(deadline));
+}
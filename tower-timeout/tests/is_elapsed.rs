@@ -0,0 +1,57 @@
+extern crate futures;
+extern crate tokio;
+extern crate tower_mock;
+extern crate tower_service;
+extern crate tower_timeout;
+
+use std::time::Duration;
+use std::{error, fmt};
+use tower_service::Service;
+use tower_timeout::error::is_elapsed;
+use tower_timeout::Timeout;
+
+type Error = Box<error::Error + Send + Sync>;
+type Mock = tower_mock::Mock<&'static str, &'static str>;
+
+/// Wraps an error, like a `Buffer` does.
+#[derive(Debug)]
+struct Wrapped(Error);
+
+impl fmt::Display for Wrapped {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "wrapped: {}", self.0)
+    }
+}
+
+impl error::Error for Wrapped {
+    fn source(&self) -> Option<&(error::Error + 'static)> {
+        Some(&*self.0)
+    }
+}
+
+#[test]
+fn elapsed_survives_wrapping() {
+    let mut rt = tokio::runtime::current_thread::Runtime::new().unwrap();
+    let (service, mut handle) = Mock::new();
+    let mut service = Timeout::new(service, Duration::from_millis(20));
+
+    assert!(service.poll_ready().unwrap().is_ready());
+    let response = service.call("hello");
+    let request = handle.next_request().unwrap();
+
+    let err = rt.block_on(response).unwrap_err();
+    assert!(is_elapsed(&*err));
+
+    let wrapped: Error = Box::new(Wrapped(err));
+    assert!(is_elapsed(&*wrapped));
+    drop(request);
+}
+
+#[test]
+fn other_errors_are_not_elapsed() {
+    let err: Error = "boom".into();
+    assert!(!is_elapsed(&*err));
+
+    let wrapped: Error = Box::new(Wrapped(err));
+    assert!(!is_elapsed(&*wrapped));
+}
@@ -0,0 +1,40 @@
+extern crate futures;
+extern crate tokio_mock_task;
+extern crate tower_mock;
+extern crate tower_service;
+extern crate tower_timeout;
+
+use futures::Future;
+use std::time::Duration;
+use tokio_mock_task::MockTask;
+use tower_mock::clock::MockClock;
+use tower_service::Service;
+use tower_timeout::error::Elapsed;
+use tower_timeout::Timeout;
+
+type Mock = tower_mock::Mock<&'static str, &'static str>;
+
+#[test]
+fn times_out_without_waiting() {
+    let mut task = MockTask::new();
+    let (service, mut handle) = Mock::new();
+    let mut service = Timeout::new(service, Duration::from_secs(60));
+
+    MockClock::new().enter(|clock| {
+        let mut response = task.enter(|| {
+            assert!(service.poll_ready().unwrap().is_ready());
+            service.call("hello")
+        });
+        let _request = handle.next_request().unwrap();
+
+        assert!(task.enter(|| response.poll()).unwrap().is_not_ready());
+
+        clock.advance(Duration::from_secs(59));
+        assert!(task.enter(|| response.poll()).unwrap().is_not_ready());
+
+        clock.advance(Duration::from_secs(1));
+        assert!(task.is_notified());
+        let err = task.enter(|| response.poll()).unwrap_err();
+        assert!(err.is::<Elapsed>());
+    });
+}
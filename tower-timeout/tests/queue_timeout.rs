@@ -0,0 +1,36 @@
+extern crate futures;
+extern crate tokio;
+extern crate tower_mock;
+extern crate tower_service;
+extern crate tower_timeout;
+
+use futures::future;
+use std::time::Duration;
+use tower_service::Service;
+use tower_timeout::error::QueueElapsed;
+use tower_timeout::QueueTimeout;
+
+type Mock = tower_mock::Mock<&'static str, &'static str>;
+
+#[test]
+fn fails_when_queued_too_long() {
+    let mut rt = tokio::runtime::current_thread::Runtime::new().unwrap();
+    let (service, mut handle) = Mock::new();
+    let mut service = QueueTimeout::new(service, Duration::from_millis(20));
+
+    handle.allow(0);
+
+    let err = rt
+        .block_on(future::poll_fn(|| service.poll_ready()))
+        .unwrap_err();
+    assert!(err.is::<QueueElapsed>());
+
+    // Once capacity is available, requests go through again.
+    handle.allow(1);
+    let ready = rt.block_on(future::lazy(|| service.poll_ready())).unwrap();
+    assert!(ready.is_ready());
+
+    let response = service.call("hello");
+    handle.next_request().unwrap().respond("world");
+    assert_eq!(rt.block_on(response).unwrap(), "world");
+}
@@ -0,0 +1,48 @@
+extern crate futures;
+extern crate tokio;
+extern crate tower_mock;
+extern crate tower_service;
+extern crate tower_timeout;
+
+use std::time::Duration;
+use tower_service::Service;
+use tower_timeout::error::Elapsed;
+use tower_timeout::RequestTimeout;
+
+type Mock = tower_mock::Mock<&'static str, &'static str>;
+
+fn timeout(req: &&'static str) -> Option<Duration> {
+    match *req {
+        "slow" => Some(Duration::from_millis(20)),
+        _ => None,
+    }
+}
+
+#[test]
+fn timeout_from_request() {
+    let mut rt = tokio::runtime::current_thread::Runtime::new().unwrap();
+    let (service, mut handle) = Mock::new();
+    let mut service = RequestTimeout::new(service, timeout);
+
+    // Requests with a timeout fail once it elapses...
+    assert!(service.poll_ready().unwrap().is_ready());
+    let response = service.call("slow");
+    let request = handle.next_request().unwrap();
+
+    let err = rt.block_on(response).unwrap_err();
+    assert!(err.is::<Elapsed>());
+    drop(request);
+
+    // ...while those without one wait for the response.
+    assert!(service.poll_ready().unwrap().is_ready());
+    let response = service.call("hello");
+    let request = handle.next_request().unwrap();
+
+    let responder = std::thread::spawn(move || {
+        std::thread::sleep(Duration::from_millis(40));
+        request.respond("world");
+    });
+
+    assert_eq!(rt.block_on(response).unwrap(), "world");
+    responder.join().unwrap();
+}
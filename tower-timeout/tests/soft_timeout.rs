@@ -0,0 +1,60 @@
+extern crate futures;
+extern crate tokio_mock_task;
+extern crate tower_mock;
+extern crate tower_service;
+extern crate tower_timeout;
+
+use futures::Future;
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+use tokio_mock_task::MockTask;
+use tower_mock::clock::MockClock;
+use tower_service::Service;
+use tower_timeout::SoftTimeout;
+
+type Mock = tower_mock::Mock<&'static str, &'static str>;
+
+#[test]
+fn reports_slow_requests() {
+    let mut task = MockTask::new();
+    let (service, mut handle) = Mock::new();
+
+    let slow = Arc::new(Mutex::new(Vec::new()));
+    let reported = slow.clone();
+    let mut service = SoftTimeout::new(
+        service,
+        Duration::from_secs(10),
+        move |req: &&'static str| {
+            let req = *req;
+            let reported = reported.clone();
+            move |elapsed: Duration| reported.lock().unwrap().push((req, elapsed))
+        },
+    );
+
+    MockClock::new().enter(|clock| {
+        // Slow requests are reported once, and still complete...
+        assert!(service.poll_ready().unwrap().is_ready());
+        let mut response = service.call("slow");
+        let request = handle.next_request().unwrap();
+        assert!(task.enter(|| response.poll()).unwrap().is_not_ready());
+
+        clock.advance(Duration::from_secs(10));
+        assert!(task.is_notified());
+        assert!(task.enter(|| response.poll()).unwrap().is_not_ready());
+        assert_eq!(*slow.lock().unwrap(), [("slow", Duration::from_secs(10))]);
+
+        clock.advance(Duration::from_secs(10));
+        request.respond("world");
+        assert_eq!(task.enter(|| response.poll()).unwrap(), "world".into());
+        assert_eq!(slow.lock().unwrap().len(), 1);
+
+        // ...while fast ones are not.
+        assert!(service.poll_ready().unwrap().is_ready());
+        let mut response = service.call("fast");
+        handle.next_request().unwrap().respond("world");
+
+        assert_eq!(task.enter(|| response.poll()).unwrap(), "world".into());
+        clock.advance(Duration::from_secs(10));
+        assert_eq!(slow.lock().unwrap().len(), 1);
+    });
+}
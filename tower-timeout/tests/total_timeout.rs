@@ -0,0 +1,88 @@
+extern crate futures;
+extern crate tokio;
+extern crate tokio_mock_task;
+extern crate tower_mock;
+extern crate tower_service;
+extern crate tower_timeout;
+
+use futures::{future, Future};
+use std::time::Duration;
+use tokio_mock_task::MockTask;
+use tower_mock::clock::MockClock;
+use tower_service::Service;
+use tower_timeout::error::Elapsed;
+use tower_timeout::TotalTimeout;
+
+type Mock = tower_mock::Mock<&'static str, &'static str>;
+
+#[test]
+fn includes_time_waiting_for_readiness() {
+    let mut rt = tokio::runtime::current_thread::Runtime::new().unwrap();
+    let (service, mut handle) = Mock::new();
+    let mut service = TotalTimeout::new(service, Duration::from_millis(50));
+
+    // The clock starts while the service is not ready...
+    handle.allow(0);
+    let ready = rt.block_on(future::lazy(|| service.poll_ready())).unwrap();
+    assert!(ready.is_not_ready());
+
+    std::thread::sleep(Duration::from_millis(30));
+
+    // ...and keeps running once the request is dispatched.
+    handle.allow(1);
+    let ready = rt.block_on(future::lazy(|| service.poll_ready())).unwrap();
+    assert!(ready.is_ready());
+
+    let response = service.call("hello");
+    let request = handle.next_request().unwrap();
+
+    let responder = std::thread::spawn(move || {
+        std::thread::sleep(Duration::from_millis(40));
+        request.respond("world");
+    });
+
+    let err = rt.block_on(response).unwrap_err();
+    assert!(err.is::<Elapsed>());
+    responder.join().unwrap();
+}
+
+#[test]
+fn fails_when_never_ready() {
+    let mut rt = tokio::runtime::current_thread::Runtime::new().unwrap();
+    let (service, mut handle) = Mock::new();
+    let mut service = TotalTimeout::new(service, Duration::from_millis(20));
+
+    handle.allow(0);
+
+    let err = rt
+        .block_on(future::poll_fn(|| service.poll_ready()))
+        .unwrap_err();
+    assert!(err.is::<Elapsed>());
+}
+
+#[test]
+fn restarts_clock_after_readiness_error() {
+    let mut task = MockTask::new();
+    let (service, mut handle) = Mock::new();
+    let mut service = TotalTimeout::new(service, Duration::from_secs(10));
+
+    MockClock::new().enter(|clock| {
+        handle.allow(0);
+        assert!(task.enter(|| service.poll_ready()).unwrap().is_not_ready());
+        clock.advance(Duration::from_secs(8));
+
+        handle.error("boom");
+        assert!(task.enter(|| service.poll_ready()).is_err());
+
+        // The failed attempt's clock is not carried over to the next request.
+        handle.allow(1);
+        let mut response = task.enter(|| {
+            assert!(service.poll_ready().unwrap().is_ready());
+            service.call("hello")
+        });
+        let _request = handle.next_request().unwrap();
+
+        clock.advance(Duration::from_secs(5));
+        assert!(task.enter(|| response.poll()).unwrap().is_not_ready());
+    });
+}
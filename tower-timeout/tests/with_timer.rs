@@ -0,0 +1,93 @@
+extern crate futures;
+extern crate tokio_timer;
+extern crate tower_mock;
+extern crate tower_service;
+extern crate tower_timeout;
+
+use futures::{future, Future};
+use std::sync::mpsc;
+use std::thread;
+use std::time::Duration;
+use tokio_timer::{timer, Timer};
+use tower_service::Service;
+use tower_timeout::error::{Elapsed, QueueElapsed};
+use tower_timeout::{QueueTimeout, RequestTimeout, Timeout, TotalTimeout};
+
+type Mock = tower_mock::Mock<&'static str, &'static str>;
+
+/// A timer driven by its own thread, while services and responses are polled
+/// outside of any runtime.
+fn spawn_timer() -> timer::Handle {
+    let (tx, rx) = mpsc::channel();
+    thread::spawn(move || {
+        let mut timer = Timer::default();
+        tx.send(timer.handle()).unwrap();
+        loop {
+            timer.turn(None).unwrap();
+        }
+    });
+    rx.recv().unwrap()
+}
+
+#[test]
+fn times_out_with_given_timer() {
+    let timer = spawn_timer();
+
+    let (service, mut handle) = Mock::new();
+    let mut service = Timeout::new(service, Duration::from_millis(20)).with_timer(timer);
+
+    let response = future::lazy(|| {
+        assert!(service.poll_ready().unwrap().is_ready());
+        Ok::<_, ()>(service.call("hello"))
+    })
+    .wait()
+    .unwrap();
+    let _request = handle.next_request().unwrap();
+
+    let err = response.wait().unwrap_err();
+    assert!(err.is::<Elapsed>());
+}
+
+#[test]
+fn queue_times_out_with_given_timer() {
+    let timer = spawn_timer();
+
+    let (service, mut handle) = Mock::new();
+    handle.allow(0);
+    let mut service = QueueTimeout::new(service, Duration::from_millis(20)).with_timer(timer);
+
+    let err = future::poll_fn(|| service.poll_ready()).wait().unwrap_err();
+    assert!(err.is::<QueueElapsed>());
+}
+
+#[test]
+fn total_times_out_with_given_timer() {
+    let timer = spawn_timer();
+
+    let (service, mut handle) = Mock::new();
+    handle.allow(0);
+    let mut service = TotalTimeout::new(service, Duration::from_millis(20)).with_timer(timer);
+
+    let err = future::poll_fn(|| service.poll_ready()).wait().unwrap_err();
+    assert!(err.is::<Elapsed>());
+}
+
+#[test]
+fn request_times_out_with_given_timer() {
+    let timer = spawn_timer();
+
+    let (service, mut handle) = Mock::new();
+    let timeout = |_: &&'static str| Some(Duration::from_millis(20));
+    let mut service = RequestTimeout::new(service, timeout).with_timer(timer);
+
+    let response = future::lazy(|| {
+        assert!(service.poll_ready().unwrap().is_ready());
+        Ok::<_, ()>(service.call("hello"))
+    })
+    .wait()
+    .unwrap();
+    let _request = handle.next_request().unwrap();
+
+    let err = response.wait().unwrap_err();
+    assert!(err.is::<Elapsed>());
+}
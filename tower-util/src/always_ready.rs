@@ -0,0 +1,70 @@
+use crate::{PollReadyN, Unready};
+use futures::{Async, Poll};
+use tower_service::Service;
+
+/// Wraps a service whose `poll_ready` is trivially ready, so that it is never
+/// polled for readiness.
+///
+/// Pure services, such as those transforming requests without any shared
+/// resource, are always ready. Wrapping them in `AlwaysReady` lets callers
+/// that fan out to many of them skip polling each one: `poll_ready` returns
+/// `Ready` without calling into the inner service, and `poll_ready_n`
+/// reserves every requested call at once.
+///
+/// The inner service is still required to accept every call. Wrapping a
+/// service that may not be ready breaks its backpressure, and it may panic
+/// when called.
+#[derive(Clone, Copy, Debug)]
+pub struct AlwaysReady<S> {
+    inner: S,
+}
+
+impl<S> AlwaysReady<S> {
+    /// Create a new `AlwaysReady`, asserting that `inner` is always ready.
+    pub fn new(inner: S) -> Self {
+        AlwaysReady { inner }
+    }
+
+    /// Get a reference to the inner service
+    pub fn get_ref(&self) -> &S {
+        &self.inner
+    }
+
+    /// Get a mutable reference to the inner service
+    pub fn get_mut(&mut self) -> &mut S {
+        &mut self.inner
+    }
+
+    /// Consume `self`, returning the inner service
+    pub fn into_inner(self) -> S {
+        self.inner
+    }
+}
+
+impl<S, Request> Service<Request> for AlwaysReady<S>
+where
+    S: Service<Request>,
+{
+    type Response = S::Response;
+    type Error = S::Error;
+    type Future = S::Future;
+
+    fn poll_ready(&mut self) -> Poll<(), Self::Error> {
+        Ok(Async::Ready(()))
+    }
+
+    fn call(&mut self, request: Request) -> Self::Future {
+        self.inner.call(request)
+    }
+}
+
+impl<S, Request> PollReadyN<Request> for AlwaysReady<S>
+where
+    S: Service<Request>,
+{
+    fn poll_ready_n(&mut self, n: usize) -> Poll<usize, Self::Error> {
+        Ok(Async::Ready(n))
+    }
+}
+
+impl<S, Request> Unready<Request> for AlwaysReady<S> where S: Service<Request> {}
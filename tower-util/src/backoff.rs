@@ -0,0 +1,206 @@
+//! Exponential backoff with jitter.
+//!
+//! Middleware that tries something again after a failure, such as retrying
+//! a request or reconnecting to a backend, should wait longer after every
+//! consecutive failure. `Backoff` computes these delays. Adding jitter to the
+//! delays keeps clients that failed at the same time from trying again in
+//! lockstep.
+//!
+//! The jitter strategies are those described in "Exponential Backoff And
+//! Jitter" on the AWS Architecture Blog.
+//!
+//! With the `timer` feature, `Delayed` waits out a delay before handing back
+//! a value, e.g. the state to try again with.
+
+#[cfg(feature = "timer")]
+use futures::{Async, Future, Poll};
+use rand::Rng;
+use std::cmp;
+#[cfg(feature = "timer")]
+use std::fmt;
+use std::time::Duration;
+#[cfg(feature = "timer")]
+use std::time::Instant;
+#[cfg(feature = "timer")]
+use tokio_timer::{clock, Delay};
+
+/// How randomness is applied to the delays of a `Backoff`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Jitter {
+    /// Use the exponential delay as-is.
+    None,
+    /// Pick a delay between zero and the exponential delay.
+    Full,
+    /// Pick a delay between half of the exponential delay and all of it.
+    Equal,
+    /// Pick a delay between the base delay and three times the previous
+    /// delay, rather than growing exponentially with the number of attempts.
+    Decorrelated,
+}
+
+/// Computes the delays between consecutive attempts.
+///
+/// The first delay is `base`, and every delay after it is `multiplier` times
+/// longer (2 by default), capped at `max`. Jitter is then applied to each
+/// delay, which never exceeds `max`.
+///
+/// The random number generator is passed to `next_delay`, so that tests can
+/// use a seeded generator to make the delays deterministic.
+#[derive(Debug, Clone)]
+pub struct Backoff {
+    base: Duration,
+    max: Duration,
+    multiplier: f64,
+    jitter: Jitter,
+    /// The exponential delay of the next attempt, before jitter.
+    next: Duration,
+    /// The last delay returned, used by decorrelated jitter.
+    prev: Duration,
+}
+
+impl Backoff {
+    /// Create a new `Backoff` waiting from `base` up to `max` between
+    /// attempts, without jitter.
+    ///
+    /// # Panics
+    ///
+    /// This function panics if `base` is greater than `max`.
+    pub fn new(base: Duration, max: Duration) -> Self {
+        assert!(base <= max, "base delay must not exceed the max delay");
+
+        Backoff {
+            base,
+            max,
+            multiplier: 2.0,
+            jitter: Jitter::None,
+            next: base,
+            prev: base,
+        }
+    }
+
+    /// Set the factor each delay is multiplied by for the next attempt.
+    ///
+    /// # Panics
+    ///
+    /// This function panics if `multiplier` is less than 1.
+    pub fn multiplier(mut self, multiplier: f64) -> Self {
+        assert!(multiplier >= 1.0, "multiplier must be at least 1");
+        self.multiplier = multiplier;
+        self
+    }
+
+    /// Set the jitter applied to the delays.
+    pub fn jitter(mut self, jitter: Jitter) -> Self {
+        self.jitter = jitter;
+        self
+    }
+
+    /// Returns the delay before the next attempt.
+    pub fn next_delay<R: Rng>(&mut self, rng: &mut R) -> Duration {
+        let exp = self.next;
+        self.next = cmp::min(mul(self.next, self.multiplier), self.max);
+
+        let delay = match self.jitter {
+            Jitter::None => exp,
+            Jitter::Full => between(rng, Duration::from_secs(0), exp),
+            Jitter::Equal => between(rng, exp / 2, exp),
+            Jitter::Decorrelated => {
+                let upper = cmp::min(mul(self.prev, 3.0), self.max);
+                between(rng, self.base, upper)
+            }
+        };
+
+        self.prev = delay;
+        delay
+    }
+
+    /// Start over from the base delay, e.g. after an attempt succeeded.
+    pub fn reset(&mut self) {
+        self.next = self.base;
+        self.prev = self.base;
+    }
+}
+
+/// Yields a value once a delay has elapsed.
+///
+/// A `Delayed` never fails: if the timer does, e.g. because it was shut down,
+/// the value is yielded right away, so that the attempt it holds back is made
+/// early rather than never.
+#[cfg(feature = "timer")]
+pub struct Delayed<T> {
+    delay: Delay,
+    value: Option<T>,
+}
+
+#[cfg(feature = "timer")]
+impl<T> Delayed<T> {
+    /// Yield `value` once `delay` has elapsed.
+    pub fn new(value: T, delay: Duration) -> Self {
+        Delayed::until(value, clock::now() + delay)
+    }
+
+    /// Yield `value` at `deadline`.
+    pub fn until(value: T, deadline: Instant) -> Self {
+        Delayed {
+            delay: Delay::new(deadline),
+            value: Some(value),
+        }
+    }
+}
+
+#[cfg(feature = "timer")]
+impl<T> Future for Delayed<T> {
+    type Item = T;
+    type Error = ();
+
+    fn poll(&mut self) -> Poll<T, ()> {
+        if let Ok(Async::NotReady) = self.delay.poll() {
+            return Ok(Async::NotReady);
+        }
+
+        let value = self.value.take().expect("polled after complete");
+        Ok(Async::Ready(value))
+    }
+}
+
+#[cfg(feature = "timer")]
+impl<T: fmt::Debug> fmt::Debug for Delayed<T> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_struct("Delayed")
+            .field("delay", &self.delay)
+            .field("value", &self.value)
+            .finish()
+    }
+}
+
+/// Picks a duration between `low` and `high`, inclusive.
+fn between<R: Rng>(rng: &mut R, low: Duration, high: Duration) -> Duration {
+    if high <= low {
+        return low;
+    }
+
+    let range = nanos(high - low);
+    low + from_nanos(rng.gen_range(0, range.saturating_add(1)))
+}
+
+/// Multiplies `duration` by `factor`, saturating on overflow.
+fn mul(duration: Duration, factor: f64) -> Duration {
+    let product = nanos(duration) as f64 * factor;
+
+    if product >= ::std::u64::MAX as f64 {
+        return from_nanos(::std::u64::MAX);
+    }
+
+    from_nanos(product as u64)
+}
+
+fn nanos(duration: Duration) -> u64 {
+    duration
+        .as_secs()
+        .saturating_mul(1_000_000_000)
+        .saturating_add(u64::from(duration.subsec_nanos()))
+}
+
+fn from_nanos(nanos: u64) -> Duration {
+    Duration::new(nanos / 1_000_000_000, (nanos % 1_000_000_000) as u32)
+}
@@ -0,0 +1,117 @@
+use crate::{PollReadyN, Unready};
+use futures::{Future, Poll};
+use tower_service::Service;
+
+use std::fmt;
+
+/// A boxed `Service + Send + Clone` trait object.
+///
+/// `BoxCloneService` turns a service into a trait object like `BoxService`,
+/// but preserves the ability to `Clone` the service. This is useful for stacks
+/// that rely on cloning the inner service, e.g. to issue each request on its
+/// own clone.
+///
+/// See module level documentation for more details.
+pub struct BoxCloneService<T, U, E> {
+    inner: Box<CloneService<T, Response = U, Error = E, Future = BoxFuture<U, E>> + Send>,
+}
+
+/// A boxed `Future + Send` trait object.
+type BoxFuture<T, E> = Box<Future<Item = T, Error = E> + Send>;
+
+/// An object-safe `Service` that can clone itself into a new trait object.
+trait CloneService<Request>: Service<Request> {
+    fn clone_box(
+        &self,
+    ) -> Box<
+        CloneService<Request, Response = Self::Response, Error = Self::Error, Future = Self::Future>
+            + Send,
+    >;
+}
+
+#[derive(Debug, Clone)]
+struct CloneBoxed<S> {
+    inner: S,
+}
+
+impl<T, U, E> BoxCloneService<T, U, E> {
+    /// Create a new `BoxCloneService` wrapping `inner`.
+    pub fn new<S>(inner: S) -> Self
+    where
+        S: Service<T, Response = U, Error = E> + Clone + Send + 'static,
+        S::Future: Send + 'static,
+    {
+        let inner = Box::new(CloneBoxed { inner });
+        BoxCloneService { inner }
+    }
+}
+
+impl<T, U, E> Service<T> for BoxCloneService<T, U, E> {
+    type Response = U;
+    type Error = E;
+    type Future = BoxFuture<U, E>;
+
+    fn poll_ready(&mut self) -> Poll<(), E> {
+        self.inner.poll_ready()
+    }
+
+    fn call(&mut self, request: T) -> BoxFuture<U, E> {
+        self.inner.call(request)
+    }
+}
+
+impl<T, U, E> PollReadyN<T> for BoxCloneService<T, U, E> {}
+
+impl<T, U, E> Unready<T> for BoxCloneService<T, U, E> {}
+
+impl<T, U, E> Clone for BoxCloneService<T, U, E> {
+    fn clone(&self) -> Self {
+        BoxCloneService {
+            inner: self.inner.clone_box(),
+        }
+    }
+}
+
+impl<T, U, E> fmt::Debug for BoxCloneService<T, U, E>
+where
+    T: fmt::Debug,
+    U: fmt::Debug,
+    E: fmt::Debug,
+{
+    fn fmt(&self, fmt: &mut fmt::Formatter) -> fmt::Result {
+        fmt.debug_struct("BoxCloneService").finish()
+    }
+}
+
+impl<S, Request> Service<Request> for CloneBoxed<S>
+where
+    S: Service<Request> + 'static,
+    S::Future: Send + 'static,
+{
+    type Response = S::Response;
+    type Error = S::Error;
+    type Future = Box<Future<Item = S::Response, Error = S::Error> + Send>;
+
+    fn poll_ready(&mut self) -> Poll<(), Self::Error> {
+        self.inner.poll_ready()
+    }
+
+    fn call(&mut self, request: Request) -> Self::Future {
+        Box::new(self.inner.call(request))
+    }
+}
+
+impl<S, Request> CloneService<Request> for CloneBoxed<S>
+where
+    S: Service<Request> + Clone + Send + 'static,
+    S::Future: Send + 'static,
+{
+    fn clone_box(
+        &self,
+    ) -> Box<
+        CloneService<Request, Response = Self::Response, Error = Self::Error, Future = Self::Future>
+            + Send,
+    > {
+        Box::new(self.clone())
+    }
+}
@@ -5,11 +5,13 @@
 //! be useful when the service instance cannot be explicitly named for whatever
 //! reason.
 //!
-//! There are two variants of service objects. `BoxService` requires both the
+//! There are three variants of service objects. `BoxService` requires both the
 //! service and the response future to be `Send`. These values can move freely
 //! across threads. `UnsyncBoxService` requires both the service and the
 //! response future to remain on the current thread. This is useful for
 //! representing services that are backed by `Rc` or other non-`Send` types.
+//! `BoxCloneService` is like `BoxService`, but additionally requires the
+//! service to be `Clone` and is itself `Clone`.
 //!
 //! # Examples
 //!
@@ -55,8 +57,10 @@
 //! }
 //! ```
 
+mod clone;
 mod sync;
 mod unsync;
 
+pub use self::clone::BoxCloneService;
 pub use self::sync::BoxService;
 pub use self::unsync::UnsyncBoxService;
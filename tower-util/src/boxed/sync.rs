@@ -1,5 +1,6 @@
 use futures::{Future, Poll};
 use tower_service::Service;
+use crate::{PollReadyN, Unready};
 
 use std::fmt;
 
@@ -26,6 +27,7 @@ struct Boxed<S> {
 }
 
 impl<T, U, E> BoxService<T, U, E> {
+    /// Create a new `BoxService` wrapping `inner`.
     pub fn new<S>(inner: S) -> Self
     where
         S: Service<T, Response = U, Error = E> + Send + 'static,
@@ -50,6 +52,10 @@ impl<T, U, E> Service<T> for BoxService<T, U, E> {
     }
 }
 
+impl<T, U, E> PollReadyN<T> for BoxService<T, U, E> {}
+
+impl<T, U, E> Unready<T> for BoxService<T, U, E> {}
+
 impl<T, U, E> fmt::Debug for BoxService<T, U, E>
 where
     T: fmt::Debug,
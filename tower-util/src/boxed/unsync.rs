@@ -1,9 +1,17 @@
 use futures::{Future, Poll};
 use tower_service::Service;
+use crate::{PollReadyN, Unready};
 
 use std::fmt;
 
 /// A boxed `Service` trait object.
+///
+/// `UnsyncBoxService` turns a service into a trait object, allowing the
+/// response future type to be dynamic. Unlike `BoxService`, neither the service
+/// nor the response future are required to be `Send`, so they must remain on
+/// the current thread.
+///
+/// See module level documentation for more details.
 pub struct UnsyncBoxService<T, U, E> {
     inner: Box<Service<T, Response = U, Error = E, Future = UnsyncBoxFuture<U, E>>>,
 }
@@ -20,6 +28,7 @@ struct UnsyncBoxed<S> {
 }
 
 impl<T, U, E> UnsyncBoxService<T, U, E> {
+    /// Create a new `UnsyncBoxService` wrapping `inner`.
     pub fn new<S>(inner: S) -> Self
     where
         S: Service<T, Response = U, Error = E> + 'static,
@@ -44,6 +53,10 @@ impl<T, U, E> Service<T> for UnsyncBoxService<T, U, E> {
     }
 }
 
+impl<T, U, E> PollReadyN<T> for UnsyncBoxService<T, U, E> {}
+
+impl<T, U, E> Unready<T> for UnsyncBoxService<T, U, E> {}
+
 impl<T, U, E> fmt::Debug for UnsyncBoxService<T, U, E>
 where
     T: fmt::Debug,
@@ -0,0 +1,327 @@
+//! Contains `Capture` and related types and functions.
+//!
+//! See `Capture` documentation for more details.
+
+use crate::layer::Never;
+use futures::sync::mpsc;
+use futures::{Async, Future, Poll, Stream};
+use std::fmt;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::Arc;
+use tower_layer::Layer;
+use tower_service::Service;
+
+/// Captures a summary of every request and its response, e.g. for audit
+/// logging.
+///
+/// For each request, the hook `F` is called with a reference to the request
+/// and returns a completion closure. Once the response (or error) is
+/// available, the completion is called with it and returns the record.
+///
+/// Records are sent to a [`Records`](struct.Records.html) stream, which is
+/// meant to be forwarded to the actual sink on its own task. The stream
+/// buffers at most `capacity` records; while it is full, new records are
+/// dropped and counted rather than waited on, so a slow sink can never apply
+/// backpressure to the request path.
+pub struct Capture<S, F, R> {
+    inner: S,
+    summarize: F,
+    recorder: Arc<Recorder<R>>,
+}
+
+/// Applies `Capture` to services.
+///
+/// All services produced by the layer send their records to the same stream.
+pub struct CaptureLayer<F, R> {
+    summarize: F,
+    recorder: Arc<Recorder<R>>,
+}
+
+/// The stream of records captured by a `Capture`.
+///
+/// The stream ends once every `Capture`, `CaptureLayer` and response future
+/// sending to it has been dropped.
+pub struct Records<R> {
+    rx: mpsc::UnboundedReceiver<R>,
+    counts: Arc<Counts>,
+}
+
+/// Response future returned by `Capture`.
+pub struct ResponseFuture<T, G, R> {
+    inner: T,
+    complete: Option<G>,
+    recorder: Arc<Recorder<R>>,
+}
+
+struct Recorder<R> {
+    tx: mpsc::UnboundedSender<R>,
+    counts: Arc<Counts>,
+}
+
+#[derive(Debug)]
+struct Counts {
+    capacity: usize,
+    buffered: AtomicUsize,
+    dropped: AtomicUsize,
+}
+
+/// Returns a recorder and its stream, buffering up to `capacity` records.
+fn records<R>(capacity: usize) -> (Arc<Recorder<R>>, Records<R>) {
+    assert!(capacity > 0, "capacity must be greater than zero");
+
+    let (tx, rx) = mpsc::unbounded();
+    let counts = Arc::new(Counts {
+        capacity,
+        buffered: AtomicUsize::new(0),
+        dropped: AtomicUsize::new(0),
+    });
+
+    let recorder = Arc::new(Recorder {
+        tx,
+        counts: counts.clone(),
+    });
+
+    (recorder, Records { rx, counts })
+}
+
+// ===== impl Capture =====
+
+impl<S, F, R> Capture<S, F, R> {
+    /// Create a new `Capture` summarizing requests to `inner` with
+    /// `summarize`, along with the stream of its records.
+    ///
+    /// # Panics
+    ///
+    /// This function panics if `capacity` is 0.
+    pub fn new(inner: S, summarize: F, capacity: usize) -> (Self, Records<R>) {
+        let (recorder, records) = records(capacity);
+        let capture = Capture {
+            inner,
+            summarize,
+            recorder,
+        };
+
+        (capture, records)
+    }
+
+    /// Get a reference to the inner service
+    pub fn get_ref(&self) -> &S {
+        &self.inner
+    }
+
+    /// Get a mutable reference to the inner service
+    pub fn get_mut(&mut self) -> &mut S {
+        &mut self.inner
+    }
+
+    /// Consume `self`, returning the inner service
+    pub fn into_inner(self) -> S {
+        self.inner
+    }
+}
+
+impl<S, F, G, R, Request> Service<Request> for Capture<S, F, R>
+where
+    S: Service<Request>,
+    F: Fn(&Request) -> G,
+    G: FnOnce(Result<&S::Response, &S::Error>) -> R,
+{
+    type Response = S::Response;
+    type Error = S::Error;
+    type Future = ResponseFuture<S::Future, G, R>;
+
+    fn poll_ready(&mut self) -> Poll<(), Self::Error> {
+        self.inner.poll_ready()
+    }
+
+    fn call(&mut self, request: Request) -> Self::Future {
+        let complete = (self.summarize)(&request);
+
+        ResponseFuture {
+            inner: self.inner.call(request),
+            complete: Some(complete),
+            recorder: self.recorder.clone(),
+        }
+    }
+}
+
+impl<S, F, R> Clone for Capture<S, F, R>
+where
+    S: Clone,
+    F: Clone,
+{
+    fn clone(&self) -> Self {
+        Capture {
+            inner: self.inner.clone(),
+            summarize: self.summarize.clone(),
+            recorder: self.recorder.clone(),
+        }
+    }
+}
+
+impl<S, F, R> fmt::Debug for Capture<S, F, R>
+where
+    S: fmt::Debug,
+{
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_struct("Capture")
+            .field("inner", &self.inner)
+            .field("recorder", &self.recorder)
+            .finish()
+    }
+}
+
+// ===== impl CaptureLayer =====
+
+impl<F, R> CaptureLayer<F, R> {
+    /// Create a new `CaptureLayer` summarizing requests with `summarize`,
+    /// along with the stream of its records.
+    ///
+    /// # Panics
+    ///
+    /// This function panics if `capacity` is 0.
+    pub fn new(summarize: F, capacity: usize) -> (Self, Records<R>) {
+        let (recorder, records) = records(capacity);
+        let layer = CaptureLayer {
+            summarize,
+            recorder,
+        };
+
+        (layer, records)
+    }
+}
+
+impl<S, F, G, R, Request> Layer<S, Request> for CaptureLayer<F, R>
+where
+    S: Service<Request>,
+    F: Fn(&Request) -> G + Clone,
+    G: FnOnce(Result<&S::Response, &S::Error>) -> R,
+{
+    type Response = S::Response;
+    type Error = S::Error;
+    type LayerError = Never;
+    type Service = Capture<S, F, R>;
+
+    fn layer(&self, inner: S) -> Result<Self::Service, Self::LayerError> {
+        Ok(Capture {
+            inner,
+            summarize: self.summarize.clone(),
+            recorder: self.recorder.clone(),
+        })
+    }
+}
+
+impl<F, R> Clone for CaptureLayer<F, R>
+where
+    F: Clone,
+{
+    fn clone(&self) -> Self {
+        CaptureLayer {
+            summarize: self.summarize.clone(),
+            recorder: self.recorder.clone(),
+        }
+    }
+}
+
+impl<F, R> fmt::Debug for CaptureLayer<F, R> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_struct("CaptureLayer")
+            .field("recorder", &self.recorder)
+            .finish()
+    }
+}
+
+// ===== impl Records =====
+
+impl<R> Records<R> {
+    /// Returns the number of records dropped because the stream was full.
+    pub fn dropped(&self) -> usize {
+        self.counts.dropped.load(Ordering::SeqCst)
+    }
+}
+
+impl<R> Stream for Records<R> {
+    type Item = R;
+    type Error = ();
+
+    fn poll(&mut self) -> Poll<Option<R>, ()> {
+        let record = try_ready!(self.rx.poll());
+
+        if record.is_some() {
+            self.counts.buffered.fetch_sub(1, Ordering::SeqCst);
+        }
+
+        Ok(Async::Ready(record))
+    }
+}
+
+impl<R> fmt::Debug for Records<R> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_struct("Records")
+            .field("counts", &self.counts)
+            .finish()
+    }
+}
+
+// ===== impl ResponseFuture =====
+
+impl<T, G, R> Future for ResponseFuture<T, G, R>
+where
+    T: Future,
+    G: FnOnce(Result<&T::Item, &T::Error>) -> R,
+{
+    type Item = T::Item;
+    type Error = T::Error;
+
+    fn poll(&mut self) -> Poll<Self::Item, Self::Error> {
+        let result = match self.inner.poll() {
+            Ok(Async::NotReady) => return Ok(Async::NotReady),
+            Ok(Async::Ready(response)) => Ok(response),
+            Err(e) => Err(e),
+        };
+
+        let complete = self.complete.take().expect("polled after complete");
+        self.recorder.record(complete(result.as_ref()));
+
+        result.map(Async::Ready)
+    }
+}
+
+impl<T, G, R> fmt::Debug for ResponseFuture<T, G, R>
+where
+    T: fmt::Debug,
+{
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_struct("ResponseFuture")
+            .field("inner", &self.inner)
+            .finish()
+    }
+}
+
+// ===== impl Recorder =====
+
+impl<R> Recorder<R> {
+    /// Sends `record` to the stream, dropping it if the stream is full.
+    fn record(&self, record: R) {
+        let buffered = self.counts.buffered.fetch_add(1, Ordering::SeqCst);
+
+        if buffered >= self.counts.capacity {
+            self.counts.buffered.fetch_sub(1, Ordering::SeqCst);
+            self.counts.dropped.fetch_add(1, Ordering::SeqCst);
+            return;
+        }
+
+        if self.tx.unbounded_send(record).is_err() {
+            // The stream is gone, so nobody is interested in the record.
+            self.counts.buffered.fetch_sub(1, Ordering::SeqCst);
+        }
+    }
+}
+
+impl<R> fmt::Debug for Recorder<R> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_struct("Recorder")
+            .field("counts", &self.counts)
+            .finish()
+    }
+}
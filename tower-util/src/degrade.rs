@@ -0,0 +1,108 @@
+//! Contains `Degrade` and related types and functions.
+//!
+//! See `Degrade` documentation for more details.
+
+use crate::Either;
+use futures::Poll;
+use std::fmt;
+use tower_service::Service;
+
+type Error = Box<::std::error::Error + Send + Sync>;
+
+/// Routes requests to a degraded service while under pressure.
+///
+/// Each time the service is polled for readiness, the pressure signal `F` is
+/// checked. While it is active, requests are sent to the degraded service `D`,
+/// which is expected to be cheap (e.g. returning a static or cached response).
+/// Once the signal clears, requests go to the primary service `P` again.
+///
+/// Both services must be of the same request and response types, while their
+/// errors are converted into a boxed error.
+pub struct Degrade<P, D, F> {
+    primary: P,
+    degraded: D,
+    pressure: F,
+    route: Route,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Route {
+    Primary,
+    Degraded,
+}
+
+impl<P, D, F> Degrade<P, D, F>
+where
+    F: Fn() -> bool,
+{
+    /// Create a new `Degrade` that uses `degraded` whenever `pressure`
+    /// returns `true`.
+    pub fn new(primary: P, degraded: D, pressure: F) -> Self {
+        Degrade {
+            primary,
+            degraded,
+            pressure,
+            route: Route::Primary,
+        }
+    }
+
+    /// Returns `true` if the last readiness check routed to the degraded
+    /// service.
+    pub fn is_degraded(&self) -> bool {
+        self.route == Route::Degraded
+    }
+
+    /// Get a reference to the primary service
+    pub fn primary(&self) -> &P {
+        &self.primary
+    }
+
+    /// Get a reference to the degraded service
+    pub fn degraded(&self) -> &D {
+        &self.degraded
+    }
+}
+
+impl<P, D, F, Request> Service<Request> for Degrade<P, D, F>
+where
+    P: Service<Request>,
+    P::Error: Into<Error>,
+    D: Service<Request, Response = P::Response>,
+    D::Error: Into<Error>,
+    F: Fn() -> bool,
+{
+    type Response = P::Response;
+    type Error = Error;
+    type Future = Either<P::Future, D::Future>;
+
+    fn poll_ready(&mut self) -> Poll<(), Self::Error> {
+        if (self.pressure)() {
+            self.route = Route::Degraded;
+            self.degraded.poll_ready().map_err(Into::into)
+        } else {
+            self.route = Route::Primary;
+            self.primary.poll_ready().map_err(Into::into)
+        }
+    }
+
+    fn call(&mut self, request: Request) -> Self::Future {
+        match self.route {
+            Route::Primary => Either::A(self.primary.call(request)),
+            Route::Degraded => Either::B(self.degraded.call(request)),
+        }
+    }
+}
+
+impl<P, D, F> fmt::Debug for Degrade<P, D, F>
+where
+    P: fmt::Debug,
+    D: fmt::Debug,
+{
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_struct("Degrade")
+            .field("primary", &self.primary)
+            .field("degraded", &self.degraded)
+            .field("route", &self.route)
+            .finish()
+    }
+}
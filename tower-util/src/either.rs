@@ -2,16 +2,21 @@
 //!
 //! See `Either` documentation for more details.
 
+use crate::{Unready, UnreadyReason};
 use futures::{Future, Poll};
 use tower_service::Service;
 
 /// Combine two different service types into a single type.
 ///
-/// Both services must be of the same request, response, and error types.
-/// `Either` is useful for handling conditional branching in service middleware
-/// to different inner service types.
+/// Both services must be of the same request and response types, while their
+/// errors are converted into a boxed error. `Either` is useful for handling
+/// conditional branching in service middleware to different inner service
+/// types, e.g. `if tls { Either::A(a) } else { Either::B(b) }`.
+#[derive(Debug, Clone)]
 pub enum Either<A, B> {
+    /// One type of backing `Service`.
     A(A),
+    /// The other type of backing `Service`.
     B(B),
 }
 
@@ -47,6 +52,21 @@ where
     }
 }
 
+impl<A, B, Request> Unready<Request> for Either<A, B>
+where
+    A: Unready<Request>,
+    A::Error: Into<Error>,
+    B: Unready<Request, Response = A::Response>,
+    B::Error: Into<Error>,
+{
+    fn unready_reason(&self) -> Option<UnreadyReason> {
+        match self {
+            Either::A(service) => service.unready_reason(),
+            Either::B(service) => service.unready_reason(),
+        }
+    }
+}
+
 impl<A, B> Future for Either<A, B>
 where
     A: Future,
@@ -0,0 +1,83 @@
+use std::fmt;
+
+use futures::{Future, Poll};
+use tower_service::Service;
+
+/// Returns a new `FutureService` for the given future.
+///
+/// A `FutureService` allows a `Service` to be used before it has been created,
+/// e.g. while waiting on an asynchronous initialization step such as fetching
+/// configuration or completing a handshake. It reports itself as not ready
+/// until the future resolves, after which all calls are delegated to the
+/// resolved service.
+///
+/// The error of the future must match the error of the service.
+pub fn future_service<F, S, Request>(future: F) -> FutureService<F, S>
+where
+    F: Future<Item = S, Error = S::Error>,
+    S: Service<Request>,
+{
+    FutureService::new(future)
+}
+
+/// A `Service` backed by a future that resolves to a `Service`.
+///
+/// See `future_service` for more details.
+pub struct FutureService<F, S> {
+    state: State<F, S>,
+}
+
+enum State<F, S> {
+    Future(F),
+    Service(S),
+}
+
+impl<F, S> FutureService<F, S> {
+    /// Returns a new `FutureService` for the given future.
+    pub fn new(future: F) -> Self {
+        FutureService {
+            state: State::Future(future),
+        }
+    }
+}
+
+impl<F, S, Request> Service<Request> for FutureService<F, S>
+where
+    F: Future<Item = S, Error = S::Error>,
+    S: Service<Request>,
+{
+    type Response = S::Response;
+    type Error = S::Error;
+    type Future = S::Future;
+
+    fn poll_ready(&mut self) -> Poll<(), Self::Error> {
+        loop {
+            self.state = match self.state {
+                State::Future(ref mut future) => State::Service(try_ready!(future.poll())),
+                State::Service(ref mut svc) => return svc.poll_ready(),
+            };
+        }
+    }
+
+    fn call(&mut self, req: Request) -> Self::Future {
+        match self.state {
+            State::Service(ref mut svc) => svc.call(req),
+            State::Future(_) => panic!("FutureService::call before poll_ready"),
+        }
+    }
+}
+
+impl<F, S> fmt::Debug for FutureService<F, S>
+where
+    S: fmt::Debug,
+{
+    fn fmt(&self, fmt: &mut fmt::Formatter) -> fmt::Result {
+        match self.state {
+            State::Future(_) => fmt
+                .debug_tuple("FutureService")
+                .field(&format_args!("<pending>"))
+                .finish(),
+            State::Service(ref svc) => fmt.debug_tuple("FutureService").field(svc).finish(),
+        }
+    }
+}
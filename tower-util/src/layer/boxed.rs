@@ -0,0 +1,88 @@
+use crate::BoxService;
+use std::fmt;
+use std::sync::Arc;
+use tower_layer::Layer;
+use tower_service::Service;
+
+/// A boxed `Layer` trait object.
+///
+/// `BoxLayer` turns a layer into a trait object, allowing both the layer and
+/// the service it produces to be dynamic. The produced service is a
+/// `BoxService`, so layers producing different service types may be stored
+/// together, e.g. in a `Vec` assembled from runtime configuration.
+///
+/// `In` is the type of the service being wrapped, while `T`, `U` and `E` are
+/// the request, response and error types of the produced `BoxService`.
+pub struct BoxLayer<In, T, U, E> {
+    boxed: Arc<
+        Layer<In, T, Response = U, Error = E, LayerError = Error, Service = BoxService<T, U, E>>
+            + Send
+            + Sync,
+    >,
+}
+
+type Error = Box<dyn std::error::Error + Send + Sync>;
+
+#[derive(Debug)]
+struct Boxed<L> {
+    inner: L,
+}
+
+impl<In, T, U, E> BoxLayer<In, T, U, E> {
+    /// Create a new `BoxLayer` wrapping `inner`.
+    pub fn new<L>(inner: L) -> Self
+    where
+        L: Layer<In, T, Response = U, Error = E> + Send + Sync + 'static,
+        L::LayerError: Into<Error>,
+        L::Service: Send + 'static,
+        <L::Service as Service<T>>::Future: Send + 'static,
+    {
+        let boxed = Arc::new(Boxed { inner });
+        BoxLayer { boxed }
+    }
+}
+
+impl<In, T, U, E> Layer<In, T> for BoxLayer<In, T, U, E> {
+    type Response = U;
+    type Error = E;
+    type LayerError = Error;
+    type Service = BoxService<T, U, E>;
+
+    fn layer(&self, inner: In) -> Result<Self::Service, Self::LayerError> {
+        self.boxed.layer(inner)
+    }
+}
+
+impl<In, T, U, E> Clone for BoxLayer<In, T, U, E> {
+    fn clone(&self) -> Self {
+        BoxLayer {
+            boxed: self.boxed.clone(),
+        }
+    }
+}
+
+impl<In, T, U, E> fmt::Debug for BoxLayer<In, T, U, E> {
+    fn fmt(&self, fmt: &mut fmt::Formatter) -> fmt::Result {
+        fmt.debug_struct("BoxLayer").finish()
+    }
+}
+
+impl<L, In, T> Layer<In, T> for Boxed<L>
+where
+    L: Layer<In, T>,
+    L::LayerError: Into<Error>,
+    L::Service: Send + 'static,
+    <L::Service as Service<T>>::Future: Send + 'static,
+{
+    type Response = L::Response;
+    type Error = L::Error;
+    type LayerError = Error;
+    type Service = BoxService<T, L::Response, L::Error>;
+
+    fn layer(&self, inner: In) -> Result<Self::Service, Self::LayerError> {
+        self.inner
+            .layer(inner)
+            .map(BoxService::new)
+            .map_err(Into::into)
+    }
+}
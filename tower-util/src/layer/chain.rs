@@ -1,28 +1,38 @@
+use std::fmt;
+use std::marker::PhantomData;
 use tower_layer::Layer;
-use tower_service::Service;
 
 /// Two middlewares chained together.
 ///
+/// `Request` is the request type of the service produced by `Inner`. Since
+/// `Outer` wraps that service, it is also the request type `Outer` consumes.
+/// The service produced by `Outer` may accept a different request type,
+/// allowing layers that translate requests (such as codecs) to be composed
+/// with the rest of the stack.
+///
 /// This type is produced by `Layer::chain`.
-#[derive(Debug)]
-pub struct Chain<Inner, Outer> {
+pub struct Chain<Inner, Outer, Request> {
     inner: Inner,
     outer: Outer,
+    _p: PhantomData<fn(Request)>,
 }
 
 type Error = Box<dyn std::error::Error + Send + Sync>;
 
-impl<Inner, Outer> Chain<Inner, Outer> {
+impl<Inner, Outer, Request> Chain<Inner, Outer, Request> {
     /// Create a new `Chain`.
     pub fn new(inner: Inner, outer: Outer) -> Self {
-        Chain { inner, outer }
+        Chain {
+            inner,
+            outer,
+            _p: PhantomData,
+        }
     }
 }
 
-impl<S, Request, Inner, Outer> Layer<S, Request> for Chain<Inner, Outer>
+impl<S, Request, InnerRequest, Inner, Outer> Layer<S, Request> for Chain<Inner, Outer, InnerRequest>
 where
-    S: Service<Request>,
-    Inner: Layer<S, Request>,
+    Inner: Layer<S, InnerRequest>,
     Inner::LayerError: Into<Error>,
     Outer: Layer<Inner::Service, Request>,
     Outer::LayerError: Into<Error>,
@@ -38,3 +48,26 @@ where
         self.outer.layer(inner).map_err(Into::into)
     }
 }
+
+impl<Inner, Outer, Request> Clone for Chain<Inner, Outer, Request>
+where
+    Inner: Clone,
+    Outer: Clone,
+{
+    fn clone(&self) -> Self {
+        Chain::new(self.inner.clone(), self.outer.clone())
+    }
+}
+
+impl<Inner, Outer, Request> fmt::Debug for Chain<Inner, Outer, Request>
+where
+    Inner: fmt::Debug,
+    Outer: fmt::Debug,
+{
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_struct("Chain")
+            .field("inner", &self.inner)
+            .field("outer", &self.outer)
+            .finish()
+    }
+}
@@ -1,5 +1,12 @@
+mod boxed;
 mod chain;
 mod identity;
 
+pub use self::boxed::BoxLayer;
 pub use self::chain::Chain;
 pub use self::identity::Identity;
+pub use crate::capture::CaptureLayer;
+pub use crate::per_item::PerItemLayer;
+pub use crate::startup::StartupGateLayer;
+
+pub(crate) use self::identity::Never;
@@ -2,34 +2,65 @@
 
 #[macro_use]
 extern crate futures;
+extern crate rand;
 #[cfg(feature = "io")]
 extern crate tokio_io;
+#[cfg(feature = "timer")]
+extern crate tokio_timer;
 extern crate tower_layer;
 extern crate tower_service;
 
+pub mod backoff;
+mod always_ready;
 mod boxed;
 mod call_all;
+mod capture;
+mod degrade;
 mod either;
+mod future_service;
 pub mod layer;
 #[cfg(feature = "io")]
 mod make_connection;
 mod make_service;
+mod maybe_ready;
 mod oneshot;
 mod optional;
+mod per_item;
+mod poll_ready_fn;
+mod poll_ready_n;
 mod ready;
+mod registry;
+mod reload;
+mod response_future;
 mod sealed;
 mod service_fn;
+mod shared;
+mod startup;
+mod unready;
 
-pub use crate::boxed::{BoxService, UnsyncBoxService};
+pub use crate::always_ready::AlwaysReady;
+pub use crate::boxed::{BoxCloneService, BoxService, UnsyncBoxService};
 pub use crate::call_all::{CallAll, CallAllUnordered};
+pub use crate::capture::{Capture, Records};
+pub use crate::degrade::Degrade;
 pub use crate::either::Either;
+pub use crate::future_service::{future_service, FutureService};
 #[cfg(feature = "io")]
 pub use crate::make_connection::MakeConnection;
-pub use crate::make_service::MakeService;
+pub use crate::make_service::{AsService, IntoService, MakeService};
+pub use crate::maybe_ready::MaybeReady;
 pub use crate::oneshot::Oneshot;
 pub use crate::optional::Optional;
+pub use crate::per_item::PerItem;
+pub use crate::poll_ready_fn::{poll_ready_fn, PollReadyFn};
+pub use crate::poll_ready_n::PollReadyN;
 pub use crate::ready::Ready;
-pub use crate::service_fn::ServiceFn;
+pub use crate::registry::Registry;
+pub use crate::reload::Reload;
+pub use crate::service_fn::{service_fn, ServiceFn};
+pub use crate::shared::SharedMakeService;
+pub use crate::startup::StartupGate;
+pub use crate::unready::{Unready, UnreadyReason, UnreadyWatch, Watched};
 
 pub mod error {
     //! Error types
@@ -40,7 +71,18 @@ pub mod error {
 pub mod future {
     //! Future types
 
-    #[cfg(feature = "either")]
-    pub use crate::either::future as either;
     pub use crate::optional::future as optional;
+    pub use crate::response_future::ResponseFuture;
+
+    pub mod capture {
+        //! Future types for `Capture`
+
+        pub use crate::capture::ResponseFuture;
+    }
+
+    pub mod per_item {
+        //! Future types for `PerItem`
+
+        pub use crate::per_item::ResponseFuture;
+    }
 }
@@ -1,5 +1,7 @@
 use crate::sealed::Sealed;
 use futures::{Future, Poll};
+use std::fmt;
+use std::marker::PhantomData;
 use tower_service::Service;
 
 /// Creates new `Service` values.
@@ -10,7 +12,11 @@ use tower_service::Service;
 /// `MakeService` trait, and uses that new `Service` value to process inbound
 /// requests on that new TCP stream.
 ///
-/// This is essentially a trait alias for a `Service` of `Service`s.
+/// This is essentially a trait alias for a `Service` of `Service`s. Any
+/// `Service<Target>` whose responses are services is a `MakeService`, and
+/// `into_service` / `as_service` turn a `MakeService` back into a
+/// `Service<Target>`, so that middleware such as timeouts or limits may be
+/// applied to building services.
 pub trait MakeService<Target, Request>: Sealed<(Target, Request)> {
     /// Responses given by the service
     type Response;
@@ -40,6 +46,44 @@ pub trait MakeService<Target, Request>: Sealed<(Target, Request)> {
 
     /// Create and return a new service value asynchronously.
     fn make_service(&mut self, target: Target) -> Self::Future;
+
+    /// Consume this `MakeService`, converting it into a `Service<Target>`.
+    fn into_service(self) -> IntoService<Self, Request>
+    where
+        Self: Sized,
+    {
+        IntoService {
+            make: self,
+            _p: PhantomData,
+        }
+    }
+
+    /// Borrow this `MakeService` as a `Service<Target>`.
+    fn as_service(&mut self) -> AsService<Self, Request>
+    where
+        Self: Sized,
+    {
+        AsService {
+            make: self,
+            _p: PhantomData,
+        }
+    }
+}
+
+/// A `Service<Target>` backed by a `MakeService`.
+///
+/// This type is produced by `MakeService::into_service`.
+pub struct IntoService<M, Request> {
+    make: M,
+    _p: PhantomData<fn(Request)>,
+}
+
+/// A `Service<Target>` backed by a borrowed `MakeService`.
+///
+/// This type is produced by `MakeService::as_service`.
+pub struct AsService<'a, M: 'a, Request> {
+    make: &'a mut M,
+    _p: PhantomData<fn(Request)>,
 }
 
 impl<M, S, Target, Request> Sealed<(Target, Request)> for M
@@ -68,3 +112,82 @@ where
         Service::call(self, target)
     }
 }
+
+// ===== impl IntoService =====
+
+impl<M, Request> IntoService<M, Request> {
+    /// Consume `self`, returning the inner `MakeService`.
+    pub fn into_inner(self) -> M {
+        self.make
+    }
+}
+
+impl<M, Target, Request> Service<Target> for IntoService<M, Request>
+where
+    M: MakeService<Target, Request>,
+{
+    type Response = M::Service;
+    type Error = M::MakeError;
+    type Future = M::Future;
+
+    fn poll_ready(&mut self) -> Poll<(), Self::Error> {
+        self.make.poll_ready()
+    }
+
+    fn call(&mut self, target: Target) -> Self::Future {
+        self.make.make_service(target)
+    }
+}
+
+impl<M, Request> Clone for IntoService<M, Request>
+where
+    M: Clone,
+{
+    fn clone(&self) -> Self {
+        IntoService {
+            make: self.make.clone(),
+            _p: PhantomData,
+        }
+    }
+}
+
+impl<M, Request> fmt::Debug for IntoService<M, Request>
+where
+    M: fmt::Debug,
+{
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_struct("IntoService")
+            .field("make", &self.make)
+            .finish()
+    }
+}
+
+// ===== impl AsService =====
+
+impl<'a, M, Target, Request> Service<Target> for AsService<'a, M, Request>
+where
+    M: MakeService<Target, Request>,
+{
+    type Response = M::Service;
+    type Error = M::MakeError;
+    type Future = M::Future;
+
+    fn poll_ready(&mut self) -> Poll<(), Self::Error> {
+        self.make.poll_ready()
+    }
+
+    fn call(&mut self, target: Target) -> Self::Future {
+        self.make.make_service(target)
+    }
+}
+
+impl<'a, M, Request> fmt::Debug for AsService<'a, M, Request>
+where
+    M: fmt::Debug,
+{
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_struct("AsService")
+            .field("make", &self.make)
+            .finish()
+    }
+}
@@ -0,0 +1,100 @@
+use futures::{Future, Poll};
+use std::fmt;
+
+/// A value that is either still being computed by a future, or available.
+///
+/// Middleware often needs to wait for something before it can serve
+/// requests, e.g. an inner service being built or a connection being
+/// established, which is usually tracked by a two-state enum. `MaybeReady`
+/// is that enum: `poll_ready` drives the future until it resolves, after
+/// which the value can be used with `get_mut`.
+///
+/// ```
+/// # extern crate futures;
+/// # extern crate tower_util;
+/// # use futures::future;
+/// # use tower_util::MaybeReady;
+/// # fn main() {
+/// let mut value = MaybeReady::new(future::ok::<_, ()>(1));
+/// assert!(value.get_mut().is_none());
+///
+/// assert!(value.poll_ready().unwrap().is_ready());
+/// assert_eq!(value.get_mut(), Some(&mut 1));
+/// # }
+/// ```
+pub enum MaybeReady<F: Future> {
+    /// The value is still being computed.
+    Pending(F),
+    /// The value is available.
+    Ready(F::Item),
+}
+
+impl<F: Future> MaybeReady<F> {
+    /// Returns a new `MaybeReady` waiting for `future` to resolve.
+    pub fn new(future: F) -> Self {
+        MaybeReady::Pending(future)
+    }
+
+    /// Drives the future until the value is available.
+    ///
+    /// Once the value is available, this always returns `Ready`. If the
+    /// future fails, its error is returned, and `MaybeReady` must not be
+    /// polled again.
+    pub fn poll_ready(&mut self) -> Poll<(), F::Error> {
+        let value = match *self {
+            MaybeReady::Pending(ref mut future) => try_ready!(future.poll()),
+            MaybeReady::Ready(_) => return Ok(().into()),
+        };
+
+        *self = MaybeReady::Ready(value);
+        Ok(().into())
+    }
+
+    /// Returns `true` if the value is available.
+    pub fn is_ready(&self) -> bool {
+        match *self {
+            MaybeReady::Pending(_) => false,
+            MaybeReady::Ready(_) => true,
+        }
+    }
+
+    /// Returns a reference to the value, if it is available.
+    pub fn get_ref(&self) -> Option<&F::Item> {
+        match *self {
+            MaybeReady::Pending(_) => None,
+            MaybeReady::Ready(ref value) => Some(value),
+        }
+    }
+
+    /// Returns a mutable reference to the value, if it is available.
+    pub fn get_mut(&mut self) -> Option<&mut F::Item> {
+        match *self {
+            MaybeReady::Pending(_) => None,
+            MaybeReady::Ready(ref mut value) => Some(value),
+        }
+    }
+
+    /// Consumes `self`, returning the value if it is available.
+    pub fn into_ready(self) -> Option<F::Item> {
+        match self {
+            MaybeReady::Pending(_) => None,
+            MaybeReady::Ready(value) => Some(value),
+        }
+    }
+}
+
+impl<F> fmt::Debug for MaybeReady<F>
+where
+    F: Future,
+    F::Item: fmt::Debug,
+{
+    fn fmt(&self, fmt: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            MaybeReady::Pending(_) => fmt
+                .debug_tuple("Pending")
+                .field(&format_args!("<future>"))
+                .finish(),
+            MaybeReady::Ready(ref value) => fmt.debug_tuple("Ready").field(value).finish(),
+        }
+    }
+}
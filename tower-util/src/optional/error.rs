@@ -1,6 +1,7 @@
 use std::error;
 use std::fmt;
 
+/// Error returned if the inner service of an `Optional` is not present.
 #[derive(Debug)]
 pub struct None(());
 
@@ -14,7 +15,7 @@ impl None {
 
 impl fmt::Display for None {
     fn fmt(&self, fmt: &mut fmt::Formatter) -> fmt::Result {
-        write!(fmt, "None")
+        write!(fmt, "service not available")
     }
 }
 
@@ -2,6 +2,7 @@ use super::{error, Error};
 use futures::{Future, Poll};
 
 /// Response future returned by `Optional`.
+#[derive(Debug)]
 pub struct ResponseFuture<T> {
     inner: Option<T>,
 }
@@ -1,7 +1,6 @@
-//! Contains `OptionService` and related types and functions.
-//!
-//! See `OptionService` documentation for more details.
+//! Contains `Optional` and related types and functions.
 //!
+//! See `Optional` documentation for more details.
 
 pub mod error;
 pub mod future;
@@ -13,13 +12,16 @@ use tower_service::Service;
 
 /// Optionally forwards requests to an inner service.
 ///
-/// If the inner service is `None`, `Error::None` is returned as the response.
+/// If the inner service is `None`, `error::None` is returned as the response.
+/// This keeps the type of a stack fixed when a backend is only conditionally
+/// configured.
+#[derive(Debug)]
 pub struct Optional<T> {
     inner: Option<T>,
 }
 
 impl<T> Optional<T> {
-    /// Create a new `OptionService`
+    /// Create a new `Optional`
     pub fn new<Request>(inner: Option<T>) -> Optional<T>
     where
         T: Service<Request>,
@@ -0,0 +1,135 @@
+//! Contains `PerItem` and related types and functions.
+//!
+//! See `PerItem` documentation for more details.
+
+use crate::layer::Never;
+use crate::CallAll;
+use futures::{Async, Future, Poll, Stream};
+use tower_layer::Layer;
+use tower_service::Service;
+
+type Error = Box<::std::error::Error + Send + Sync>;
+
+/// Applies a `Service` to every item of a streaming response.
+///
+/// The responses of the inner service `S` are `Stream`s. Each item yielded by
+/// such a stream is passed to a clone of the item service `T`, and the
+/// resulting stream yields the item service's responses in order. Since `T`
+/// is an ordinary `Service`, per-item processing (mapping, timeouts, ...)
+/// composes from the same middleware as requests do.
+#[derive(Clone, Debug)]
+pub struct PerItem<S, T> {
+    inner: S,
+    items: T,
+}
+
+/// Applies `PerItem` to services.
+#[derive(Clone, Debug)]
+pub struct PerItemLayer<T> {
+    items: T,
+}
+
+/// Response future returned by `PerItem`.
+#[derive(Debug)]
+pub struct ResponseFuture<F, T> {
+    inner: F,
+    items: Option<T>,
+}
+
+// ===== impl PerItem =====
+
+impl<S, T> PerItem<S, T> {
+    /// Create a new `PerItem` passing every item of `inner`'s responses to
+    /// `items`.
+    pub fn new(inner: S, items: T) -> Self {
+        PerItem { inner, items }
+    }
+
+    /// Get a reference to the inner service
+    pub fn get_ref(&self) -> &S {
+        &self.inner
+    }
+
+    /// Get a mutable reference to the inner service
+    pub fn get_mut(&mut self) -> &mut S {
+        &mut self.inner
+    }
+
+    /// Consume `self`, returning the inner service
+    pub fn into_inner(self) -> S {
+        self.inner
+    }
+}
+
+impl<S, T, Request> Service<Request> for PerItem<S, T>
+where
+    S: Service<Request>,
+    S::Response: Stream,
+    <S::Response as Stream>::Error: Into<Error>,
+    T: Service<<S::Response as Stream>::Item> + Clone,
+    T::Error: Into<Error>,
+{
+    type Response = CallAll<T, S::Response>;
+    type Error = S::Error;
+    type Future = ResponseFuture<S::Future, T>;
+
+    fn poll_ready(&mut self) -> Poll<(), Self::Error> {
+        self.inner.poll_ready()
+    }
+
+    fn call(&mut self, request: Request) -> Self::Future {
+        ResponseFuture {
+            inner: self.inner.call(request),
+            items: Some(self.items.clone()),
+        }
+    }
+}
+
+// ===== impl PerItemLayer =====
+
+impl<T> PerItemLayer<T> {
+    /// Create a new `PerItemLayer` passing every item of a service's
+    /// responses to `items`.
+    pub fn new(items: T) -> Self {
+        PerItemLayer { items }
+    }
+}
+
+impl<S, T, Request> Layer<S, Request> for PerItemLayer<T>
+where
+    S: Service<Request>,
+    S::Response: Stream,
+    <S::Response as Stream>::Error: Into<Error>,
+    T: Service<<S::Response as Stream>::Item> + Clone,
+    T::Error: Into<Error>,
+{
+    type Response = CallAll<T, S::Response>;
+    type Error = S::Error;
+    type LayerError = Never;
+    type Service = PerItem<S, T>;
+
+    fn layer(&self, inner: S) -> Result<Self::Service, Self::LayerError> {
+        Ok(PerItem::new(inner, self.items.clone()))
+    }
+}
+
+// ===== impl ResponseFuture =====
+
+impl<F, T> Future for ResponseFuture<F, T>
+where
+    F: Future,
+    F::Item: Stream,
+    <F::Item as Stream>::Error: Into<Error>,
+    T: Service<<F::Item as Stream>::Item>,
+    T::Error: Into<Error>,
+{
+    type Item = CallAll<T, F::Item>;
+    type Error = F::Error;
+
+    fn poll(&mut self) -> Poll<Self::Item, Self::Error> {
+        let stream = try_ready!(self.inner.poll());
+        let items = self.items.take().expect("polled after complete");
+
+        Ok(Async::Ready(CallAll::new(items, stream)))
+    }
+}
@@ -0,0 +1,71 @@
+use futures::{IntoFuture, Poll};
+use std::fmt;
+use tower_service::Service;
+
+/// Returns a new `PollReadyFn` with the given closures.
+///
+/// Unlike `service_fn`, the service's readiness is also implemented by a
+/// closure, `poll_ready`, which is called on each call to
+/// `Service::poll_ready`. This is useful to prototype middleware, or to test
+/// how middleware handles services that are not always ready.
+///
+/// ```
+/// # extern crate futures;
+/// # extern crate tower_service;
+/// # extern crate tower_util;
+/// # use futures::{Async, Future};
+/// # use tower_service::Service;
+/// # use tower_util::poll_ready_fn;
+/// # fn main() {
+/// let mut ready = false;
+/// let mut svc = poll_ready_fn(
+///     move || {
+///         // Only ready every other time.
+///         ready = !ready;
+///         Ok::<_, ()>(if ready { Async::Ready(()) } else { Async::NotReady })
+///     },
+///     |req: u32| Ok(req + 1),
+/// );
+///
+/// assert!(svc.poll_ready().unwrap().is_ready());
+/// assert_eq!(svc.call(1).wait(), Ok(2));
+/// assert!(svc.poll_ready().unwrap().is_not_ready());
+/// # }
+/// ```
+pub fn poll_ready_fn<P, C>(poll_ready: P, call: C) -> PollReadyFn<P, C> {
+    PollReadyFn { poll_ready, call }
+}
+
+/// A `Service` whose readiness and calls are implemented by closures.
+///
+/// See `poll_ready_fn` for more details.
+#[derive(Copy, Clone)]
+pub struct PollReadyFn<P, C> {
+    poll_ready: P,
+    call: C,
+}
+
+impl<P, C, F, Request> Service<Request> for PollReadyFn<P, C>
+where
+    P: FnMut() -> Poll<(), F::Error>,
+    C: FnMut(Request) -> F,
+    F: IntoFuture,
+{
+    type Response = F::Item;
+    type Error = F::Error;
+    type Future = F::Future;
+
+    fn poll_ready(&mut self) -> Poll<(), F::Error> {
+        (self.poll_ready)()
+    }
+
+    fn call(&mut self, req: Request) -> Self::Future {
+        (self.call)(req).into_future()
+    }
+}
+
+impl<P, C> fmt::Debug for PollReadyFn<P, C> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_struct("PollReadyFn").finish()
+    }
+}
@@ -0,0 +1,27 @@
+use futures::{Async, Poll};
+use tower_service::Service;
+
+/// A `Service` that can reserve capacity for several calls at once.
+///
+/// Bulk producers submitting large bursts of requests may use `poll_ready_n`
+/// to amortize the synchronization needed to acquire capacity for each
+/// request. When `poll_ready_n(n)` returns `Ready(k)`, the caller may dispatch
+/// `k` requests using `call` without calling `poll_ready` in between. `k` is
+/// always at least 1 and at most `n`.
+///
+/// Services that cannot reserve more than a single call at a time may
+/// implement this trait with an empty `impl` block. The provided
+/// implementation falls back to `poll_ready` and reserves one call.
+pub trait PollReadyN<Request>: Service<Request> {
+    /// Returns `Ready(k)` once the service is able to process `k` requests,
+    /// where `0 < k <= n`.
+    ///
+    /// # Panics
+    ///
+    /// Implementations may panic if `n` is 0.
+    fn poll_ready_n(&mut self, n: usize) -> Poll<usize, Self::Error> {
+        assert!(n > 0, "must reserve at least one call");
+        try_ready!(self.poll_ready());
+        Ok(Async::Ready(1))
+    }
+}
@@ -0,0 +1,105 @@
+//! Contains `Registry` and related types and functions.
+//!
+//! See `Registry` documentation for more details.
+
+use std::any::{Any, TypeId};
+use std::collections::HashMap;
+use std::fmt;
+use std::sync::{Arc, Mutex};
+
+/// A registry of resources shared between service stacks.
+///
+/// Middleware that shares state between the services it produces, such as
+/// `GlobalInFlightLimitLayer` or a retry `Budget`, may be registered under a
+/// name and retrieved wherever another stack is built, so that the state is
+/// shared without threading it through the application by hand.
+///
+/// Resources are identified by both their name and their type, and are
+/// cloned out of the registry, so they are typically cheap handles (e.g.
+/// wrapping an `Arc`). Cloning the `Registry` itself yields a handle to the
+/// same resources.
+///
+/// ```
+/// # extern crate tower_util;
+/// # use std::sync::Arc;
+/// # use std::sync::atomic::AtomicUsize;
+/// # use tower_util::Registry;
+/// # fn main() {
+/// let registry = Registry::new();
+///
+/// let a = registry.get_or_insert_with("requests", || Arc::new(AtomicUsize::new(0)));
+/// let b = registry.get_or_insert_with("requests", || Arc::new(AtomicUsize::new(0)));
+///
+/// assert!(Arc::ptr_eq(&a, &b));
+/// # }
+/// ```
+#[derive(Clone, Default)]
+pub struct Registry {
+    resources: Arc<Mutex<HashMap<(String, TypeId), Box<Any + Send + Sync>>>>,
+}
+
+impl Registry {
+    /// Create a new, empty `Registry`.
+    pub fn new() -> Self {
+        Registry::default()
+    }
+
+    /// Returns the resource registered as `name`, if any.
+    pub fn get<T>(&self, name: &str) -> Option<T>
+    where
+        T: Any + Clone + Send + Sync,
+    {
+        let resources = self.resources.lock().expect("registry poisoned");
+        resources
+            .get(&(name.to_owned(), TypeId::of::<T>()))
+            .and_then(|resource| resource.downcast_ref::<T>())
+            .cloned()
+    }
+
+    /// Returns the resource registered as `name`, registering the result of
+    /// `f` if there is none yet.
+    pub fn get_or_insert_with<T, F>(&self, name: &str, f: F) -> T
+    where
+        T: Any + Clone + Send + Sync,
+        F: FnOnce() -> T,
+    {
+        let mut resources = self.resources.lock().expect("registry poisoned");
+        resources
+            .entry((name.to_owned(), TypeId::of::<T>()))
+            .or_insert_with(|| Box::new(f()))
+            .downcast_ref::<T>()
+            .expect("resource registered with a different type")
+            .clone()
+    }
+
+    /// Registers `resource` as `name`, returning the resource it replaces.
+    pub fn insert<T>(&self, name: &str, resource: T) -> Option<T>
+    where
+        T: Any + Clone + Send + Sync,
+    {
+        let mut resources = self.resources.lock().expect("registry poisoned");
+        resources
+            .insert((name.to_owned(), TypeId::of::<T>()), Box::new(resource))
+            .and_then(|prev| prev.downcast::<T>().ok())
+            .map(|prev| *prev)
+    }
+
+    /// Removes the resource registered as `name`, returning it.
+    pub fn remove<T>(&self, name: &str) -> Option<T>
+    where
+        T: Any + Clone + Send + Sync,
+    {
+        let mut resources = self.resources.lock().expect("registry poisoned");
+        resources
+            .remove(&(name.to_owned(), TypeId::of::<T>()))
+            .and_then(|prev| prev.downcast::<T>().ok())
+            .map(|prev| *prev)
+    }
+}
+
+impl fmt::Debug for Registry {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let len = self.resources.lock().map(|r| r.len()).unwrap_or(0);
+        f.debug_struct("Registry").field("resources", &len).finish()
+    }
+}
@@ -0,0 +1,99 @@
+//! Contains `Reload` and related types and functions.
+//!
+//! See `Reload` documentation for more details.
+
+use futures::future::MapErr;
+use futures::{Async, Future, Poll, Stream};
+use tower_service::Service;
+
+type Error = Box<::std::error::Error + Send + Sync>;
+
+/// A `MakeService` that switches to new make services as they are received,
+/// e.g. when a server's configuration is reloaded.
+///
+/// Each make service yielded by `updates` replaces the current one. The
+/// switch happens in `poll_ready`, so every call is made to the make service
+/// that was ready for it. Services that were already made, such as those
+/// serving existing connections, are not affected: they keep running on the
+/// old stack until they are dropped, while new connections use the new one.
+///
+/// Once `updates` ends, the current make service is used from then on.
+#[derive(Debug)]
+pub struct Reload<M, U> {
+    current: M,
+    updates: Option<U>,
+    reloads: usize,
+}
+
+impl<M, U> Reload<M, U>
+where
+    U: Stream<Item = M>,
+{
+    /// Create a new `Reload` starting with `make`, and switching to each make
+    /// service yielded by `updates`.
+    pub fn new(make: M, updates: U) -> Self {
+        Reload {
+            current: make,
+            updates: Some(updates),
+            reloads: 0,
+        }
+    }
+
+    /// Returns how many times the make service has been replaced.
+    pub fn reloads(&self) -> usize {
+        self.reloads
+    }
+
+    /// Get a reference to the current make service
+    pub fn get_ref(&self) -> &M {
+        &self.current
+    }
+
+    /// Get a mutable reference to the current make service
+    pub fn get_mut(&mut self) -> &mut M {
+        &mut self.current
+    }
+
+    /// Switch to the latest update, if any.
+    fn poll_updates(&mut self) -> Result<(), U::Error> {
+        loop {
+            let update = match self.updates {
+                Some(ref mut updates) => updates.poll()?,
+                None => return Ok(()),
+            };
+
+            match update {
+                Async::Ready(Some(make)) => {
+                    self.current = make;
+                    self.reloads += 1;
+                }
+                Async::Ready(None) => {
+                    self.updates = None;
+                    return Ok(());
+                }
+                Async::NotReady => return Ok(()),
+            }
+        }
+    }
+}
+
+impl<M, U, Target> Service<Target> for Reload<M, U>
+where
+    M: Service<Target>,
+    M::Error: Into<Error>,
+    U: Stream<Item = M>,
+    U::Error: Into<Error>,
+{
+    type Response = M::Response;
+    type Error = Error;
+    type Future = MapErr<M::Future, fn(M::Error) -> Error>;
+
+    fn poll_ready(&mut self) -> Poll<(), Self::Error> {
+        self.poll_updates().map_err(Into::into)?;
+        self.current.poll_ready().map_err(Into::into)
+    }
+
+    fn call(&mut self, target: Target) -> Self::Future {
+        self.current.call(target).map_err(Into::into as fn(_) -> _)
+    }
+}
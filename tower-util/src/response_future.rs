@@ -0,0 +1,68 @@
+use futures::{Future, Poll};
+
+/// A response future for middleware that may fail a request without calling
+/// the inner service.
+///
+/// Many middleware decide in `call` whether to forward a request, e.g.
+/// because a limit was reached or the request is invalid. `ResponseFuture`
+/// either drives the inner service's future, converting its error into `E`,
+/// or fails with an error decided up front, so that such middleware need
+/// not define their own future.
+///
+/// ```
+/// # extern crate futures;
+/// # extern crate tower_util;
+/// # use futures::{future, Future};
+/// # use tower_util::future::ResponseFuture;
+/// # fn main() {
+/// let called = ResponseFuture::<_, String>::called(future::ok::<_, &str>(1));
+/// assert_eq!(called.wait(), Ok(1));
+///
+/// let failed = ResponseFuture::<future::FutureResult<u32, &str>, _>::failed("rejected");
+/// assert_eq!(failed.wait(), Err("rejected".to_string()));
+/// # }
+/// ```
+#[derive(Debug)]
+pub struct ResponseFuture<F, E> {
+    state: State<F, E>,
+}
+
+#[derive(Debug)]
+enum State<F, E> {
+    Called(F),
+    Failed(Option<E>),
+}
+
+impl<F, E> ResponseFuture<F, E> {
+    /// Returns a future resolving with the response to a request forwarded
+    /// to the inner service.
+    pub fn called(future: F) -> Self {
+        ResponseFuture {
+            state: State::Called(future),
+        }
+    }
+
+    /// Returns a future failing with `error`, for a request that was not
+    /// forwarded.
+    pub fn failed<T: Into<E>>(error: T) -> Self {
+        ResponseFuture {
+            state: State::Failed(Some(error.into())),
+        }
+    }
+}
+
+impl<F, E> Future for ResponseFuture<F, E>
+where
+    F: Future,
+    F::Error: Into<E>,
+{
+    type Item = F::Item;
+    type Error = E;
+
+    fn poll(&mut self) -> Poll<Self::Item, Self::Error> {
+        match self.state {
+            State::Called(ref mut future) => future.poll().map_err(Into::into),
+            State::Failed(ref mut error) => Err(error.take().expect("polled after error")),
+        }
+    }
+}
@@ -1,15 +1,39 @@
-use futures::{IntoFuture, Poll};
+use crate::{PollReadyN, Unready};
+use futures::{Async, IntoFuture, Poll};
 use tower_service::Service;
 
+/// Returns a new `ServiceFn` with the given closure.
+///
+/// The closure receives each request and returns anything that can be
+/// converted into a future of the response.
+///
+/// ```
+/// # extern crate futures;
+/// # extern crate tower_service;
+/// # extern crate tower_util;
+/// # use futures::Future;
+/// # use tower_service::Service;
+/// # use tower_util::service_fn;
+/// # fn main() {
+/// let mut svc = service_fn(|req: u32| Ok::<_, ()>(req + 1));
+///
+/// assert_eq!(svc.call(1).wait(), Ok(2));
+/// # }
+/// ```
+pub fn service_fn<T>(f: T) -> ServiceFn<T> {
+    ServiceFn { f }
+}
+
 /// A `Service` implemented by a closure.
+#[derive(Copy, Clone, Debug)]
 pub struct ServiceFn<T> {
     f: T,
 }
 
 impl<T> ServiceFn<T> {
-    /// Returns a new `NewServiceFn` with the given closure.
+    /// Returns a new `ServiceFn` with the given closure.
     pub fn new(f: T) -> Self {
-        ServiceFn { f }
+        service_fn(f)
     }
 }
 
@@ -30,3 +54,21 @@ where
         (self.f)(req).into_future()
     }
 }
+
+impl<T, F, Request> PollReadyN<Request> for ServiceFn<T>
+where
+    T: Fn(Request) -> F,
+    F: IntoFuture,
+{
+    fn poll_ready_n(&mut self, n: usize) -> Poll<usize, F::Error> {
+        // A closure is always ready, so every requested call can be reserved.
+        Ok(Async::Ready(n))
+    }
+}
+
+impl<T, F, Request> Unready<Request> for ServiceFn<T>
+where
+    T: Fn(Request) -> F,
+    F: IntoFuture,
+{
+}
@@ -0,0 +1,56 @@
+use futures::Poll;
+use std::sync::{Arc, Mutex};
+use tower_service::Service;
+
+/// A `MakeService` that can be cloned cheaply and shared across tasks.
+///
+/// The inner `MakeService` is kept behind an `Arc<Mutex<_>>`, so it does not
+/// need to be `Clone` itself. This is useful when an accept loop runs on
+/// multiple threads, each of which needs its own handle to the maker.
+///
+/// Since all clones share the same inner service, readiness observed through
+/// one clone may be consumed by a call through another.
+#[derive(Debug)]
+pub struct SharedMakeService<M> {
+    inner: Arc<Mutex<M>>,
+}
+
+impl<M> SharedMakeService<M> {
+    /// Create a new `SharedMakeService` wrapping `make`.
+    pub fn new(make: M) -> Self {
+        SharedMakeService {
+            inner: Arc::new(Mutex::new(make)),
+        }
+    }
+}
+
+impl<M> Clone for SharedMakeService<M> {
+    fn clone(&self) -> Self {
+        SharedMakeService {
+            inner: self.inner.clone(),
+        }
+    }
+}
+
+impl<M, Target> Service<Target> for SharedMakeService<M>
+where
+    M: Service<Target>,
+{
+    type Response = M::Response;
+    type Error = M::Error;
+    type Future = M::Future;
+
+    fn poll_ready(&mut self) -> Poll<(), Self::Error> {
+        self.inner
+            .lock()
+            .expect("shared make service poisoned")
+            .poll_ready()
+    }
+
+    fn call(&mut self, target: Target) -> Self::Future {
+        self.inner
+            .lock()
+            .expect("shared make service poisoned")
+            .call(target)
+    }
+}
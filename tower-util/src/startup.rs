@@ -0,0 +1,133 @@
+//! Contains `StartupGate` and related types and functions.
+//!
+//! See `StartupGate` documentation for more details.
+
+use crate::layer::Never;
+use futures::future::{MapErr, Shared, SharedError};
+use futures::{Async, Future, Poll};
+use tower_layer::Layer;
+use tower_service::Service;
+
+type Error = Box<::std::error::Error + Send + Sync>;
+
+/// Holds a service unready until a one-time initialization completes.
+///
+/// `poll_ready` returns `NotReady` until the `init` future resolves (e.g.
+/// after warming caches or fetching configuration). From then on, the gate is
+/// transparent. If initialization fails, the error is returned from
+/// `poll_ready`.
+#[derive(Debug)]
+pub struct StartupGate<S, F> {
+    inner: S,
+    init: Option<F>,
+}
+
+/// Applies a `StartupGate` to services, all waiting on the same
+/// initialization.
+#[derive(Debug)]
+pub struct StartupGateLayer<F: Future> {
+    init: Shared<F>,
+}
+
+// ===== impl StartupGate =====
+
+impl<S, F> StartupGate<S, F> {
+    /// Create a new `StartupGate` that holds `inner` unready until `init`
+    /// completes.
+    pub fn new(inner: S, init: F) -> Self {
+        StartupGate {
+            inner,
+            init: Some(init),
+        }
+    }
+
+    /// Returns `true` if initialization has completed.
+    pub fn is_open(&self) -> bool {
+        self.init.is_none()
+    }
+
+    /// Get a reference to the inner service
+    pub fn get_ref(&self) -> &S {
+        &self.inner
+    }
+
+    /// Get a mutable reference to the inner service
+    pub fn get_mut(&mut self) -> &mut S {
+        &mut self.inner
+    }
+
+    /// Consume `self`, returning the inner service
+    pub fn into_inner(self) -> S {
+        self.inner
+    }
+}
+
+impl<S, F, Request> Service<Request> for StartupGate<S, F>
+where
+    S: Service<Request>,
+    S::Error: Into<Error>,
+    F: Future,
+    F::Error: Into<Error>,
+{
+    type Response = S::Response;
+    type Error = Error;
+    type Future = MapErr<S::Future, fn(S::Error) -> Error>;
+
+    fn poll_ready(&mut self) -> Poll<(), Self::Error> {
+        if let Some(ref mut init) = self.init {
+            match init.poll() {
+                Ok(Async::Ready(_)) => {}
+                Ok(Async::NotReady) => return Ok(Async::NotReady),
+                Err(e) => return Err(e.into()),
+            }
+        }
+        self.init = None;
+
+        self.inner.poll_ready().map_err(Into::into)
+    }
+
+    fn call(&mut self, request: Request) -> Self::Future {
+        assert!(self.is_open(), "StartupGate::call before poll_ready");
+
+        self.inner.call(request).map_err(Into::into as fn(_) -> _)
+    }
+}
+
+// ===== impl StartupGateLayer =====
+
+impl<F: Future> StartupGateLayer<F> {
+    /// Create a new `StartupGateLayer` waiting on `init`.
+    ///
+    /// `init` is driven by whichever service polls it first, and completes
+    /// for all services at once.
+    pub fn new(init: F) -> Self {
+        StartupGateLayer {
+            init: init.shared(),
+        }
+    }
+}
+
+impl<F: Future> Clone for StartupGateLayer<F> {
+    fn clone(&self) -> Self {
+        StartupGateLayer {
+            init: self.init.clone(),
+        }
+    }
+}
+
+impl<S, F, Request> Layer<S, Request> for StartupGateLayer<F>
+where
+    S: Service<Request>,
+    S::Error: Into<Error>,
+    F: Future,
+    SharedError<F::Error>: Into<Error>,
+{
+    type Response = S::Response;
+    type Error = Error;
+    type LayerError = Never;
+    type Service = StartupGate<S, Shared<F>>;
+
+    fn layer(&self, service: S) -> Result<Self::Service, Self::LayerError> {
+        Ok(StartupGate::new(service, self.init.clone()))
+    }
+}
@@ -0,0 +1,161 @@
+use futures::{Async, Poll};
+use std::sync::{Arc, Mutex, Weak};
+use tower_service::Service;
+
+/// Why a service is not ready, as reported by `Unready::unready_reason`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum UnreadyReason {
+    /// The maximum number of requests are in flight.
+    InFlightLimit,
+    /// The rate limit has been reached.
+    RateLimited,
+    /// A queue in front of the service, such as a buffer, is full.
+    QueueFull,
+    /// Requests are refused while a circuit breaker is open.
+    CircuitOpen,
+    /// The service is draining, and won't accept new requests.
+    Draining,
+    /// Another reason, described by the middleware reporting it.
+    Other(&'static str),
+}
+
+/// A `Service` that can explain why it is not ready.
+///
+/// `poll_ready` returning `NotReady` says nothing about the cause, so it can
+/// be hard to tell why a stack of middleware is stuck. After `poll_ready` has
+/// returned `NotReady`, `unready_reason` returns the reason reported by the
+/// layer that is holding back requests.
+///
+/// Middleware that does not hold back requests itself should return the
+/// reason of its inner service. Services that cannot tell why they are not
+/// ready may implement this trait with an empty `impl` block, in which case
+/// `None` is returned.
+pub trait Unready<Request>: Service<Request> {
+    /// Returns why the service is not ready, if known.
+    ///
+    /// The result is only meaningful right after `poll_ready` returned
+    /// `NotReady`.
+    fn unready_reason(&self) -> Option<UnreadyReason> {
+        None
+    }
+}
+
+/// Collects why the watched services are not ready, so that every stuck
+/// stack of a process can be found in one place, e.g. by a health endpoint.
+///
+/// Services are watched by wrapping them with `UnreadyWatch::watch`. Each time
+/// a watched service is polled, it records whether it is ready, and why not
+/// if it is not, and `unready` lists the services last found not ready.
+/// Services are forgotten once dropped. Cloning the `UnreadyWatch` yields a
+/// handle to the same services.
+#[derive(Clone, Debug, Default)]
+pub struct UnreadyWatch {
+    services: Arc<Mutex<Vec<(String, Weak<Mutex<Readiness>>)>>>,
+}
+
+/// A service reporting its readiness to an `UnreadyWatch`.
+#[derive(Debug)]
+pub struct Watched<S> {
+    inner: S,
+    readiness: Arc<Mutex<Readiness>>,
+}
+
+#[derive(Debug)]
+enum Readiness {
+    Ready,
+    NotReady(Option<UnreadyReason>),
+}
+
+// ===== impl UnreadyWatch =====
+
+impl UnreadyWatch {
+    /// Create a new `UnreadyWatch`, watching no services.
+    pub fn new() -> Self {
+        UnreadyWatch::default()
+    }
+
+    /// Watch `inner` under `name`.
+    ///
+    /// Several services may be watched under the same name, e.g. the clones
+    /// of a stack, in which case each is listed separately.
+    pub fn watch<N, S>(&self, name: N, inner: S) -> Watched<S>
+    where
+        N: Into<String>,
+    {
+        let readiness = Arc::new(Mutex::new(Readiness::Ready));
+        self.services
+            .lock()
+            .expect("unready watch poisoned")
+            .push((name.into(), Arc::downgrade(&readiness)));
+
+        Watched { inner, readiness }
+    }
+
+    /// Returns the names of the watched services that were not ready when
+    /// last polled, with the reason each reported, if any.
+    pub fn unready(&self) -> Vec<(String, Option<UnreadyReason>)> {
+        let mut services = self.services.lock().expect("unready watch poisoned");
+        services.retain(|&(_, ref readiness)| readiness.upgrade().is_some());
+
+        services
+            .iter()
+            .filter_map(|&(ref name, ref readiness)| {
+                let readiness = readiness.upgrade()?;
+                let readiness = readiness.lock().expect("readiness poisoned");
+                match *readiness {
+                    Readiness::Ready => None,
+                    Readiness::NotReady(reason) => Some((name.clone(), reason)),
+                }
+            })
+            .collect()
+    }
+}
+
+// ===== impl Watched =====
+
+impl<S> Watched<S> {
+    /// Get a reference to the inner service
+    pub fn get_ref(&self) -> &S {
+        &self.inner
+    }
+
+    /// Get a mutable reference to the inner service
+    pub fn get_mut(&mut self) -> &mut S {
+        &mut self.inner
+    }
+}
+
+impl<S, Request> Service<Request> for Watched<S>
+where
+    S: Unready<Request>,
+{
+    type Response = S::Response;
+    type Error = S::Error;
+    type Future = S::Future;
+
+    fn poll_ready(&mut self) -> Poll<(), Self::Error> {
+        let ready = self.inner.poll_ready();
+
+        // A failed service is not stuck, so it is reported as ready.
+        let readiness = match ready {
+            Ok(Async::NotReady) => Readiness::NotReady(self.inner.unready_reason()),
+            _ => Readiness::Ready,
+        };
+        *self.readiness.lock().expect("readiness poisoned") = readiness;
+
+        ready
+    }
+
+    fn call(&mut self, request: Request) -> Self::Future {
+        self.inner.call(request)
+    }
+}
+
+impl<S, Request> Unready<Request> for Watched<S>
+where
+    S: Unready<Request>,
+{
+    fn unready_reason(&self) -> Option<UnreadyReason> {
+        self.inner.unready_reason()
+    }
+}
@@ -0,0 +1,46 @@
+extern crate futures;
+extern crate tower_service;
+extern crate tower_util;
+
+use futures::future::{self, FutureResult};
+use futures::{Future, Poll};
+use tower_service::Service;
+use tower_util::{AlwaysReady, PollReadyN};
+
+/// A service that must never be polled for readiness.
+struct Pure;
+
+impl Service<u32> for Pure {
+    type Response = u32;
+    type Error = ();
+    type Future = FutureResult<u32, ()>;
+
+    fn poll_ready(&mut self) -> Poll<(), ()> {
+        panic!("AlwaysReady polled the inner service");
+    }
+
+    fn call(&mut self, req: u32) -> Self::Future {
+        future::ok(req * 2)
+    }
+}
+
+#[test]
+fn skips_inner_readiness() {
+    let mut svc = AlwaysReady::new(Pure);
+
+    assert!(svc.poll_ready().unwrap().is_ready());
+    assert_eq!(svc.call(21).wait(), Ok(42));
+}
+
+#[test]
+fn reserves_every_call() {
+    let mut svc = AlwaysReady::new(Pure);
+
+    let reserved = svc.poll_ready_n(16).unwrap();
+    assert!(reserved.is_ready());
+    assert_eq!(reserved, futures::Async::Ready(16));
+
+    for i in 0..16 {
+        assert_eq!(svc.call(i).wait(), Ok(i * 2));
+    }
+}
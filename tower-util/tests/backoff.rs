@@ -0,0 +1,77 @@
+extern crate rand;
+extern crate tower_util;
+
+use rand::rngs::SmallRng;
+use rand::SeedableRng;
+use std::time::Duration;
+use tower_util::backoff::{Backoff, Jitter};
+
+fn ms(n: u64) -> Duration {
+    Duration::from_millis(n)
+}
+
+fn rng() -> SmallRng {
+    SmallRng::from_seed([7; 16])
+}
+
+#[test]
+fn grows_exponentially_up_to_max() {
+    let mut rng = rng();
+    let mut backoff = Backoff::new(ms(10), ms(50));
+
+    let delays: Vec<_> = (0..5).map(|_| backoff.next_delay(&mut rng)).collect();
+    assert_eq!(delays, vec![ms(10), ms(20), ms(40), ms(50), ms(50)]);
+
+    backoff.reset();
+    assert_eq!(backoff.next_delay(&mut rng), ms(10));
+}
+
+#[test]
+fn full_jitter() {
+    let mut rng = rng();
+    let mut backoff = Backoff::new(ms(10), ms(1000)).jitter(Jitter::Full);
+
+    let mut exp = ms(10);
+    for _ in 0..10 {
+        assert!(backoff.next_delay(&mut rng) <= exp);
+        exp = ::std::cmp::min(exp * 2, ms(1000));
+    }
+}
+
+#[test]
+fn equal_jitter() {
+    let mut rng = rng();
+    let mut backoff = Backoff::new(ms(10), ms(1000)).jitter(Jitter::Equal);
+
+    let mut exp = ms(10);
+    for _ in 0..10 {
+        let delay = backoff.next_delay(&mut rng);
+        assert!(delay >= exp / 2 && delay <= exp, "{:?} not in {:?}", delay, exp);
+        exp = ::std::cmp::min(exp * 2, ms(1000));
+    }
+}
+
+#[test]
+fn decorrelated_jitter() {
+    let mut rng = rng();
+    let mut backoff = Backoff::new(ms(10), ms(1000)).jitter(Jitter::Decorrelated);
+
+    let mut prev = ms(10);
+    for _ in 0..20 {
+        let delay = backoff.next_delay(&mut rng);
+        assert!(delay >= ms(10), "{:?} below base", delay);
+        assert!(delay <= ::std::cmp::min(prev * 3, ms(1000)));
+        prev = delay;
+    }
+}
+
+#[test]
+fn seeded_delays_are_deterministic() {
+    let mut a = Backoff::new(ms(10), ms(1000)).jitter(Jitter::Full);
+    let mut b = a.clone();
+    let (mut rng_a, mut rng_b) = (rng(), rng());
+
+    for _ in 0..10 {
+        assert_eq!(a.next_delay(&mut rng_a), b.next_delay(&mut rng_b));
+    }
+}
@@ -0,0 +1,93 @@
+extern crate futures;
+extern crate tower_layer;
+extern crate tower_service;
+extern crate tower_util;
+
+use futures::future::{ok, FutureResult};
+use futures::{Future, Poll};
+use std::cell::Cell;
+use std::rc::Rc;
+use tower_service::Service;
+use tower_layer::Layer;
+use tower_util::layer::{BoxLayer, Identity};
+use tower_util::{BoxCloneService, BoxService, UnsyncBoxService};
+
+/// A service backed by an `Rc`, which is neither `Send` nor `Sync`.
+struct Counter {
+    count: Rc<Cell<usize>>,
+}
+
+impl Service<()> for Counter {
+    type Response = usize;
+    type Error = ();
+    type Future = FutureResult<usize, ()>;
+
+    fn poll_ready(&mut self) -> Poll<(), ()> {
+        Ok(().into())
+    }
+
+    fn call(&mut self, _: ()) -> Self::Future {
+        self.count.set(self.count.get() + 1);
+        ok(self.count.get())
+    }
+}
+
+#[test]
+fn unsync_box_service() {
+    let count = Rc::new(Cell::new(0));
+    let mut service: UnsyncBoxService<(), usize, ()> = UnsyncBoxService::new(Counter {
+        count: count.clone(),
+    });
+
+    assert!(service.poll_ready().unwrap().is_ready());
+    assert_eq!(service.call(()).wait(), Ok(1));
+
+    assert!(service.poll_ready().unwrap().is_ready());
+    assert_eq!(service.call(()).wait(), Ok(2));
+
+    assert_eq!(count.get(), 2);
+}
+
+#[derive(Clone)]
+struct Echo;
+
+impl Service<&'static str> for Echo {
+    type Response = &'static str;
+    type Error = ();
+    type Future = FutureResult<&'static str, ()>;
+
+    fn poll_ready(&mut self) -> Poll<(), ()> {
+        Ok(().into())
+    }
+
+    fn call(&mut self, req: &'static str) -> Self::Future {
+        ok(req)
+    }
+}
+
+#[test]
+fn box_clone_service() {
+    let mut service: BoxCloneService<&'static str, &'static str, ()> = BoxCloneService::new(Echo);
+    let mut cloned = service.clone();
+
+    assert!(service.poll_ready().unwrap().is_ready());
+    assert_eq!(service.call("hello").wait(), Ok("hello"));
+
+    assert!(cloned.poll_ready().unwrap().is_ready());
+    assert_eq!(cloned.call("world").wait(), Ok("world"));
+}
+
+#[test]
+fn box_layer() {
+    type Svc = BoxService<&'static str, &'static str, ()>;
+
+    let layers: Vec<BoxLayer<Svc, &'static str, &'static str, ()>> =
+        vec![BoxLayer::new(Identity::new()), BoxLayer::new(Identity::new())];
+
+    let mut service = layers
+        .iter()
+        .fold(BoxService::new(Echo), |svc, layer| layer.layer(svc).unwrap());
+
+    assert!(service.poll_ready().unwrap().is_ready());
+    assert_eq!(service.call("hello").wait(), Ok("hello"));
+}
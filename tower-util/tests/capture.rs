@@ -0,0 +1,52 @@
+extern crate futures;
+extern crate tower_service;
+extern crate tower_util;
+
+use futures::{Future, Stream};
+use tower_service::Service;
+use tower_util::{service_fn, Capture};
+
+#[test]
+fn records_requests_and_responses() {
+    let inner = service_fn(|n: u32| if n > 0 { Ok(n * 2) } else { Err("zero") });
+    let (mut svc, records) = Capture::new(
+        inner,
+        |request: &u32| {
+            let request = *request;
+            move |result: Result<&u32, &&'static str>| (request, result.ok().cloned())
+        },
+        10,
+    );
+
+    assert_eq!(svc.call(1).wait(), Ok(2));
+    assert_eq!(svc.call(0).wait(), Err("zero"));
+    drop(svc);
+
+    let records = records.collect().wait().unwrap();
+    assert_eq!(records, vec![(1, Some(2)), (0, None)]);
+}
+
+#[test]
+fn drops_records_when_full() {
+    let inner = service_fn(|n: u32| Ok::<_, ()>(n));
+    let (mut svc, mut records) = Capture::new(
+        inner,
+        |request: &u32| {
+            let request = *request;
+            move |_: Result<&u32, &()>| request
+        },
+        2,
+    );
+
+    for n in 0..3 {
+        assert_eq!(svc.call(n).wait(), Ok(n));
+    }
+    assert_eq!(records.dropped(), 1);
+
+    // Draining the stream makes room for more records.
+    assert_eq!(records.by_ref().take(2).collect().wait(), Ok(vec![0, 1]));
+    assert_eq!(svc.call(3).wait(), Ok(3));
+    drop(svc);
+
+    assert_eq!(records.collect().wait(), Ok(vec![3]));
+}
@@ -0,0 +1,32 @@
+extern crate futures;
+extern crate tower_service;
+extern crate tower_util;
+
+use futures::Future;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+use tower_service::Service;
+use tower_util::{service_fn, Degrade};
+
+#[test]
+fn routes_by_pressure() {
+    let pressure = Arc::new(AtomicBool::new(false));
+    let signal = pressure.clone();
+
+    let primary = service_fn(|req: &'static str| Ok::<_, ()>(format!("primary {}", req)));
+    let degraded = service_fn(|_: &'static str| Ok::<_, ()>("degraded".to_string()));
+    let mut svc = Degrade::new(primary, degraded, move || signal.load(Ordering::SeqCst));
+
+    assert!(svc.poll_ready().unwrap().is_ready());
+    assert!(!svc.is_degraded());
+    assert_eq!(svc.call("hello").wait().unwrap(), "primary hello");
+
+    pressure.store(true, Ordering::SeqCst);
+    assert!(svc.poll_ready().unwrap().is_ready());
+    assert!(svc.is_degraded());
+    assert_eq!(svc.call("hello").wait().unwrap(), "degraded");
+
+    pressure.store(false, Ordering::SeqCst);
+    assert!(svc.poll_ready().unwrap().is_ready());
+    assert_eq!(svc.call("hello").wait().unwrap(), "primary hello");
+}
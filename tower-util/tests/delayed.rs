@@ -0,0 +1,38 @@
+#![cfg(feature = "timer")]
+
+extern crate futures;
+extern crate tokio_mock_task;
+extern crate tower_mock;
+extern crate tower_util;
+
+use futures::{Async, Future};
+use std::time::Duration;
+use tokio_mock_task::MockTask;
+use tower_mock::clock::MockClock;
+use tower_util::backoff::Delayed;
+
+#[test]
+fn yields_value_once_elapsed() {
+    let mut task = MockTask::new();
+
+    MockClock::new().enter(|clock| {
+        let mut delayed = Delayed::new("again", Duration::from_secs(10));
+        assert!(task.enter(|| delayed.poll()).unwrap().is_not_ready());
+
+        clock.advance(Duration::from_secs(9));
+        assert!(task.enter(|| delayed.poll()).unwrap().is_not_ready());
+
+        clock.advance(Duration::from_secs(1));
+        assert!(task.is_notified());
+        assert_eq!(task.enter(|| delayed.poll()), Ok(Async::Ready("again")));
+    });
+}
+
+#[test]
+fn yields_value_if_timer_fails() {
+    let mut task = MockTask::new();
+
+    // Without a timer, the delay fails rather than never elapsing.
+    let mut delayed = Delayed::new("again", Duration::from_secs(10));
+    assert_eq!(task.enter(|| delayed.poll()), Ok(Async::Ready("again")));
+}
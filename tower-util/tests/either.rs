@@ -0,0 +1,63 @@
+extern crate futures;
+extern crate tower_service;
+extern crate tower_util;
+
+use futures::future::{err, ok, FutureResult};
+use futures::{Future, Poll};
+use tower_service::Service;
+use tower_util::Either;
+
+struct Succeeding;
+struct Failing;
+
+impl Service<()> for Succeeding {
+    type Response = &'static str;
+    type Error = ::std::io::Error;
+    type Future = FutureResult<Self::Response, Self::Error>;
+
+    fn poll_ready(&mut self) -> Poll<(), Self::Error> {
+        Ok(().into())
+    }
+
+    fn call(&mut self, _: ()) -> Self::Future {
+        ok("ok")
+    }
+}
+
+impl Service<()> for Failing {
+    type Response = &'static str;
+    type Error = ::std::fmt::Error;
+    type Future = FutureResult<Self::Response, Self::Error>;
+
+    fn poll_ready(&mut self) -> Poll<(), Self::Error> {
+        Ok(().into())
+    }
+
+    fn call(&mut self, _: ()) -> Self::Future {
+        err(::std::fmt::Error)
+    }
+}
+
+fn service(fail: bool) -> Either<Succeeding, Failing> {
+    if fail {
+        Either::B(Failing)
+    } else {
+        Either::A(Succeeding)
+    }
+}
+
+#[test]
+fn either_a() {
+    let mut svc = service(false);
+    assert!(svc.poll_ready().unwrap().is_ready());
+    assert_eq!(svc.call(()).wait().unwrap(), "ok");
+}
+
+#[test]
+fn either_b_boxes_error() {
+    let mut svc = service(true);
+    assert!(svc.poll_ready().unwrap().is_ready());
+
+    let e = svc.call(()).wait().unwrap_err();
+    assert!(e.is::<::std::fmt::Error>());
+}
@@ -0,0 +1,38 @@
+extern crate futures;
+extern crate tower_service;
+extern crate tower_util;
+
+use futures::future::{self, Future};
+use futures::sync::oneshot;
+use futures::Async;
+use tower_service::Service;
+use tower_util::{future_service, service_fn};
+
+#[test]
+fn not_ready_until_resolved() {
+    let (tx, rx) = oneshot::channel();
+    let rx = rx.map_err(|_| "canceled");
+    let mut svc = future_service::<_, _, u32>(rx);
+
+    future::lazy(|| {
+        assert_eq!(svc.poll_ready(), Ok(Async::NotReady));
+        Ok::<_, ()>(())
+    })
+    .wait()
+    .unwrap();
+
+    tx.send(service_fn(|req: u32| Ok::<_, &'static str>(req + 1)))
+        .ok()
+        .unwrap();
+
+    assert_eq!(svc.poll_ready(), Ok(Async::Ready(())));
+    assert_eq!(svc.call(1).wait(), Ok(2));
+}
+
+#[test]
+fn propagates_future_error() {
+    let f = future::err::<tower_util::ServiceFn<fn(u32) -> Result<u32, &'static str>>, _>("init");
+    let mut svc = future_service::<_, _, u32>(f);
+
+    assert_eq!(svc.poll_ready(), Err("init"));
+}
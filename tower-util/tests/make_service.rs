@@ -0,0 +1,96 @@
+extern crate futures;
+extern crate tower_service;
+extern crate tower_util;
+
+use futures::Future;
+use tower_service::Service;
+use tower_util::{service_fn, MakeService};
+
+/// Only sees a `Service<Target>`, as middleware applied to a `MakeService`
+/// would.
+fn make<S: Service<u32>>(mut make: S, target: u32) -> S::Response
+where
+    S::Error: ::std::fmt::Debug,
+{
+    assert!(make.poll_ready().unwrap().is_ready());
+    make.call(target).wait().unwrap()
+}
+
+fn adder(n: u32) -> Result<impl Service<u32, Response = u32, Error = ()>, ()> {
+    Ok(service_fn(move |req: u32| Ok::<_, ()>(req + n)))
+}
+
+#[test]
+fn into_service() {
+    let maker = service_fn(adder);
+    let svc = MakeService::<u32, u32>::into_service(maker);
+
+    let mut adder = make(svc, 2);
+    assert_eq!(adder.call(1).wait(), Ok(3));
+}
+
+#[test]
+fn as_service() {
+    let mut maker = service_fn(adder);
+
+    let mut adder = make(MakeService::<u32, u32>::as_service(&mut maker), 2);
+    assert_eq!(adder.call(1).wait(), Ok(3));
+
+    let mut adder = make(MakeService::<u32, u32>::as_service(&mut maker), 5);
+    assert_eq!(adder.call(1).wait(), Ok(6));
+}
+
+/// A maker that is not `Clone`.
+struct MakeAdder;
+
+impl Service<u32> for MakeAdder {
+    type Response = Adder;
+    type Error = ();
+    type Future = futures::future::FutureResult<Adder, ()>;
+
+    fn poll_ready(&mut self) -> futures::Poll<(), ()> {
+        Ok(().into())
+    }
+
+    fn call(&mut self, n: u32) -> Self::Future {
+        futures::future::ok(Adder(n))
+    }
+}
+
+struct Adder(u32);
+
+impl Service<u32> for Adder {
+    type Response = u32;
+    type Error = ();
+    type Future = futures::future::FutureResult<u32, ()>;
+
+    fn poll_ready(&mut self) -> futures::Poll<(), ()> {
+        Ok(().into())
+    }
+
+    fn call(&mut self, req: u32) -> Self::Future {
+        futures::future::ok(req + self.0)
+    }
+}
+
+#[test]
+fn shared_make_service() {
+    use std::thread;
+    use tower_util::SharedMakeService;
+
+    let maker = SharedMakeService::new(MakeAdder);
+
+    let threads: Vec<_> = (0..4)
+        .map(|i| {
+            let maker = maker.clone();
+            thread::spawn(move || {
+                let mut adder = make(maker, i);
+                adder.call(1).wait().unwrap()
+            })
+        })
+        .collect();
+
+    let mut results: Vec<u32> = threads.into_iter().map(|t| t.join().unwrap()).collect();
+    results.sort();
+    assert_eq!(results, vec![1, 2, 3, 4]);
+}
@@ -0,0 +1,38 @@
+extern crate futures;
+extern crate tower_util;
+
+use futures::sync::oneshot;
+use futures::{future, Future};
+use tower_util::MaybeReady;
+
+#[test]
+fn ready_once_resolved() {
+    let (tx, rx) = oneshot::channel::<&'static str>();
+    let mut value = MaybeReady::new(rx);
+
+    future::lazy(|| {
+        assert!(value.poll_ready().unwrap().is_not_ready());
+        Ok::<_, ()>(())
+    })
+    .wait()
+    .unwrap();
+    assert!(!value.is_ready());
+    assert_eq!(value.get_ref(), None);
+
+    tx.send("hello").unwrap();
+
+    assert!(value.poll_ready().unwrap().is_ready());
+    assert!(value.is_ready());
+    assert_eq!(value.get_ref(), Some(&"hello"));
+
+    // Once ready, the future is not polled again.
+    assert!(value.poll_ready().unwrap().is_ready());
+    assert_eq!(value.into_ready(), Some("hello"));
+}
+
+#[test]
+fn future_errors() {
+    let mut value = MaybeReady::new(future::err::<(), _>("boom"));
+
+    assert_eq!(value.poll_ready(), Err("boom"));
+}
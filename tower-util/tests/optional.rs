@@ -0,0 +1,42 @@
+extern crate futures;
+extern crate tower_service;
+extern crate tower_util;
+
+use futures::future::{ok, FutureResult};
+use futures::{Future, Poll};
+use tower_service::Service;
+use tower_util::error::optional::None;
+use tower_util::Optional;
+
+struct Echo;
+
+impl Service<&'static str> for Echo {
+    type Response = &'static str;
+    type Error = ::std::io::Error;
+    type Future = FutureResult<Self::Response, Self::Error>;
+
+    fn poll_ready(&mut self) -> Poll<(), Self::Error> {
+        Ok(().into())
+    }
+
+    fn call(&mut self, req: &'static str) -> Self::Future {
+        ok(req)
+    }
+}
+
+#[test]
+fn some() {
+    let mut svc = Optional::new(Some(Echo));
+    assert!(svc.poll_ready().unwrap().is_ready());
+    assert_eq!(svc.call("hello").wait().unwrap(), "hello");
+}
+
+#[test]
+fn none() {
+    let mut svc = Optional::new::<&'static str>(Option::None::<Echo>);
+    assert!(svc.poll_ready().unwrap().is_ready());
+
+    let e = svc.call("hello").wait().unwrap_err();
+    assert!(e.is::<None>());
+    assert_eq!(e.to_string(), "service not available");
+}
@@ -0,0 +1,38 @@
+extern crate futures;
+extern crate tower_layer;
+extern crate tower_service;
+extern crate tower_util;
+
+use futures::{stream, Future, Stream};
+use tower_layer::Layer;
+use tower_service::Service;
+use tower_util::layer::PerItemLayer;
+use tower_util::{service_fn, PerItem};
+
+#[test]
+fn applies_service_to_each_item() {
+    let inner = service_fn(|n: u32| Ok::<_, ()>(stream::iter_ok::<_, ()>(0..n)));
+    let double = service_fn(|item: u32| Ok::<_, ()>(item * 2));
+    let mut svc = PerItem::new(inner, double);
+
+    assert!(svc.poll_ready().unwrap().is_ready());
+    let items = svc.call(3).wait().unwrap().collect().wait().unwrap();
+    assert_eq!(items, vec![0, 2, 4]);
+}
+
+#[test]
+fn item_errors_end_the_stream() {
+    let inner = service_fn(|n: u32| Ok::<_, ()>(stream::iter_ok::<_, ()>(0..n)));
+    let layer = PerItemLayer::new(service_fn(|item: u32| {
+        if item < 1 {
+            Ok(item)
+        } else {
+            Err("too big")
+        }
+    }));
+    let mut svc = Layer::<_, u32>::layer(&layer, inner).unwrap();
+
+    let mut items = svc.call(3).wait().unwrap().wait();
+    assert_eq!(items.next().unwrap().unwrap(), 0);
+    assert!(items.next().unwrap().is_err());
+}
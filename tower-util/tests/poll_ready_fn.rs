@@ -0,0 +1,36 @@
+extern crate futures;
+extern crate tower_service;
+extern crate tower_util;
+
+use futures::{Async, Future};
+use std::cell::Cell;
+use tower_service::Service;
+use tower_util::poll_ready_fn;
+
+#[test]
+fn readiness_from_closure() {
+    let ready = Cell::new(false);
+    let mut svc = poll_ready_fn(
+        || {
+            if ready.get() {
+                Ok(Async::Ready(()))
+            } else {
+                Ok(Async::NotReady)
+            }
+        },
+        |req: &'static str| Ok::<_, ()>(req.len()),
+    );
+
+    assert!(svc.poll_ready().unwrap().is_not_ready());
+
+    ready.set(true);
+    assert!(svc.poll_ready().unwrap().is_ready());
+    assert_eq!(svc.call("hello").wait(), Ok(5));
+}
+
+#[test]
+fn readiness_errors() {
+    let mut svc = poll_ready_fn(|| Err("closed"), |req: u32| Ok(req));
+
+    assert_eq!(svc.poll_ready(), Err("closed"));
+}
@@ -0,0 +1,36 @@
+extern crate tower_util;
+
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::Arc;
+use tower_util::Registry;
+
+#[test]
+fn shared_between_clones() {
+    let registry = Registry::new();
+    let other = registry.clone();
+
+    let a = registry.get_or_insert_with("limit", || Arc::new(AtomicUsize::new(0)));
+    a.fetch_add(1, Ordering::SeqCst);
+
+    let b = other
+        .get::<Arc<AtomicUsize>>("limit")
+        .expect("resource registered");
+    assert_eq!(b.load(Ordering::SeqCst), 1);
+}
+
+#[test]
+fn keyed_by_name_and_type() {
+    let registry = Registry::new();
+
+    registry.insert("budget", 1u32);
+    registry.insert("budget", "one");
+    registry.insert("other", 2u32);
+
+    assert_eq!(registry.get::<u32>("budget"), Some(1));
+    assert_eq!(registry.get::<&'static str>("budget"), Some("one"));
+    assert_eq!(registry.get::<u32>("other"), Some(2));
+    assert_eq!(registry.get::<u64>("budget"), None);
+
+    assert_eq!(registry.remove::<u32>("budget"), Some(1));
+    assert_eq!(registry.get::<u32>("budget"), None);
+}
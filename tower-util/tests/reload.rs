@@ -0,0 +1,53 @@
+extern crate futures;
+extern crate tokio_mock_task;
+extern crate tower_service;
+extern crate tower_util;
+
+use futures::future::{self, FutureResult};
+use futures::sync::mpsc;
+use futures::{Future, Poll, Stream};
+use tokio_mock_task::MockTask;
+use tower_service::Service;
+use tower_util::Reload;
+
+/// Makes the version of the configuration it was built with.
+struct Version(u32);
+
+impl Service<()> for Version {
+    type Response = u32;
+    type Error = &'static str;
+    type Future = FutureResult<u32, &'static str>;
+
+    fn poll_ready(&mut self) -> Poll<(), Self::Error> {
+        Ok(().into())
+    }
+
+    fn call(&mut self, _: ()) -> Self::Future {
+        future::ok(self.0)
+    }
+}
+
+#[test]
+fn switches_to_new_make_service() {
+    let mut task = MockTask::new();
+    let (tx, rx) = mpsc::unbounded();
+    let mut reload = Reload::new(Version(1), rx.map_err(|()| "updates failed"));
+
+    task.enter(|| assert!(reload.poll_ready().unwrap().is_ready()));
+    let old = reload.call(());
+
+    tx.unbounded_send(Version(2)).unwrap();
+    assert!(task.is_notified());
+
+    task.enter(|| assert!(reload.poll_ready().unwrap().is_ready()));
+    assert_eq!(reload.call(()).wait().unwrap(), 2);
+    assert_eq!(reload.reloads(), 1);
+
+    // A call made before the reload is unaffected.
+    assert_eq!(old.wait().unwrap(), 1);
+
+    // Once the updates end, the latest make service is kept.
+    drop(tx);
+    task.enter(|| assert!(reload.poll_ready().unwrap().is_ready()));
+    assert_eq!(reload.call(()).wait().unwrap(), 2);
+}
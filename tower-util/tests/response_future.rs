@@ -0,0 +1,22 @@
+extern crate futures;
+extern crate tower_util;
+
+use futures::{future, Future};
+use tower_util::future::ResponseFuture;
+
+type Error = Box<::std::error::Error + Send + Sync>;
+
+#[test]
+fn called_converts_errors() {
+    let fut = ResponseFuture::<_, Error>::called(future::ok::<_, &str>("hello"));
+    assert_eq!(fut.wait().unwrap(), "hello");
+
+    let fut = ResponseFuture::<_, Error>::called(future::err::<(), _>("boom"));
+    assert_eq!(fut.wait().unwrap_err().to_string(), "boom");
+}
+
+#[test]
+fn failed_without_calling() {
+    let fut = ResponseFuture::<future::FutureResult<(), Error>, Error>::failed("rejected");
+    assert_eq!(fut.wait().unwrap_err().to_string(), "rejected");
+}
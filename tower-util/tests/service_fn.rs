@@ -0,0 +1,29 @@
+extern crate futures;
+extern crate tower_service;
+extern crate tower_util;
+
+use futures::Future;
+use tower_service::Service;
+use tower_util::service_fn;
+
+#[test]
+fn service_fn_calls_closure() {
+    let mut svc = service_fn(|req: &'static str| Ok::<_, ()>(req.len()));
+
+    assert!(svc.poll_ready().unwrap().is_ready());
+    assert_eq!(svc.call("hello").wait(), Ok(5));
+}
+
+#[test]
+fn service_fn_into_future() {
+    let mut svc = service_fn(|req: u32| {
+        if req == 0 {
+            Err("zero")
+        } else {
+            Ok(req * 2)
+        }
+    });
+
+    assert_eq!(svc.call(2).wait(), Ok(4));
+    assert_eq!(svc.call(0).wait(), Err("zero"));
+}
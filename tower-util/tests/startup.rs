@@ -0,0 +1,73 @@
+extern crate futures;
+extern crate tokio_mock_task;
+extern crate tower_layer;
+extern crate tower_service;
+extern crate tower_util;
+
+use futures::sync::oneshot;
+use futures::{Async, Future};
+use tokio_mock_task::MockTask;
+use tower_layer::Layer;
+use tower_service::Service;
+use tower_util::layer::StartupGateLayer;
+use tower_util::{service_fn, StartupGate};
+
+#[test]
+fn unready_until_initialized() {
+    let mut task = MockTask::new();
+
+    let (tx, rx) = oneshot::channel::<()>();
+    let inner = service_fn(|req: &'static str| Ok::<_, ()>(req));
+    let mut svc = StartupGate::new(inner, rx);
+
+    task.enter(|| {
+        assert_eq!(svc.poll_ready().unwrap(), Async::NotReady);
+    });
+    assert!(!svc.is_open());
+
+    tx.send(()).unwrap();
+    assert!(task.is_notified());
+
+    task.enter(|| {
+        assert_eq!(svc.poll_ready().unwrap(), Async::Ready(()));
+    });
+    assert!(svc.is_open());
+    assert_eq!(svc.call("hello").wait().unwrap(), "hello");
+}
+
+#[test]
+fn initialization_error() {
+    let (tx, rx) = oneshot::channel::<()>();
+    let inner = service_fn(|req: &'static str| Ok::<_, ()>(req));
+    let mut svc = StartupGate::new(inner, rx);
+
+    drop(tx);
+    assert!(svc.poll_ready().is_err());
+}
+
+#[test]
+fn layer_shares_initialization() {
+    let mut task = MockTask::new();
+
+    let (tx, rx) = oneshot::channel::<()>();
+    let layer = StartupGateLayer::new(rx);
+
+    let mut a = layer
+        .layer(service_fn(|req: &'static str| Ok::<_, ()>(req)))
+        .unwrap();
+    let mut b = layer
+        .layer(service_fn(|req: &'static str| Ok::<_, ()>(req)))
+        .unwrap();
+
+    task.enter(|| {
+        assert_eq!(a.poll_ready().unwrap(), Async::NotReady);
+        assert_eq!(b.poll_ready().unwrap(), Async::NotReady);
+    });
+
+    tx.send(()).unwrap();
+
+    task.enter(|| {
+        assert_eq!(a.poll_ready().unwrap(), Async::Ready(()));
+        assert_eq!(b.poll_ready().unwrap(), Async::Ready(()));
+    });
+}
@@ -0,0 +1,88 @@
+extern crate futures;
+extern crate tower_service;
+extern crate tower_util;
+
+use futures::future::{ok, FutureResult};
+use futures::{Async, Poll};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+use tower_service::Service;
+use tower_util::{Unready, UnreadyReason, UnreadyWatch};
+
+#[derive(Clone)]
+struct Limited {
+    ready: Arc<AtomicBool>,
+}
+
+impl Service<()> for Limited {
+    type Response = ();
+    type Error = ();
+    type Future = FutureResult<(), ()>;
+
+    fn poll_ready(&mut self) -> Poll<(), Self::Error> {
+        if self.ready.load(Ordering::SeqCst) {
+            Ok(Async::Ready(()))
+        } else {
+            Ok(Async::NotReady)
+        }
+    }
+
+    fn call(&mut self, _: ()) -> Self::Future {
+        ok(())
+    }
+}
+
+impl Unready<()> for Limited {
+    fn unready_reason(&self) -> Option<UnreadyReason> {
+        Some(UnreadyReason::RateLimited)
+    }
+}
+
+#[test]
+fn lists_services_last_found_not_ready() {
+    let ready = Arc::new(AtomicBool::new(false));
+    let watch = UnreadyWatch::new();
+
+    let mut api = watch.watch(
+        "api",
+        Limited {
+            ready: ready.clone(),
+        },
+    );
+    let mut db = watch.watch(
+        "db",
+        Limited {
+            ready: Arc::new(AtomicBool::new(true)),
+        },
+    );
+
+    assert!(watch.unready().is_empty(), "nothing polled yet");
+
+    assert!(api.poll_ready().unwrap().is_not_ready());
+    assert!(db.poll_ready().unwrap().is_ready());
+    assert_eq!(
+        watch.clone().unready(),
+        vec![("api".to_string(), Some(UnreadyReason::RateLimited))]
+    );
+
+    ready.store(true, Ordering::SeqCst);
+    assert!(api.poll_ready().unwrap().is_ready());
+    assert!(watch.unready().is_empty());
+}
+
+#[test]
+fn forgets_dropped_services() {
+    let watch = UnreadyWatch::new();
+    let mut api = watch.watch(
+        "api",
+        Limited {
+            ready: Arc::new(AtomicBool::new(false)),
+        },
+    );
+
+    assert!(api.poll_ready().unwrap().is_not_ready());
+    assert_eq!(watch.unready().len(), 1);
+
+    drop(api);
+    assert!(watch.unready().is_empty());
+}
@@ -0,0 +1,288 @@
+//! Baseline services and measurement helpers for benchmarking middleware.
+//!
+//! Benchmarking a layer on its own says little unless it wraps a service
+//! with known behavior. This module provides a few such services, which
+//! middleware can be stacked on top of:
+//!
+//! * [`Noop`](struct.Noop.html) responds immediately.
+//! * [`Latency`](struct.Latency.html) responds after a fixed delay.
+//! * [`Failing`](struct.Failing.html) fails every request.
+//!
+//! [`measure`](fn.measure.html) then drives requests through the resulting
+//! stack, recording the latency of each.
+//!
+//! This module is only available with the `bench` feature.
+
+use futures::future::{self, FutureResult};
+use futures::{Async, Future, Poll};
+use std::time::{Duration, Instant};
+use std::{cmp, error, fmt};
+use timer::{clock, Delay};
+use tower_service::Service;
+
+type Error = Box<error::Error + Send + Sync>;
+
+/// A service that responds to every request immediately.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct Noop;
+
+/// A service that responds to every request after a fixed delay.
+///
+/// This must be used from within a Tokio runtime.
+#[derive(Debug, Clone, Copy)]
+pub struct Latency {
+    latency: Duration,
+}
+
+/// Response future returned by `Latency`.
+#[derive(Debug)]
+pub struct LatencyFuture {
+    delay: Delay,
+}
+
+/// A service that fails every request immediately.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct Failing;
+
+/// Error returned by `Failing`.
+#[derive(Debug)]
+pub struct Failed(());
+
+/// Drives requests through a service one at a time, measuring the latency of
+/// each.
+///
+/// Each request is made once the previous one has completed, so the
+/// latencies are not skewed by queueing. Failed requests are measured as
+/// well, and counted in `Measurement::errors`.
+///
+/// The returned future fails if the service fails to become ready.
+pub fn measure<S, I>(service: S, requests: I) -> Measure<S, I::IntoIter>
+where
+    S: Service<I::Item>,
+    I: IntoIterator,
+{
+    Measure {
+        service,
+        requests: requests.into_iter(),
+        in_flight: None,
+        started: None,
+        measurement: Measurement {
+            latencies: Vec::new(),
+            errors: 0,
+            elapsed: Duration::from_secs(0),
+        },
+    }
+}
+
+/// Future returned by `measure`.
+#[derive(Debug)]
+pub struct Measure<S, I>
+where
+    I: Iterator,
+    S: Service<I::Item>,
+{
+    service: S,
+    requests: I,
+    in_flight: Option<(S::Future, Instant)>,
+    started: Option<Instant>,
+    measurement: Measurement,
+}
+
+/// The results of `measure`.
+#[derive(Debug, Clone)]
+pub struct Measurement {
+    latencies: Vec<Duration>,
+    errors: usize,
+    elapsed: Duration,
+}
+
+// ===== impl Noop =====
+
+impl<Request> Service<Request> for Noop {
+    type Response = ();
+    type Error = Error;
+    type Future = FutureResult<(), Error>;
+
+    fn poll_ready(&mut self) -> Poll<(), Self::Error> {
+        Ok(Async::Ready(()))
+    }
+
+    fn call(&mut self, _: Request) -> Self::Future {
+        future::ok(())
+    }
+}
+
+// ===== impl Latency =====
+
+impl Latency {
+    /// Create a new `Latency` responding after `latency`.
+    pub fn new(latency: Duration) -> Self {
+        Latency { latency }
+    }
+}
+
+impl<Request> Service<Request> for Latency {
+    type Response = ();
+    type Error = Error;
+    type Future = LatencyFuture;
+
+    fn poll_ready(&mut self) -> Poll<(), Self::Error> {
+        Ok(Async::Ready(()))
+    }
+
+    fn call(&mut self, _: Request) -> Self::Future {
+        LatencyFuture {
+            delay: Delay::new(clock::now() + self.latency),
+        }
+    }
+}
+
+impl Future for LatencyFuture {
+    type Item = ();
+    type Error = Error;
+
+    fn poll(&mut self) -> Poll<(), Error> {
+        self.delay.poll().map_err(Into::into)
+    }
+}
+
+// ===== impl Failing =====
+
+impl<Request> Service<Request> for Failing {
+    type Response = ();
+    type Error = Error;
+    type Future = FutureResult<(), Error>;
+
+    fn poll_ready(&mut self) -> Poll<(), Self::Error> {
+        Ok(Async::Ready(()))
+    }
+
+    fn call(&mut self, _: Request) -> Self::Future {
+        future::err(Failed(()).into())
+    }
+}
+
+impl fmt::Display for Failed {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.pad("request failed")
+    }
+}
+
+impl error::Error for Failed {}
+
+// ===== impl Measure =====
+
+impl<S, I> Future for Measure<S, I>
+where
+    I: Iterator,
+    S: Service<I::Item>,
+{
+    type Item = Measurement;
+    type Error = S::Error;
+
+    fn poll(&mut self) -> Poll<Measurement, S::Error> {
+        let started = *self.started.get_or_insert_with(clock::now);
+
+        loop {
+            if let Some((ref mut fut, at)) = self.in_flight {
+                let failed = match fut.poll() {
+                    Ok(Async::NotReady) => return Ok(Async::NotReady),
+                    Ok(Async::Ready(_)) => false,
+                    Err(_) => true,
+                };
+
+                self.measurement.latencies.push(clock::now() - at);
+                if failed {
+                    self.measurement.errors += 1;
+                }
+            }
+            self.in_flight = None;
+
+            try_ready!(self.service.poll_ready());
+
+            match self.requests.next() {
+                Some(request) => {
+                    let at = clock::now();
+                    self.in_flight = Some((self.service.call(request), at));
+                }
+                None => {
+                    self.measurement.elapsed = clock::now() - started;
+                    return Ok(Async::Ready(self.measurement.clone()));
+                }
+            }
+        }
+    }
+}
+
+// ===== impl Measurement =====
+
+impl Measurement {
+    /// Returns the number of requests made.
+    pub fn count(&self) -> usize {
+        self.latencies.len()
+    }
+
+    /// Returns the number of requests that failed.
+    pub fn errors(&self) -> usize {
+        self.errors
+    }
+
+    /// Returns the time taken to make all of the requests.
+    pub fn elapsed(&self) -> Duration {
+        self.elapsed
+    }
+
+    /// Returns the latency of each request, in the order they were made.
+    pub fn latencies(&self) -> &[Duration] {
+        &self.latencies
+    }
+
+    /// Returns the mean latency of a request.
+    ///
+    /// Returns `None` if no requests were made.
+    pub fn mean(&self) -> Option<Duration> {
+        if self.latencies.is_empty() {
+            return None;
+        }
+
+        let total = self
+            .latencies
+            .iter()
+            .fold(Duration::from_secs(0), |total, latency| total + *latency);
+        Some(total / self.latencies.len() as u32)
+    }
+
+    /// Returns the latency below which `p` percent of requests completed.
+    ///
+    /// Returns `None` if no requests were made.
+    ///
+    /// # Panics
+    ///
+    /// This function panics if `p` is not within `0.0..=100.0`.
+    pub fn percentile(&self, p: f64) -> Option<Duration> {
+        assert!(p >= 0.0 && p <= 100.0, "percentile must be within 0..=100");
+
+        if self.latencies.is_empty() {
+            return None;
+        }
+
+        let mut sorted = self.latencies.clone();
+        sorted.sort();
+
+        let rank = (p / 100.0 * sorted.len() as f64).ceil() as usize;
+        let idx = cmp::min(rank.saturating_sub(1), sorted.len() - 1);
+        Some(sorted[idx])
+    }
+
+    /// Returns the number of requests completed per second.
+    pub fn throughput(&self) -> f64 {
+        let secs =
+            self.elapsed.as_secs() as f64 + f64::from(self.elapsed.subsec_nanos()) / 1e9;
+
+        if secs == 0.0 {
+            return 0.0;
+        }
+
+        self.latencies.len() as f64 / secs
+    }
+}
@@ -1,13 +1,17 @@
 //! Builder types to compose layers and services
 
+#[cfg(feature = "time")]
+pub mod presets;
+pub mod order;
 mod service;
 
+#[cfg(feature = "time")]
+pub use self::presets::{ClientConfig, ServerConfig};
 pub use self::service::{LayeredMakeService, ServiceFuture};
+pub use tower_util::layer::{Chain, Identity};
 
 use tower_layer::Layer;
 use tower_service::Service;
-use tower_util::layer::{Chain, Identity};
-use tower_util::MakeService;
 
 pub(super) type Error = Box<::std::error::Error + Send + Sync>;
 
@@ -150,11 +154,67 @@ pub(super) type Error = Box<::std::error::Error + Send + Sync>;
 ///     .layer(RateLimitLayer::new(5, Duration::from_secs(1)))
 ///     .build_service(MyService);
 /// ```
+///
+/// # Extending the builder
+///
+/// Crates providing their own layers may add named methods to the builder
+/// (e.g. `.my_auth()`) through an extension trait implemented for
+/// `ServiceBuilder<L>`. See [`Layered`](type.Layered.html) for an example.
 #[derive(Debug)]
 pub struct ServiceBuilder<L> {
     layer: L,
 }
 
+/// The builder returned by adding the layer `T` to a `ServiceBuilder<L>`.
+///
+/// This names the return type of builder methods that add a layer, which is
+/// needed by extension traits adding such methods to `ServiceBuilder`:
+///
+/// ```
+/// # extern crate tower;
+/// # extern crate tower_in_flight_limit;
+/// # extern crate futures;
+/// # use tower::Service;
+/// # use futures::{Poll, future::{self, FutureResult}};
+/// # #[derive(Debug)]
+/// # struct MyService;
+/// # impl Service<()> for MyService {
+/// #    type Response = ();
+/// #    type Error = &'static str;
+/// #    type Future = FutureResult<Self::Response, Self::Error>;
+/// #    fn poll_ready(&mut self) -> Poll<(), Self::Error> {
+/// #        Ok(().into())
+/// #    }
+/// #    fn call(&mut self, _: ()) -> Self::Future {
+/// #        future::ok(())
+/// #    }
+/// # }
+/// use tower::builder::{Layered, ServiceBuilder};
+/// use tower_in_flight_limit::InFlightLimitLayer;
+///
+/// /// Adds the layers of `my_crate` to a `ServiceBuilder`.
+/// pub trait ServiceBuilderExt<L> {
+///     /// Allow a single request in flight at a time.
+///     fn one_at_a_time<Request>(self) -> Layered<InFlightLimitLayer, L, Request>;
+/// }
+///
+/// impl<L> ServiceBuilderExt<L> for ServiceBuilder<L> {
+///     fn one_at_a_time<Request>(self) -> Layered<InFlightLimitLayer, L, Request> {
+///         self.layer(InFlightLimitLayer::new(1))
+///     }
+/// }
+///
+/// # fn main() {
+/// // The extension composes with the built-in methods.
+/// let mut service = ServiceBuilder::new()
+///     .one_at_a_time()
+///     .build_service(MyService)
+///     .unwrap();
+/// # assert!(service.poll_ready().unwrap().is_ready());
+/// # }
+/// ```
+pub type Layered<T, L, Request> = ServiceBuilder<Chain<T, L, Request>>;
+
 impl ServiceBuilder<Identity> {
     /// Create a new `ServiceBuilder` from a `MakeService`.
     pub fn new() -> Self {
@@ -166,25 +226,64 @@ impl ServiceBuilder<Identity> {
 
 impl<L> ServiceBuilder<L> {
     /// Layer a new layer `T` onto the `ServiceBuilder`.
-    pub fn layer<T>(self, layer: T) -> ServiceBuilder<Chain<T, L>> {
+    ///
+    /// `Request` is the request type of the service produced by `T`. It does
+    /// not need to match the request type of the service wrapped by `T`, so
+    /// layers translating between request types (e.g. encoding a typed
+    /// request into a wire representation) may be used anywhere in the stack.
+    pub fn layer<T, Request>(self, layer: T) -> Layered<T, L, Request> {
         ServiceBuilder {
             layer: Chain::new(layer, self.layer),
         }
     }
 
     /// Create a `LayeredMakeService` from the composed layers and transport `MakeService`.
+    ///
+    /// `Request` is the request type of the services produced by the returned
+    /// `LayeredMakeService`.
     pub fn build_make_service<M, Target, Request>(self, mk: M) -> LayeredMakeService<M, L, Request>
     where
-        M: MakeService<Target, Request>,
+        M: Service<Target>,
+        L: Layer<M::Response, Request>,
     {
         LayeredMakeService::new(mk, self.layer)
     }
 
+    /// Check at compile time that the layers are composed in a sensible
+    /// order, e.g. that `Retry` only wraps services that can be cloned.
+    ///
+    /// `Base` describes the service the layers will wrap, as an
+    /// `order::Stack`. All layers must implement `order::Validate`. See
+    /// [`order`](order/index.html) for details.
+    ///
+    /// ```
+    /// # extern crate tower;
+    /// # extern crate tower_buffer;
+    /// # extern crate tower_load_shed;
+    /// # use tower::builder::order::{Backpressure, NotCloneable, Stack};
+    /// # use tower::builder::ServiceBuilder;
+    /// # use tower_buffer::BufferLayer;
+    /// # use tower_load_shed::LoadShedLayer;
+    /// # fn main() {
+    /// // Shed load once the buffer is full.
+    /// let builder = ServiceBuilder::new()
+    ///     .layer(LoadShedLayer::new())
+    ///     .layer(BufferLayer::new(10))
+    ///     .validate::<Stack<NotCloneable, Backpressure>>();
+    /// # drop(builder);
+    /// # }
+    /// ```
+    pub fn validate<Base>(self) -> Self
+    where
+        L: order::Validate<Base>,
+    {
+        self
+    }
+
     /// Wrap the service `S` with the layers.
     pub fn build_service<S, Request>(self, service: S) -> Result<L::Service, L::LayerError>
     where
         L: Layer<S, Request>,
-        S: Service<Request>,
     {
         self.layer.layer(service)
     }
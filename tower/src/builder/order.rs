@@ -0,0 +1,284 @@
+//! Compile-time checks of the order of layers.
+//!
+//! Some layers only work when the services they wrap have certain
+//! properties. `Retry` and `Hedge` clone the service they wrap, and
+//! `LoadShed` and `Buffer` rely on it reporting when it is not ready: a
+//! `Buffer` wrapping a `LoadShed` never holds any requests. Getting the
+//! order of layers wrong either fails with confusing trait bound errors
+//! deep inside the stack, or silently builds a stack that does not behave
+//! as intended.
+//!
+//! `ServiceBuilder::validate` checks the order of the layers of a builder,
+//! tracking the properties of the stack as a `Stack` type, from the wrapped
+//! service outwards. An impossible order fails to compile, with an error
+//! naming the requirement that is not met, such as
+//! `Stack<NotCloneable, Backpressure>: ProvidesClone`.
+//!
+//! Layers take part in validation by implementing `Validate`, which is
+//! implemented by all of Tower's layers, including `HandshakeLimitLayer` for
+//! stacks of make services. The discovery wrappers of `balance` and
+//! `discover`, such as `WithPendingRequests`, are not layers, and are not
+//! validated. Validation is opt-in: layers that do not implement `Validate`
+//! can still be used with `ServiceBuilder`, but not with `validate`.
+//!
+//! # Example
+//!
+//! ```compile_fail
+//! # extern crate tower;
+//! # extern crate tower_buffer;
+//! # extern crate tower_load_shed;
+//! # use tower::builder::order::{Backpressure, Cloneable, Stack};
+//! # use tower::builder::ServiceBuilder;
+//! # use tower_buffer::BufferLayer;
+//! # use tower_load_shed::LoadShedLayer;
+//! # fn main() {
+//! // A buffer in front of a service that never applies backpressure is
+//! // rejected.
+//! ServiceBuilder::new()
+//!     .layer(BufferLayer::new(10))
+//!     .layer(LoadShedLayer::new())
+//!     .validate::<Stack<Cloneable, Backpressure>>();
+//! # }
+//! ```
+
+use std::marker::PhantomData;
+use tower_util::layer::{Chain, Identity};
+
+/// The services of a stack implement `Clone`.
+#[derive(Debug)]
+pub enum Cloneable {}
+
+/// The services of a stack may not implement `Clone`.
+#[derive(Debug)]
+pub enum NotCloneable {}
+
+/// The services of a stack report when they are not ready.
+#[derive(Debug)]
+pub enum Backpressure {}
+
+/// The services of a stack are always ready.
+#[derive(Debug)]
+pub enum NoBackpressure {}
+
+/// The properties of a stack of services.
+///
+/// `C` is either `Cloneable` or `NotCloneable`, and `B` is either
+/// `Backpressure` or `NoBackpressure`.
+#[derive(Debug)]
+pub struct Stack<C, B> {
+    _p: PhantomData<fn() -> (C, B)>,
+}
+
+/// Stacks whose services implement `Clone`.
+pub trait ProvidesClone {}
+
+/// Stacks whose services report when they are not ready.
+pub trait ProvidesBackpressure {}
+
+/// Layers whose order is checked by `ServiceBuilder::validate`.
+///
+/// A layer implements `Validate` for each `Inner` stack it may wrap, with
+/// `Output` describing the stack it produces. Requirements on the wrapped
+/// stack are expressed as bounds on the implementation, e.g.
+/// `Inner: ProvidesClone`.
+pub trait Validate<Inner> {
+    /// The properties of the stack produced by the layer.
+    type Output;
+}
+
+impl<B> ProvidesClone for Stack<Cloneable, B> {}
+
+impl<C> ProvidesBackpressure for Stack<C, Backpressure> {}
+
+impl<In> Validate<In> for Identity {
+    type Output = In;
+}
+
+impl<In, Inner, Outer, Request> Validate<In> for Chain<Inner, Outer, Request>
+where
+    Inner: Validate<In>,
+    Outer: Validate<Inner::Output>,
+{
+    type Output = Outer::Output;
+}
+
+// ===== impl Validate for Tower's layers =====
+
+mod layers {
+    use super::*;
+
+    use futures::Future;
+    use tower_codec::CodecLayer;
+    use tower_filter::FilterLayer;
+    use tower_in_flight_limit::{GlobalInFlightLimitLayer, InFlightLimitLayer};
+    use tower_load_shed::LoadShedLayer;
+    use tower_util::layer::{BoxLayer, CaptureLayer, PerItemLayer, StartupGateLayer};
+
+    impl<C, B, In, T, U, E> Validate<Stack<C, B>> for BoxLayer<In, T, U, E> {
+        type Output = Stack<NotCloneable, B>;
+    }
+
+    impl<C, B, F, R> Validate<Stack<C, B>> for CaptureLayer<F, R> {
+        type Output = Stack<C, B>;
+    }
+
+    impl<C, B, E, D> Validate<Stack<C, B>> for CodecLayer<E, D> {
+        type Output = Stack<C, B>;
+    }
+
+    impl<C, B, U> Validate<Stack<C, B>> for FilterLayer<U> {
+        type Output = Stack<NotCloneable, B>;
+    }
+
+    impl<C, B> Validate<Stack<C, B>> for InFlightLimitLayer {
+        type Output = Stack<C, Backpressure>;
+    }
+
+    impl<C, B> Validate<Stack<C, B>> for GlobalInFlightLimitLayer {
+        type Output = Stack<C, Backpressure>;
+    }
+
+    impl<C, B> Validate<Stack<C, B>> for LoadShedLayer
+    where
+        Stack<C, B>: ProvidesBackpressure,
+    {
+        type Output = Stack<C, NoBackpressure>;
+    }
+
+    impl<C, B, T> Validate<Stack<C, B>> for PerItemLayer<T> {
+        type Output = Stack<C, B>;
+    }
+
+    impl<C, B, F: Future> Validate<Stack<C, B>> for StartupGateLayer<F> {
+        type Output = Stack<NotCloneable, Backpressure>;
+    }
+
+    #[cfg(feature = "spawn")]
+    impl<C, B, E> Validate<Stack<C, B>> for ::tower_buffer::BufferLayer<E>
+    where
+        Stack<C, B>: ProvidesBackpressure,
+    {
+        type Output = Stack<Cloneable, Backpressure>;
+    }
+
+    #[cfg(feature = "time")]
+    mod time {
+        use super::*;
+
+        use server::{CatchPanicLayer, DrainLayer, HandshakeLimitLayer};
+        use tower_hedge::annotate::AnnotatedHedgeLayer;
+        use tower_hedge::HedgeLayer;
+        use tower_in_flight_limit::AdaptiveInFlightLimitLayer;
+        use tower_load_shed::deadline::DeadlineShedLayer;
+        use tower_rate_limit::{KeyedRateLimitLayer, RateLimitLayer, SharedRateLimitLayer};
+        use tower_reconnect::ReconnectLayer;
+        use tower_retry::annotate::AnnotatedRetryLayer;
+        use tower_retry::timeout::RetryWithTimeoutLayer;
+        use tower_retry::RetryLayer;
+        use tower_timeout::{
+            DeadlineTimeoutLayer, QueueTimeoutLayer, RequestTimeoutLayer, SoftTimeoutLayer,
+            TimeoutLayer, TotalTimeoutLayer,
+        };
+
+        impl<C, B, P> Validate<Stack<C, B>> for HedgeLayer<P>
+        where
+            Stack<C, B>: ProvidesClone,
+        {
+            type Output = Stack<C, B>;
+        }
+
+        impl<C, B, P> Validate<Stack<C, B>> for AnnotatedHedgeLayer<P>
+        where
+            Stack<C, B>: ProvidesClone,
+        {
+            type Output = Stack<C, B>;
+        }
+
+        impl<C, B> Validate<Stack<C, B>> for CatchPanicLayer {
+            type Output = Stack<C, B>;
+        }
+
+        impl<C, B> Validate<Stack<C, B>> for DrainLayer {
+            type Output = Stack<C, B>;
+        }
+
+        impl<C, B> Validate<Stack<C, B>> for HandshakeLimitLayer {
+            type Output = Stack<C, NoBackpressure>;
+        }
+
+        impl<C, B> Validate<Stack<C, B>> for AdaptiveInFlightLimitLayer {
+            type Output = Stack<C, Backpressure>;
+        }
+
+        impl<C, B> Validate<Stack<C, B>> for DeadlineShedLayer {
+            type Output = Stack<C, B>;
+        }
+
+        impl<C, B, Cost> Validate<Stack<C, B>> for RateLimitLayer<Cost> {
+            type Output = Stack<NotCloneable, Backpressure>;
+        }
+
+        impl<C, B, K, F> Validate<Stack<C, B>> for KeyedRateLimitLayer<K, F>
+        where
+            K: ::std::hash::Hash + Eq,
+        {
+            type Output = Stack<NotCloneable, Backpressure>;
+        }
+
+        impl<C, B> Validate<Stack<C, B>> for SharedRateLimitLayer {
+            type Output = Stack<C, Backpressure>;
+        }
+
+        impl<C, B, T> Validate<Stack<C, B>> for ReconnectLayer<T> {
+            type Output = Stack<NotCloneable, Backpressure>;
+        }
+
+        impl<C, B, P> Validate<Stack<C, B>> for RetryLayer<P>
+        where
+            Stack<C, B>: ProvidesClone,
+        {
+            type Output = Stack<C, B>;
+        }
+
+        impl<C, B, P> Validate<Stack<C, B>> for AnnotatedRetryLayer<P>
+        where
+            Stack<C, B>: ProvidesClone,
+        {
+            type Output = Stack<C, B>;
+        }
+
+        impl<C, B, P> Validate<Stack<C, B>> for RetryWithTimeoutLayer<P>
+        where
+            Stack<C, B>: ProvidesClone,
+        {
+            type Output = Stack<C, B>;
+        }
+
+        impl<C, B> Validate<Stack<C, B>> for TimeoutLayer {
+            type Output = Stack<C, B>;
+        }
+
+        impl<C, B> Validate<Stack<C, B>> for DeadlineTimeoutLayer {
+            type Output = Stack<C, B>;
+        }
+
+        impl<C, B, F> Validate<Stack<C, B>> for RequestTimeoutLayer<F> {
+            type Output = Stack<C, B>;
+        }
+
+        impl<C, B, F> Validate<Stack<C, B>> for SoftTimeoutLayer<F> {
+            type Output = Stack<C, B>;
+        }
+
+        impl<C, B> Validate<Stack<C, B>> for TotalTimeoutLayer {
+            type Output = Stack<C, B>;
+        }
+
+        impl<C, B> Validate<Stack<C, B>> for QueueTimeoutLayer
+        where
+            Stack<C, B>: ProvidesBackpressure,
+        {
+            type Output = Stack<NotCloneable, B>;
+        }
+    }
+}
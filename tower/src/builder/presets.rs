@@ -0,0 +1,331 @@
+//! Ready-made layer stacks with sensible defaults.
+
+use super::{Error, LayeredMakeService, ServiceBuilder};
+use server::{CatchPanicLayer, DrainHandle, DrainLayer, HandshakeLimit, HandshakeLimitLayer};
+use std::fmt;
+use std::sync::Arc;
+use std::time::Duration;
+use timer::timer;
+use tower_in_flight_limit::InFlightLimitLayer;
+use tower_layer::Layer;
+use tower_load_shed::LoadShedLayer;
+use tower_retry::budget::{Budget, Budgeted};
+use tower_retry::event::Event;
+use tower_retry::RetryLayer;
+use tower_service::Service;
+use tower_timeout::{QueueTimeoutLayer, TimeoutLayer};
+use tower_util::layer::{Chain, Identity};
+
+/// The layers applied by `ServiceBuilder::standard_client`.
+///
+/// From the outside in: load shedding, an in-flight limit, budgeted retries
+/// and a timeout on each attempt.
+pub type ClientLayer<P, Request> = Chain<
+    TimeoutLayer,
+    Chain<
+        RetryLayer<Budgeted<P>>,
+        Chain<InFlightLimitLayer, Chain<LoadShedLayer, Identity, Request>, Request>,
+        Request,
+    >,
+    Request,
+>;
+
+/// The layers applied by `ServiceBuilder::standard_server`.
+///
+/// From the outside in: panic catching, draining, a queue timeout, an
+/// in-flight limit and a timeout.
+pub type ServerLayer<Request> = Chain<
+    TimeoutLayer,
+    Chain<
+        InFlightLimitLayer,
+        Chain<
+            QueueTimeoutLayer,
+            Chain<DrainLayer, Chain<CatchPanicLayer, Identity, Request>, Request>,
+            Request,
+        >,
+        Request,
+    >,
+    Request,
+>;
+
+/// The make service returned by `ServiceBuilder::standard_server_make_service`.
+///
+/// The services it makes are wrapped in a `ServerLayer`, and its handshakes
+/// are limited by a `HandshakeLimitLayer`.
+pub type ServerMakeService<M, Request> =
+    HandshakeLimit<LayeredMakeService<M, ServerLayer<Request>, Request>>;
+
+/// Configuration for `ServiceBuilder::standard_client`.
+#[derive(Clone)]
+pub struct ClientConfig<P> {
+    retry: P,
+    budget: Arc<Budget>,
+    timeout: Duration,
+    max_in_flight: usize,
+    on_event: Option<Arc<Fn(Event) + Send + Sync>>,
+    timer: timer::Handle,
+}
+
+impl<P> ClientConfig<P> {
+    /// Create a new client configuration retrying requests with `retry`.
+    ///
+    /// Retries are withdrawn from a default `Budget`, each attempt times out
+    /// after 10 seconds and at most 100 requests may be in flight, unless
+    /// configured otherwise.
+    pub fn new(retry: P) -> Self {
+        ClientConfig {
+            retry,
+            budget: Arc::new(Budget::default()),
+            timeout: Duration::from_secs(10),
+            max_in_flight: 100,
+            on_event: None,
+            timer: timer::Handle::default(),
+        }
+    }
+
+    /// Set the budget retries are withdrawn from.
+    ///
+    /// The budget may be shared with other clients, so that they are limited
+    /// together.
+    pub fn budget(mut self, budget: Arc<Budget>) -> Self {
+        self.budget = budget;
+        self
+    }
+
+    /// Call `f` with each retry `Event` of the client's requests, e.g. to
+    /// count attempts and retries in metrics.
+    ///
+    /// See [`retry::event`](../../retry/event/index.html) for details.
+    pub fn on_event<F>(mut self, f: F) -> Self
+    where
+        F: Fn(Event) + Send + Sync + 'static,
+    {
+        self.on_event = Some(Arc::new(f));
+        self
+    }
+
+    /// Set the timeout of each attempt of a request.
+    pub fn timeout(mut self, timeout: Duration) -> Self {
+        self.timeout = timeout;
+        self
+    }
+
+    /// Set the maximum number of requests in flight.
+    ///
+    /// Requests made while the limit is reached are shed rather than queued.
+    pub fn max_in_flight(mut self, max: usize) -> Self {
+        self.max_in_flight = max;
+        self
+    }
+
+    /// Time out attempts with `timer`, rather than the timer of the runtime
+    /// that polls the responses.
+    pub fn timer(mut self, timer: timer::Handle) -> Self {
+        self.timer = timer;
+        self
+    }
+}
+
+impl<P: fmt::Debug> fmt::Debug for ClientConfig<P> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_struct("ClientConfig")
+            .field("retry", &self.retry)
+            .field("budget", &self.budget)
+            .field("timeout", &self.timeout)
+            .field("max_in_flight", &self.max_in_flight)
+            .field("timer", &self.timer)
+            .finish()
+    }
+}
+
+/// Configuration for `ServiceBuilder::standard_server`.
+#[derive(Clone, Debug)]
+pub struct ServerConfig {
+    timeout: Duration,
+    max_in_flight: usize,
+    queue_timeout: Duration,
+    handshake_timeout: Duration,
+    max_handshakes: usize,
+    drain: DrainHandle,
+    timer: timer::Handle,
+}
+
+impl ServerConfig {
+    /// Create a new server configuration.
+    ///
+    /// Requests time out after 30 seconds, at most 100 requests may be in
+    /// flight on each connection, and requests wait at most a second for one
+    /// of them to complete. Handshakes time out after 10 seconds, and at most
+    /// 100 may be in progress. All of these may be configured otherwise. A
+    /// new `DrainHandle` drains the services, unless one is given with
+    /// `drain_handle`.
+    pub fn new() -> Self {
+        ServerConfig {
+            timeout: Duration::from_secs(30),
+            max_in_flight: 100,
+            queue_timeout: Duration::from_secs(1),
+            handshake_timeout: Duration::from_secs(10),
+            max_handshakes: 100,
+            drain: DrainHandle::new(),
+            timer: timer::Handle::default(),
+        }
+    }
+
+    /// Set the timeout of each request.
+    pub fn timeout(mut self, timeout: Duration) -> Self {
+        self.timeout = timeout;
+        self
+    }
+
+    /// Set the maximum number of requests in flight on each connection.
+    ///
+    /// Requests made while the limit is reached wait for the `queue_timeout`
+    /// at most.
+    pub fn max_in_flight(mut self, max: usize) -> Self {
+        self.max_in_flight = max;
+        self
+    }
+
+    /// Set how long a request may wait while the connection has the maximum
+    /// number of requests in flight.
+    pub fn queue_timeout(mut self, timeout: Duration) -> Self {
+        self.queue_timeout = timeout;
+        self
+    }
+
+    /// Set the timeout of each handshake.
+    pub fn handshake_timeout(mut self, timeout: Duration) -> Self {
+        self.handshake_timeout = timeout;
+        self
+    }
+
+    /// Set the maximum number of handshakes in progress.
+    ///
+    /// Connections accepted while the limit is reached are rejected.
+    pub fn max_handshakes(mut self, max: usize) -> Self {
+        self.max_handshakes = max;
+        self
+    }
+
+    /// Set the handle draining the services, e.g. to share it between
+    /// several servers.
+    pub fn drain_handle(mut self, handle: DrainHandle) -> Self {
+        self.drain = handle;
+        self
+    }
+
+    /// Returns the handle draining the services on shutdown.
+    ///
+    /// Calling `DrainHandle::drain` makes the services fail `poll_ready`, so
+    /// that their connections are closed, and returns a future completed once
+    /// their requests in flight have completed.
+    pub fn drain(&self) -> DrainHandle {
+        self.drain.clone()
+    }
+
+    /// Time out requests, waiting requests and handshakes with `timer`,
+    /// rather than the timer of the runtime that polls them.
+    pub fn timer(mut self, timer: timer::Handle) -> Self {
+        self.timer = timer;
+        self
+    }
+
+    /// Returns the layer limiting the handshakes of the make service.
+    ///
+    /// `standard_server_make_service` applies it already. When layers are
+    /// added on top of `standard_server`, it wraps the make service returned
+    /// by `build_make_service`:
+    ///
+    /// ```rust,ignore
+    /// let make = ServiceBuilder::standard_server(config.clone())
+    ///     .layer(my_layer)
+    ///     .build_make_service(make);
+    /// let make = config.handshake_limit().layer(make)?;
+    /// ```
+    pub fn handshake_limit(&self) -> HandshakeLimitLayer {
+        HandshakeLimitLayer::new(self.handshake_timeout, self.max_handshakes)
+            .with_timer(self.timer.clone())
+    }
+}
+
+impl Default for ServerConfig {
+    fn default() -> Self {
+        ServerConfig::new()
+    }
+}
+
+impl ServiceBuilder<Identity> {
+    /// Create a `ServiceBuilder` with the recommended layers for a client.
+    ///
+    /// Requests are shed once `max_in_flight` requests are outstanding,
+    /// retried according to the configured policy as long as the
+    /// [`Budget`](../retry/budget/struct.Budget.html) allows, so that retries
+    /// cannot overwhelm the backend, and every attempt is given its own
+    /// timeout. Retry events are reported to the `on_event` callback, if any.
+    /// Retrying requires the wrapped service to be `Clone`.
+    ///
+    /// Further layers may be added on top of the returned builder.
+    pub fn standard_client<P, Request>(
+        config: ClientConfig<P>,
+    ) -> ServiceBuilder<ClientLayer<P, Request>> {
+        let mut retry = RetryLayer::new(Budgeted::new(config.retry, config.budget));
+        if let Some(on_event) = config.on_event {
+            retry = retry.on_event(move |event| on_event(event));
+        }
+
+        ServiceBuilder::new()
+            .layer(LoadShedLayer::new())
+            .layer(InFlightLimitLayer::new(config.max_in_flight))
+            .layer(retry)
+            .layer(TimeoutLayer::new(config.timeout).with_timer(config.timer))
+    }
+
+    /// Create a `ServiceBuilder` with the recommended layers for a server.
+    ///
+    /// This is intended to be used with `build_make_service`, so that the
+    /// layers are applied to the service of each connection. Panics of the
+    /// service fail the request that caused them with
+    /// `server::error::Panicked`. Once the `ServerConfig::drain` handle is
+    /// drained, the services stop accepting requests. Every connection is
+    /// limited to `max_in_flight` concurrent requests. Requests beyond the
+    /// limit fail once they have waited for the `queue_timeout`, and requests
+    /// that are admitted fail once they exceed the timeout.
+    ///
+    /// Further layers may be added on top of the returned builder, in which
+    /// case the make service should be wrapped with
+    /// [`ServerConfig::handshake_limit`](struct.ServerConfig.html#method.handshake_limit).
+    /// Otherwise, `standard_server_make_service` applies both.
+    pub fn standard_server<Request>(config: ServerConfig) -> ServiceBuilder<ServerLayer<Request>> {
+        ServiceBuilder::new()
+            .layer(CatchPanicLayer::new())
+            .layer(DrainLayer::new(config.drain))
+            .layer(QueueTimeoutLayer::new(config.queue_timeout).with_timer(config.timer.clone()))
+            .layer(InFlightLimitLayer::new(config.max_in_flight))
+            .layer(TimeoutLayer::new(config.timeout).with_timer(config.timer))
+    }
+
+    /// Wrap `make` in the recommended layers for a server.
+    ///
+    /// The services made are wrapped in the layers of `standard_server`, and
+    /// the handshakes of `make` are bounded by
+    /// [`ServerConfig::handshake_limit`](struct.ServerConfig.html#method.handshake_limit).
+    pub fn standard_server_make_service<M, Target, Request>(
+        config: ServerConfig,
+        make: M,
+    ) -> ServerMakeService<M, Request>
+    where
+        M: Service<Target>,
+        M::Error: Into<Error>,
+        ServerLayer<Request>: Layer<M::Response, Request> + Send + Sync + 'static,
+        <ServerLayer<Request> as Layer<M::Response, Request>>::LayerError: Into<Error>,
+        Target: Clone,
+    {
+        let handshake_limit = config.handshake_limit();
+        let make = ServiceBuilder::standard_server(config).build_make_service(make);
+
+        match Layer::<_, Target>::layer(&handshake_limit, make) {
+            Ok(make) => make,
+            Err(never) => match never {},
+        }
+    }
+}
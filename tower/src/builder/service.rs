@@ -3,10 +3,13 @@ use futures::{Async, Future, Poll};
 use std::marker::PhantomData;
 use std::sync::Arc;
 use tower_layer::Layer;
-use tower_util::MakeService;
 use Service;
 
 /// Composed `MakeService` produced from `ServiceBuilder`
+///
+/// `Request` is the request type of the services produced after the layers
+/// have been applied, which may differ from the request type of the services
+/// produced by `S`.
 #[derive(Debug)]
 pub struct LayeredMakeService<S, L, Request> {
     maker: S,
@@ -18,10 +21,11 @@ pub struct LayeredMakeService<S, L, Request> {
 #[derive(Debug)]
 pub struct ServiceFuture<S, L, Target, Request>
 where
-    S: MakeService<Target, Request>,
+    S: Service<Target>,
 {
     inner: S::Future,
     layer: Arc<L>,
+    _pd: PhantomData<fn(Request)>,
 }
 
 impl<S, L, Request> LayeredMakeService<S, L, Request> {
@@ -36,9 +40,9 @@ impl<S, L, Request> LayeredMakeService<S, L, Request> {
 
 impl<S, L, Target, Request> Service<Target> for LayeredMakeService<S, L, Request>
 where
-    S: MakeService<Target, Request>,
-    S::MakeError: Into<Error>,
-    L: Layer<S::Service, Request> + Sync + Send + 'static,
+    S: Service<Target>,
+    S::Error: Into<Error>,
+    L: Layer<S::Response, Request> + Sync + Send + 'static,
     L::LayerError: Into<Error>,
     Target: Clone,
 {
@@ -51,18 +55,22 @@ where
     }
 
     fn call(&mut self, target: Target) -> Self::Future {
-        let inner = self.maker.make_service(target);
+        let inner = self.maker.call(target);
         let layer = Arc::clone(&self.layer);
 
-        ServiceFuture { inner, layer }
+        ServiceFuture {
+            inner,
+            layer,
+            _pd: PhantomData,
+        }
     }
 }
 
 impl<S, L, Target, Request> Future for ServiceFuture<S, L, Target, Request>
 where
-    S: MakeService<Target, Request>,
-    S::MakeError: Into<Error>,
-    L: Layer<S::Service, Request>,
+    S: Service<Target>,
+    S::Error: Into<Error>,
+    L: Layer<S::Response, Request>,
     L::LayerError: Into<Error>,
 {
     type Item = L::Service;
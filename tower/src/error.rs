@@ -0,0 +1,176 @@
+//! Working with the boxed errors produced by middleware.
+//!
+//! Most middleware converts the errors of the services it wraps into a
+//! [`BoxError`](type.BoxError.html), often wrapping them in an error of its
+//! own along the way. This module helps services produce such errors with
+//! some context attached, and helps callers find a specific error in the
+//! resulting chain of sources, however deep it is nested.
+
+use std::error::Error;
+use std::fmt;
+
+/// The boxed error type used throughout Tower.
+pub type BoxError = Box<Error + Send + Sync>;
+
+/// An error wrapping a source error with a description of what failed.
+///
+/// Created by [`wrap`](fn.wrap.html).
+#[derive(Debug)]
+pub struct Context {
+    context: String,
+    source: BoxError,
+}
+
+/// An error made from a message, for third-party error values that do not
+/// implement `std::error::Error`.
+///
+/// Created by [`msg`](fn.msg.html).
+pub struct Message<M> {
+    msg: M,
+}
+
+/// An iterator over an error and its sources.
+///
+/// Created by [`chain`](fn.chain.html).
+#[derive(Debug, Clone)]
+pub struct Chain<'a> {
+    next: Option<&'a (Error + 'static)>,
+}
+
+/// Wrap `source` in an error describing what failed.
+///
+/// The returned error displays as `context`, and returns `source` from
+/// `Error::source`, so it can still be found with [`find`](fn.find.html).
+///
+/// # Example
+///
+/// ```
+/// # extern crate tower;
+/// use std::io;
+/// use tower::error;
+///
+/// # fn main() {
+/// let io = io::Error::new(io::ErrorKind::NotFound, "no such file");
+/// let err = error::wrap(io, "failed to load config");
+///
+/// assert_eq!(err.to_string(), "failed to load config");
+/// assert!(error::find::<io::Error>(&*err).is_some());
+/// # }
+/// ```
+pub fn wrap<E, C>(source: E, context: C) -> BoxError
+where
+    E: Into<BoxError>,
+    C: fmt::Display,
+{
+    Box::new(Context {
+        context: context.to_string(),
+        source: source.into(),
+    })
+}
+
+/// Create an error from `msg`.
+///
+/// Unlike converting a string into a `BoxError`, the message keeps its type,
+/// so it can be found again with [`find`](fn.find.html).
+pub fn msg<M>(msg: M) -> BoxError
+where
+    M: fmt::Display + fmt::Debug + Send + Sync + 'static,
+{
+    Box::new(Message { msg })
+}
+
+/// Returns an iterator over `err` and all of its sources, starting with
+/// `err`.
+pub fn chain<'a>(err: &'a (Error + 'static)) -> Chain<'a> {
+    Chain { next: Some(err) }
+}
+
+/// Returns the first error of type `T` in `err` or its sources.
+///
+/// `Error::downcast_ref` only looks at the error itself, which is rarely the
+/// error of interest once middleware has wrapped it.
+pub fn find<'a, T>(err: &'a (Error + 'static)) -> Option<&'a T>
+where
+    T: Error + 'static,
+{
+    chain(err).filter_map(|err| err.downcast_ref::<T>()).next()
+}
+
+/// Returns `true` if `err` or any of its sources is of type `T`.
+pub fn is<T>(err: &(Error + 'static)) -> bool
+where
+    T: Error + 'static,
+{
+    find::<T>(err).is_some()
+}
+
+// ===== impl Context =====
+
+impl Context {
+    /// Returns the description of what failed.
+    pub fn context(&self) -> &str {
+        &self.context
+    }
+
+    /// Returns the wrapped error.
+    pub fn get_ref(&self) -> &(Error + Send + Sync + 'static) {
+        &*self.source
+    }
+
+    /// Consume `self`, returning the wrapped error.
+    pub fn into_inner(self) -> BoxError {
+        self.source
+    }
+}
+
+impl fmt::Display for Context {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.pad(&self.context)
+    }
+}
+
+impl Error for Context {
+    fn source(&self) -> Option<&(Error + 'static)> {
+        Some(&*self.source)
+    }
+}
+
+// ===== impl Message =====
+
+impl<M> Message<M> {
+    /// Returns a reference to the message.
+    pub fn get_ref(&self) -> &M {
+        &self.msg
+    }
+
+    /// Consume `self`, returning the message.
+    pub fn into_inner(self) -> M {
+        self.msg
+    }
+}
+
+impl<M: fmt::Debug> fmt::Debug for Message<M> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        fmt::Debug::fmt(&self.msg, f)
+    }
+}
+
+impl<M: fmt::Display> fmt::Display for Message<M> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        fmt::Display::fmt(&self.msg, f)
+    }
+}
+
+impl<M: fmt::Display + fmt::Debug> Error for Message<M> {}
+
+// ===== impl Chain =====
+
+impl<'a> Iterator for Chain<'a> {
+    type Item = &'a (Error + 'static);
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let next = self.next.take()?;
+        self.next = next.source();
+        Some(next)
+    }
+}
@@ -2,29 +2,53 @@
 
 pub use tower_layer::Layer;
 
+#[cfg(feature = "spawn")]
 pub use tower_buffer::BufferLayer;
+pub use tower_codec::CodecLayer;
 pub use tower_filter::FilterLayer;
-pub use tower_in_flight_limit::InFlightLimitLayer;
+#[cfg(feature = "time")]
+pub use tower_hedge::HedgeLayer;
+#[cfg(feature = "time")]
+pub use tower_in_flight_limit::AdaptiveInFlightLimitLayer;
+pub use tower_in_flight_limit::{GlobalInFlightLimitLayer, InFlightLimitLayer};
+#[cfg(feature = "time")]
+pub use tower_load_shed::deadline::DeadlineShedLayer;
 pub use tower_load_shed::LoadShedLayer;
-pub use tower_rate_limit::RateLimitLayer;
+#[cfg(feature = "time")]
+pub use tower_rate_limit::{KeyedRateLimitLayer, RateLimitLayer, SharedRateLimitLayer};
+#[cfg(feature = "time")]
+pub use tower_reconnect::ReconnectLayer;
+#[cfg(feature = "time")]
 pub use tower_retry::RetryLayer;
-pub use tower_timeout::TimeoutLayer;
+#[cfg(feature = "time")]
+pub use tower_timeout::{
+    DeadlineTimeoutLayer, QueueTimeoutLayer, RequestTimeoutLayer, SoftTimeoutLayer, TimeoutLayer,
+    TotalTimeoutLayer,
+};
 
 pub mod util {
+    pub use tower_util::layer::BoxLayer;
+    pub use tower_util::layer::CaptureLayer;
     pub use tower_util::layer::Chain;
     pub use tower_util::layer::Identity;
+    pub use tower_util::layer::PerItemLayer;
+    pub use tower_util::layer::StartupGateLayer;
 }
 
+use self::util::Chain;
+
 /// An extension trait for `Layer`'s that provides a variety of convenient
 /// adapters.
 pub trait LayerExt<S, Request>: Layer<S, Request> {
     /// Return a new `Layer` instance that applies both `self` and
     /// `middleware` to services being wrapped.
     ///
-    /// This defines a middleware stack.
-    fn chain<T>(self, middleware: T) -> Chain<Self, T>
+    /// This defines a middleware stack. `middleware` wraps services accepting
+    /// `Request`, but may itself produce a service accepting a different
+    /// request type, `U`.
+    fn chain<T, U>(self, middleware: T) -> Chain<Self, T, Request>
     where
-        T: Layer<Self::Service, Request>,
+        T: Layer<Self::Service, U>,
         Self: Sized,
     {
         Chain::new(self, middleware)
@@ -1,33 +1,65 @@
-// Allows refining features in the future without breaking backwards
-// compatibility
-#![cfg(feature = "full")]
-
 //! Various utility types and functions that are generally with Tower.
+//!
+//! # Features
+//!
+//! All middleware is available with the default `full` feature. Without it,
+//! only the pure composition pieces are built, such as `ServiceBuilder`, the
+//! combinators in `util` and the in-flight limits, so that Tower can be used
+//! on targets without a timer or an executor. The parts that need them can
+//! be enabled separately:
+//!
+//! - `time`: middleware needing a timer, i.e. `balance`, `hedge`,
+//!   `rate_limit`, `reconnect`, `retry`, `timeout`, `server`, the builder
+//!   presets, adaptive in-flight limits and shedding by deadline.
+//! - `spawn`: middleware spawning background tasks, i.e. `buffer`.
+//! - `io`: services built on `tokio-io`, i.e. `MakeConnection`.
+//!
+//! Discovery by resolving DNS names, i.e. `discover::Dns`, is enabled by the
+//! `dns` feature, which is not part of `full`.
 
 #[macro_use]
 extern crate futures;
 
+#[cfg(any(feature = "bench", feature = "time"))]
+extern crate timer;
 extern crate tower_layer;
 extern crate tower_service;
 extern crate tower_util;
 
+#[cfg(feature = "time")]
 pub extern crate tower_balance as balance;
+#[cfg(feature = "spawn")]
 pub extern crate tower_buffer as buffer;
+pub extern crate tower_codec as codec;
 pub extern crate tower_discover as discover;
 pub extern crate tower_filter as filter;
+#[cfg(feature = "time")]
+pub extern crate tower_hedge as hedge;
 pub extern crate tower_in_flight_limit as in_flight_limit;
 pub extern crate tower_load_shed as load_shed;
+#[cfg(feature = "time")]
 pub extern crate tower_rate_limit as rate_limit;
+#[cfg(feature = "time")]
 pub extern crate tower_reconnect as reconnect;
+#[cfg(feature = "time")]
 pub extern crate tower_retry as retry;
+#[cfg(feature = "time")]
 pub extern crate tower_timeout as timeout;
 
+#[cfg(feature = "bench")]
+pub mod bench;
 pub mod builder;
+pub mod error;
 pub mod layer;
+#[cfg(feature = "time")]
+mod never;
+#[cfg(feature = "time")]
+pub mod server;
 pub mod util;
 
 pub use builder::ServiceBuilder;
 pub use tower_service::Service;
+#[cfg(feature = "io")]
 pub use tower_util::MakeConnection;
 pub use tower_util::MakeService;
 pub use util::ServiceExt;
@@ -0,0 +1,134 @@
+use super::error::Panicked;
+use super::Error;
+use futures::{Future, Poll};
+use never::Never;
+use std::panic::{self, AssertUnwindSafe};
+use tower_layer::Layer;
+use tower_service::Service;
+
+/// Turns panics of the inner service into `error::Panicked` errors.
+///
+/// A panic in `poll_ready`, `call` or the response future would otherwise
+/// unwind through the task driving the connection, and take its other
+/// requests down with it. Only the request that panicked fails; since the
+/// inner service may be left in an inconsistent state, the server should
+/// close the connection once `poll_ready` fails.
+#[derive(Debug, Clone)]
+pub struct CatchPanic<S> {
+    inner: S,
+}
+
+/// Applies `CatchPanic` to services.
+#[derive(Debug, Clone, Default)]
+pub struct CatchPanicLayer {
+    _p: (),
+}
+
+/// The response future of a `CatchPanic` service.
+#[derive(Debug)]
+pub struct CatchPanicFuture<F> {
+    state: State<F>,
+}
+
+#[derive(Debug)]
+enum State<F> {
+    Called(F),
+    Panicked(Option<Panicked>),
+}
+
+// ===== impl CatchPanic =====
+
+impl<S> CatchPanic<S> {
+    /// Create a new `CatchPanic` wrapping `inner`.
+    pub fn new(inner: S) -> Self {
+        CatchPanic { inner }
+    }
+
+    /// Get a reference to the inner service
+    pub fn get_ref(&self) -> &S {
+        &self.inner
+    }
+
+    /// Consume `self`, returning the inner service
+    pub fn into_inner(self) -> S {
+        self.inner
+    }
+}
+
+impl<S, Request> Service<Request> for CatchPanic<S>
+where
+    S: Service<Request>,
+    Error: From<S::Error>,
+{
+    type Response = S::Response;
+    type Error = Error;
+    type Future = CatchPanicFuture<S::Future>;
+
+    fn poll_ready(&mut self) -> Poll<(), Self::Error> {
+        let inner = &mut self.inner;
+        match panic::catch_unwind(AssertUnwindSafe(|| inner.poll_ready())) {
+            Ok(ready) => ready.map_err(Error::from),
+            Err(payload) => Err(Panicked::new(payload).into()),
+        }
+    }
+
+    fn call(&mut self, request: Request) -> Self::Future {
+        let inner = &mut self.inner;
+        let state = match panic::catch_unwind(AssertUnwindSafe(|| inner.call(request))) {
+            Ok(future) => State::Called(future),
+            Err(payload) => State::Panicked(Some(Panicked::new(payload))),
+        };
+
+        CatchPanicFuture { state }
+    }
+}
+
+// ===== impl CatchPanicLayer =====
+
+impl CatchPanicLayer {
+    /// Create a new `CatchPanicLayer`.
+    pub fn new() -> Self {
+        CatchPanicLayer::default()
+    }
+}
+
+impl<S, Request> Layer<S, Request> for CatchPanicLayer
+where
+    S: Service<Request>,
+    Error: From<S::Error>,
+{
+    type Response = S::Response;
+    type Error = Error;
+    type LayerError = Never;
+    type Service = CatchPanic<S>;
+
+    fn layer(&self, service: S) -> Result<Self::Service, Self::LayerError> {
+        Ok(CatchPanic::new(service))
+    }
+}
+
+// ===== impl CatchPanicFuture =====
+
+impl<F> Future for CatchPanicFuture<F>
+where
+    F: Future,
+    Error: From<F::Error>,
+{
+    type Item = F::Item;
+    type Error = Error;
+
+    fn poll(&mut self) -> Poll<Self::Item, Self::Error> {
+        let future = match self.state {
+            State::Called(ref mut future) => future,
+            State::Panicked(ref mut panicked) => {
+                let panicked = panicked.take().expect("polled after error");
+                return Err(panicked.into());
+            }
+        };
+
+        match panic::catch_unwind(AssertUnwindSafe(|| future.poll())) {
+            Ok(poll) => poll.map_err(Error::from),
+            Err(payload) => Err(Panicked::new(payload).into()),
+        }
+    }
+}
@@ -0,0 +1,209 @@
+use super::error::Draining;
+use super::Error;
+use futures::task::AtomicTask;
+use futures::{Async, Future, Poll};
+use never::Never;
+use std::sync::atomic::{AtomicBool, AtomicUsize, Ordering};
+use std::sync::Arc;
+use tower_layer::Layer;
+use tower_service::Service;
+
+/// Stops the services of a `DrainHandle` from accepting requests once the
+/// server shuts down, while letting their requests in flight complete.
+///
+/// Once draining, `poll_ready` fails with `error::Draining`, so that the
+/// server closes the connection rather than reading further requests.
+#[derive(Debug, Clone)]
+pub struct Drain<S> {
+    inner: S,
+    handle: DrainHandle,
+}
+
+/// Applies `Drain` to services, all sharing the same `DrainHandle`.
+#[derive(Debug, Clone)]
+pub struct DrainLayer {
+    handle: DrainHandle,
+}
+
+/// Starts draining the services it was given to, and waits for their
+/// requests in flight to complete.
+///
+/// Cloning the handle yields a handle to the same services.
+#[derive(Debug, Clone, Default)]
+pub struct DrainHandle {
+    shared: Arc<Shared>,
+}
+
+/// Future completed once no request of the services of a `DrainHandle` is
+/// in flight anymore.
+///
+/// See [`DrainHandle::drain`](struct.DrainHandle.html#method.drain).
+#[derive(Debug)]
+pub struct Drained {
+    shared: Arc<Shared>,
+}
+
+/// The response future of a `Drain` service.
+#[derive(Debug)]
+pub struct DrainFuture<F> {
+    inner: F,
+    _in_flight: InFlight,
+}
+
+#[derive(Debug, Default)]
+struct Shared {
+    draining: AtomicBool,
+    in_flight: AtomicUsize,
+    /// The task waiting for the requests in flight to complete.
+    task: AtomicTask,
+}
+
+/// Counts a request as in flight until dropped.
+#[derive(Debug)]
+struct InFlight {
+    shared: Arc<Shared>,
+}
+
+// ===== impl Drain =====
+
+impl<S> Drain<S> {
+    /// Create a new `Drain` wrapping `inner`, drained by `handle`.
+    pub fn new(inner: S, handle: DrainHandle) -> Self {
+        Drain { inner, handle }
+    }
+
+    /// Get a reference to the inner service
+    pub fn get_ref(&self) -> &S {
+        &self.inner
+    }
+
+    /// Consume `self`, returning the inner service
+    pub fn into_inner(self) -> S {
+        self.inner
+    }
+}
+
+impl<S, Request> Service<Request> for Drain<S>
+where
+    S: Service<Request>,
+    Error: From<S::Error>,
+{
+    type Response = S::Response;
+    type Error = Error;
+    type Future = DrainFuture<S::Future>;
+
+    fn poll_ready(&mut self) -> Poll<(), Self::Error> {
+        if self.handle.is_draining() {
+            return Err(Draining(()).into());
+        }
+
+        self.inner.poll_ready().map_err(Error::from)
+    }
+
+    fn call(&mut self, request: Request) -> Self::Future {
+        let shared = self.handle.shared.clone();
+        shared.in_flight.fetch_add(1, Ordering::SeqCst);
+
+        DrainFuture {
+            inner: self.inner.call(request),
+            _in_flight: InFlight { shared },
+        }
+    }
+}
+
+// ===== impl DrainLayer =====
+
+impl DrainLayer {
+    /// Create a new `DrainLayer`, drained by `handle`.
+    pub fn new(handle: DrainHandle) -> Self {
+        DrainLayer { handle }
+    }
+}
+
+impl<S, Request> Layer<S, Request> for DrainLayer
+where
+    S: Service<Request>,
+    Error: From<S::Error>,
+{
+    type Response = S::Response;
+    type Error = Error;
+    type LayerError = Never;
+    type Service = Drain<S>;
+
+    fn layer(&self, service: S) -> Result<Self::Service, Self::LayerError> {
+        Ok(Drain::new(service, self.handle.clone()))
+    }
+}
+
+// ===== impl DrainHandle =====
+
+impl DrainHandle {
+    /// Create a new `DrainHandle`, not draining.
+    pub fn new() -> Self {
+        DrainHandle::default()
+    }
+
+    /// Stop the services from accepting requests, returning a future
+    /// completed once their requests in flight have completed.
+    ///
+    /// Only one task may wait for the services to be drained at a time.
+    pub fn drain(&self) -> Drained {
+        self.shared.draining.store(true, Ordering::SeqCst);
+
+        Drained {
+            shared: self.shared.clone(),
+        }
+    }
+
+    /// Returns `true` once `drain` was called.
+    pub fn is_draining(&self) -> bool {
+        self.shared.draining.load(Ordering::SeqCst)
+    }
+
+    /// Returns the number of requests in flight.
+    pub fn in_flight(&self) -> usize {
+        self.shared.in_flight.load(Ordering::SeqCst)
+    }
+}
+
+// ===== impl Drained =====
+
+impl Future for Drained {
+    type Item = ();
+    type Error = ();
+
+    fn poll(&mut self) -> Poll<(), ()> {
+        self.shared.task.register();
+
+        if self.shared.in_flight.load(Ordering::SeqCst) == 0 {
+            return Ok(Async::Ready(()));
+        }
+
+        Ok(Async::NotReady)
+    }
+}
+
+// ===== impl DrainFuture =====
+
+impl<F> Future for DrainFuture<F>
+where
+    F: Future,
+    Error: From<F::Error>,
+{
+    type Item = F::Item;
+    type Error = Error;
+
+    fn poll(&mut self) -> Poll<Self::Item, Self::Error> {
+        self.inner.poll().map_err(Error::from)
+    }
+}
+
+// ===== impl InFlight =====
+
+impl Drop for InFlight {
+    fn drop(&mut self) {
+        if self.shared.in_flight.fetch_sub(1, Ordering::SeqCst) == 1 {
+            self.shared.task.notify();
+        }
+    }
+}
@@ -0,0 +1,49 @@
+//! Error types
+
+use std::any::Any;
+use std::{error, fmt};
+
+/// The service panicked while handling a request.
+#[derive(Debug)]
+pub struct Panicked {
+    message: Option<String>,
+}
+
+impl Panicked {
+    pub(crate) fn new(payload: Box<Any + Send>) -> Self {
+        let message = match payload.downcast::<String>() {
+            Ok(message) => Some(*message),
+            Err(payload) => payload.downcast_ref::<&str>().map(|s| s.to_string()),
+        };
+
+        Panicked { message }
+    }
+
+    /// Returns the message the service panicked with, if it was a string.
+    pub fn message(&self) -> Option<&str> {
+        self.message.as_ref().map(|s| s.as_str())
+    }
+}
+
+impl fmt::Display for Panicked {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self.message {
+            Some(ref message) => write!(f, "service panicked: {}", message),
+            None => f.pad("service panicked"),
+        }
+    }
+}
+
+impl error::Error for Panicked {}
+
+/// The server is draining, and does not accept new requests.
+#[derive(Debug)]
+pub struct Draining(pub(super) ());
+
+impl fmt::Display for Draining {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.pad("server is draining")
+    }
+}
+
+impl error::Error for Draining {}
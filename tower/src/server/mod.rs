@@ -0,0 +1,93 @@
+//! Protecting the accept side of servers.
+//!
+//! Servers build a service for every accepted connection, by calling a
+//! `MakeService` with the connection's target. Building the service may
+//! involve a handshake, e.g. TLS, which a misbehaving or malicious client can
+//! stall indefinitely. Since the make service is itself a `Service`, it can
+//! be wrapped in middleware like any other: [`HandshakeLimitLayer`] bounds
+//! how long each handshake may take, and how many may be in progress at
+//! once, rejecting excess connections right away instead of letting them
+//! pile up.
+//!
+//! Once connected, [`CatchPanicLayer`] keeps a panicking service from taking
+//! down the task driving its connection, and [`DrainLayer`] stops the
+//! services of all connections from accepting requests on shutdown, while
+//! their requests in flight complete.
+//!
+//! [`HandshakeLimitLayer`]: struct.HandshakeLimitLayer.html
+//! [`CatchPanicLayer`]: struct.CatchPanicLayer.html
+//! [`DrainLayer`]: struct.DrainLayer.html
+
+mod catch_panic;
+mod drain;
+pub mod error;
+
+pub use self::catch_panic::{CatchPanic, CatchPanicFuture, CatchPanicLayer};
+pub use self::drain::{Drain, DrainFuture, DrainHandle, DrainLayer, Drained};
+
+use in_flight_limit::InFlightLimit;
+use load_shed::LoadShed;
+use never::Never;
+use std::time::Duration;
+use timeout::Timeout;
+use timer::timer;
+use tower_layer::Layer;
+use tower_service::Service;
+
+type Error = Box<::std::error::Error + Send + Sync>;
+
+/// A `MakeService` bounding the duration and number of in-progress
+/// handshakes.
+///
+/// Handshakes that take too long fail with `timeout::error::Elapsed`, and
+/// handshakes started while too many are in progress fail immediately with
+/// `load_shed::error::Overloaded`.
+pub type HandshakeLimit<M> = LoadShed<InFlightLimit<Timeout<M>>>;
+
+/// Applies `HandshakeLimit` to make services.
+#[derive(Debug, Clone)]
+pub struct HandshakeLimitLayer {
+    timeout: Duration,
+    max_in_progress: usize,
+    timer: timer::Handle,
+}
+
+impl HandshakeLimitLayer {
+    /// Create a new `HandshakeLimitLayer`, failing handshakes that take
+    /// longer than `timeout`, and rejecting connections while
+    /// `max_in_progress` handshakes are already in progress.
+    pub fn new(timeout: Duration, max_in_progress: usize) -> Self {
+        HandshakeLimitLayer {
+            timeout,
+            max_in_progress,
+            timer: timer::Handle::default(),
+        }
+    }
+
+    /// Time out handshakes with `timer`, rather than the timer of the runtime
+    /// that polls them.
+    ///
+    /// See [`Timeout::with_timer`](../timeout/struct.Timeout.html#method.with_timer).
+    pub fn with_timer(mut self, timer: timer::Handle) -> Self {
+        self.timer = timer;
+        self
+    }
+}
+
+impl<M, Target> Layer<M, Target> for HandshakeLimitLayer
+where
+    M: Service<Target>,
+    Error: From<M::Error>,
+{
+    type Response = M::Response;
+    type Error = Error;
+    type LayerError = Never;
+    type Service = HandshakeLimit<M>;
+
+    fn layer(&self, make: M) -> Result<Self::Service, Self::LayerError> {
+        let timeout = Timeout::new(make, self.timeout).with_timer(self.timer.clone());
+        let limit = InFlightLimit::new::<Target>(timeout, self.max_in_progress);
+
+        Ok(LoadShed::new(limit))
+    }
+}
@@ -1,13 +1,36 @@
 //! Combinators for working with `Service`s
 
+pub use tower_util::backoff;
+pub use tower_util::future;
+pub use tower_util::future_service;
+pub use tower_util::poll_ready_fn;
+pub use tower_util::service_fn;
+pub use tower_util::AlwaysReady;
+pub use tower_util::AsService;
+pub use tower_util::BoxCloneService;
 pub use tower_util::BoxService;
 pub use tower_util::CallAll;
 pub use tower_util::CallAllUnordered;
+pub use tower_util::Capture;
+pub use tower_util::Degrade;
 pub use tower_util::Either;
+pub use tower_util::FutureService;
+pub use tower_util::IntoService;
+pub use tower_util::MaybeReady;
 pub use tower_util::Oneshot;
 pub use tower_util::Optional;
+pub use tower_util::PerItem;
+pub use tower_util::PollReadyFn;
+pub use tower_util::PollReadyN;
 pub use tower_util::Ready;
+pub use tower_util::Records;
+pub use tower_util::Registry;
+pub use tower_util::Reload;
 pub use tower_util::ServiceFn;
+pub use tower_util::SharedMakeService;
+pub use tower_util::StartupGate;
+pub use tower_util::Unready;
+pub use tower_util::UnreadyReason;
 pub use tower_util::UnsyncBoxService;
 
 use futures::Stream;
@@ -0,0 +1,40 @@
+#![cfg(feature = "bench")]
+
+extern crate futures;
+extern crate tokio;
+extern crate tower;
+extern crate tower_in_flight_limit;
+
+use futures::Future;
+use std::time::Duration;
+use tower::bench::{measure, Failing, Latency, Noop};
+use tower_in_flight_limit::InFlightLimit;
+
+#[test]
+fn measure_noop() {
+    let svc = InFlightLimit::new(Noop, 1);
+    let measurement = measure(svc, 0..10).wait().unwrap();
+
+    assert_eq!(measurement.count(), 10);
+    assert_eq!(measurement.errors(), 0);
+    assert!(measurement.mean().is_some());
+}
+
+#[test]
+fn measure_failing() {
+    let measurement = measure(Failing, 0..3).wait().unwrap();
+
+    assert_eq!(measurement.count(), 3);
+    assert_eq!(measurement.errors(), 3);
+}
+
+#[test]
+fn measure_latency() {
+    let mut rt = tokio::runtime::Runtime::new().unwrap();
+    let svc = Latency::new(Duration::from_millis(10));
+    let measurement = rt.block_on(measure(svc, 0..3)).unwrap();
+
+    assert_eq!(measurement.count(), 3);
+    assert!(measurement.percentile(50.0).unwrap() >= Duration::from_millis(10));
+    assert!(measurement.elapsed() >= Duration::from_millis(30));
+}
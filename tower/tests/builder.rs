@@ -1,3 +1,5 @@
+#![cfg(feature = "full")]
+
 extern crate futures;
 extern crate tokio;
 extern crate tower;
@@ -11,12 +13,18 @@ extern crate void;
 
 use futures::future::{self, FutureResult};
 use futures::prelude::*;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::Arc;
 use std::time::Duration;
-use tower::builder::ServiceBuilder;
+use tower::builder::order::{Backpressure, NotCloneable, Stack};
+use tower::builder::{ClientConfig, Layered, ServerConfig, ServiceBuilder};
+use tower::layer::Layer;
 use tower_buffer::BufferLayer;
 use tower_in_flight_limit::InFlightLimitLayer;
 use tower_rate_limit::RateLimitLayer;
-use tower_reconnect::Reconnect;
+use tower_reconnect::{Reconnect, ReconnectLayer};
+use tower_retry::budget::Budget;
+use tower_retry::event::Event;
 use tower_retry::{Policy, RetryLayer};
 use tower_service::*;
 use void::Void;
@@ -40,6 +48,24 @@ fn builder_make_service() {
     }));
 }
 
+#[test]
+fn builder_reconnect_layer() {
+    tokio::run(future::lazy(|| {
+        let mut client = ServiceBuilder::new()
+            .layer(BufferLayer::new(5))
+            .layer(InFlightLimitLayer::new(5))
+            .layer(ReconnectLayer::new(()))
+            .build_service(MockMaker)
+            .unwrap();
+
+        client.poll_ready().unwrap();
+        client
+            .call(Request)
+            .map(|_| ())
+            .map_err(|_| panic!("this is bad"))
+    }));
+}
+
 #[test]
 fn builder_service() {
     tokio::run(future::lazy(|| {
@@ -81,6 +107,138 @@ fn builder_make_service_retry() {
     }));
 }
 
+#[test]
+fn builder_validate() {
+    tokio::run(future::lazy(|| {
+        // Retry wraps a Buffer, so the stack it wraps can be cloned.
+        let mut client = ServiceBuilder::new()
+            .layer(BufferLayer::new(5))
+            .layer(RateLimitLayer::new(5, Duration::from_secs(1)))
+            .layer(InFlightLimitLayer::new(5))
+            .layer(RetryLayer::new(MockPolicy))
+            .layer(BufferLayer::new(5))
+            .validate::<Stack<NotCloneable, Backpressure>>()
+            .build_service(MockSvc)
+            .unwrap();
+
+        client.poll_ready().unwrap();
+        client
+            .call(Request)
+            .map(|_| ())
+            .map_err(|_| panic!("this is bad"))
+    }));
+}
+
+#[test]
+fn builder_service_translating_request() {
+    tokio::run(future::lazy(|| {
+        let mut client = ServiceBuilder::new()
+            .layer(BufferLayer::new(5))
+            .layer(InFlightLimitLayer::new(5))
+            .layer(IntoRequestLayer)
+            .build_service(MockSvc)
+            .unwrap();
+
+        client.poll_ready().unwrap();
+        client
+            .call("hello")
+            .map(|_| ())
+            .map_err(|_| panic!("this is bad"))
+    }));
+}
+
+#[test]
+fn builder_standard_client() {
+    tokio::run(future::lazy(|| {
+        let attempts = Arc::new(AtomicUsize::new(0));
+        let counter = attempts.clone();
+        let config = ClientConfig::new(MockPolicy)
+            .budget(Arc::new(Budget::default()))
+            .timeout(Duration::from_secs(1))
+            .max_in_flight(5)
+            .on_event(move |event| {
+                if let Event::AttemptStarted { .. } = event {
+                    counter.fetch_add(1, Ordering::SeqCst);
+                }
+            });
+
+        let mut client = ServiceBuilder::standard_client(config)
+            .build_service(MockSvc)
+            .unwrap();
+
+        client.poll_ready().unwrap();
+        client
+            .call(Request)
+            .map(move |_| assert_eq!(attempts.load(Ordering::SeqCst), 1))
+            .map_err(|_| panic!("this is bad"))
+    }));
+}
+
+#[test]
+fn builder_standard_server() {
+    tokio::run(future::lazy(|| {
+        let config = ServerConfig::new()
+            .timeout(Duration::from_secs(1))
+            .max_in_flight(5)
+            .queue_timeout(Duration::from_millis(100))
+            .max_handshakes(5);
+
+        let maker = ServiceBuilder::standard_server_make_service(config, MockMaker);
+
+        let mut client = Reconnect::new(maker, ());
+
+        client.poll_ready().unwrap();
+        client
+            .call(Request)
+            .map(|_| ())
+            .map_err(|_| panic!("this is bad"))
+    }));
+}
+
+#[test]
+fn builder_standard_server_drains() {
+    tokio::run(future::lazy(|| {
+        let config = ServerConfig::new();
+        let drain = config.drain();
+
+        let mut service = ServiceBuilder::standard_server(config)
+            .build_service(MockSvc)
+            .unwrap();
+        assert!(service.poll_ready().unwrap().is_ready());
+
+        drain.drain();
+        assert!(service.poll_ready().is_err());
+        Ok(())
+    }));
+}
+
+#[test]
+fn builder_extension_trait() {
+    tokio::run(future::lazy(|| {
+        let mut client = ServiceBuilder::new()
+            .limited(5)
+            .layer(RateLimitLayer::new(5, Duration::from_secs(1)))
+            .build_service(MockSvc)
+            .unwrap();
+
+        client.poll_ready().unwrap();
+        client
+            .call(Request)
+            .map(|_| ())
+            .map_err(|_| panic!("this is bad"))
+    }));
+}
+
+trait ServiceBuilderExt<L> {
+    fn limited<Request>(self, max: usize) -> Layered<InFlightLimitLayer, L, Request>;
+}
+
+impl<L> ServiceBuilderExt<L> for ServiceBuilder<L> {
+    fn limited<Request>(self, max: usize) -> Layered<InFlightLimitLayer, L, Request> {
+        self.layer(InFlightLimitLayer::new(max))
+    }
+}
+
 #[derive(Debug)]
 struct MockMaker;
 impl Service<()> for MockMaker {
@@ -101,7 +259,7 @@ impl Service<()> for MockMaker {
 struct Request;
 #[derive(Debug, Clone)]
 struct Response;
-#[derive(Debug)]
+#[derive(Debug, Clone)]
 struct MockSvc;
 impl Service<Request> for MockSvc {
     type Response = Response;
@@ -117,6 +275,43 @@ impl Service<Request> for MockSvc {
     }
 }
 
+/// Translates `&'static str` requests into `Request`s.
+#[derive(Debug)]
+struct IntoRequestLayer;
+#[derive(Debug)]
+struct IntoRequest<S>(S);
+
+impl<S> Layer<S, &'static str> for IntoRequestLayer
+where
+    S: Service<Request>,
+{
+    type Response = S::Response;
+    type Error = S::Error;
+    type LayerError = Void;
+    type Service = IntoRequest<S>;
+
+    fn layer(&self, service: S) -> Result<Self::Service, Self::LayerError> {
+        Ok(IntoRequest(service))
+    }
+}
+
+impl<S> Service<&'static str> for IntoRequest<S>
+where
+    S: Service<Request>,
+{
+    type Response = S::Response;
+    type Error = S::Error;
+    type Future = S::Future;
+
+    fn poll_ready(&mut self) -> Poll<(), Self::Error> {
+        self.0.poll_ready()
+    }
+
+    fn call(&mut self, _: &'static str) -> Self::Future {
+        self.0.call(Request)
+    }
+}
+
 #[derive(Debug, Clone)]
 struct MockPolicy;
 
@@ -0,0 +1,46 @@
+extern crate tower;
+
+use std::io;
+use tower::error::{self, BoxError, Context, Message};
+
+fn not_found() -> io::Error {
+    io::Error::new(io::ErrorKind::NotFound, "no such file")
+}
+
+#[test]
+fn wrap_keeps_source() {
+    let err = error::wrap(not_found(), "failed to load config");
+    assert_eq!(err.to_string(), "failed to load config");
+
+    let source = err.source().expect("source");
+    assert_eq!(source.to_string(), "no such file");
+}
+
+#[test]
+fn find_searches_the_whole_chain() {
+    let inner = error::wrap(not_found(), "failed to open file");
+    let err: BoxError = error::wrap(inner, "failed to load config");
+
+    // Only the outermost error is found by `downcast_ref`.
+    assert!(err.downcast_ref::<io::Error>().is_none());
+
+    let io = error::find::<io::Error>(&*err).expect("io error in chain");
+    assert_eq!(io.kind(), io::ErrorKind::NotFound);
+
+    assert!(error::is::<Context>(&*err));
+    assert!(!error::is::<Message<u32>>(&*err));
+
+    let messages: Vec<_> = error::chain(&*err).map(|e| e.to_string()).collect();
+    assert_eq!(
+        messages,
+        vec!["failed to load config", "failed to open file", "no such file"]
+    );
+}
+
+#[test]
+fn msg_keeps_its_type() {
+    let err = error::wrap(error::msg(404u16), "request failed");
+
+    let status = error::find::<Message<u16>>(&*err).expect("message in chain");
+    assert_eq!(*status.get_ref(), 404);
+}
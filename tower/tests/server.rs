@@ -0,0 +1,122 @@
+#![cfg(feature = "time")]
+
+extern crate futures;
+extern crate tokio;
+extern crate tower;
+extern crate tower_mock;
+extern crate tower_service;
+
+use futures::{future, Async, Future};
+use std::time::Duration;
+use tower::layer::Layer;
+use tower::load_shed::error::Overloaded;
+use tower::server::error::{Draining, Panicked};
+use tower::server::{CatchPanicLayer, DrainHandle, DrainLayer, HandshakeLimitLayer};
+use tower::timeout::error::Elapsed;
+use tower::util::service_fn;
+use tower_mock::make::MakeMock;
+use tower_mock::Mock;
+use tower_service::Service;
+
+type Make = MakeMock<&'static str, &'static str>;
+type Error = Box<::std::error::Error + Send + Sync>;
+
+#[test]
+fn rejects_handshakes_over_the_limit() {
+    let mut rt = tokio::runtime::current_thread::Runtime::new().unwrap();
+    let (make, mut handle) = Make::new();
+    let mut make = HandshakeLimitLayer::new(Duration::from_secs(10), 1)
+        .layer(make)
+        .unwrap();
+
+    let first = rt
+        .block_on(future::lazy(|| {
+            assert!(make.poll_ready().unwrap().is_ready());
+            Ok::<_, ()>(make.call("first"))
+        }))
+        .unwrap();
+    let construction = handle.expect_target("first");
+
+    // The first handshake is still in progress, so the second is rejected
+    // without reaching the inner make service.
+    let second = rt
+        .block_on(future::lazy(|| {
+            assert!(make.poll_ready().unwrap().is_ready());
+            Ok::<_, ()>(make.call("second"))
+        }))
+        .unwrap();
+    let err = rt.block_on(second).unwrap_err();
+    assert!(err.is::<Overloaded>());
+    handle.assert_no_construction();
+
+    construction.resolve("service");
+    assert_eq!(rt.block_on(first).unwrap(), "service");
+}
+
+#[test]
+fn fails_slow_handshakes() {
+    let mut rt = tokio::runtime::current_thread::Runtime::new().unwrap();
+    let (make, mut handle) = Make::new();
+    let mut make = HandshakeLimitLayer::new(Duration::from_millis(20), 1)
+        .layer(make)
+        .unwrap();
+
+    let handshake = rt
+        .block_on(future::lazy(|| {
+            assert!(make.poll_ready().unwrap().is_ready());
+            Ok::<_, ()>(make.call("slow"))
+        }))
+        .unwrap();
+    let _construction = handle.expect_target("slow");
+
+    let err = rt.block_on(handshake).unwrap_err();
+    assert!(err.is::<Elapsed>());
+}
+
+#[test]
+fn turns_panics_into_errors() {
+    let mut service = CatchPanicLayer::new()
+        .layer(service_fn(|request: &'static str| {
+            if request == "boom" {
+                panic!("boom");
+            }
+            Ok::<_, Error>(request)
+        }))
+        .unwrap();
+
+    let err = service.call("boom").wait().unwrap_err();
+    let panicked = err.downcast_ref::<Panicked>().expect("not a panic");
+    assert_eq!(panicked.message(), Some("boom"));
+
+    assert_eq!(service.call("hello").wait().unwrap(), "hello");
+}
+
+#[test]
+fn drains_requests_in_flight() {
+    let mut rt = tokio::runtime::current_thread::Runtime::new().unwrap();
+    let (service, mut handle) = Mock::<&'static str, &'static str>::new();
+    let drain = DrainHandle::new();
+    let mut service = DrainLayer::new(drain.clone()).layer(service).unwrap();
+
+    let response = rt
+        .block_on(future::lazy(|| {
+            assert!(service.poll_ready().unwrap().is_ready());
+            Ok::<_, ()>(service.call("hello"))
+        }))
+        .unwrap();
+    let request = handle.next_request().unwrap();
+
+    let mut drained = drain.drain();
+    rt.block_on(future::lazy(|| {
+        // New requests are refused, while the one in flight completes.
+        let err = service.poll_ready().unwrap_err();
+        assert!(err.is::<Draining>());
+        assert_eq!(drained.poll(), Ok(Async::NotReady));
+        Ok::<_, ()>(())
+    }))
+    .unwrap();
+
+    request.respond("world");
+    assert_eq!(rt.block_on(response).unwrap(), "world");
+    assert_eq!(rt.block_on(drained), Ok(()));
+}